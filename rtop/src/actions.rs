@@ -0,0 +1,343 @@
+// src/actions.rs
+use crate::keymap::KeyBinding;
+use serde::Deserialize;
+use std::io::{Read, Write};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Output kept per stream; the rest is dropped.
+const OUTPUT_LIMIT: usize = 64 * 1024;
+
+/// Variables passed through to commands; everything else is cleared.
+const KEPT_ENV: [&str; 5] = ["PATH", "HOME", "USER", "LANG", "TZ"];
+
+fn default_timeout() -> u64 {
+    10
+}
+
+/// A command bound to a key in the config file's `[[actions]]` list.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserAction {
+    pub name: String,
+    pub key: KeyBinding,
+    /// Program and arguments, run without a shell. `{pid}` and `{name}` are
+    /// replaced with the selected process.
+    pub command: Vec<String>,
+    /// Seconds before the command and its children are killed.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    /// Run `command` as a `sh -c` script instead; the process is passed as
+    /// `$1`/`$2` rather than substituted into the script.
+    #[serde(default)]
+    pub shell: bool,
+}
+
+/// The process an action runs against.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Target {
+    pub pid: Option<u32>,
+    pub name: String,
+}
+
+impl UserAction {
+    /// The argv to execute for `target`.
+    pub fn argv(&self, target: &Target) -> Vec<String> {
+        let pid = target.pid.map(|pid| pid.to_string()).unwrap_or_default();
+        if self.shell {
+            let script = self.command.join(" ");
+            return vec![
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                script,
+                "rtop".to_string(),
+                pid,
+                target.name.clone(),
+            ];
+        }
+        self.command
+            .iter()
+            .map(|arg| arg.replace("{pid}", &pid).replace("{name}", &target.name))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Exited(i32),
+    Signaled,
+    TimedOut,
+    FailedToStart(String),
+}
+
+/// What a finished command produced, for the output popup.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Execution {
+    pub title: String,
+    pub argv: Vec<String>,
+    pub outcome: Outcome,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration: Duration,
+}
+
+/// Time given to the output readers to finish once the process group was
+/// killed.
+const KILL_GRACE: Duration = Duration::from_millis(100);
+
+/// Reads a stream of the child on a thread of its own until it closes.
+struct Capture {
+    kept: Arc<Mutex<Vec<u8>>>,
+    /// Disconnected once the stream is closed.
+    done: Receiver<()>,
+}
+
+impl Capture {
+    fn start(mut stream: impl Read + Send + 'static) -> Capture {
+        let kept = Arc::new(Mutex::new(Vec::new()));
+        let (sender, done) = mpsc::channel::<()>();
+        let thread_kept = kept.clone();
+        std::thread::spawn(move || {
+            let _sender = sender;
+            let mut buffer = [0; 4096];
+            // Keep draining past the limit so the child never blocks on a full pipe.
+            while let Ok(n @ 1..) = stream.read(&mut buffer) {
+                let mut kept = thread_kept.lock().unwrap_or_else(PoisonError::into_inner);
+                let room = OUTPUT_LIMIT.saturating_sub(kept.len());
+                kept.extend_from_slice(&buffer[..n.min(room)]);
+            }
+        });
+        Capture { kept, done }
+    }
+
+    /// Waits until the stream is closed or `deadline` passes; returns
+    /// whether it was closed.
+    fn wait(&self, deadline: Instant) -> bool {
+        let left = deadline.saturating_duration_since(Instant::now());
+        !matches!(self.done.recv_timeout(left), Err(RecvTimeoutError::Timeout))
+    }
+
+    /// What was read so far.
+    fn output(&self) -> String {
+        String::from_utf8_lossy(&self.kept.lock().unwrap_or_else(PoisonError::into_inner))
+            .into_owned()
+    }
+}
+
+/// SIGKILLs the process group `group`.
+fn kill_group(group: u32) {
+    unsafe { libc::kill(-(group as i32), libc::SIGKILL) };
+}
+
+/// Runs `argv` directly (no shell), with a minimal environment, `input` (or
+/// nothing) on stdin and its own process group, which is killed once
+/// `timeout` passes, also when a process left behind in it still holds
+/// stdout or stderr open.
+pub fn execute(
+    title: &str,
+    argv: &[String],
+    env: &[(&str, String)],
+    input: Option<&[u8]>,
+    timeout: Duration,
+) -> Execution {
+    let started = Instant::now();
+    let finish = |outcome, stdout, stderr| Execution {
+        title: title.to_string(),
+        argv: argv.to_vec(),
+        outcome,
+        stdout,
+        stderr,
+        duration: started.elapsed(),
+    };
+    let Some((program, args)) = argv.split_first() else {
+        return finish(
+            Outcome::FailedToStart("empty command".to_string()),
+            String::new(),
+            String::new(),
+        );
+    };
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .env_clear()
+        .envs(
+            KEPT_ENV
+                .iter()
+                .filter_map(|key| Some((key, std::env::var_os(key)?))),
+        )
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0);
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            return finish(
+                Outcome::FailedToStart(err.to_string()),
+                String::new(),
+                String::new(),
+            )
+        }
+    };
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        // A thread, so a command that never reads cannot block us past the timeout.
+        let input = input.to_vec();
+        std::thread::spawn(move || stdin.write_all(&input));
+    }
+    let captures: Vec<Capture> = [
+        child.stdout.take().map(Capture::start),
+        child.stderr.take().map(Capture::start),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let deadline = started + timeout;
+    let mut outcome = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status.code().map_or(Outcome::Signaled, Outcome::Exited),
+            Ok(None) if Instant::now() >= deadline => {
+                // The whole group, so a script's children don't outlive it.
+                kill_group(child.id());
+                let _ = child.wait();
+                break Outcome::TimedOut;
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+            Err(err) => break Outcome::FailedToStart(err.to_string()),
+        }
+    };
+    // A process the command put in the background may keep the pipes open
+    // after it exited; it gets the same deadline. The group outlives the
+    // command while such a process is in it, so its ID is not reused yet.
+    if !captures.iter().all(|capture| capture.wait(deadline)) {
+        kill_group(child.id());
+        outcome = Outcome::TimedOut;
+        // Abandoned if they still do not finish.
+        let grace = Instant::now() + KILL_GRACE;
+        for capture in &captures {
+            capture.wait(grace);
+        }
+    }
+    let output = |index: usize| captures.get(index).map(Capture::output).unwrap_or_default();
+    finish(outcome, output(0), output(1))
+}
+
+/// Runs one action at a time in the background so the UI keeps refreshing.
+pub struct ActionRunner {
+    running: Option<(String, Receiver<Execution>)>,
+    pub finished: Option<Execution>,
+}
+
+impl ActionRunner {
+    pub fn new() -> ActionRunner {
+        ActionRunner {
+            running: None,
+            finished: None,
+        }
+    }
+
+    /// Starts `action`; ignored while another action is still running.
+    pub fn start(&mut self, action: &UserAction, target: &Target) {
+        if self.running.is_some() {
+            return;
+        }
+        let argv = action.argv(target);
+        let env = vec![
+            (
+                "RTOP_PID",
+                target.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+            ),
+            ("RTOP_NAME", target.name.clone()),
+        ];
+        let (title, timeout) = (action.name.clone(), Duration::from_secs(action.timeout));
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(execute(&title, &argv, &env, None, timeout));
+        });
+        self.finished = None;
+        self.running = Some((action.name.clone(), receiver));
+    }
+
+    /// The name of the action still running, if any.
+    pub fn running(&self) -> Option<&str> {
+        self.running.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    /// Moves a finished execution into `finished`.
+    pub fn poll(&mut self) {
+        if let Some((_, receiver)) = &self.running {
+            match receiver.try_recv() {
+                Ok(execution) => {
+                    self.finished = Some(execution);
+                    self.running = None;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.running = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(command: &[&str], shell: bool) -> UserAction {
+        UserAction {
+            name: "test".to_string(),
+            key: "F5".parse().unwrap(),
+            command: command.iter().map(|arg| arg.to_string()).collect(),
+            timeout: 1,
+            shell,
+        }
+    }
+
+    #[test]
+    fn test_argv_substitution() {
+        let target = Target {
+            pid: Some(42),
+            name: "a; rm -rf /".to_string(),
+        };
+        let argv = action(&["echo", "pid={pid}", "{name}"], false).argv(&target);
+        assert_eq!(argv, vec!["echo", "pid=42", "a; rm -rf /"]);
+        let argv = action(&["echo", "$1"], true).argv(&target);
+        assert_eq!(argv[..3], ["/bin/sh", "-c", "echo $1"]);
+        assert_eq!(argv[4..], ["42", "a; rm -rf /"]);
+    }
+
+    #[test]
+    fn test_execute_captures_output_and_times_out() {
+        let argv: Vec<String> = ["sh", "-c", "echo out; echo err >&2; exit 3"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let execution = execute("t", &argv, &[], None, Duration::from_secs(5));
+        assert_eq!(execution.outcome, Outcome::Exited(3));
+        assert_eq!(execution.stdout, "out\n");
+        assert_eq!(execution.stderr, "err\n");
+
+        let argv = vec!["sleep".to_string(), "5".to_string()];
+        let execution = execute("t", &argv, &[], None, Duration::from_millis(100));
+        assert_eq!(execution.outcome, Outcome::TimedOut);
+        assert!(execution.duration < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_execute_kills_backgrounded_grandchild() {
+        let argv: Vec<String> = ["sh", "-c", "echo started; sleep 1000 &"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let execution = execute("t", &argv, &[], None, Duration::from_millis(300));
+        assert_eq!(execution.outcome, Outcome::TimedOut);
+        assert_eq!(execution.stdout, "started\n");
+        assert!(execution.duration < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,185 @@
+// src/alarm.rs
+use crate::health::{Finding, Severity};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// How long the banner stays in each of its two colors.
+const FLASH: Duration = Duration::from_millis(500);
+
+/// The check OOM kills are reported under. A kill is over by the next
+/// refresh, so the alarm keeps ringing for it until acknowledged.
+pub const OOM_KILL: &str = "oom_kill";
+
+/// The full-screen alarm (`--alarm`): rings when an alert turns critical
+/// and keeps ringing until acknowledged.
+#[derive(Default)]
+pub struct Alarm {
+    /// Critical alerts acknowledged; they ring again once they recover and
+    /// turn critical anew.
+    acknowledged: HashSet<String>,
+    /// Critical alerts not acknowledged yet.
+    pub ringing: Vec<Finding>,
+    /// The last OOM kill not acknowledged yet.
+    oom_kill: Option<Finding>,
+    since: Option<Instant>,
+}
+
+impl Alarm {
+    /// Takes the alerts firing unsilenced after a refresh.
+    pub fn update<'a>(&mut self, now: Instant, firing: impl Iterator<Item = &'a Finding>) {
+        let (oom_kills, critical): (Vec<&Finding>, Vec<&Finding>) = firing
+            .filter(|alert| alert.severity == Severity::Critical)
+            .partition(|alert| alert.check == OOM_KILL);
+        if let Some(oom_kill) = oom_kills.last() {
+            self.oom_kill = Some((*oom_kill).clone());
+        }
+        self.acknowledged
+            .retain(|check| critical.iter().any(|alert| alert.check == *check));
+        self.ringing = critical
+            .into_iter()
+            .filter(|alert| !self.acknowledged.contains(&alert.check))
+            .cloned()
+            .chain(self.oom_kill.clone())
+            .collect();
+        self.since = match self.since {
+            Some(since) if self.is_ringing() => Some(since),
+            _ if self.is_ringing() => Some(now),
+            _ => None,
+        };
+    }
+
+    pub fn is_ringing(&self) -> bool {
+        !self.ringing.is_empty()
+    }
+
+    /// Silences what rings now until it recovers.
+    pub fn acknowledge(&mut self) {
+        self.acknowledged.extend(
+            self.ringing
+                .drain(..)
+                .map(|alert| alert.check)
+                .filter(|check| check != OOM_KILL),
+        );
+        self.oom_kill = None;
+        self.since = None;
+    }
+
+    /// Whether the banner is in its bright phase.
+    pub fn flash(&self, now: Instant) -> bool {
+        self.since.is_some_and(|since| {
+            (now.saturating_duration_since(since).as_millis() / FLASH.as_millis()).is_multiple_of(2)
+        })
+    }
+}
+
+/// Watches the kernel's count of OOM kills, `oom_kill` in `/proc/vmstat`
+/// (Linux 4.13 and later).
+#[derive(Default)]
+pub struct OomKills {
+    last: Option<u64>,
+}
+
+impl OomKills {
+    /// Whether processes were killed for lack of memory since the last
+    /// call; `None` where the kernel does not count them.
+    pub fn finding(&mut self) -> Option<Finding> {
+        let vmstat = std::fs::read_to_string("/proc/vmstat").ok()?;
+        Some(self.record(parse_oom_kills(&vmstat)?))
+    }
+
+    fn record(&mut self, count: u64) -> Finding {
+        let killed = self.last.map_or(0, |last| count.saturating_sub(last));
+        self.last = Some(count);
+        Finding {
+            check: OOM_KILL.to_string(),
+            severity: if killed > 0 {
+                Severity::Critical
+            } else {
+                Severity::Ok
+            },
+            message: match killed {
+                0 => "no process killed for lack of memory".to_string(),
+                1 => "1 process killed for lack of memory".to_string(),
+                n => format!("{} processes killed for lack of memory", n),
+            },
+            silenced: None,
+        }
+    }
+}
+
+fn parse_oom_kills(vmstat: &str) -> Option<u64> {
+    vmstat
+        .lines()
+        .find_map(|line| line.strip_prefix("oom_kill ")?.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(check: &str, severity: Severity) -> Finding {
+        Finding {
+            check: check.to_string(),
+            severity,
+            message: "100.0% used".to_string(),
+            silenced: None,
+        }
+    }
+
+    #[test]
+    fn test_alarm_rings_until_acknowledged() {
+        let mut alarm = Alarm::default();
+        let now = Instant::now();
+        let (root, swap) = (
+            alert("disk:/", Severity::Critical),
+            alert("swap", Severity::Warning),
+        );
+        alarm.update(now, [&swap].into_iter());
+        assert!(!alarm.is_ringing());
+
+        alarm.update(now, [&root, &swap].into_iter());
+        assert_eq!(alarm.ringing.len(), 1);
+        assert!(alarm.flash(now));
+        assert!(!alarm.flash(now + FLASH));
+
+        alarm.acknowledge();
+        alarm.update(now, [&root].into_iter());
+        assert!(!alarm.is_ringing());
+        // Once it recovered, the same alert rings again.
+        alarm.update(now, std::iter::empty());
+        alarm.update(now, [&root].into_iter());
+        assert!(alarm.is_ringing());
+    }
+
+    #[test]
+    fn test_oom_kill_rings_until_acknowledged() {
+        let vmstat = "pgfault 1234\noom_kill 2\npgmajfault 5\n";
+        assert_eq!(parse_oom_kills(vmstat), Some(2));
+        assert_eq!(parse_oom_kills("pgfault 1234\n"), None);
+
+        let mut kills = OomKills::default();
+        // Kills from before rtop started do not count.
+        assert_eq!(kills.record(2).severity, Severity::Ok);
+        let killed = kills.record(3);
+        assert_eq!(killed.severity, Severity::Critical);
+        assert_eq!(killed.message, "1 process killed for lack of memory");
+
+        let mut alarm = Alarm::default();
+        let now = Instant::now();
+        alarm.update(now, [&killed].into_iter());
+        assert!(alarm.is_ringing());
+        // Still ringing once the next refresh saw no new kill.
+        let quiet = kills.record(3);
+        alarm.update(now, [&quiet].into_iter());
+        assert_eq!(alarm.ringing.len(), 1);
+        alarm.acknowledge();
+        alarm.update(now, [&quiet].into_iter());
+        assert!(!alarm.is_ringing());
+        // A later kill rings again right away.
+        alarm.update(now, [&kills.record(5)].into_iter());
+        assert_eq!(
+            alarm.ringing[0].message,
+            "2 processes killed for lack of memory"
+        );
+    }
+}
@@ -0,0 +1,389 @@
+// src/alerts.rs
+use crate::health::{Finding, Severity};
+use crate::source::Source;
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use sysinfo::Pid;
+
+/// Names of the built-in rules of the Alerts tab, which user rules may not take.
+const BUILTIN: [&str; 6] = ["cpu", "memory", "swap", "load", "zombies", "d_state"];
+
+/// Entries kept in the alert log.
+const LOG_SIZE: usize = 500;
+
+/// What an alert rule watches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// Total CPU usage, in percent.
+    Cpu,
+    /// Used memory, in percent of the total.
+    Memory,
+    /// Used swap, in percent of the total.
+    Swap,
+    /// The 1 minute load average.
+    Load,
+    /// CPU usage of any one process, in percent of a core.
+    ProcessCpu,
+    /// Resident memory of any one process, in percent of the total.
+    ProcessMemory,
+}
+
+impl Metric {
+    pub fn per_process(self) -> bool {
+        matches!(self, Metric::ProcessCpu | Metric::ProcessMemory)
+    }
+
+    pub fn format(self, value: f64) -> String {
+        match self {
+            Metric::Load => format!("{:.2}", value),
+            _ => format!("{:.1}%", value),
+        }
+    }
+}
+
+/// A rule from the config's `[[alerts]]` tables, such as "any process above
+/// 90% CPU for 30 seconds".
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    /// Shown in the Alerts tab, and what silences and maintenance windows match.
+    pub name: String,
+    pub metric: Metric,
+    pub above: f64,
+    /// Seconds the value has to stay above `above` before the rule fires.
+    #[serde(default)]
+    pub for_seconds: u64,
+    #[serde(default = "default_severity")]
+    pub severity: Severity,
+    /// For process metrics, only processes whose name contains this.
+    #[serde(default)]
+    pub process: Option<String>,
+}
+
+fn default_severity() -> Severity {
+    Severity::Warning
+}
+
+impl AlertRule {
+    /// Checks the name and severity, for config errors at startup.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty()
+            || BUILTIN.contains(&self.name.as_str())
+            || self.name.starts_with("disk:")
+        {
+            return Err(format!("alerts: '{}' is not a free rule name", self.name));
+        }
+        if self.severity == Severity::Ok {
+            return Err(format!(
+                "alerts.{}: severity is warning or critical",
+                self.name
+            ));
+        }
+        if self.process.is_some() && !self.metric.per_process() {
+            return Err(format!(
+                "alerts.{}: process only applies to process metrics",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One process as alert rules see it.
+pub struct Usage<'a> {
+    pub pid: Pid,
+    /// As shown in the UI, so redacted with `--redact`.
+    pub name: Cow<'a, str>,
+    pub cpu: f64,
+    /// Resident memory, in percent of the total.
+    pub memory: f64,
+}
+
+/// Evaluates the `[[alerts]]` rules on every refresh.
+#[derive(Default)]
+pub struct AlertRules {
+    rules: Vec<AlertRule>,
+    /// Since when each rule has been above its threshold, per process for
+    /// process metrics.
+    above_since: HashMap<(usize, Option<Pid>), Instant>,
+    /// The rules each process breaks, by index.
+    offenders: HashMap<Pid, Vec<usize>>,
+}
+
+impl AlertRules {
+    pub fn new(rules: Vec<AlertRule>) -> AlertRules {
+        AlertRules {
+            rules,
+            ..AlertRules::default()
+        }
+    }
+
+    /// One finding per rule, firing once its value stayed above the
+    /// threshold for `for_seconds`.
+    pub fn evaluate<'a>(
+        &mut self,
+        now: Instant,
+        source: &dyn Source,
+        processes: impl Iterator<Item = Usage<'a>>,
+    ) -> Vec<Finding> {
+        let processes: Vec<Usage> = processes.collect();
+        let mut above_since = HashMap::new();
+        self.offenders.clear();
+        let mut findings = Vec::with_capacity(self.rules.len());
+        for (index, rule) in self.rules.iter().enumerate() {
+            let values: Vec<(Option<&Usage>, f64)> = match rule.metric {
+                Metric::Cpu => vec![(None, source.cpu_usage() as f64)],
+                Metric::Memory => vec![(None, source.memory_percent())],
+                Metric::Swap => vec![(None, source.swap_percent())],
+                Metric::Load => vec![(None, source.load_average()[0])],
+                Metric::ProcessCpu | Metric::ProcessMemory => processes
+                    .iter()
+                    .filter(|usage| {
+                        rule.process
+                            .as_ref()
+                            .is_none_or(|name| usage.name.contains(name.as_str()))
+                    })
+                    .map(|usage| match rule.metric {
+                        Metric::ProcessCpu => (Some(usage), usage.cpu),
+                        _ => (Some(usage), usage.memory),
+                    })
+                    .collect(),
+            };
+            // Values above the threshold, with how long they have been.
+            let mut above: Vec<(Option<&Usage>, f64, Duration)> = Vec::new();
+            for (usage, value) in values.into_iter().filter(|(_, value)| *value > rule.above) {
+                let key = (index, usage.map(|usage| usage.pid));
+                let since = *self.above_since.get(&key).unwrap_or(&now);
+                above_since.insert(key, since);
+                above.push((usage, value, now.saturating_duration_since(since)));
+            }
+            let hold = Duration::from_secs(rule.for_seconds);
+            above.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let (firing, pending): (Vec<_>, Vec<_>) =
+                above.into_iter().partition(|(_, _, held)| *held >= hold);
+            for (usage, _, _) in &firing {
+                if let Some(usage) = usage {
+                    self.offenders.entry(usage.pid).or_default().push(index);
+                }
+            }
+            findings.push(Finding {
+                check: rule.name.clone(),
+                severity: if firing.is_empty() {
+                    Severity::Ok
+                } else {
+                    rule.severity
+                },
+                message: message(rule, &firing, &pending, hold),
+                silenced: None,
+            });
+        }
+        self.above_since = above_since;
+        findings
+    }
+
+    /// The highest severity of the rules in `firing` that `pid` breaks.
+    pub fn offending(&self, pid: Pid, firing: &HashSet<String>) -> Option<Severity> {
+        self.offenders
+            .get(&pid)?
+            .iter()
+            .map(|&index| &self.rules[index])
+            .filter(|rule| firing.contains(&rule.name))
+            .map(|rule| rule.severity)
+            .max()
+    }
+}
+
+/// "yes (4242) 99.0%, stress (77) 95.2%", or the value of a system metric.
+fn describe(rule: &AlertRule, above: &[(Option<&Usage>, f64, Duration)]) -> String {
+    let mut parts: Vec<String> = above
+        .iter()
+        .take(3)
+        .map(|(usage, value, _)| match usage {
+            Some(usage) => format!(
+                "{} ({}) {}",
+                usage.name,
+                usage.pid,
+                rule.metric.format(*value)
+            ),
+            None => rule.metric.format(*value),
+        })
+        .collect();
+    if above.len() > parts.len() {
+        parts.push("...".to_string());
+    }
+    parts.join(", ")
+}
+
+fn message(
+    rule: &AlertRule,
+    firing: &[(Option<&Usage>, f64, Duration)],
+    pending: &[(Option<&Usage>, f64, Duration)],
+    hold: Duration,
+) -> String {
+    if !firing.is_empty() {
+        return describe(rule, firing);
+    }
+    if let Some(held) = pending.iter().map(|(_, _, held)| *held).max() {
+        return format!(
+            "{}, fires in {}s",
+            describe(rule, pending),
+            (hold - held).as_secs().max(1)
+        );
+    }
+    let scope = if rule.metric.per_process() {
+        "no process above "
+    } else {
+        "below "
+    };
+    format!("{}{}", scope, rule.metric.format(rule.above))
+}
+
+/// An alert that fired during the session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogEntry {
+    pub check: String,
+    pub severity: Severity,
+    /// The finding's message when it fired.
+    pub message: String,
+    pub fired: DateTime<Local>,
+    /// When it stopped firing, or was silenced.
+    pub cleared: Option<DateTime<Local>>,
+}
+
+/// Every alert that fired during the session, newest last.
+#[derive(Default)]
+pub struct AlertLog {
+    pub entries: VecDeque<LogEntry>,
+}
+
+impl AlertLog {
+    /// Logs the rules of `alerts` that started firing and marks those that
+    /// stopped as cleared.
+    pub fn update(&mut self, alerts: &[Finding], firing: &HashSet<String>, now: DateTime<Local>) {
+        for entry in self
+            .entries
+            .iter_mut()
+            .filter(|entry| entry.cleared.is_none())
+        {
+            if !firing.contains(&entry.check) {
+                entry.cleared = Some(now);
+            }
+        }
+        for alert in alerts.iter().filter(|alert| firing.contains(&alert.check)) {
+            let open = self
+                .entries
+                .iter()
+                .any(|entry| entry.check == alert.check && entry.cleared.is_none());
+            if !open {
+                if self.entries.len() == LOG_SIZE {
+                    self.entries.pop_front();
+                }
+                self.entries.push_back(LogEntry {
+                    check: alert.check.clone(),
+                    severity: alert.severity,
+                    message: alert.message.clone(),
+                    fired: now,
+                    cleared: None,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use sysinfo::{System, SystemExt};
+
+    #[test]
+    fn test_process_rule_fires_after_hold() {
+        let config = Config::parse(
+            r#"
+[[alerts]]
+name = "runaway"
+metric = "process_cpu"
+above = 90.0
+for_seconds = 30
+severity = "critical"
+"#,
+        )
+        .unwrap();
+        let mut rules = AlertRules::new(config.alerts);
+        let system = System::new();
+        let usage = |pid: usize, name: &'static str, cpu: f64| Usage {
+            pid: Pid::from(pid),
+            name: Cow::Borrowed(name),
+            cpu,
+            memory: 1.0,
+        };
+        let start = Instant::now();
+        let mut at = |seconds: u64, processes: Vec<Usage<'static>>| {
+            rules
+                .evaluate(
+                    start + Duration::from_secs(seconds),
+                    &system,
+                    processes.into_iter(),
+                )
+                .remove(0)
+        };
+        let finding = at(0, vec![usage(1, "yes", 99.0), usage(2, "sh", 5.0)]);
+        assert_eq!(finding.severity, Severity::Ok);
+        assert_eq!(finding.message, "yes (1) 99.0%, fires in 30s");
+        let finding = at(31, vec![usage(1, "yes", 95.0), usage(2, "sh", 5.0)]);
+        assert_eq!(finding.severity, Severity::Critical);
+        assert_eq!(finding.message, "yes (1) 95.0%");
+        let firing = HashSet::from(["runaway".to_string()]);
+        assert_eq!(
+            rules.offending(Pid::from(1), &firing),
+            Some(Severity::Critical)
+        );
+        assert_eq!(rules.offending(Pid::from(2), &firing), None);
+
+        // Dropping below the threshold starts the hold over.
+        let mut rules = AlertRules::new(rules.rules.clone());
+        let mut at = |seconds: u64, cpu: f64| {
+            rules
+                .evaluate(
+                    start + Duration::from_secs(seconds),
+                    &system,
+                    vec![usage(1, "yes", cpu)].into_iter(),
+                )
+                .remove(0)
+                .severity
+        };
+        assert_eq!(at(0, 99.0), Severity::Ok);
+        assert_eq!(at(20, 10.0), Severity::Ok);
+        assert_eq!(at(40, 99.0), Severity::Ok);
+        assert_eq!(at(70, 99.0), Severity::Critical);
+
+        assert!(
+            Config::parse("[[alerts]]\nname = \"cpu\"\nmetric = \"cpu\"\nabove = 1.0").is_err()
+        );
+    }
+
+    #[test]
+    fn test_alert_log() {
+        let finding = |check: &str| Finding {
+            check: check.to_string(),
+            severity: Severity::Warning,
+            message: "96.0% used".to_string(),
+            silenced: None,
+        };
+        let alerts = [finding("memory"), finding("swap")];
+        let mut log = AlertLog::default();
+        let now = Local::now();
+        log.update(&alerts, &HashSet::from(["memory".to_string()]), now);
+        log.update(&alerts, &HashSet::from(["memory".to_string()]), now);
+        assert_eq!(log.entries.len(), 1);
+        log.update(&alerts, &HashSet::new(), now);
+        assert_eq!(log.entries[0].cleared, Some(now));
+        log.update(&alerts, &HashSet::from(["memory".to_string()]), now);
+        assert_eq!(log.entries.len(), 2);
+        assert_eq!(log.entries[1].cleared, None);
+    }
+}
@@ -0,0 +1,132 @@
+// src/anomaly.rs
+use std::collections::{HashMap, VecDeque};
+use sysinfo::Pid;
+
+/// Refreshes each process's recent norm is computed over.
+const WINDOW: usize = 120;
+/// Samples needed before a process can be flagged at all.
+const MIN_SAMPLES: usize = 20;
+/// Smallest standard deviations taken into account, so that a process idling
+/// at a flat 0.0% is not flagged for reaching 0.5%.
+const MIN_CPU_DEVIATION: f64 = 2.0;
+const MIN_MEMORY_DEVIATION: f64 = 4.0 * 1024.0 * 1024.0;
+
+/// The last `WINDOW` values of one metric of one process.
+#[derive(Default)]
+struct Rolling {
+    samples: VecDeque<f64>,
+}
+
+impl Rolling {
+    fn push(&mut self, value: f64) {
+        if self.samples.len() == WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(value);
+    }
+
+    /// Standard deviations `value` is above the mean of the window.
+    fn z_score(&self, value: f64, min_deviation: f64) -> Option<f64> {
+        if self.samples.len() < MIN_SAMPLES {
+            return None;
+        }
+        let count = self.samples.len() as f64;
+        let mean = self.samples.iter().sum::<f64>() / count;
+        let variance = self.samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / count;
+        Some((value - mean) / variance.sqrt().max(min_deviation))
+    }
+}
+
+/// Which metrics of a process are far above its own recent norm.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Anomaly {
+    pub cpu: bool,
+    pub memory: bool,
+}
+
+/// Rolling CPU and memory statistics of every process.
+#[derive(Default)]
+pub struct Anomalies {
+    cpu: HashMap<Pid, Rolling>,
+    memory: HashMap<Pid, Rolling>,
+    flagged: HashMap<Pid, Anomaly>,
+}
+
+impl Anomalies {
+    /// Takes one sample of CPU% and resident bytes per process and flags
+    /// those at least `threshold` standard deviations above their window.
+    pub fn record(&mut self, samples: impl Iterator<Item = (Pid, f64, f64)>, threshold: f64) {
+        let (mut cpu, mut memory) = (HashMap::new(), HashMap::new());
+        self.flagged.clear();
+        for (pid, cpu_usage, resident) in samples {
+            let mut cpu_window = self.cpu.remove(&pid).unwrap_or_default();
+            let mut memory_window = self.memory.remove(&pid).unwrap_or_default();
+            let anomaly = Anomaly {
+                cpu: cpu_window
+                    .z_score(cpu_usage, MIN_CPU_DEVIATION)
+                    .is_some_and(|z| z >= threshold),
+                memory: memory_window
+                    .z_score(resident, MIN_MEMORY_DEVIATION)
+                    .is_some_and(|z| z >= threshold),
+            };
+            if anomaly != Anomaly::default() {
+                self.flagged.insert(pid, anomaly);
+            }
+            cpu_window.push(cpu_usage);
+            memory_window.push(resident);
+            cpu.insert(pid, cpu_window);
+            memory.insert(pid, memory_window);
+        }
+        // Exited processes drop out.
+        self.cpu = cpu;
+        self.memory = memory;
+    }
+
+    pub fn get(&self, pid: Pid) -> Anomaly {
+        self.flagged.get(&pid).copied().unwrap_or_default()
+    }
+
+    /// A copy that only answers `get`, without the windows.
+    pub fn flags(&self) -> Anomalies {
+        Anomalies {
+            flagged: self.flagged.clone(),
+            ..Anomalies::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anomalies() {
+        let (steady, noisy) = (Pid::from(1), Pid::from(2));
+        let mut anomalies = Anomalies::default();
+        let mb = 1024.0 * 1024.0;
+        for i in 0..MIN_SAMPLES {
+            // `noisy` swings between 0% and 60% all the time.
+            let swing = if i % 2 == 0 { 0.0 } else { 60.0 };
+            let samples = [(steady, 5.0, 100.0 * mb), (noisy, swing, 100.0 * mb)];
+            anomalies.record(samples.into_iter(), 3.0);
+        }
+        assert_eq!(anomalies.get(steady), Anomaly::default());
+
+        let samples = [(steady, 50.0, 101.0 * mb), (noisy, 60.0, 300.0 * mb)];
+        anomalies.record(samples.into_iter(), 3.0);
+        assert_eq!(
+            anomalies.get(steady),
+            Anomaly {
+                cpu: true,
+                memory: false
+            }
+        );
+        assert_eq!(
+            anomalies.get(noisy),
+            Anomaly {
+                cpu: false,
+                memory: true
+            }
+        );
+    }
+}
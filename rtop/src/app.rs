@@ -0,0 +1,1689 @@
+// src/app.rs
+use clap::ValueEnum;
+use crossterm::event::{self, KeyCode, KeyModifiers};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use chrono::Timelike;
+use sysinfo::{CpuExt, ProcessExt, System, SystemExt, Pid, PidExt, Signal, UserExt};
+use crate::actions::ActionRunner;
+use crate::collectors::battery::BatteryInfo;
+use crate::config::{Column, Config, SizeClass, SizeLayout};
+use crate::details::{DetailsTab, ProcessDetails};
+use crate::collectors::disks::DiskMonitor;
+use crate::export::TableFormat;
+use crate::collectors::filesystems::FilesystemInfo;
+use crate::collectors::gpu::GpuMonitor;
+use crate::input::{LineInput, Prompt};
+use crate::collectors::interrupts::InterruptMonitor;
+use crate::keymap::{Action, KeyBinding, Keymap};
+use crate::mirror::MirrorServer;
+use crate::collectors::network::NetworkMonitor;
+use crate::privilege::Privilege;
+use crate::race::CpuRace;
+use crate::redact::Redactor;
+use crate::repeat::KeyRepeat;
+use crate::sampler::{ProcessReads, Sample, Wants};
+use crate::collectors::sockets::ConnectionSummary;
+use crate::theme::{ColorDepth, Theme};
+use crate::tree::TreeRow;
+use crate::collectors::{background, battery, filesystems, sockets};
+use crate::{actions, alarm, alerts, baseline, capture, cluster, columns, control, dstate, envgroup, export, health, history, hooks, hosts, input, kiosk, lock, mdns, metrics, notify, plugins, record, rotate, silence, sort, sparkline, tree, watchdog, whatif};
+use crate::ui::process_table_columns;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    Cpu,
+    Memory,
+    Name,
+    Pid,
+    #[serde(rename = "disk_read")]
+    #[value(name = "disk_read")]
+    DiskRead,
+    #[serde(rename = "disk_write")]
+    #[value(name = "disk_write")]
+    DiskWrite,
+}
+
+impl SortBy {
+    /// The process table column whose header shows the sort direction.
+    pub(crate) fn column(self) -> Column {
+        match self {
+            SortBy::Cpu => Column::Cpu,
+            SortBy::Memory => Column::Rss,
+            SortBy::Name => Column::Name,
+            SortBy::Pid => Column::Pid,
+            SortBy::DiskRead => Column::DiskRead,
+            SortBy::DiskWrite => Column::DiskWrite,
+        }
+    }
+
+    /// The name of its comparator in `sort::Comparators`.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            SortBy::Cpu => "cpu",
+            SortBy::Memory => "memory",
+            SortBy::Name => "name",
+            SortBy::Pid => "pid",
+            SortBy::DiskRead => "disk_read",
+            SortBy::DiskWrite => "disk_write",
+        }
+    }
+
+    /// Usage sorts biggest first; name and PID sort ascending.
+    pub(crate) fn descending(self) -> bool {
+        !matches!(self, SortBy::Name | SortBy::Pid)
+    }
+}
+
+/// Top-level views of the main screen, cycled with Tab.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tab {
+    Processes,
+    Disks,
+    #[serde(alias = "irqs")]
+    Interrupts,
+    Network,
+    Race,
+    Cluster,
+    Alerts,
+    Plugins,
+}
+
+impl Tab {
+    pub(crate) const ALL: [Tab; 8] = [Tab::Processes, Tab::Disks, Tab::Interrupts, Tab::Network, Tab::Race, Tab::Cluster, Tab::Alerts, Tab::Plugins];
+
+    pub(crate) fn title(self) -> &'static str {
+        match self {
+            Tab::Processes => "Processes",
+            Tab::Disks => "Disks",
+            Tab::Interrupts => "IRQs",
+            Tab::Network => "Network",
+            Tab::Race => "CPU Race",
+            Tab::Cluster => "Cluster",
+            Tab::Alerts => "Alerts",
+            Tab::Plugins => "Plugins",
+        }
+    }
+
+    pub(crate) fn index(self) -> usize {
+        Tab::ALL.iter().position(|&t| t == self).unwrap_or(0)
+    }
+
+    pub(crate) fn next(self) -> Tab {
+        Tab::ALL[(self.index() + 1) % Tab::ALL.len()]
+    }
+
+    pub(crate) fn previous(self) -> Tab {
+        Tab::ALL[(self.index() + Tab::ALL.len() - 1) % Tab::ALL.len()]
+    }
+}
+
+#[derive(PartialEq)]
+pub(crate) enum AppState {
+    Main,
+    ProcessMenu,
+    Details,
+    ActionOutput,
+    /// Editing `App::input`.
+    Input,
+    /// Editing `App::column_editor`.
+    Columns,
+    /// Editing `App::affinity_editor`, over the process details.
+    Affinity,
+    /// Showing what would exceed `App::what_if`.
+    WhatIf,
+    /// Picking agents in `App::host_picker`.
+    Hosts,
+}
+
+/// A signal waiting for a typed "yes" because it targets protected processes.
+pub(crate) struct PendingSignal {
+    pub(crate) signal: Signal,
+    pub(crate) verb: String,
+    pub(crate) targets: Vec<Pid>,
+    /// The protected targets, as "sshd (812)".
+    pub(crate) protected: Vec<String>,
+}
+
+pub struct App {
+    pub(crate) system: System,
+    pub(crate) selected_process: Option<usize>,
+    /// Name of the comparator the process table is sorted with.
+    pub(crate) sort_by: String,
+    /// Inverts the natural order of `sort_by`; set by pressing its key again.
+    pub(crate) sort_reversed: bool,
+    pub(crate) comparators: sort::Comparators,
+    /// Show the process table as a parent/child tree.
+    pub(crate) tree: bool,
+    /// Only processes whose name or command line contains this are listed.
+    pub(crate) filter: String,
+    /// Only processes owned by this user name or UID (`u`, `--user`).
+    pub(crate) user_filter: Option<String>,
+    /// Order the process table by the value of `group_env` (`G`).
+    pub(crate) group_by_env: bool,
+    /// Only processes whose `group_env` has this value (`=`).
+    pub(crate) group_filter: Option<String>,
+    pub(crate) input: Option<LineInput>,
+    /// The column setup screen while it is open.
+    pub(crate) column_editor: Option<columns::ColumnEditor>,
+    /// The agents found via mDNS, while picking them for the Cluster tab.
+    pub(crate) host_picker: Option<hosts::HostPicker>,
+    pub(crate) affinity_editor: Option<control::AffinityEditor>,
+    /// Hypothetical limit checked against the current sample (`L`).
+    pub(crate) what_if: Option<whatif::Limit>,
+    /// Columns chosen on the setup screen; they win over the config until rtop exits.
+    pub(crate) custom_columns: Option<Vec<Column>>,
+    /// PIDs whose subtrees are folded in tree mode.
+    pub(crate) collapsed: HashSet<Pid>,
+    /// Where the process table was last drawn, for mouse clicks.
+    pub(crate) process_table_area: tui::layout::Rect,
+    /// First process row shown; the table scrolls to keep the selection visible.
+    pub(crate) process_table_offset: usize,
+    /// Speeds up Up/Down while held.
+    pub(crate) key_repeat: KeyRepeat<Action>,
+    pub(crate) state: AppState,
+    pub(crate) tab: Tab,
+    pub(crate) details: Option<ProcessDetails>,
+    pub(crate) disks: DiskMonitor,
+    pub(crate) filesystems: Vec<FilesystemInfo>,
+    pub(crate) gpu: GpuMonitor,
+    /// What rtop reads per process itself; the `Sampler` reads it along
+    /// with each sample.
+    pub(crate) per_process: ProcessReads,
+    /// `None` on machines without a battery.
+    pub(crate) battery: Option<BatteryInfo>,
+    pub(crate) interrupts: InterruptMonitor,
+    pub(crate) show_per_core: bool,
+    pub(crate) show_cpu_history: bool,
+    pub(crate) show_memory_history: bool,
+    /// Total CPU usage, used memory and used swap over the last
+    /// `history_seconds`, in percent.
+    pub(crate) history: history::Histories,
+    /// Terminal size class as of the last frame; `None` before the first.
+    pub(crate) size_class: Option<SizeClass>,
+    pub(crate) connections: ConnectionSummary,
+    pub(crate) network: NetworkMonitor,
+    /// Read disks, network and the like on threads of their own; without
+    /// them, `update` reads everything itself.
+    pub(crate) collectors: Option<background::Collectors>,
+    pub(crate) race: CpuRace,
+    /// Set with `--share` to feed `rtop attach` viewers.
+    pub(crate) mirror: Option<MirrorServer>,
+    /// Set with `--record`.
+    pub(crate) recorder: Option<(PathBuf, record::Recorder)>,
+    /// Set with `--listen` to serve Prometheus metrics.
+    pub(crate) metrics: Option<metrics::MetricsServer>,
+    /// Set with `--advertise` to answer `rtop discover`.
+    pub(crate) advertiser: Option<mdns::Advertiser>,
+    /// Other hosts polled for the Cluster tab (`--agent`).
+    pub(crate) cluster: Option<cluster::Cluster>,
+    /// Panels of the Plugins tab, started by `cli::run_with`.
+    pub(crate) plugins: plugins::Plugins,
+    /// The health checks of `rtop check`, re-run every refresh, followed by
+    /// the `[[alerts]]` rules.
+    pub(crate) alerts: Vec<health::Finding>,
+    pub(crate) alert_rules: alerts::AlertRules,
+    /// Alerts that fired during the session, for the Alerts tab.
+    pub(crate) alert_log: alerts::AlertLog,
+    pub(crate) silences: silence::Silences,
+    /// Row highlighted in the Alerts tab.
+    pub(crate) selected_alert: usize,
+    /// The signal list of the kill menu.
+    pub(crate) signal_picker: control::SignalPicker,
+    pub(crate) pending_signal: Option<PendingSignal>,
+    /// Set by the lock key; keys only go to it until they unlock.
+    pub(crate) lock: Option<lock::Lock>,
+    /// Set with `--kiosk`: refuses anything that changes the system.
+    pub(crate) kiosk: bool,
+    /// Cycles through tabs; set with `--rotate` or `--kiosk`.
+    pub(crate) rotation: Option<rotate::Rotation>,
+    /// Recent CPU usage per process, recorded while the CPU Trend column is shown.
+    pub(crate) cpu_trends: sparkline::CpuTrends,
+    /// How long processes have been in uninterruptible sleep.
+    pub(crate) d_state: dstate::DStateTracker,
+    /// Whether refreshing is paused with `z`; navigation and signals still work.
+    pub(crate) paused: bool,
+    /// Processes tagged with Space; signals and renicing apply to all of them.
+    pub(crate) tagged: HashSet<Pid>,
+    /// Where alert captures go; set by the interactive UI when enabled.
+    pub(crate) capture_dir: Option<PathBuf>,
+    /// Captures being recorded, by alert rule.
+    pub(crate) captures: Vec<(String, std::thread::JoinHandle<std::io::Result<PathBuf>>)>,
+    /// Sends desktop notifications; set by the interactive UI when enabled.
+    pub(crate) notifier: Option<notify::Notifier>,
+    /// Set with `--alarm` or `alarm` in the config.
+    pub(crate) alarm: Option<alarm::Alarm>,
+    pub(crate) oom_kills: alarm::OomKills,
+    /// Set with `--watchdog` or `enabled` in [watchdog].
+    pub(crate) watchdog: Option<watchdog::Watchdog>,
+    /// Alert rules firing unsilenced as of the last refresh.
+    pub(crate) firing: HashSet<String>,
+    /// Typical usage by hour, loaded by the interactive UI when enabled.
+    pub(crate) baseline: Option<baseline::Baseline>,
+    pub(crate) baseline_saved: Instant,
+    /// Totals for the summary passed to the exit hook.
+    pub(crate) session: hooks::Session,
+    pub(crate) config: Config,
+    pub(crate) keymap: Keymap,
+    pub(crate) actions: ActionRunner,
+    pub(crate) theme: Theme,
+    /// Colors the terminal shows, detected or from `color_depth` in the config.
+    pub(crate) color_depth: ColorDepth,
+    pub(crate) redact: Redactor,
+    /// Outcome of the last one-off command such as a CSV export, shown in the tab bar for a few seconds.
+    pub(crate) status_message: Option<(Instant, Result<String, String>)>,
+    pub(crate) privilege: Privilege,
+}
+
+impl App {
+    pub fn new(config: Config) -> App {
+        let system = System::new_all();
+        let color_depth = config.color_depth.unwrap_or_else(ColorDepth::detect);
+        App {
+            filesystems: filesystems::collect(&system),
+            system,
+            selected_process: None,
+            sort_by: config.sort.name().to_string(),
+            sort_reversed: false,
+            comparators: sort::Comparators::builtin(),
+            tree: false,
+            filter: String::new(),
+            user_filter: None,
+            group_by_env: false,
+            group_filter: None,
+            input: None,
+            column_editor: None,
+            host_picker: None,
+            affinity_editor: None,
+            what_if: None,
+            custom_columns: None,
+            collapsed: HashSet::new(),
+            process_table_area: tui::layout::Rect::default(),
+            process_table_offset: 0,
+            key_repeat: KeyRepeat::new(),
+            state: AppState::Main,
+            tab: config.layout.tab,
+            details: None,
+            disks: DiskMonitor::new(),
+            gpu: GpuMonitor::new(),
+            per_process: ProcessReads::default(),
+            battery: battery::read(std::path::Path::new(battery::POWER_SUPPLY)),
+            interrupts: InterruptMonitor::new(),
+            show_per_core: config.layout.show_per_core,
+            show_cpu_history: config.layout.cpu_history,
+            show_memory_history: config.layout.memory_history,
+            history: history::Histories::new(Duration::from_secs(config.layout.history_seconds)),
+            size_class: None,
+            connections: ConnectionSummary::from_sockets(&sockets::read_host_tcp_sockets()),
+            network: NetworkMonitor::new(),
+            collectors: None,
+            race: CpuRace::new(),
+            mirror: None,
+            recorder: None,
+            metrics: None,
+            advertiser: None,
+            cluster: None,
+            plugins: plugins::Plugins::default(),
+            alerts: Vec::new(),
+            alert_rules: alerts::AlertRules::new(config.alerts.clone()),
+            alert_log: alerts::AlertLog::default(),
+            silences: silence::Silences::new(config.maintenance.clone()),
+            selected_alert: 0,
+            signal_picker: control::SignalPicker::new(),
+            pending_signal: None,
+            lock: None,
+            kiosk: false,
+            rotation: None,
+            cpu_trends: sparkline::CpuTrends::default(),
+            d_state: dstate::DStateTracker::default(),
+            paused: false,
+            tagged: HashSet::new(),
+            capture_dir: None,
+            captures: Vec::new(),
+            notifier: None,
+            alarm: None,
+            oom_kills: alarm::OomKills::default(),
+            watchdog: None,
+            firing: HashSet::new(),
+            baseline: None,
+            baseline_saved: Instant::now(),
+            session: hooks::Session::new(),
+            keymap: Keymap::new(&config.keys),
+            actions: ActionRunner::new(),
+            theme: Theme::resolve(&config.theme, &config.themes).unwrap_or_default().for_depth(color_depth),
+            color_depth,
+            redact: Redactor::new(false),
+            status_message: None,
+            config,
+            privilege: Privilege::current(),
+        }
+    }
+
+    /// Creates an `App` refreshed twice so that CPU usage figures are meaningful,
+    /// for headless commands that only take a single sample.
+    pub(crate) fn new_sampled(config: Config) -> App {
+        let mut app = App::new(config);
+        std::thread::sleep(System::MINIMUM_CPU_UPDATE_INTERVAL);
+        app.update();
+        app
+    }
+
+    pub fn update(&mut self) {
+        let selected = self.get_selected_process().map(|(pid, _)| pid);
+        self.system.refresh_all();
+        self.per_process.refresh(&self.system, self.wants());
+        self.collect(selected);
+    }
+
+    /// Takes `sample`, read by a `Sampler`, as the new sample like `update`,
+    /// and returns the `System` it replaces.
+    pub fn update_from(&mut self, sample: Sample) -> System {
+        let selected = self.get_selected_process().map(|(pid, _)| pid);
+        let previous = std::mem::replace(&mut self.system, sample.system);
+        self.per_process = sample.processes;
+        self.collect(selected);
+        previous
+    }
+
+    /// What the columns shown need read per process.
+    pub fn wants(&self) -> Wants {
+        let columns = self.columns();
+        Wants {
+            network: columns.iter().any(|column| column.is_network()),
+            statuses: columns.iter().any(|column| column.reads_status()),
+            wchans: columns.contains(&Column::Wchan),
+            anomaly_z: self.config.thresholds.anomaly_z,
+        }
+    }
+
+    /// The last sample.
+    pub fn system(&self) -> &System {
+        &self.system
+    }
+
+    pub fn tab(&self) -> Tab {
+        self.tab
+    }
+
+    pub fn set_tab(&mut self, tab: Tab) {
+        self.tab = tab;
+    }
+
+    /// Name of the comparator the process table is sorted with, such as `cpu`.
+    pub fn sort_by(&self) -> &str {
+        &self.sort_by
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Findings of the health checks and `[[alerts]]` rules as of the last sample.
+    pub fn alerts(&self) -> &[health::Finding] {
+        &self.alerts
+    }
+
+    /// Reads everything besides `system` and re-evaluates what depends on
+    /// the new sample, keeping `selected` selected.
+    pub(crate) fn collect(&mut self, selected: Option<Pid>) {
+        if self.columns().contains(&Column::CpuTrend) {
+            self.cpu_trends.record(self.system.processes().iter().map(|(&pid, process)| (pid, process.cpu_usage())));
+        }
+        if let Some(collectors) = &self.collectors {
+            self.merge(collectors.take());
+        } else {
+            self.disks.refresh();
+            self.filesystems = filesystems::collect(&self.system);
+            self.interrupts.refresh();
+            self.battery = battery::read(std::path::Path::new(battery::POWER_SUPPLY));
+            self.connections = ConnectionSummary::from_sockets(&sockets::read_host_tcp_sockets());
+            self.network.refresh(&self.system);
+        }
+        self.gpu.refresh();
+        if self.columns().iter().any(|column| column.is_gpu()) {
+            self.gpu.refresh_processes();
+        }
+        self.race.record(&self.system);
+        let now = Instant::now();
+        self.history.push(now, &self.system);
+        self.record_baseline();
+        self.tagged.retain(|pid| self.system.process(*pid).is_some());
+        self.d_state.record(self.system.processes().iter().map(|(&pid, process)| (pid, process.status())));
+        if let Some(cluster) = &mut self.cluster {
+            cluster.poll();
+        }
+        self.alerts = health::evaluate(&self.system);
+        if self.config.d_state_alert {
+            self.alerts.push(self.d_state_finding());
+        }
+        self.alerts.extend(self.oom_kills.finding());
+        let total_memory = self.system.total_memory().max(1) as f64;
+        let usage = self.system.processes().iter().map(|(&pid, process)| alerts::Usage {
+            pid,
+            name: self.redact.process(process.name()),
+            cpu: process.cpu_usage() as f64,
+            memory: process.memory() as f64 / total_memory * 100.0,
+        });
+        self.alerts.extend(self.alert_rules.evaluate(now, &self.system, usage));
+        let now = chrono::Local::now();
+        let firing: HashSet<String> = self
+            .alerts
+            .iter()
+            .filter(|alert| alert.severity != health::Severity::Ok && self.silences.status(&alert.check, now).is_none())
+            .map(|alert| alert.check.clone())
+            .collect();
+        self.session.record(self.system.global_cpu_info().cpu_usage() as f64, self.system.used_memory(), firing.iter().map(String::as_str));
+        self.capture_alerts(&firing);
+        self.notify_alerts(&firing);
+        self.alert_log.update(&self.alerts, &firing, now);
+        self.firing = firing;
+        if let Some(alarm) = &mut self.alarm {
+            alarm.update(Instant::now(), self.alerts.iter().filter(|alert| self.firing.contains(&alert.check)));
+        }
+        self.run_watchdog();
+        if let Some(details) = &mut self.details {
+            details.refresh();
+        }
+        self.reselect(selected);
+    }
+
+    /// Takes what the background collectors read since the last sample,
+    /// keeping the view settings of the Disks and Network tabs.
+    pub(crate) fn merge(&mut self, snapshot: background::Snapshot) {
+        if let Some(mut disks) = snapshot.disks {
+            disks.show_partitions = self.disks.show_partitions;
+            self.disks = disks;
+        }
+        if let Some(mut network) = snapshot.network {
+            network.hide_virtual = self.network.hide_virtual;
+            self.network = network;
+        }
+        if let Some(filesystems) = snapshot.filesystems {
+            self.filesystems = filesystems;
+        }
+        if let Some(interrupts) = snapshot.interrupts {
+            self.interrupts = interrupts;
+        }
+        if let Some(connections) = snapshot.connections {
+            self.connections = connections;
+        }
+        if let Some(battery) = snapshot.battery {
+            self.battery = battery;
+        }
+    }
+
+    /// Keeps `pid` selected after the rows changed or moved. If it has exited
+    /// (or is filtered out), the row index stays, so the next process down
+    /// takes its place.
+    pub(crate) fn reselect(&mut self, pid: Option<Pid>) {
+        let Some(pid) = pid else {
+            return;
+        };
+        let (position, len) = {
+            let processes = self.visible_processes();
+            (processes.iter().position(|(p, _)| *p == pid), processes.len())
+        };
+        self.selected_process = position.or_else(|| Some(self.selected_process?.min(len.checked_sub(1)?)));
+    }
+
+    /// Starts a capture for every rule in `firing` that was not firing on the
+    /// previous refresh, and reports the captures that finished.
+    pub(crate) fn capture_alerts(&mut self, firing: &HashSet<String>) {
+        let (finished, running): (Vec<_>, Vec<_>) = std::mem::take(&mut self.captures).into_iter().partition(|(_, capture)| capture.is_finished());
+        self.captures = running;
+        for (check, capture) in finished {
+            let result = match capture.join() {
+                Ok(Ok(path)) => Ok(format!("Captured alert {} to {}", check, path.display())),
+                Ok(Err(err)) => Err(format!("Cannot capture alert {}: {}", check, err)),
+                Err(_) => Err(format!("Cannot capture alert {}", check)),
+            };
+            self.status_message = Some((Instant::now(), result));
+        }
+        let Some(dir) = &self.capture_dir else {
+            return;
+        };
+        for alert in &self.alerts {
+            let started = firing.contains(&alert.check) && !self.firing.contains(&alert.check);
+            if started && !self.captures.iter().any(|(check, _)| *check == alert.check) {
+                let snapshot = export::Snapshot::capture(self, None);
+                let capture = capture::start(alert.clone(), snapshot, &self.config.capture, dir.clone(), self.redact.clone());
+                self.captures.push((alert.check.clone(), capture));
+            }
+        }
+    }
+
+    /// Sends a desktop notification for the rules in `firing` that were not
+    /// firing on the previous refresh.
+    pub(crate) fn notify_alerts(&mut self, firing: &HashSet<String>) {
+        let Some(notifier) = &mut self.notifier else {
+            return;
+        };
+        let started: Vec<&health::Finding> = self
+            .alerts
+            .iter()
+            .filter(|alert| firing.contains(&alert.check) && !self.firing.contains(&alert.check))
+            .collect();
+        if started.is_empty() {
+            return;
+        }
+        let host = self.system.host_name().map_or_else(|| "this host".to_string(), |name| self.redact.host(&name).into_owned());
+        let Some(notification) = notifier.notification(Instant::now(), &host, &started) else {
+            return;
+        };
+        if let Err(err) = notifier.send(&notification) {
+            self.status_message = Some((Instant::now(), Err(format!("Cannot send a desktop notification: {}", err))));
+        }
+    }
+
+    /// Signals the processes that break a watchdog rule and reports the
+    /// last action in the status line.
+    pub(crate) fn run_watchdog(&mut self) {
+        let Some(watchdog) = &mut self.watchdog else {
+            return;
+        };
+        let total_memory = self.system.total_memory().max(1) as f64;
+        let processes = self.system.processes().iter().map(|(&pid, process)| watchdog::Candidate {
+            pid,
+            name: process.name(),
+            command: process.cmd().join(" "),
+            cpu: process.cpu_usage() as f64,
+            memory: process.memory() as f64 / total_memory * 100.0,
+        });
+        let actions = watchdog.check(Instant::now(), processes, control::send_signal);
+        let logged = watchdog.append_log(&actions);
+        if let Some(action) = actions.last() {
+            let (signal, name) = (control::signal_name(action.signal), self.redact.process(&action.name));
+            let message = match &action.error {
+                None => Ok(format!("Watchdog sent {} to {} ({}): {}", signal, name, action.pid, action.rule)),
+                Some(err) => Err(format!("Watchdog could not send {} to {} ({}): {}", signal, name, action.pid, err)),
+            };
+            self.status_message = Some((Instant::now(), message));
+        }
+        if let Err(err) = logged {
+            self.status_message = Some((Instant::now(), Err(format!("Cannot write the watchdog log: {}", err))));
+        }
+    }
+
+    pub(crate) fn record_baseline(&mut self) {
+        let Some(baseline) = &mut self.baseline else {
+            return;
+        };
+        let memory = self.system.used_memory() as f64 / self.system.total_memory().max(1) as f64 * 100.0;
+        baseline.record(chrono::Local::now().hour(), self.system.global_cpu_info().cpu_usage() as f64, memory);
+        if self.baseline_saved.elapsed() >= BASELINE_SAVE_INTERVAL {
+            self.baseline_saved = Instant::now();
+            if let Err(err) = baseline.save() {
+                self.status_message = Some((Instant::now(), Err(format!("Cannot save the baseline: {}", err))));
+            }
+        }
+    }
+
+    /// How current CPU and memory usage compare to what is typical for this
+    /// hour of the day, like "+30% vs normal".
+    pub(crate) fn baseline_comparison(&self) -> (Option<String>, Option<String>) {
+        let Some(typical) = self.baseline.as_ref().and_then(|baseline| baseline.typical(chrono::Local::now().hour())) else {
+            return (None, None);
+        };
+        let memory = self.system.used_memory() as f64 / self.system.total_memory().max(1) as f64 * 100.0;
+        (
+            baseline::compare(self.system.global_cpu_info().cpu_usage() as f64, typical.cpu),
+            baseline::compare(memory, typical.memory),
+        )
+    }
+
+    /// The `d_state` alert rule: processes stuck in uninterruptible sleep.
+    pub(crate) fn d_state_finding(&self) -> health::Finding {
+        let stuck = self.d_state.stuck(self.config.thresholds.d_state_refreshes);
+        let names: Vec<String> = stuck
+            .iter()
+            .take(3)
+            .map(|(pid, _)| {
+                let name = self.system.process(*pid).map_or("?", |process| process.name());
+                format!("{} ({})", self.redact.process(name), pid)
+            })
+            .collect();
+        health::Finding {
+            check: "d_state".to_string(),
+            severity: if stuck.is_empty() { health::Severity::Ok } else { health::Severity::Warning },
+            message: match stuck.len() {
+                0 => "no process stuck in D state".to_string(),
+                n if n > names.len() => format!("{} stuck in D state: {}, ...", n, names.join(", ")),
+                n => format!("{} stuck in D state: {}", n, names.join(", ")),
+            },
+            silenced: None,
+        }
+    }
+
+    /// Whether `pid` has been in D state for the configured number of refreshes.
+    pub(crate) fn d_state_stuck(&self, pid: Pid) -> bool {
+        let limit = self.config.thresholds.d_state_refreshes;
+        limit > 0 && self.d_state.refreshes(pid).is_some_and(|refreshes| refreshes >= limit)
+    }
+
+    pub fn get_sorted_processes(&self) -> Vec<(Pid, &sysinfo::Process)> {
+        let mut processes: Vec<_> = self
+            .system
+            .processes()
+            .iter()
+            .filter(|(_, proc)| self.filter.is_empty() || input::matches(&self.filter, proc.name(), &proc.cmd().join(" ")))
+            .filter(|(_, proc)| self.user_filter.as_ref().is_none_or(|user| self.owned_by(proc, user)))
+            .filter(|(_, proc)| self.group_filter.as_ref().is_none_or(|group| self.env_group(proc) == Some(group)))
+            .map(|(&pid, proc)| (pid, proc))
+            .collect();
+        self.comparators.sort(self, &self.sort_by, self.sort_reversed, &mut processes);
+        if self.group_by_env {
+            envgroup::group(&mut processes, |(_, proc)| self.env_group(proc));
+        }
+        processes
+    }
+
+    /// The value of the `group_env` variable in the environment of `process`.
+    pub(crate) fn env_group<'a>(&self, process: &'a sysinfo::Process) -> Option<&'a str> {
+        envgroup::value(process.environ(), self.config.group_env.as_deref()?)
+    }
+
+    /// `G`: order the table by `group_env`, keeping the selected process.
+    pub(crate) fn toggle_env_grouping(&mut self) {
+        if self.config.group_env.is_none() {
+            let message = "Set group_env in the config file to group processes by an environment variable";
+            self.status_message = Some((Instant::now(), Err(message.to_string())));
+            return;
+        }
+        let selected = self.get_selected_process().map(|(pid, _)| pid);
+        self.group_by_env = !self.group_by_env;
+        self.reselect(selected);
+    }
+
+    /// `=`: show only the processes in the selected process's group, or
+    /// everything again.
+    pub(crate) fn toggle_group_filter(&mut self) {
+        if self.group_filter.take().is_none() {
+            let Some(name) = self.config.group_env.clone() else {
+                return self.toggle_env_grouping();
+            };
+            let Some((pid, process)) = self.get_selected_process() else {
+                return;
+            };
+            match self.env_group(process) {
+                Some(group) => self.group_filter = Some(group.to_string()),
+                None => {
+                    let message = format!("PID {} has no {} or its environment is not readable", pid, name);
+                    self.status_message = Some((Instant::now(), Err(message)));
+                }
+            }
+        }
+        self.selected_process = (!self.visible_processes().is_empty()).then_some(0);
+    }
+
+    /// Configured process table columns, minus those without data on this
+    /// machine (GPU columns without a supported GPU).
+    pub(crate) fn columns(&self) -> Vec<Column> {
+        let mut columns: Vec<Column> = self
+            .custom_columns
+            .as_ref()
+            .or_else(|| self.size_layout().and_then(|layout| layout.columns.as_ref()))
+            .unwrap_or(&self.config.columns)
+            .iter()
+            .copied()
+            .filter(|column| !column.is_gpu() || self.gpu.is_available())
+            .collect();
+        // Grouping shows the values it groups by.
+        if (self.group_by_env || self.group_filter.is_some()) && !columns.contains(&Column::Group) {
+            let after_name = columns.iter().position(|column| *column == Column::Name).map_or(0, |i| i + 1);
+            columns.insert(after_name, Column::Group);
+        }
+        columns
+    }
+
+    /// The `[layouts.*]` entry for the current terminal size, if configured.
+    pub(crate) fn size_layout(&self) -> Option<&SizeLayout> {
+        self.config.layouts.get(self.size_class?)
+    }
+
+    /// Whether the process table is striped at the current terminal size.
+    pub(crate) fn zebra(&self) -> bool {
+        self.size_layout().and_then(|layout| layout.zebra).unwrap_or(self.config.layout.zebra)
+    }
+
+    /// Picks the size class for a terminal of `size`; entering a class with
+    /// `show_per_core` set applies it.
+    pub(crate) fn resize(&mut self, size: tui::layout::Rect) {
+        let class = SizeClass::of(size.width, size.height);
+        if self.size_class == Some(class) {
+            return;
+        }
+        self.size_class = Some(class);
+        if let Some(show) = self.size_layout().and_then(|layout| layout.show_per_core) {
+            self.show_per_core = show;
+        }
+    }
+
+    /// The process tree in display order; siblings follow the current sort.
+    pub(crate) fn tree_rows(&self) -> Vec<TreeRow> {
+        let entries: Vec<_> = self.get_sorted_processes().iter().map(|(pid, proc)| (*pid, proc.parent())).collect();
+        tree::flatten(&entries, &self.collapsed)
+    }
+
+    /// The rows of the process table, in display order. Exports use this so
+    /// files contain exactly what is on screen.
+    pub(crate) fn visible_processes(&self) -> Vec<(Pid, &sysinfo::Process)> {
+        if !self.tree {
+            return self.get_sorted_processes();
+        }
+        self.tree_rows()
+            .iter()
+            .filter_map(|row| Some((row.pid, self.system.process(row.pid)?)))
+            .collect()
+    }
+
+    pub(crate) fn toggle_tree(&mut self) {
+        let selected = self.get_selected_process().map(|(pid, _)| pid);
+        self.tree = !self.tree;
+        self.reselect(selected);
+    }
+
+    /// Left/Right in tree mode, like a file manager: fold or unfold the
+    /// selected subtree, or move to the parent or the first child.
+    pub(crate) fn fold(&mut self, collapse: bool) {
+        let rows = self.tree_rows();
+        let Some((index, row)) = self.selected_process.and_then(|i| Some((i, *rows.get(i)?))) else {
+            return;
+        };
+        if collapse {
+            if row.has_children && !row.collapsed {
+                self.collapsed.insert(row.pid);
+            } else if let Some(parent) = self.system.process(row.pid).and_then(|p| p.parent()) {
+                if let Some(i) = rows.iter().position(|r| r.pid == parent) {
+                    self.selected_process = Some(i);
+                }
+            }
+        } else if row.collapsed {
+            self.collapsed.remove(&row.pid);
+        } else if row.has_children {
+            self.selected_process = Some(index + 1);
+        }
+    }
+
+    /// Selects the clicked process row; in tree mode a click on a fold marker
+    /// also folds or unfolds that subtree. A click on a column header sorts by
+    /// that column, like its sort key.
+    pub(crate) fn click(&mut self, column: u16, row: u16) {
+        let area = self.process_table_area;
+        // Rows start below the top border and the header line and end above the totals row.
+        let first_row = area.y + 2;
+        if self.state == AppState::Main && self.tab == Tab::Processes && row == area.y + 1 {
+            let clicked = process_table_columns(self, area).into_iter().rev().find(|(_, x)| column >= *x);
+            if let Some(name) = clicked.and_then(|(c, _)| self.comparators.of_column(c)).map(|comparator| comparator.name.clone()) {
+                self.sort(&name);
+            }
+            return;
+        }
+        if self.state != AppState::Main
+            || self.tab != Tab::Processes
+            || row < first_row
+            || row >= area.bottom().saturating_sub(2)
+            || column <= area.x
+            || column >= area.right().saturating_sub(1)
+        {
+            return;
+        }
+        let index = self.process_table_offset + (row - first_row) as usize;
+        if index >= self.visible_processes().len() {
+            return;
+        }
+        self.selected_process = Some(index);
+        if !self.tree {
+            return;
+        }
+        let Some(name_x) = process_table_columns(self, area).into_iter().find(|(c, _)| *c == Column::Name).map(|(_, x)| x) else {
+            return;
+        };
+        let Some(tree_row) = self.tree_rows().get(index).copied() else {
+            return;
+        };
+        let on_marker = column >= name_x && tree_row.marker_columns().contains(&(column - name_x));
+        if tree_row.has_children && on_marker && !self.collapsed.remove(&tree_row.pid) {
+            self.collapsed.insert(tree_row.pid);
+        }
+    }
+
+    /// Process rows that fit in the table as last drawn.
+    pub(crate) fn process_table_rows(&self) -> usize {
+        // Borders, header and totals row take four lines.
+        self.process_table_area.height.saturating_sub(4) as usize
+    }
+
+    /// Moves the selection by a few rows per wheel notch, so the table scrolls
+    /// along with it.
+    pub(crate) fn scroll(&mut self, down: bool) {
+        if self.state != AppState::Main || self.tab != Tab::Processes {
+            return;
+        }
+        let last = self.visible_processes().len().saturating_sub(1);
+        let selected = self.selected_process.unwrap_or(0);
+        self.selected_process = Some(if down {
+            (selected + WHEEL_ROWS).min(last)
+        } else {
+            selected.saturating_sub(WHEEL_ROWS)
+        });
+    }
+
+    pub(crate) fn get_selected_process(&self) -> Option<(Pid, &sysinfo::Process)> {
+        self.selected_process.and_then(|idx| self.visible_processes().get(idx).cloned())
+    }
+
+    /// The processes that signals and renicing apply to: the tagged ones, or else the selected one.
+    pub(crate) fn targets(&self) -> Vec<Pid> {
+        if self.tagged.is_empty() {
+            return self.get_selected_process().map(|(pid, _)| pid).into_iter().collect();
+        }
+        let mut targets: Vec<Pid> = self.tagged.iter().copied().collect();
+        targets.sort();
+        targets
+    }
+
+    /// Space: tags or untags the selected process and moves to the next one, like htop.
+    pub(crate) fn toggle_tag(&mut self) {
+        let Some((pid, _)) = self.get_selected_process() else {
+            self.perform(Action::Down);
+            return;
+        };
+        if !self.tagged.remove(&pid) {
+            self.tagged.insert(pid);
+        }
+        self.perform(Action::Down);
+    }
+
+    /// Sends `signal` to the targets, or asks for "yes" first if any of
+    /// them is protected.
+    pub(crate) fn send_signal(&mut self, signal: Signal, verb: &str) {
+        let targets = self.targets();
+        let protected: Vec<String> = targets
+            .iter()
+            .filter_map(|&pid| {
+                let name = self.system.process(pid)?.name();
+                control::is_protected(pid, name, &self.config.protected).then(|| format!("{} ({})", self.redact.process(name), pid))
+            })
+            .collect();
+        if protected.is_empty() {
+            self.signal_targets(&targets, signal, verb);
+        } else {
+            self.pending_signal = Some(PendingSignal { signal, verb: verb.to_string(), targets, protected });
+            self.open_prompt(Prompt::Confirm);
+        }
+    }
+
+    /// Enter on the confirmation prompt: sends the pending signal if `text` is "yes".
+    pub(crate) fn confirm_signal(&mut self, text: &str) {
+        let Some(pending) = self.pending_signal.take() else {
+            return;
+        };
+        if text.eq_ignore_ascii_case("yes") {
+            self.signal_targets(&pending.targets, pending.signal, &pending.verb);
+        } else {
+            self.status_message = Some((Instant::now(), Err(format!("Not sent: type yes to signal {}", pending.protected.join(", ")))));
+        }
+    }
+
+    /// Sends `signal` to `targets`, reporting the outcome as "`verb` PID (name)".
+    pub(crate) fn signal_targets(&mut self, targets: &[Pid], signal: Signal, verb: &str) {
+        let sent = targets.iter().filter(|pid| self.system.process(**pid).is_some_and(|process| process.kill_with(signal) == Some(true))).count();
+        let result = match targets {
+            [] => return,
+            [pid] => {
+                let name = self.system.process(*pid).map_or(String::new(), |process| self.redact.process(process.name()).into_owned());
+                if sent == 1 { Ok(format!("{} {} ({})", verb, pid, name)) } else { Err(format!("Could not signal {} ({})", pid, name)) }
+            }
+            _ if sent == targets.len() => Ok(format!("{} {} processes", verb, sent)),
+            _ => Err(format!("{} {} of {} processes", verb, sent, targets.len())),
+        };
+        self.status_message = Some((Instant::now(), result));
+    }
+
+    /// Changes the nice value of the targets by `step`.
+    pub(crate) fn renice(&mut self, step: i32) {
+        let targets = self.targets();
+        let results: Vec<Result<String, String>> = targets
+            .iter()
+            .map(|&pid| {
+                control::nice(pid)
+                    .and_then(|nice| Ok((nice, control::set_nice(pid, nice + step)?)))
+                    .map(|(old, new)| format!("PID {}: nice {} -> {}", pid, old, new))
+                    .map_err(|err| match err.kind() {
+                        std::io::ErrorKind::PermissionDenied if step < 0 => {
+                            format!("Cannot renice PID {}: lowering nice needs root", pid)
+                        }
+                        _ => format!("Cannot renice PID {}: {}", pid, err),
+                    })
+            })
+            .collect();
+        let failures: Vec<&String> = results.iter().filter_map(|result| result.as_ref().err()).collect();
+        let result = match (results.len(), failures.first()) {
+            (0, _) => return,
+            (1, _) => results[0].clone(),
+            (count, None) => Ok(format!("Reniced {} processes by {:+}", count, step)),
+            (count, Some(first)) => Err(format!("{} ({} of {} failed)", first, failures.len(), count)),
+        };
+        self.status_message = Some((Instant::now(), result));
+    }
+
+    pub(crate) fn open_details(&mut self) {
+        if let Some((pid, _)) = self.get_selected_process() {
+            self.details = Some(ProcessDetails::collect(pid));
+            self.state = AppState::Details;
+        }
+    }
+
+    pub(crate) fn close_details(&mut self) {
+        self.details = None;
+    }
+
+    /// Runs `action` if it applies to the current view and returns whether it did,
+    /// so a key bound to several actions triggers the first one that fits.
+    pub(crate) fn perform(&mut self, action: Action) -> bool {
+        let main = self.state == AppState::Main;
+        match action {
+            Action::Quit => return false,
+            _ if self.kiosk && !kiosk::allows(action) => self.refuse_in_kiosk(),
+            Action::Open if self.kiosk && self.state == AppState::Details && self.details.as_ref().is_some_and(|d| d.tab == DetailsTab::Affinity) => {
+                self.refuse_in_kiosk()
+            }
+            Action::Down if self.state == AppState::Details => self.select_child(true),
+            Action::Up if self.state == AppState::Details => self.select_child(false),
+            Action::Open if self.state == AppState::Details && self.details.as_ref().is_some_and(|d| d.tab == DetailsTab::Affinity) => {
+                self.open_affinity_editor()
+            }
+            Action::Open if self.state == AppState::Details => self.open_child_details(),
+            Action::Parent if self.state == AppState::Details => self.open_parent_details(),
+            Action::NextTab if self.state == AppState::Details => {
+                if let Some(details) = &mut self.details {
+                    details.tab = details.tab.next();
+                }
+            }
+            Action::Down | Action::Up | Action::PageDown | Action::PageUp | Action::First | Action::Last
+                if self.state == AppState::ProcessMenu =>
+            {
+                let count = self.signal_picker.entries.len() as isize;
+                self.signal_picker.select(match action {
+                    Action::Down => 1,
+                    Action::Up => -1,
+                    Action::PageDown => SIGNAL_PAGE,
+                    Action::PageUp => -SIGNAL_PAGE,
+                    Action::First => -count,
+                    _ => count,
+                });
+            }
+            Action::Open if self.state == AppState::ProcessMenu => {
+                self.state = AppState::Main;
+                if let Some(entry) = self.signal_picker.entry() {
+                    self.send_signal(entry.signal, &format!("Sent {} to", entry.name));
+                }
+            }
+            Action::Down | Action::Up if main && self.tab == Tab::Alerts => {
+                let last = self.alerts.len().saturating_sub(1);
+                self.selected_alert =
+                    if action == Action::Down { (self.selected_alert + 1).min(last) } else { self.selected_alert.saturating_sub(1) };
+            }
+            Action::Silence if main && self.tab == Tab::Alerts => self.toggle_silence(),
+            Action::Down => {
+                let step = self.key_repeat.step(action, Instant::now());
+                if self.selected_process.is_none() {
+                    self.selected_process = Some(0);
+                } else {
+                    let process_count = self.visible_processes().len();
+                    self.selected_process = self.selected_process.map(|i| (i + step).min(process_count.saturating_sub(1)));
+                }
+            }
+            Action::Up => {
+                let step = self.key_repeat.step(action, Instant::now());
+                self.selected_process = self.selected_process.map(|i| i.saturating_sub(step));
+            }
+            Action::PageDown | Action::PageUp | Action::First | Action::Last if main && self.tab == Tab::Processes => {
+                let last = self.visible_processes().len().saturating_sub(1);
+                let page = self.process_table_rows().max(1);
+                let selected = self.selected_process.unwrap_or(0);
+                self.selected_process = Some(match action {
+                    Action::PageDown => (selected + page).min(last),
+                    Action::PageUp => selected.saturating_sub(page),
+                    Action::First => 0,
+                    _ => last,
+                });
+            }
+            Action::SortCpu => self.sort(SortBy::Cpu.name()),
+            Action::SortMemory => self.sort(SortBy::Memory.name()),
+            Action::SortName => self.sort(SortBy::Name.name()),
+            Action::SortPid => self.sort(SortBy::Pid.name()),
+            Action::SortDiskRead => self.sort(SortBy::DiskRead.name()),
+            Action::SortDiskWrite => self.sort(SortBy::DiskWrite.name()),
+            Action::TogglePerCore if main => self.show_per_core = !self.show_per_core,
+            Action::ToggleCpuHistory if main => self.show_cpu_history = !self.show_cpu_history,
+            Action::ToggleMemoryHistory if main => self.show_memory_history = !self.show_memory_history,
+            Action::NextTab if main => self.tab = self.tab.next(),
+            Action::PreviousTab if main => self.tab = self.tab.previous(),
+            Action::TogglePartitions if main && self.tab == Tab::Disks => {
+                self.disks.show_partitions = !self.disks.show_partitions;
+            }
+            Action::ToggleVirtualInterfaces if main && self.tab == Tab::Network => {
+                self.network.hide_virtual = !self.network.hide_virtual;
+            }
+            Action::KillMenu if main && self.tab == Tab::Processes => {
+                self.signal_picker = control::SignalPicker::new();
+                self.state = AppState::ProcessMenu;
+            }
+            Action::Filter if main && self.tab == Tab::Processes => self.open_prompt(Prompt::Filter),
+            Action::FilterUser if main && self.tab == Tab::Processes => self.open_prompt(Prompt::User),
+            Action::JumpToPid if main && self.tab == Tab::Processes => self.open_prompt(Prompt::JumpToPid),
+            Action::WhatIf if main && self.tab == Tab::Processes => self.open_prompt(Prompt::Limit),
+            Action::ColumnSetup if main && self.tab == Tab::Processes => {
+                let available = Column::ALL.into_iter().filter(|column| !column.is_gpu() || self.gpu.is_available());
+                self.column_editor = Some(columns::ColumnEditor::new(&self.columns(), available));
+                self.state = AppState::Columns;
+            }
+            Action::PickAgents if main && self.tab == Tab::Cluster => {
+                let polled = self.cluster.iter().flat_map(|cluster| cluster.hosts.iter().map(|host| host.url.clone())).collect();
+                self.host_picker = Some(hosts::HostPicker::start(polled));
+                self.state = AppState::Hosts;
+            }
+            Action::Close if main && !self.filter.is_empty() => self.set_filter(String::new()),
+            Action::Close if main && self.user_filter.is_some() => self.set_user_filter(None),
+            Action::Close if main && self.group_filter.is_some() => self.toggle_group_filter(),
+            Action::Tag if main && self.tab == Tab::Processes => self.toggle_tag(),
+            Action::UntagAll if main && self.tab == Tab::Processes => self.tagged.clear(),
+            Action::GroupEnv if main && self.tab == Tab::Processes => self.toggle_env_grouping(),
+            Action::NiceDown | Action::NiceUp if main && self.tab == Tab::Processes => {
+                self.renice(if action == Action::NiceDown { -1 } else { 1 });
+            }
+            Action::FilterGroup if main && self.tab == Tab::Processes => self.toggle_group_filter(),
+            Action::ToggleTree if main && self.tab == Tab::Processes => self.toggle_tree(),
+            Action::Collapse if main && self.tab == Tab::Processes && self.tree => self.fold(true),
+            Action::Expand if main && self.tab == Tab::Processes && self.tree => self.fold(false),
+            Action::ExportCsv | Action::ExportJson if main && self.tab == Tab::Processes => {
+                let format = if action == Action::ExportCsv { TableFormat::Csv } else { TableFormat::Json };
+                let result = export::export_table(self, std::path::Path::new("."), format)
+                    .map(|(path, rows)| format!("Exported {} processes to {}", rows, path.display()))
+                    .map_err(|err| format!("Export failed: {}", err));
+                self.status_message = Some((Instant::now(), result));
+            }
+            Action::Open if main && self.tab == Tab::Processes => self.open_details(),
+            Action::Close => {
+                self.close_details();
+                self.what_if = None;
+                self.state = AppState::Main;
+            }
+            Action::SignalInterrupt | Action::SignalQuit | Action::SignalTerm | Action::SignalKill
+                if self.state == AppState::ProcessMenu =>
+            {
+                let (signal, verb) = match action {
+                    Action::SignalInterrupt => (Signal::Interrupt, "Sent SIGINT to"),
+                    Action::SignalQuit => (Signal::Quit, "Sent SIGQUIT to"),
+                    Action::SignalTerm => (Signal::Term, "Sent SIGTERM to"),
+                    _ => (Signal::Kill, "Sent SIGKILL to"),
+                };
+                self.state = AppState::Main;
+                self.send_signal(signal, verb);
+            }
+            Action::SignalStop | Action::SignalContinue if self.state == AppState::ProcessMenu => {
+                self.state = AppState::Main;
+                match action {
+                    Action::SignalStop => self.send_signal(Signal::Stop, "Stopped"),
+                    _ => self.send_signal(Signal::Continue, "Resumed"),
+                }
+            }
+            Action::Pause if main || self.state == AppState::Details => self.paused = !self.paused,
+            Action::Lock if main || self.state == AppState::Details => {
+                self.close_details();
+                self.state = AppState::Main;
+                self.lock = Some(lock::Lock::new(self.config.unlock_sequence.clone()));
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    pub(crate) fn refuse_in_kiosk(&mut self) {
+        self.status_message = Some((Instant::now(), Err("Not available in kiosk mode".to_string())));
+    }
+
+    /// Starts cycling through the tabs of `[rotate]` every `interval`;
+    /// without a list, through all of them but Cluster without agents and
+    /// Plugins without plugins.
+    pub(crate) fn start_rotation(&mut self, interval: Duration) {
+        let config = &self.config.rotate;
+        let tabs = if config.tabs.is_empty() {
+            Tab::ALL.into_iter().filter(|tab| (*tab != Tab::Cluster || self.cluster.as_ref().is_some_and(|cluster| !cluster.hosts.is_empty())) && (*tab != Tab::Plugins || !self.plugins.is_empty())).collect()
+        } else {
+            config.tabs.clone()
+        };
+        self.rotation = Some(rotate::Rotation::new(tabs, interval, Duration::from_secs(config.idle_seconds)));
+    }
+
+    /// Sorts with the comparator `name`, or inverts the order if it is
+    /// already the sort.
+    pub(crate) fn sort(&mut self, name: &str) {
+        let selected = self.get_selected_process().map(|(pid, _)| pid);
+        if self.sort_by == name {
+            self.sort_reversed = !self.sort_reversed;
+        } else {
+            self.sort_by = name.to_string();
+            self.sort_reversed = false;
+        }
+        self.reselect(selected);
+    }
+
+    pub(crate) fn open_prompt(&mut self, prompt: Prompt) {
+        let text = match prompt {
+            Prompt::Filter => self.filter.clone(),
+            Prompt::User => self.user_filter.clone().unwrap_or_default(),
+            Prompt::JumpToPid | Prompt::Limit | Prompt::Confirm => String::new(),
+        };
+        self.input = Some(LineInput { prompt, text, original: self.filter.clone() });
+        self.state = AppState::Input;
+    }
+
+    /// Lists only processes whose name or command line contains `filter`.
+    pub fn set_filter(&mut self, filter: String) {
+        if filter != self.filter {
+            self.filter = filter;
+            self.selected_process = (!self.visible_processes().is_empty()).then_some(0);
+        }
+    }
+
+    /// Keys of the column setup screen. Enter applies the setup for this run
+    /// and shows the config line that keeps it; Esc discards it.
+    pub(crate) fn edit_columns(&mut self, key: &event::KeyEvent) {
+        let Some(editor) = &mut self.column_editor else {
+            self.state = AppState::Main;
+            return;
+        };
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Up | KeyCode::Down if shift => editor.move_selected(key.code == KeyCode::Down),
+            KeyCode::Up | KeyCode::Down => editor.select(key.code == KeyCode::Down),
+            KeyCode::Char('[') | KeyCode::Char(']') => editor.move_selected(key.code == KeyCode::Char(']')),
+            KeyCode::Char(' ') => editor.toggle(),
+            KeyCode::Enter => {
+                let line = editor.config_line();
+                self.custom_columns = Some(editor.columns());
+                self.status_message = Some((Instant::now(), Ok(format!("Add to the config file to keep: {}", line))));
+                self.column_editor = None;
+                self.state = AppState::Main;
+            }
+            KeyCode::Esc => {
+                self.column_editor = None;
+                self.state = AppState::Main;
+            }
+            _ => {}
+        }
+    }
+
+    /// Keys of the host picker. Enter starts and stops polling the agents
+    /// checked and unchecked; Esc leaves the Cluster tab as it was.
+    pub(crate) fn pick_hosts(&mut self, key: &event::KeyEvent) {
+        let Some(picker) = &mut self.host_picker else {
+            self.state = AppState::Main;
+            return;
+        };
+        match key.code {
+            KeyCode::Up | KeyCode::Down => picker.select(key.code == KeyCode::Down),
+            KeyCode::Char(' ') => picker.toggle(),
+            KeyCode::Char('r') => picker.rescan(),
+            KeyCode::Enter => {
+                let (add, remove) = picker.changes();
+                if let Some(cluster) = &mut self.cluster {
+                    remove.iter().for_each(|url| cluster.remove(url));
+                    add.into_iter().for_each(|url| cluster.add(url));
+                }
+                self.host_picker = None;
+                self.state = AppState::Main;
+            }
+            KeyCode::Esc => {
+                self.host_picker = None;
+                self.state = AppState::Main;
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn open_affinity_editor(&mut self) {
+        let Some(pid) = self.details.as_ref().map(|details| details.pid) else {
+            return;
+        };
+        match control::affinity(pid, self.system.cpus().len()) {
+            Ok(allowed) => {
+                self.affinity_editor = Some(control::AffinityEditor::new(pid, allowed));
+                self.state = AppState::Affinity;
+            }
+            Err(err) => self.status_message = Some((Instant::now(), Err(format!("Cannot read the affinity of PID {}: {}", pid, err)))),
+        }
+    }
+
+    /// Keys of the affinity editor: arrows move over the CPU grid, Space
+    /// allows or forbids a CPU, `a` allows all, Enter applies and Esc cancels.
+    pub(crate) fn edit_affinity(&mut self, key: &event::KeyEvent) {
+        let Some(editor) = &mut self.affinity_editor else {
+            self.state = AppState::Details;
+            return;
+        };
+        match key.code {
+            KeyCode::Left => editor.select(-1),
+            KeyCode::Right => editor.select(1),
+            KeyCode::Up => editor.select(-(AFFINITY_COLUMNS as isize)),
+            KeyCode::Down => editor.select(AFFINITY_COLUMNS as isize),
+            KeyCode::Char(' ') => editor.toggle(),
+            KeyCode::Char('a') => editor.allow_all(),
+            KeyCode::Enter => {
+                let (pid, list) = (editor.pid, control::cpu_list(&editor.allowed));
+                let result = control::set_affinity(pid, &editor.allowed)
+                    .map(|()| format!("PID {} now runs on CPUs {}", pid, list))
+                    .map_err(|err| format!("Cannot set the affinity of PID {}: {}", pid, err));
+                self.status_message = Some((Instant::now(), result));
+                self.affinity_editor = None;
+                self.state = AppState::Details;
+            }
+            KeyCode::Esc => {
+                self.affinity_editor = None;
+                self.state = AppState::Details;
+            }
+            _ => {}
+        }
+    }
+
+    /// What the session looked like, for the `on_exit` hook.
+    pub(crate) fn session_summary(&self) -> hooks::SessionSummary {
+        // The hook may forward it anywhere, so it is redacted like all output.
+        let top = self.race.ranking(5).into_iter().map(|entry| hooks::TopProcess { pid: entry.pid.as_u32(), name: self.redact.process(&entry.name).into_owned(), cpu_seconds: entry.cpu_seconds });
+        let host = self.system.host_name().map(|name| self.redact.host(&name).into_owned()).unwrap_or_default();
+        self.session.summary(host, self.system.total_memory(), top.collect(), chrono::Local::now())
+    }
+
+    /// `s` in the Alerts tab: silences the selected rule for `silence_minutes`,
+    /// or lifts its silence.
+    pub(crate) fn toggle_silence(&mut self) {
+        let Some(alert) = self.alerts.get(self.selected_alert) else {
+            return;
+        };
+        let minutes = self.config.silence_minutes;
+        let message = if self.silences.toggle(&alert.check, chrono::Duration::minutes(minutes as i64), chrono::Local::now()) {
+            format!("Silenced {} for {} minutes", alert.check, minutes)
+        } else {
+            format!("{} is no longer silenced", alert.check)
+        };
+        self.status_message = Some((Instant::now(), Ok(message)));
+    }
+
+    /// Anonymous resident memory where `/proc` has it, else all of RSS.
+    pub(crate) fn private_memory(&self, pid: Pid, process: &sysinfo::Process) -> u64 {
+        self.per_process.statuses.get(&pid).and_then(|status| status.rss_anon).unwrap_or(process.memory())
+    }
+
+    /// Whether `process` belongs to `user`, given as a name or a UID.
+    pub(crate) fn owned_by(&self, process: &sysinfo::Process, user: &str) -> bool {
+        let Some(uid) = process.user_id() else {
+            return false;
+        };
+        uid.to_string() == user || self.system.get_user_by_id(uid).is_some_and(|owner| owner.name() == user)
+    }
+
+    pub(crate) fn set_user_filter(&mut self, user: Option<String>) {
+        if user != self.user_filter {
+            self.user_filter = user;
+            self.selected_process = (!self.visible_processes().is_empty()).then_some(0);
+        }
+    }
+
+    /// The name of the user owning `process`, or its UID if unknown.
+    pub(crate) fn user_name(&self, process: &sysinfo::Process) -> String {
+        let Some(uid) = process.user_id() else {
+            return "-".to_string();
+        };
+        match self.system.get_user_by_id(uid) {
+            Some(user) => self.redact.user(user.name()).into_owned(),
+            None => uid.to_string(),
+        }
+    }
+
+    /// Edits the open prompt. The filter applies as it is typed; Enter keeps
+    /// it and Esc restores the previous one.
+    pub(crate) fn edit_input(&mut self, key: &event::KeyEvent) {
+        let Some(input) = &mut self.input else {
+            self.state = AppState::Main;
+            return;
+        };
+        let prompt = input.prompt;
+        match key.code {
+            KeyCode::Enter => {
+                let text = std::mem::take(&mut input.text);
+                self.input = None;
+                self.state = AppState::Main;
+                match prompt {
+                    Prompt::JumpToPid => self.jump_to_pid(&text),
+                    Prompt::Limit => match whatif::Limit::parse(&text) {
+                        Ok(limit) => {
+                            self.what_if = Some(limit);
+                            self.state = AppState::WhatIf;
+                        }
+                        Err(err) => self.status_message = Some((Instant::now(), Err(err))),
+                    },
+                    Prompt::User => self.set_user_filter(Some(text).filter(|user| !user.is_empty())),
+                    Prompt::Confirm => self.confirm_signal(&text),
+                    Prompt::Filter => {}
+                }
+                return;
+            }
+            KeyCode::Esc => {
+                let original = std::mem::take(&mut input.original);
+                self.input = None;
+                self.pending_signal = None;
+                self.state = AppState::Main;
+                self.set_filter(original);
+                return;
+            }
+            KeyCode::Backspace => {
+                input.text.pop();
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => input.text.clear(),
+            KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) && prompt.accepts(c) => {
+                input.text.push(c);
+            }
+            _ => return,
+        }
+        self.input_changed();
+    }
+
+    /// Appends bracketed-paste `text` to the open prompt.
+    pub(crate) fn paste(&mut self, text: &str) {
+        if let Some(input) = &mut self.input {
+            input.text.push_str(&input.prompt.paste(text));
+            self.input_changed();
+        }
+    }
+
+    pub(crate) fn input_changed(&mut self) {
+        if let Some(input) = &self.input {
+            if input.prompt == Prompt::Filter {
+                let filter = input.text.clone();
+                self.set_filter(filter);
+            }
+        }
+    }
+
+    pub(crate) fn jump_to_pid(&mut self, text: &str) {
+        let Ok(pid) = text.parse::<usize>() else {
+            return;
+        };
+        let pid = Pid::from(pid);
+        match self.visible_processes().iter().position(|(p, _)| *p == pid) {
+            Some(index) => self.selected_process = Some(index),
+            None => {
+                let message = if self.system.process(pid).is_some() {
+                    format!("PID {} is hidden by the filter or a folded subtree", pid)
+                } else {
+                    format!("No process with PID {}", pid)
+                };
+                self.status_message = Some((Instant::now(), Err(message)));
+            }
+        }
+    }
+
+    /// Starts the `[[actions]]` entry bound to `key` against the selected process.
+    pub(crate) fn run_user_action(&mut self, key: &event::KeyEvent) -> bool {
+        let pressed = KeyBinding::from_event(key);
+        let Some(action) = self.config.actions.iter().find(|a| a.key == pressed) else {
+            return false;
+        };
+        let target = match self.get_selected_process() {
+            Some((pid, process)) => actions::Target {
+                pid: Some(usize::from(pid) as u32),
+                name: process.name().to_string(),
+            },
+            None => actions::Target::default(),
+        };
+        self.actions.start(action, &target);
+        self.state = AppState::ActionOutput;
+        true
+    }
+
+    /// Direct children of `pid`, ordered by PID.
+    pub(crate) fn get_children(&self, pid: Pid) -> Vec<(Pid, &sysinfo::Process)> {
+        let mut children: Vec<_> = self
+            .system
+            .processes()
+            .iter()
+            .filter(|(_, proc)| proc.parent() == Some(pid))
+            .map(|(&pid, proc)| (pid, proc))
+            .collect();
+        children.sort_by_key(|c| c.0);
+        children
+    }
+
+    pub(crate) fn select_child(&mut self, forward: bool) {
+        let Some(pid) = self.details.as_ref().map(|d| d.pid) else {
+            return;
+        };
+        let count = self.get_children(pid).len();
+        if let Some(details) = &mut self.details {
+            details.selected_child = match (details.selected_child, forward) {
+                _ if count == 0 => None,
+                (None, _) => Some(0),
+                (Some(i), true) => Some((i + 1).min(count - 1)),
+                (Some(i), false) => Some(i.saturating_sub(1)),
+            };
+        }
+    }
+
+    pub(crate) fn open_child_details(&mut self) {
+        let Some(details) = &self.details else {
+            return;
+        };
+        let child = details
+            .selected_child
+            .and_then(|i| self.get_children(details.pid).get(i).map(|c| c.0));
+        if let Some(pid) = child {
+            let tab = details.tab;
+            let mut details = ProcessDetails::collect(pid);
+            details.tab = tab;
+            self.details = Some(details);
+        }
+    }
+
+    pub(crate) fn open_parent_details(&mut self) {
+        let parent = self
+            .details
+            .as_ref()
+            .and_then(|d| self.system.process(d.pid))
+            .and_then(|p| p.parent());
+        if let (Some(pid), Some(tab)) = (parent, self.details.as_ref().map(|d| d.tab)) {
+            let mut details = ProcessDetails::collect(pid);
+            details.tab = tab;
+            self.details = Some(details);
+        }
+    }
+}
+
+/// Process rows one mouse wheel notch moves the selection.
+const WHEEL_ROWS: usize = 3;
+
+/// CPUs per row of the affinity editor's grid.
+pub(crate) const AFFINITY_COLUMNS: usize = 8;
+
+/// Signals skipped by PageUp/PageDown in the kill menu.
+const SIGNAL_PAGE: isize = 10;
+
+/// How often the baseline is written to disk, besides on exit.
+const BASELINE_SAVE_INTERVAL: Duration = Duration::from_secs(300);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_click_fold_marker() {
+        // The test process with a child of its own is a subtree that always exists.
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let mut app = App::new(Config::default());
+        app.tree = true;
+        app.process_table_area = tui::layout::Rect::new(0, 0, 100, 50);
+        let own = Pid::from_u32(std::process::id());
+        let (index, row) = app.tree_rows().into_iter().enumerate().find(|(_, row)| row.pid == own).unwrap();
+        assert!(row.has_children);
+        let child_pid = Pid::from_u32(child.id());
+        assert!(app.tree_rows().iter().any(|row| row.pid == child_pid));
+        // Scrolled so the test process is the first row.
+        app.process_table_offset = index;
+        let name_x = process_table_columns(&app, app.process_table_area)[1].1;
+        let y = 2;
+
+        app.click(1, y);
+        assert_eq!(app.selected_process, Some(index));
+        assert!(app.collapsed.is_empty());
+        app.click(name_x + row.marker_columns().start, y);
+        assert!(app.collapsed.contains(&own));
+        assert!(!app.tree_rows().iter().any(|row| row.pid == child_pid));
+        app.click(name_x + row.marker_columns().start + 1, y);
+        assert!(app.collapsed.is_empty());
+        assert!(app.tree_rows().iter().any(|row| row.pid == child_pid));
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    #[test]
+    fn test_click_header_sorts() {
+        let mut app = App::new(Config::default());
+        app.process_table_area = tui::layout::Rect::new(0, 0, 100, 50);
+        let columns = process_table_columns(&app, app.process_table_area);
+        let (_, rss_x) = columns.iter().find(|(c, _)| *c == Column::Rss).copied().unwrap();
+        app.click(rss_x + 1, 1);
+        assert_eq!(app.sort_by, "memory");
+        assert!(!app.sort_reversed);
+        app.click(rss_x, 1);
+        assert!(app.sort_reversed);
+
+        app.scroll(true);
+        assert_eq!(app.selected_process, Some(WHEEL_ROWS.min(app.visible_processes().len() - 1)));
+        app.scroll(false);
+        assert_eq!(app.selected_process, Some(0));
+    }
+
+    #[test]
+    fn test_page_navigation() {
+        let mut app = App::new(Config::default());
+        app.process_table_area = tui::layout::Rect::new(0, 0, 100, 6);
+        let last = app.visible_processes().len() - 1;
+        app.perform(Action::PageDown);
+        assert_eq!(app.selected_process, Some(2.min(last)));
+        app.perform(Action::Last);
+        assert_eq!(app.selected_process, Some(last));
+        app.perform(Action::PageUp);
+        assert_eq!(app.selected_process, Some(last.saturating_sub(2)));
+        app.perform(Action::First);
+        assert_eq!(app.selected_process, Some(0));
+    }
+
+    #[test]
+    fn test_selection_follows_pid() {
+        let mut app = App::new(Config::default());
+        let pid = Pid::from_u32(std::process::id());
+        app.selected_process = app.visible_processes().iter().position(|(p, _)| *p == pid);
+        app.sort(SortBy::Name.name());
+        app.update();
+        app.sort(SortBy::Name.name());
+        assert_eq!(app.get_selected_process().map(|(p, _)| p), Some(pid));
+        // Once the process is gone, the selection stays on the same row.
+        app.selected_process = Some(1);
+        app.reselect(Some(Pid::from_u32(u32::MAX)));
+        assert_eq!(app.selected_process, Some(1));
+    }
+
+    #[test]
+    fn test_protected_signal_needs_yes() {
+        let mut app = App::new(Config::default());
+        let pid = Pid::from_u32(std::process::id());
+        app.selected_process = app.visible_processes().iter().position(|(p, _)| *p == pid);
+        let type_and_enter = |app: &mut App, text: &str| {
+            for c in text.chars() {
+                app.edit_input(&event::KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            }
+            app.edit_input(&event::KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        };
+        // rtop itself is protected; SIGCONT keeps the test harmless.
+        app.send_signal(Signal::Continue, "Resumed");
+        assert!(app.state == AppState::Input);
+        type_and_enter(&mut app, "y");
+        assert!(app.status_message.as_ref().is_some_and(|(_, result)| result.is_err()));
+        assert!(app.pending_signal.is_none());
+
+        app.send_signal(Signal::Continue, "Resumed");
+        type_and_enter(&mut app, "yes");
+        let expected = format!("Resumed {} (", pid);
+        assert!(app.status_message.as_ref().is_some_and(|(_, result)| result.as_ref().is_ok_and(|message| message.starts_with(&expected))));
+        assert!(app.state == AppState::Main);
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut app = App::new(Config::default());
+        assert!(app.perform(Action::Pause) && app.paused);
+        // In the kill menu, `z` is SIGSTOP.
+        app.state = AppState::ProcessMenu;
+        assert!(!app.perform(Action::Pause));
+        app.state = AppState::Main;
+        app.perform(Action::Pause);
+        assert!(!app.paused);
+    }
+
+    #[test]
+    fn test_tagging() {
+        let mut app = App::new(Config::default());
+        // The first Space only selects a row, like Down.
+        app.perform(Action::Tag);
+        let first = app.get_selected_process().unwrap().0;
+        assert!(app.targets() == vec![first] && app.tagged.is_empty());
+        app.perform(Action::Tag);
+        assert!(app.tagged.contains(&first));
+        assert_eq!(app.targets(), vec![first]);
+        app.perform(Action::UntagAll);
+        assert!(app.tagged.is_empty());
+    }
+
+    #[test]
+    fn test_sort_direction() {
+        let mut app = App::new(Config::default());
+        app.sort(SortBy::Pid.name());
+        let pids = |app: &App| app.get_sorted_processes().iter().map(|(pid, _)| *pid).collect::<Vec<_>>();
+        let ascending = pids(&app);
+        assert!(ascending.windows(2).all(|pair| pair[0] < pair[1]));
+        app.sort(SortBy::Pid.name());
+        assert!(app.sort_reversed);
+        assert_eq!(pids(&app), ascending.iter().rev().copied().collect::<Vec<_>>());
+        // Another column starts in its natural order again.
+        app.sort(SortBy::Cpu.name());
+        assert!(!app.sort_reversed);
+    }
+
+    #[test]
+    fn test_session_summary_redacted() {
+        let mut app = App::new(Config::default());
+        app.redact = Redactor::new(true);
+        app.race.insert(42, "secret-job", 3.0);
+        let summary = app.session_summary();
+        assert_eq!(summary.top_processes[0].pid, 42);
+        assert!(summary.top_processes[0].name.starts_with("proc-"));
+        let json = serde_json::to_string(&summary).unwrap();
+        assert!(!json.contains("secret-job"));
+        if let Some(host) = app.system.host_name().filter(|host| !host.is_empty()) {
+            assert_ne!(summary.host, host);
+            assert!(!json.contains(&format!("\"{}\"", host)));
+        }
+    }
+
+    #[test]
+    fn test_app_update() {
+        let mut app = App::new(Config::default());
+        let initial_process_count = app.system.processes().len();
+        app.update();
+        assert!(app.system.processes().len() >= initial_process_count);
+    }
+}
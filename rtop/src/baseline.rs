@@ -0,0 +1,116 @@
+// src/baseline.rs
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Samples after which an hour's averages count as "normal".
+const MIN_SAMPLES: u64 = 1000;
+/// Weight of the averages at most, so that old days fade out and the
+/// baseline follows a changed workload.
+const MAX_WEIGHT: u64 = 50_000;
+
+/// Running averages for one hour of the day.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Bucket {
+    pub samples: u64,
+    /// Total CPU usage, in percent.
+    pub cpu: f64,
+    /// Used memory, in percent of the total.
+    pub memory: f64,
+}
+
+/// Typical CPU and memory usage of this host by hour of the day, kept in
+/// `$XDG_STATE_HOME/rtop/baseline-HOST.json` across sessions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Baseline {
+    hours: Vec<Bucket>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Baseline {
+    /// `baseline-HOST.json` in the state directory.
+    pub fn default_path(host: &str) -> Option<PathBuf> {
+        Some(Config::state_dir()?.join(format!("baseline-{}.json", host)))
+    }
+
+    /// Loads `path`, starting over if it is missing or unreadable.
+    pub fn load(path: &Path) -> Baseline {
+        let baseline = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Baseline>(&content).ok())
+            .filter(|baseline| baseline.hours.len() == 24);
+        Baseline {
+            path: path.to_path_buf(),
+            ..baseline.unwrap_or_else(|| Baseline {
+                hours: vec![Bucket::default(); 24],
+                path: PathBuf::new(),
+            })
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Written aside and renamed, so a crash never leaves half a file.
+        let temporary = self.path.with_extension("json.tmp");
+        std::fs::write(&temporary, serde_json::to_vec(self)?)?;
+        std::fs::rename(&temporary, &self.path)
+    }
+
+    pub fn record(&mut self, hour: u32, cpu: f64, memory: f64) {
+        let bucket = &mut self.hours[hour as usize % 24];
+        let weight = bucket.samples.min(MAX_WEIGHT) as f64;
+        bucket.cpu = (bucket.cpu * weight + cpu) / (weight + 1.0);
+        bucket.memory = (bucket.memory * weight + memory) / (weight + 1.0);
+        bucket.samples += 1;
+    }
+
+    /// The usual usage at `hour`, once enough of it has been seen.
+    pub fn typical(&self, hour: u32) -> Option<Bucket> {
+        Some(self.hours[hour as usize % 24]).filter(|bucket| bucket.samples >= MIN_SAMPLES)
+    }
+}
+
+/// `current` relative to `typical`, like "+30% vs normal" ("normal" within
+/// 10%), or `None` when the baseline is too small to compare against.
+pub fn compare(current: f64, typical: f64) -> Option<String> {
+    if typical < 1.0 {
+        return None;
+    }
+    let change = (current - typical) / typical * 100.0;
+    if change.abs() < 10.0 {
+        return Some("normal".to_string());
+    }
+    Some(format!("{:+.0}% vs normal", change))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_baseline_round_trip() {
+        let path = std::env::temp_dir().join(format!("rtop-baseline-{}.json", std::process::id()));
+        let mut baseline = Baseline::load(&path);
+        for _ in 0..MIN_SAMPLES {
+            baseline.record(14, 20.0, 40.0);
+        }
+        baseline.record(3, 90.0, 90.0);
+        baseline.save().unwrap();
+
+        let baseline = Baseline::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let typical = baseline.typical(14).unwrap();
+        assert!((typical.cpu - 20.0).abs() < 1e-9 && (typical.memory - 40.0).abs() < 1e-9);
+        // One sample is no baseline yet.
+        assert_eq!(baseline.typical(3), None);
+
+        assert_eq!(compare(52.0, 40.0).as_deref(), Some("+30% vs normal"));
+        assert_eq!(compare(20.0, 40.0).as_deref(), Some("-50% vs normal"));
+        assert_eq!(compare(42.0, 40.0).as_deref(), Some("normal"));
+        assert_eq!(compare(5.0, 0.5), None);
+    }
+}
@@ -0,0 +1,189 @@
+// src/capture.rs
+use crate::export::Snapshot;
+use crate::health::Finding;
+use crate::redact::Redactor;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use sysinfo::{CpuExt, PidExt, ProcessExt, System, SystemExt};
+
+/// Processes listed in each burst sample.
+const BUSIEST: usize = 10;
+
+/// The `[capture]` table: what to record when an alert starts firing.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CaptureConfig {
+    pub on_alert: bool,
+    /// Where capture files go [default: `$XDG_STATE_HOME/rtop/captures`].
+    pub dir: Option<PathBuf>,
+    /// Samples in the burst.
+    pub samples: usize,
+    /// Milliseconds between them; at least 200, or CPU usage is meaningless.
+    pub interval_ms: u64,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> CaptureConfig {
+        CaptureConfig {
+            on_alert: true,
+            dir: None,
+            samples: 25,
+            interval_ms: 200,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct BusyProcess {
+    pid: u32,
+    name: String,
+    cpu_usage: f32,
+    memory: u64,
+}
+
+/// One sample of the burst.
+#[derive(Debug, Serialize)]
+struct BurstSample {
+    /// Milliseconds since the alert fired.
+    offset_ms: u64,
+    cpu_usage: f32,
+    cores: Vec<f32>,
+    memory_used: u64,
+    swap_used: u64,
+    load_average: [f64; 3],
+    /// The busiest processes by CPU, for this sample.
+    processes: Vec<BusyProcess>,
+}
+
+/// What a capture file holds.
+#[derive(Debug, Serialize)]
+struct Capture<'a> {
+    alert: &'a Finding,
+    /// The process table as rtop saw it when the alert fired.
+    snapshot: &'a Snapshot,
+    samples: Vec<BurstSample>,
+}
+
+/// `rtop-capture-YYYYmmdd-HHMMSS-RULE.json`, with the rule name made safe
+/// for a file name (`disk:/var` becomes `disk-var`).
+fn file_name(check: &str) -> String {
+    let rule: String = check
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let rule: Vec<&str> = rule.split('-').filter(|part| !part.is_empty()).collect();
+    format!(
+        "rtop-capture-{}-{}.json",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        rule.join("-")
+    )
+}
+
+fn sample(system: &mut System, started: Instant, redact: &Redactor) -> BurstSample {
+    system.refresh_cpu();
+    system.refresh_memory();
+    system.refresh_processes();
+    let load = system.load_average();
+    let mut processes: Vec<_> = system.processes().values().collect();
+    processes.sort_by(|a, b| b.cpu_usage().total_cmp(&a.cpu_usage()));
+    BurstSample {
+        offset_ms: started.elapsed().as_millis() as u64,
+        cpu_usage: system.global_cpu_info().cpu_usage(),
+        cores: system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect(),
+        memory_used: system.used_memory(),
+        swap_used: system.used_swap(),
+        load_average: [load.one, load.five, load.fifteen],
+        processes: processes
+            .into_iter()
+            .take(BUSIEST)
+            .map(|process| BusyProcess {
+                pid: process.pid().as_u32(),
+                name: redact.process(process.name()).into_owned(),
+                cpu_usage: process.cpu_usage(),
+                memory: process.memory(),
+            })
+            .collect(),
+    }
+}
+
+/// Records the burst on a thread of its own, so the UI keeps running, and
+/// writes it with `alert` and `snapshot` to a new file in `dir`.
+pub fn start(
+    alert: Finding,
+    snapshot: Snapshot,
+    config: &CaptureConfig,
+    dir: PathBuf,
+    redact: Redactor,
+) -> JoinHandle<io::Result<PathBuf>> {
+    let interval =
+        Duration::from_millis(config.interval_ms).max(System::MINIMUM_CPU_UPDATE_INTERVAL);
+    let count = config.samples;
+    std::thread::spawn(move || {
+        let started = Instant::now();
+        let mut system = System::new_all();
+        let mut samples = Vec::with_capacity(count);
+        for _ in 0..count {
+            std::thread::sleep(interval);
+            samples.push(sample(&mut system, started, &redact));
+        }
+        let capture = Capture {
+            alert: &alert,
+            snapshot: &snapshot,
+            samples,
+        };
+        write(&dir, &file_name(&alert.check), &capture)
+    })
+}
+
+fn write(dir: &Path, name: &str, capture: &Capture) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(name);
+    std::fs::write(&path, serde_json::to_vec_pretty(capture)?)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::health::Severity;
+    use crate::App;
+
+    #[test]
+    fn test_capture_on_alert() {
+        let app = App::new(Config::default());
+        let alert = Finding {
+            check: "disk:/var/lib".to_string(),
+            severity: Severity::Critical,
+            message: "97.0% full".to_string(),
+            silenced: None,
+        };
+        let dir = std::env::temp_dir().join(format!("rtop-capture-{}", std::process::id()));
+        let config = CaptureConfig {
+            samples: 2,
+            ..CaptureConfig::default()
+        };
+        let snapshot = Snapshot::capture(&app, Some(3));
+        let path = start(alert, snapshot, &config, dir.clone(), Redactor::new(false))
+            .join()
+            .unwrap()
+            .unwrap();
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with("rtop-capture-") && name.ends_with("-disk-var-lib.json"));
+        let capture: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(capture["alert"]["check"], "disk:/var/lib");
+        assert_eq!(capture["samples"].as_array().unwrap().len(), 2);
+        assert!(capture["snapshot"]["processes"].as_array().unwrap().len() <= 3);
+    }
+}
@@ -0,0 +1,113 @@
+// src/cast.rs
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+/// Writes terminal output as an asciinema v2 cast: a JSON header line followed
+/// by one `[seconds, "o", data]` line per frame.
+pub struct Cast<S: Write> {
+    sink: S,
+    started: Instant,
+}
+
+impl<S: Write> Cast<S> {
+    pub fn new(mut sink: S, width: u16, height: u16) -> io::Result<Cast<S>> {
+        let header = serde_json::json!({
+            "version": 2,
+            "width": width,
+            "height": height,
+            "timestamp": chrono::Utc::now().timestamp(),
+            "title": "rtop",
+            "env": { "TERM": std::env::var("TERM").unwrap_or_default() },
+        });
+        writeln!(sink, "{}", header)?;
+        Ok(Cast {
+            sink,
+            started: Instant::now(),
+        })
+    }
+
+    pub fn frame(&mut self, output: &[u8]) -> io::Result<()> {
+        let event = (
+            self.started.elapsed().as_secs_f64(),
+            "o",
+            String::from_utf8_lossy(output),
+        );
+        writeln!(self.sink, "{}", serde_json::to_string(&event)?)?;
+        self.sink.flush()
+    }
+}
+
+/// Passes everything through to the terminal and, with `--cast`, also
+/// records each flushed frame.
+pub struct CastWriter<W: Write> {
+    inner: W,
+    cast: Option<Cast<BufWriter<File>>>,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> CastWriter<W> {
+    pub fn new(
+        inner: W,
+        path: Option<&Path>,
+        width: u16,
+        height: u16,
+    ) -> io::Result<CastWriter<W>> {
+        let cast = match path {
+            Some(path) => Some(Cast::new(
+                BufWriter::new(File::create(path)?),
+                width,
+                height,
+            )?),
+            None => None,
+        };
+        Ok(CastWriter {
+            inner,
+            cast,
+            pending: Vec::new(),
+        })
+    }
+}
+
+impl<W: Write> Write for CastWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if self.cast.is_some() {
+            self.pending.extend_from_slice(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        // The backend flushes once per drawn frame, so frames never split a character.
+        if let Some(cast) = &mut self.cast {
+            if !self.pending.is_empty() {
+                cast.frame(&self.pending)?;
+                self.pending.clear();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cast_format() {
+        let mut cast = Cast::new(Vec::new(), 80, 24).unwrap();
+        cast.frame("\x1b[1;1Hé".as_bytes()).unwrap();
+        let text = String::from_utf8(cast.sink).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let header: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(header["version"], 2);
+        assert_eq!(header["width"], 80);
+        let event: (f64, String, String) = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(event.1, "o");
+        assert_eq!(event.2, "\x1b[1;1Hé");
+    }
+}
@@ -0,0 +1,204 @@
+// src/cli.rs
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use std::io::stdout;
+use std::path::PathBuf;
+#[cfg(feature = "pagecache")]
+use crate::collectors::pagecache;
+use crate::{diff, export, health, mdns, metrics, mirror, replay, view};
+use crate::event::{load_config, run_agent, run_attach, run_replay, run_tui, run_view};
+use crate::app::SortBy;
+use crate::plugins::Plugins;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    tui: TuiArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the interactive terminal UI (the default when no subcommand is given)
+    Tui(Box<TuiArgs>),
+    /// Print a one-shot snapshot of system and process usage
+    Export(export::ExportArgs),
+    /// Run the health heuristics once and exit non-zero if problems are found
+    Check(health::CheckArgs),
+    /// Serve Prometheus metrics without the UI, for the Cluster tab of other instances
+    Agent(metrics::AgentArgs),
+    /// Play back a `--record` file in the UI instead of showing live data
+    Replay(replay::ReplayArgs),
+    /// Mirror an instance started with `--share`, read-only and without sampling
+    Attach(mirror::AttachArgs),
+    /// Browse a saved JSON snapshot or recording in the terminal, without sampling
+    View(view::ViewArgs),
+    /// Compare two snapshots or recordings per metric and per process
+    Diff(diff::DiffArgs),
+    /// List the files with the most bytes in the page cache
+    #[cfg(feature = "pagecache")]
+    Cache(pagecache::CacheArgs),
+    /// List rtop instances started with `--advertise` on the local network
+    Discover(mdns::DiscoverArgs),
+    /// Print a shell completion script to stdout
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Print the man page (roff) to stdout
+    Man,
+}
+
+// Options of the interactive UI, which a binary of its own can
+// `#[command(flatten)]` into its parser and pass to `event::run_tui`.
+#[derive(Args, Debug)]
+#[command(group = clap::ArgGroup::new("headless").args(["batch", "once"]))]
+pub struct TuiArgs {
+    /// Refresh rate in milliseconds [default: 250]
+    #[arg(short, long)]
+    pub(crate) refresh_rate: Option<u64>,
+
+    /// Column to sort the process list by [default: cpu]
+    #[arg(short, long, value_enum)]
+    pub(crate) sort: Option<SortBy>,
+
+    /// Config file to use instead of ~/.config/rtop/config.toml
+    #[arg(long, value_name = "FILE")]
+    pub(crate) config: Option<PathBuf>,
+
+    /// When started as root, switch to USER (default: the sudo user, else nobody) once the collectors have started
+    #[arg(long, value_name = "USER")]
+    pub(crate) drop_privileges: Option<Option<String>>,
+
+    /// Serve samples to `rtop attach` viewers on a Unix socket (default: $XDG_RUNTIME_DIR/rtop.sock)
+    #[arg(long, value_name = "SOCKET")]
+    pub(crate) share: Option<Option<PathBuf>>,
+
+    /// Print the active key bindings, including those from the config file, and exit
+    #[arg(long)]
+    pub(crate) dump_keymap: bool,
+
+    /// Color scheme: default, gruvbox, nord, solarized or a theme from the config file
+    #[arg(long, value_name = "NAME")]
+    pub(crate) theme: Option<String>,
+
+    /// Print plain-text snapshots to stdout every refresh instead of starting the UI
+    #[arg(short, long)]
+    pub(crate) batch: bool,
+
+    /// Print a single snapshot to stdout and exit
+    #[arg(long)]
+    pub(crate) once: bool,
+
+    /// With --batch, exit after this many snapshots
+    #[arg(short = 'n', long, requires = "batch")]
+    pub(crate) iterations: Option<u64>,
+
+    /// With --batch or --once, number of processes per snapshot [default: 20 for text, all for JSON]
+    #[arg(long, requires = "headless")]
+    pub(crate) limit: Option<usize>,
+
+    /// With --batch or --once, output format
+    #[arg(long, value_enum, default_value_t = export::Format::Text, requires = "headless")]
+    pub(crate) format: export::Format,
+
+    /// With --batch or --once, color text output using the thresholds and theme
+    #[arg(long, value_enum, default_value_t = export::ColorChoice::Auto, requires = "headless")]
+    pub(crate) color: export::ColorChoice,
+
+    /// Replace process names, command lines, paths and user names with hashes in the UI and all output
+    #[arg(long)]
+    pub(crate) redact: bool,
+
+    /// Show only the processes of USER (a name or UID), in the UI and in --batch/--once output
+    #[arg(short, long, value_name = "USER")]
+    pub(crate) user: Option<String>,
+
+    /// Send a desktop notification when an alert rule starts firing, like `on_alert` in [notify]
+    #[arg(long)]
+    pub(crate) notify: bool,
+
+    /// Flash a full-screen banner when an alert turns critical, until Enter is pressed, like `alarm` in the config
+    #[arg(long)]
+    pub(crate) alarm: bool,
+
+    /// Signal processes that break a [[watchdog.rules]] entry, like `enabled` in [watchdog]
+    #[arg(long)]
+    pub(crate) watchdog: bool,
+
+    /// Wallboard mode: no help bar, larger history charts, no signals or other changes to the system, and the tabs cycle every SECONDS [default: seconds in [rotate], 10]
+    #[arg(long, value_name = "SECONDS")]
+    pub(crate) kiosk: Option<Option<u64>>,
+
+    /// Cycle through the tabs of [rotate] on a timer, pausing while keys are pressed, like `enabled` in [rotate]
+    #[arg(long)]
+    pub(crate) rotate: bool,
+
+    /// Record the session as an asciinema cast that plays back without rtop
+    #[arg(long, value_name = "FILE")]
+    pub(crate) cast: Option<PathBuf>,
+
+    /// Append samples of the system and every process to FILE while the UI runs, for `rtop view`
+    #[arg(long, value_name = "FILE", conflicts_with = "headless")]
+    pub(crate) record: Option<PathBuf>,
+
+    /// With --record, seconds between recorded samples
+    #[arg(long, value_name = "SECONDS", default_value_t = 2.0, requires = "record")]
+    pub(crate) record_interval: f64,
+
+    /// Play back a --record FILE in the UI instead of showing live data, like `rtop replay`
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["headless", "record", "cast", "listen", "share", "kiosk"])]
+    pub(crate) replay: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub(crate) listen: metrics::ListenArgs,
+
+    /// Metrics URL of another rtop started with --listen, for the Cluster tab (repeatable)
+    #[arg(long, value_name = "URL")]
+    pub(crate) agent: Vec<String>,
+
+    /// Add the agents `rtop discover` finds to the Cluster tab
+    #[arg(long)]
+    pub(crate) discover_agents: bool,
+
+    /// Bearer token for --agent endpoints, read from FILE
+    #[arg(long, value_name = "FILE")]
+    pub(crate) agent_token_file: Option<PathBuf>,
+
+    /// Verify https:// agents with these PEM CA certificates instead of the system's (needs the `tls` feature)
+    #[arg(long, value_name = "FILE")]
+    pub(crate) agent_ca: Option<PathBuf>,
+}
+
+/// Parses the command line and runs the subcommand, or the UI.
+pub fn run() -> Result<(), Box<dyn std::error::Error>> {
+    run_with(Plugins::default())
+}
+
+/// `run`, with panels of a binary's own in the Plugins tab beside those of
+/// `[plugins]`.
+pub fn run_with(plugins: Plugins) -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Tui(args)) => run_tui(*args, plugins),
+        Some(Command::Export(args)) => Ok(export::run(args)?),
+        Some(Command::Check(args)) => std::process::exit(health::run(args)?),
+        Some(Command::Agent(args)) => run_agent(args),
+        Some(Command::Replay(args)) => run_replay(&args.file, load_config(args.config.as_deref())),
+        Some(Command::Attach(args)) => run_attach(args),
+        Some(Command::View(args)) => run_view(args),
+        Some(Command::Diff(args)) => Ok(diff::run(args)?),
+        #[cfg(feature = "pagecache")]
+        Some(Command::Cache(args)) => Ok(pagecache::run(args)?),
+        Some(Command::Discover(args)) => Ok(mdns::run(args)?),
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(shell, &mut Cli::command(), "rtop", &mut stdout());
+            Ok(())
+        }
+        Some(Command::Man) => Ok(clap_mangen::Man::new(Cli::command()).render(&mut stdout())?),
+        None => run_tui(cli.tui, plugins),
+    }
+}
@@ -0,0 +1,438 @@
+// src/cluster.rs
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(feature = "tls")]
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long one agent may take to connect and answer.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Where common systems keep their bundle of trusted CA certificates.
+#[cfg(feature = "tls")]
+const CA_BUNDLES: [&str; 4] = [
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/ssl/cert.pem",
+    "/usr/local/etc/openssl/cert.pem",
+];
+
+/// How HTTPS agents are verified; without the `tls` feature, or CA
+/// certificates, they cannot be polled.
+#[derive(Clone, Default)]
+pub struct Tls {
+    #[cfg(feature = "tls")]
+    config: Option<Arc<rustls::ClientConfig>>,
+}
+
+/// Trusts the PEM certificates in `ca` (such as the self-signed certificate
+/// of an agent), or else the system's CA bundle.
+#[cfg(feature = "tls")]
+pub fn tls(ca: Option<&Path>) -> io::Result<Tls> {
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::CertificateDer;
+
+    let path = match ca {
+        Some(path) => path,
+        None => match CA_BUNDLES.iter().map(Path::new).find(|path| path.exists()) {
+            Some(path) => path,
+            None => return Ok(Tls::default()),
+        },
+    };
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in CertificateDer::pem_file_iter(path)
+        .map_err(|err| io::Error::other(format!("{}: {}", path.display(), err)))?
+    {
+        let cert = cert.map_err(|err| io::Error::other(format!("{}: {}", path.display(), err)))?;
+        // Bundles can hold certificates webpki does not parse; skip those.
+        let _ = roots.add(cert);
+    }
+    let config = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(Tls {
+        config: Some(Arc::new(config)),
+    })
+}
+
+/// The heaviest process of one host, by CPU.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TopProcess {
+    pub pid: u32,
+    pub name: String,
+    pub cpu: f64,
+    pub memory: u64,
+}
+
+/// What the cluster view needs from one host.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HostSample {
+    pub cpu: f64,
+    pub memory_used: u64,
+    pub memory_total: u64,
+    pub top: Option<TopProcess>,
+}
+
+impl HostSample {
+    /// Reads the gauges `rtop --listen` exports (see `metrics::render`).
+    pub fn parse(text: &str) -> HostSample {
+        let mut sample = HostSample::default();
+        let mut processes: Vec<TopProcess> = Vec::new();
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let Some((series, value)) = line.rsplit_once(' ') else {
+                continue;
+            };
+            let Ok(value) = value.parse::<f64>() else {
+                continue;
+            };
+            let (name, labels) = series.split_once('{').unwrap_or((series, ""));
+            match name {
+                "rtop_cpu_usage_percent" => sample.cpu = value,
+                "rtop_memory_used_bytes" => sample.memory_used = value as u64,
+                "rtop_memory_total_bytes" => sample.memory_total = value as u64,
+                "rtop_process_cpu_usage_percent" | "rtop_process_resident_memory_bytes" => {
+                    let Some(pid) = label(labels, "pid").and_then(|pid| pid.parse().ok()) else {
+                        continue;
+                    };
+                    let index = match processes.iter().position(|p| p.pid == pid) {
+                        Some(index) => index,
+                        None => {
+                            processes.push(TopProcess {
+                                pid,
+                                name: label(labels, "name").unwrap_or_default(),
+                                cpu: 0.0,
+                                memory: 0,
+                            });
+                            processes.len() - 1
+                        }
+                    };
+                    if name == "rtop_process_cpu_usage_percent" {
+                        processes[index].cpu = value;
+                    } else {
+                        processes[index].memory = value as u64;
+                    }
+                }
+                _ => {}
+            }
+        }
+        sample.top = processes.into_iter().max_by(|a, b| a.cpu.total_cmp(&b.cpu));
+        sample
+    }
+}
+
+/// The unescaped value of label `key` in `a="1",b="x"}`.
+fn label(labels: &str, key: &str) -> Option<String> {
+    let start = labels.find(&format!("{}=\"", key))? + key.len() + 2;
+    let mut value = String::new();
+    let mut chars = labels[start..].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(value),
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                other => value.push(other),
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// One remote rtop instance and its latest sample.
+pub struct Host {
+    pub url: String,
+    pub sample: Option<Result<HostSample, String>>,
+    /// Set when the host is removed, to end its polling thread.
+    removed: Arc<AtomicBool>,
+}
+
+impl Host {
+    /// The `host:port` part of the URL.
+    pub fn name(&self) -> &str {
+        let rest = self
+            .url
+            .split_once("://")
+            .map_or(self.url.as_str(), |(_, rest)| rest);
+        rest.split('/').next().unwrap_or(rest)
+    }
+}
+
+/// Polls the metrics endpoints of other rtop instances (`--agent`) in the
+/// background so a slow host never stalls the UI.
+pub struct Cluster {
+    pub hosts: Vec<Host>,
+    token: Option<String>,
+    tls: Tls,
+    interval: Duration,
+    sender: Sender<(String, Result<HostSample, String>)>,
+    receiver: Receiver<(String, Result<HostSample, String>)>,
+}
+
+impl Cluster {
+    pub fn start(
+        urls: Vec<String>,
+        token: Option<String>,
+        tls: Tls,
+        interval: Duration,
+    ) -> Cluster {
+        let (sender, receiver) = mpsc::channel();
+        let mut cluster = Cluster {
+            hosts: Vec::new(),
+            token,
+            tls,
+            interval,
+            sender,
+            receiver,
+        };
+        for url in urls {
+            cluster.add(url);
+        }
+        cluster
+    }
+
+    /// Starts polling `url`, unless it already is.
+    pub fn add(&mut self, url: String) {
+        if self.hosts.iter().any(|host| host.url == url) {
+            return;
+        }
+        let removed = Arc::new(AtomicBool::new(false));
+        let (sender, token, interval) = (self.sender.clone(), self.token.clone(), self.interval);
+        let (thread_url, thread_removed, tls) = (url.clone(), removed.clone(), self.tls.clone());
+        std::thread::spawn(move || loop {
+            let sample = fetch(&thread_url, token.as_deref(), &tls)
+                .map(|text| HostSample::parse(&text))
+                .map_err(|err| err.to_string());
+            // The receiver is gone once rtop quits.
+            if thread_removed.load(Ordering::Relaxed)
+                || sender.send((thread_url.clone(), sample)).is_err()
+            {
+                return;
+            }
+            std::thread::sleep(interval);
+        });
+        self.hosts.push(Host {
+            url,
+            sample: None,
+            removed,
+        });
+    }
+
+    /// Stops polling `url`.
+    pub fn remove(&mut self, url: &str) {
+        self.hosts.retain(|host| {
+            let keep = host.url != url;
+            if !keep {
+                host.removed.store(true, Ordering::Relaxed);
+            }
+            keep
+        });
+    }
+
+    /// Stores the samples that arrived since the last call.
+    pub fn poll(&mut self) {
+        while let Ok((url, sample)) = self.receiver.try_recv() {
+            if let Some(host) = self.hosts.iter_mut().find(|host| host.url == url) {
+                host.sample = Some(sample);
+            }
+        }
+    }
+}
+
+/// GETs `url` over HTTP, or HTTPS with the `tls` feature, and returns the body.
+fn fetch(url: &str, token: Option<&str>, tls: &Tls) -> io::Result<String> {
+    let (scheme, rest) = url
+        .split_once("://")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a URL"))?;
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => rest.split_at(slash),
+        None => (rest, "/metrics"),
+    };
+    let addr = authority
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address"))?;
+    let connect = || -> io::Result<TcpStream> {
+        let stream = TcpStream::connect_timeout(&addr, FETCH_TIMEOUT)?;
+        stream.set_read_timeout(Some(FETCH_TIMEOUT))?;
+        stream.set_write_timeout(Some(FETCH_TIMEOUT))?;
+        Ok(stream)
+    };
+    let auth = token.map_or(String::new(), |token| {
+        format!("Authorization: Bearer {}\r\n", token)
+    });
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\n{}\r\n",
+        path, authority, auth
+    );
+    match scheme {
+        "http" => exchange(connect()?, &request),
+        #[cfg(feature = "tls")]
+        "https" => {
+            let config = tls.config.as_ref().ok_or_else(|| {
+                io::Error::other("no CA certificates to verify https:// agents; pass --agent-ca")
+            })?;
+            let host = authority
+                .rsplit_once(':')
+                .map_or(authority, |(host, _)| host)
+                .trim_matches(['[', ']']);
+            let name = rustls::pki_types::ServerName::try_from(host.to_string())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+            let connection =
+                rustls::ClientConnection::new(config.clone(), name).map_err(io::Error::other)?;
+            exchange(rustls::StreamOwned::new(connection, connect()?), &request)
+        }
+        #[cfg(not(feature = "tls"))]
+        "https" => {
+            let _ = tls;
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "https:// agents need rtop built with the tls feature",
+            ))
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "only http:// and https:// agents are supported",
+        )),
+    }
+}
+
+/// Sends `request` and returns the body of a 200 response.
+fn exchange(mut stream: impl Read + Write, request: &str) -> io::Result<String> {
+    stream.write_all(request.as_bytes())?;
+    let mut response = Vec::new();
+    match stream.read_to_end(&mut response) {
+        Ok(_) => {}
+        // Servers that close without a TLS close_notify.
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof && !response.is_empty() => {}
+        Err(err) => return Err(err),
+    }
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+    let status = head.lines().next().unwrap_or_default();
+    if status.split(' ').nth(1) != Some("200") {
+        return Err(io::Error::other(status.to_string()));
+    }
+    Ok(body.to_string())
+}
+
+/// Totals across this host and every agent that answered.
+#[derive(Debug, PartialEq)]
+pub struct Summary<'a> {
+    pub hosts: usize,
+    pub average_cpu: f64,
+    pub memory_used: u64,
+    pub memory_total: u64,
+    /// Name and sample of the host with the highest CPU usage.
+    pub busiest: Option<(&'a str, &'a HostSample)>,
+    /// The process using the most CPU on any host, with its host.
+    pub heaviest: Option<(&'a str, &'a TopProcess)>,
+}
+
+impl<'a> Summary<'a> {
+    pub fn of(samples: &[(&'a str, &'a HostSample)]) -> Summary<'a> {
+        let hosts = samples.len();
+        Summary {
+            hosts,
+            average_cpu: samples.iter().map(|(_, s)| s.cpu).sum::<f64>() / hosts.max(1) as f64,
+            memory_used: samples.iter().map(|(_, s)| s.memory_used).sum(),
+            memory_total: samples.iter().map(|(_, s)| s.memory_total).sum(),
+            busiest: samples
+                .iter()
+                .copied()
+                .max_by(|a, b| a.1.cpu.total_cmp(&b.1.cpu)),
+            heaviest: samples
+                .iter()
+                .filter_map(|&(host, sample)| Some((host, sample.top.as_ref()?)))
+                .max_by(|a, b| a.1.cpu.total_cmp(&b.1.cpu)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_metrics() {
+        let text = "# TYPE rtop_cpu_usage_percent gauge\n\
+            rtop_cpu_usage_percent 42.5\n\
+            rtop_memory_total_bytes 8000\n\
+            rtop_memory_used_bytes 2000\n\
+            rtop_process_cpu_usage_percent{pid=\"7\",name=\"a \\\"b\\\"\"} 30\n\
+            rtop_process_cpu_usage_percent{pid=\"9\",name=\"c\"} 5\n\
+            rtop_process_resident_memory_bytes{pid=\"7\",name=\"a \\\"b\\\"\"} 1024\n";
+        let sample = HostSample::parse(text);
+        assert_eq!(sample.cpu, 42.5);
+        assert_eq!((sample.memory_used, sample.memory_total), (2000, 8000));
+        assert_eq!(
+            sample.top,
+            Some(TopProcess {
+                pid: 7,
+                name: "a \"b\"".to_string(),
+                cpu: 30.0,
+                memory: 1024
+            })
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let process = |cpu| TopProcess {
+            pid: 1,
+            name: "x".to_string(),
+            cpu,
+            memory: 0,
+        };
+        let a = HostSample {
+            cpu: 10.0,
+            memory_used: 1,
+            memory_total: 4,
+            top: Some(process(90.0)),
+        };
+        let b = HostSample {
+            cpu: 50.0,
+            memory_used: 2,
+            memory_total: 4,
+            top: Some(process(20.0)),
+        };
+        let summary = Summary::of(&[("a", &a), ("b", &b)]);
+        assert_eq!(summary.average_cpu, 30.0);
+        assert_eq!((summary.memory_used, summary.memory_total), (3, 8));
+        assert_eq!(summary.busiest.map(|(host, _)| host), Some("b"));
+        assert_eq!(
+            summary.heaviest.map(|(host, p)| (host, p.cpu)),
+            Some(("a", 90.0))
+        );
+    }
+
+    #[test]
+    fn test_fetch_schemes() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/metrics", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            stream
+                .write_all(b"HTTP/1.0 200 OK\r\n\r\nrtop_cpu_usage_percent 1\n")
+                .unwrap();
+        });
+        let tls = Tls::default();
+        assert_eq!(
+            fetch(&url, Some("secret"), &tls).unwrap(),
+            "rtop_cpu_usage_percent 1\n"
+        );
+
+        let err = fetch("ftp://127.0.0.1:1/metrics", None, &tls).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = fetch("https://127.0.0.1:1/metrics", None, &tls).unwrap_err();
+        if cfg!(feature = "tls") {
+            assert!(err.to_string().contains("--agent-ca"));
+        } else {
+            assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        }
+    }
+}
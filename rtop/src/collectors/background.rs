@@ -0,0 +1,188 @@
+// src/collectors/background.rs
+use crate::collectors::battery::{self, BatteryInfo};
+use crate::collectors::disks::DiskMonitor;
+use crate::collectors::filesystems::{self, FilesystemInfo};
+use crate::collectors::interrupts::InterruptMonitor;
+use crate::collectors::network::NetworkMonitor;
+use crate::collectors::sockets::{self, ConnectionSummary};
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use sysinfo::{System, SystemExt};
+
+/// The `[collectors]` table: milliseconds between reads of each collector.
+/// Unset ones follow `refresh_rate`.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CollectorsConfig {
+    /// CPU usage, refreshed by the `Sampler`.
+    pub cpu: Option<u64>,
+    /// Memory and swap, refreshed by the `Sampler`.
+    pub memory: Option<u64>,
+    /// The process list, refreshed by the `Sampler` with what is read per
+    /// process.
+    pub processes: Option<u64>,
+    /// Disk I/O rates.
+    pub disks: Option<u64>,
+    /// Mounted filesystems; the `Sampler` refreshes the disk usage of its
+    /// samples at this interval too.
+    pub filesystems: Option<u64>,
+    pub interrupts: Option<u64>,
+    pub network: Option<u64>,
+    pub connections: Option<u64>,
+    /// Batteries, the only sensors rtop reads so far.
+    pub sensors: Option<u64>,
+}
+
+/// What the collectors read since the UI last took it; `None` where
+/// nothing new came in.
+#[derive(Default)]
+pub struct Snapshot {
+    pub disks: Option<DiskMonitor>,
+    pub filesystems: Option<Vec<FilesystemInfo>>,
+    pub interrupts: Option<InterruptMonitor>,
+    pub network: Option<NetworkMonitor>,
+    pub connections: Option<ConnectionSummary>,
+    pub battery: Option<Option<BatteryInfo>>,
+}
+
+/// Runs every collector on a thread of its own at its own interval, so a
+/// slow one (an NFS mount, thousands of sockets) delays nothing else.
+/// Each merges what it read into a shared `Snapshot`.
+pub struct Collectors {
+    snapshot: Arc<Mutex<Snapshot>>,
+}
+
+impl Collectors {
+    pub fn start(config: &CollectorsConfig, refresh_rate: Duration) -> Collectors {
+        let snapshot = Arc::new(Mutex::new(Snapshot::default()));
+        let interval = |ms| interval(ms, refresh_rate);
+        spawn(
+            &snapshot,
+            interval(config.disks),
+            DiskMonitor::new(),
+            |disks| {
+                disks.refresh();
+                disks.clone()
+            },
+            |snapshot, disks| snapshot.disks = Some(disks),
+        );
+        spawn(
+            &snapshot,
+            interval(config.filesystems),
+            System::new(),
+            |system| {
+                system.refresh_disks_list();
+                filesystems::collect(system)
+            },
+            |snapshot, filesystems| snapshot.filesystems = Some(filesystems),
+        );
+        spawn(
+            &snapshot,
+            interval(config.interrupts),
+            InterruptMonitor::new(),
+            |interrupts| {
+                interrupts.refresh();
+                interrupts.clone()
+            },
+            |snapshot, interrupts| snapshot.interrupts = Some(interrupts),
+        );
+        // A `System` of its own, so the byte counts sysinfo turns into
+        // deltas cover exactly the time since this collector last ran.
+        spawn(
+            &snapshot,
+            interval(config.network),
+            (NetworkMonitor::new(), System::new()),
+            |(network, system)| {
+                system.refresh_networks_list();
+                network.refresh(system);
+                network.clone()
+            },
+            |snapshot, network| snapshot.network = Some(network),
+        );
+        spawn(
+            &snapshot,
+            interval(config.connections),
+            (),
+            |_| ConnectionSummary::from_sockets(&sockets::read_host_tcp_sockets()),
+            |snapshot, connections| snapshot.connections = Some(connections),
+        );
+        spawn(
+            &snapshot,
+            interval(config.sensors),
+            (),
+            |_| battery::read(Path::new(battery::POWER_SUPPLY)),
+            |snapshot, battery| snapshot.battery = Some(battery),
+        );
+        Collectors { snapshot }
+    }
+
+    /// Takes what was read since the last call.
+    pub fn take(&self) -> Snapshot {
+        self.snapshot
+            .lock()
+            .map(|mut snapshot| std::mem::take(&mut *snapshot))
+            .unwrap_or_default()
+    }
+}
+
+/// Time between reads of a collector set to `ms`.
+pub(crate) fn interval(ms: Option<u64>, refresh_rate: Duration) -> Duration {
+    ms.map_or(refresh_rate, Duration::from_millis)
+        .max(Duration::from_millis(10))
+}
+
+/// Calls `read` with `state` every `interval` and merges the result into
+/// `snapshot` with `store`, until the `Collectors` are dropped.
+fn spawn<S, T>(
+    snapshot: &Arc<Mutex<Snapshot>>,
+    interval: Duration,
+    mut state: S,
+    mut read: impl FnMut(&mut S) -> T + Send + 'static,
+    store: fn(&mut Snapshot, T),
+) where
+    S: Send + 'static,
+    T: 'static,
+{
+    let snapshot = Arc::downgrade(snapshot);
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        // Read before locking, so the UI never waits on a slow read.
+        let value = read(&mut state);
+        let Some(snapshot) = snapshot.upgrade() else {
+            return;
+        };
+        if let Ok(mut snapshot) = snapshot.lock() {
+            store(&mut snapshot, value);
+        };
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_collectors_merge_into_snapshot() {
+        let config = CollectorsConfig {
+            disks: Some(10),
+            sensors: Some(60_000),
+            ..CollectorsConfig::default()
+        };
+        let collectors = Collectors::start(&config, Duration::from_millis(20));
+        let start = Instant::now();
+        let (mut disks, mut network) = (false, false);
+        while !(disks && network) {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            std::thread::sleep(Duration::from_millis(5));
+            let snapshot = collectors.take();
+            disks |= snapshot.disks.is_some();
+            network |= snapshot.network.is_some();
+            // Sensors are read once a minute here.
+            assert!(snapshot.battery.is_none());
+        }
+        assert!(collectors.take().battery.is_none());
+    }
+}
@@ -0,0 +1,170 @@
+// src/collectors/battery.rs
+use std::path::Path;
+use std::time::Duration;
+
+/// Where Linux lists batteries and AC adapters.
+pub const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatteryState {
+    Charging,
+    Discharging,
+    Full,
+    /// Plugged in but held below full, e.g. by a charge limit.
+    NotCharging,
+    Unknown,
+}
+
+impl BatteryState {
+    fn parse(status: &str) -> BatteryState {
+        match status {
+            "Charging" => BatteryState::Charging,
+            "Discharging" => BatteryState::Discharging,
+            "Full" => BatteryState::Full,
+            "Not charging" => BatteryState::NotCharging,
+            _ => BatteryState::Unknown,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BatteryState::Charging => "charging",
+            BatteryState::Discharging => "discharging",
+            BatteryState::Full => "full",
+            BatteryState::NotCharging => "plugged in",
+            BatteryState::Unknown => "unknown",
+        }
+    }
+}
+
+/// All batteries of the machine combined.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatteryInfo {
+    pub percent: f64,
+    pub state: BatteryState,
+    /// Until empty when discharging, until full when charging.
+    pub time_remaining: Option<Duration>,
+}
+
+/// One `BAT*` directory: remaining and full capacity and the current
+/// draw, in µWh/µW (`energy_*`) or µAh/µA (`charge_*`), whichever it has.
+struct Battery {
+    now: f64,
+    full: f64,
+    rate: f64,
+    status: String,
+}
+
+fn read_number(dir: &Path, file: &str) -> Option<f64> {
+    std::fs::read_to_string(dir.join(file))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn read_battery(dir: &Path) -> Option<Battery> {
+    let (now, full, rate) = match read_number(dir, "energy_now") {
+        Some(now) => (
+            now,
+            read_number(dir, "energy_full")?,
+            read_number(dir, "power_now"),
+        ),
+        None => (
+            read_number(dir, "charge_now")?,
+            read_number(dir, "charge_full")?,
+            read_number(dir, "current_now"),
+        ),
+    };
+    let status = std::fs::read_to_string(dir.join("status")).unwrap_or_default();
+    Some(Battery {
+        now,
+        full,
+        rate: rate.unwrap_or(0.0).abs(),
+        status: status.trim().to_string(),
+    })
+}
+
+/// Reads the batteries under `power_supply` (`/sys/class/power_supply` on
+/// Linux); `None` on machines without one.
+pub fn read(power_supply: &Path) -> Option<BatteryInfo> {
+    let mut batteries: Vec<Battery> = std::fs::read_dir(power_supply)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("BAT"))
+        .filter_map(|entry| read_battery(&entry.path()))
+        .collect();
+    batteries.retain(|battery| battery.full > 0.0);
+    if batteries.is_empty() {
+        return None;
+    }
+
+    let now: f64 = batteries.iter().map(|battery| battery.now).sum();
+    let full: f64 = batteries.iter().map(|battery| battery.full).sum();
+    let rate: f64 = batteries.iter().map(|battery| battery.rate).sum();
+    // With two batteries one may be idle while the other is charging or discharging.
+    let states: Vec<BatteryState> = batteries
+        .iter()
+        .map(|battery| BatteryState::parse(&battery.status))
+        .collect();
+    let state = [
+        BatteryState::Charging,
+        BatteryState::Discharging,
+        BatteryState::NotCharging,
+        BatteryState::Full,
+    ]
+    .into_iter()
+    .find(|state| states.contains(state))
+    .unwrap_or(BatteryState::Unknown);
+
+    let hours = match state {
+        BatteryState::Discharging if rate > 0.0 => Some(now / rate),
+        BatteryState::Charging if rate > 0.0 => Some((full - now).max(0.0) / rate),
+        _ => None,
+    };
+    Some(BatteryInfo {
+        percent: (now / full * 100.0).min(100.0),
+        state,
+        time_remaining: hours.map(|hours| Duration::from_secs_f64(hours * 3600.0)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_batteries() {
+        let dir = std::env::temp_dir().join(format!("rtop-test-battery-{}", std::process::id()));
+        let bat0 = dir.join("BAT0");
+        let bat1 = dir.join("BAT1");
+        let ac = dir.join("AC");
+        for path in [&bat0, &bat1, &ac] {
+            std::fs::create_dir_all(path).unwrap();
+        }
+        for (path, value) in [
+            (bat0.join("energy_now"), "30000000\n"),
+            (bat0.join("energy_full"), "40000000\n"),
+            (bat0.join("power_now"), "10000000\n"),
+            (bat0.join("status"), "Discharging\n"),
+            (bat1.join("energy_now"), "2000000\n"),
+            (bat1.join("energy_full"), "4000000\n"),
+            (bat1.join("status"), "Unknown\n"),
+            (ac.join("online"), "0\n"),
+        ] {
+            std::fs::write(path, value).unwrap();
+        }
+
+        let battery = read(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let battery = battery.unwrap();
+        assert_eq!(battery.state, BatteryState::Discharging);
+        assert!((battery.percent - 32.0 / 44.0 * 100.0).abs() < 1e-9);
+        assert_eq!(
+            battery.time_remaining,
+            Some(Duration::from_secs(3 * 3600 + 12 * 60))
+        );
+
+        assert_eq!(read(Path::new("/nonexistent")), None);
+    }
+}
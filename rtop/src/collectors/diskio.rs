@@ -0,0 +1,101 @@
+// src/collectors/diskio.rs
+use std::collections::HashMap;
+use std::time::Instant;
+use sysinfo::{Pid, ProcessExt, System, SystemExt};
+
+/// Bytes per second read from and written to storage by one process.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IoRate {
+    pub read: f64,
+    pub write: f64,
+}
+
+/// Per-process disk I/O rates from the change in `Process::disk_usage()`
+/// totals between two refreshes. Processes seen for the first time have no
+/// rate yet, so their lifetime totals never show up as a spike.
+#[derive(Clone)]
+pub struct ProcessIo {
+    totals: HashMap<Pid, (u64, u64)>,
+    rates: HashMap<Pid, IoRate>,
+    last: Option<Instant>,
+}
+
+impl Default for ProcessIo {
+    fn default() -> ProcessIo {
+        ProcessIo::new()
+    }
+}
+
+impl ProcessIo {
+    pub fn new() -> ProcessIo {
+        ProcessIo {
+            totals: HashMap::new(),
+            rates: HashMap::new(),
+            last: None,
+        }
+    }
+
+    pub fn refresh(&mut self, system: &System) {
+        let totals = system.processes().iter().map(|(&pid, process)| {
+            let usage = process.disk_usage();
+            (pid, (usage.total_read_bytes, usage.total_written_bytes))
+        });
+        self.record(totals, Instant::now());
+    }
+
+    fn record(&mut self, totals: impl Iterator<Item = (Pid, (u64, u64))>, now: Instant) {
+        let elapsed = self
+            .last
+            .map_or(0.0, |last| now.duration_since(last).as_secs_f64());
+        let totals: HashMap<Pid, (u64, u64)> = totals.collect();
+        self.rates = totals
+            .iter()
+            .filter(|_| elapsed > 0.0)
+            .filter_map(|(pid, &(read, written))| {
+                let &(previous_read, previous_written) = self.totals.get(pid)?;
+                Some((
+                    *pid,
+                    IoRate {
+                        read: read.saturating_sub(previous_read) as f64 / elapsed,
+                        write: written.saturating_sub(previous_written) as f64 / elapsed,
+                    },
+                ))
+            })
+            .collect();
+        self.totals = totals;
+        self.last = Some(now);
+    }
+
+    pub fn rate(&self, pid: Pid) -> IoRate {
+        self.rates.get(&pid).copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rates_from_deltas() {
+        let mut io = ProcessIo::new();
+        let start = Instant::now();
+        let (one, two) = (Pid::from(1), Pid::from(2));
+        io.record([(one, (1000, 500))].into_iter(), start);
+        assert_eq!(io.rate(one), IoRate::default());
+
+        io.record(
+            [(one, (5000, 2500)), (two, (1 << 30, 0))].into_iter(),
+            start + Duration::from_secs(2),
+        );
+        assert_eq!(
+            io.rate(one),
+            IoRate {
+                read: 2000.0,
+                write: 1000.0
+            }
+        );
+        // New process: its lifetime total is not a rate.
+        assert_eq!(io.rate(two), IoRate::default());
+    }
+}
@@ -0,0 +1,261 @@
+// src/collectors/disks.rs
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Bytes per sector as reported by `/proc/diskstats`, independent of the device's real sector size.
+const SECTOR_SIZE: u64 = 512;
+
+/// Raw counters for one line of `/proc/diskstats`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiskStat {
+    pub name: String,
+    pub sectors_read: u64,
+    pub sectors_written: u64,
+    pub in_flight: u64,
+    /// Milliseconds spent with at least one request in flight.
+    pub io_ticks: u64,
+}
+
+impl DiskStat {
+    fn is_idle(&self) -> bool {
+        self.sectors_read == 0 && self.sectors_written == 0 && self.in_flight == 0
+    }
+}
+
+pub fn parse_diskstats(content: &str) -> Vec<DiskStat> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let field = |i: usize| fields.get(i).and_then(|v| v.parse::<u64>().ok());
+            Some(DiskStat {
+                name: fields.get(2)?.to_string(),
+                sectors_read: field(5)?,
+                sectors_written: field(9)?,
+                in_flight: field(11)?,
+                io_ticks: field(12)?,
+            })
+        })
+        .collect()
+}
+
+/// Extracts the active scheduler from `queue/scheduler`, e.g. `none [mq-deadline] bfq`.
+pub fn parse_scheduler(content: &str) -> Option<String> {
+    let start = content.find('[')?;
+    let end = content[start..].find(']')? + start;
+    Some(content[start + 1..end].to_string())
+}
+
+/// What a device-mapper target is, derived from the prefix of its `dm/uuid`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapperKind {
+    Lvm,
+    Crypt,
+    Multipath,
+    Other,
+}
+
+impl MapperKind {
+    pub fn from_uuid(uuid: &str) -> MapperKind {
+        if uuid.starts_with("LVM-") {
+            MapperKind::Lvm
+        } else if uuid.starts_with("CRYPT-") {
+            MapperKind::Crypt
+        } else if uuid.starts_with("mpath-") {
+            MapperKind::Multipath
+        } else {
+            MapperKind::Other
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MapperKind::Lvm => "LVM",
+            MapperKind::Crypt => "LUKS",
+            MapperKind::Multipath => "multipath",
+            MapperKind::Other => "dm",
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_sys(name: &str, file: &str) -> Option<String> {
+    std::fs::read_to_string(format!("/sys/class/block/{}/{}", name, file))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// The device-mapper name (`vg0-root`, `luks-…`) for `dm-N` devices.
+#[cfg(target_os = "linux")]
+fn mapper_name(name: &str) -> Option<String> {
+    read_sys(name, "dm/name").filter(|n| !n.is_empty())
+}
+
+/// Human-readable name of a block device, falling back to the kernel name.
+#[cfg(target_os = "linux")]
+fn display_name(name: &str) -> String {
+    mapper_name(name).unwrap_or_else(|| name.to_string())
+}
+
+/// Describes the stack below a device-mapper device, e.g. `LVM on LUKS luks-root → sda2`.
+#[cfg(target_os = "linux")]
+fn describe_mapping(name: &str) -> Option<String> {
+    mapper_name(name)?;
+    let kind = MapperKind::from_uuid(&read_sys(name, "dm/uuid").unwrap_or_default());
+    let backing: Vec<String> = std::fs::read_dir(format!("/sys/class/block/{}/slaves", name))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| {
+                    let slave = e.file_name().to_string_lossy().into_owned();
+                    match describe_mapping(&slave) {
+                        Some(below) => format!("{} {} ({})", display_name(&slave), slave, below),
+                        None => slave,
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if backing.is_empty() {
+        Some(kind.label().to_string())
+    } else {
+        Some(format!("{} on {}", kind.label(), backing.join(", ")))
+    }
+}
+
+/// A block device or partition with rates computed over the last refresh interval.
+#[derive(Clone, Debug)]
+pub struct DiskInfo {
+    pub name: String,
+    /// Device-mapper name for `dm-N` devices, otherwise the kernel name.
+    pub label: String,
+    /// Mapping hierarchy for device-mapper devices.
+    pub mapping: Option<String>,
+    pub is_partition: bool,
+    pub scheduler: Option<String>,
+    /// Maximum number of requests the block layer queues (`queue/nr_requests`).
+    pub queue_depth: Option<u64>,
+    pub in_flight: u64,
+    /// Percentage of wall time the device was busy.
+    pub utilization: f64,
+    pub read_bytes_per_sec: f64,
+    pub write_bytes_per_sec: f64,
+}
+
+/// Samples `/proc/diskstats` and keeps the previous sample to turn counters into rates.
+#[derive(Clone)]
+pub struct DiskMonitor {
+    previous: HashMap<String, DiskStat>,
+    last_refresh: Instant,
+    /// Both whole devices and partitions; see `visible`.
+    pub devices: Vec<DiskInfo>,
+    pub show_partitions: bool,
+}
+
+impl Default for DiskMonitor {
+    fn default() -> DiskMonitor {
+        DiskMonitor::new()
+    }
+}
+
+impl DiskMonitor {
+    pub fn new() -> DiskMonitor {
+        let mut monitor = DiskMonitor {
+            previous: HashMap::new(),
+            last_refresh: Instant::now(),
+            devices: Vec::new(),
+            show_partitions: false,
+        };
+        monitor.refresh();
+        monitor
+    }
+
+    /// Whole devices, or partitions when `show_partitions` is set.
+    pub fn visible(&self) -> impl Iterator<Item = &DiskInfo> {
+        self.devices
+            .iter()
+            .filter(move |disk| disk.is_partition == self.show_partitions)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn refresh(&mut self) {
+        let Ok(content) = std::fs::read_to_string("/proc/diskstats") else {
+            return;
+        };
+        let elapsed_ms = (self.last_refresh.elapsed().as_secs_f64() * 1000.0).max(1.0);
+        let mut devices = Vec::new();
+        let mut current = HashMap::new();
+
+        for stat in parse_diskstats(&content) {
+            let class_dir = format!("/sys/class/block/{}", stat.name);
+            let is_partition = std::path::Path::new(&class_dir).join("partition").exists();
+            // A partition shares the request queue of its parent device, one level up.
+            let queue = if is_partition {
+                format!("{}/../queue", class_dir)
+            } else {
+                format!("{}/queue", class_dir)
+            };
+            if std::path::Path::new(&queue).exists() && !stat.is_idle() {
+                let previous = self.previous.get(&stat.name).unwrap_or(&stat);
+                let delta = |now: u64, before: u64| now.saturating_sub(before) as f64;
+                devices.push(DiskInfo {
+                    name: stat.name.clone(),
+                    label: display_name(&stat.name),
+                    mapping: describe_mapping(&stat.name),
+                    is_partition,
+                    scheduler: std::fs::read_to_string(format!("{}/scheduler", queue))
+                        .ok()
+                        .and_then(|s| parse_scheduler(&s)),
+                    queue_depth: std::fs::read_to_string(format!("{}/nr_requests", queue))
+                        .ok()
+                        .and_then(|s| s.trim().parse().ok()),
+                    in_flight: stat.in_flight,
+                    utilization: (delta(stat.io_ticks, previous.io_ticks) / elapsed_ms * 100.0)
+                        .min(100.0),
+                    read_bytes_per_sec: delta(stat.sectors_read, previous.sectors_read)
+                        * SECTOR_SIZE as f64
+                        / elapsed_ms
+                        * 1000.0,
+                    write_bytes_per_sec: delta(stat.sectors_written, previous.sectors_written)
+                        * SECTOR_SIZE as f64
+                        / elapsed_ms
+                        * 1000.0,
+                });
+            }
+            current.insert(stat.name.clone(), stat);
+        }
+
+        self.previous = current;
+        self.last_refresh = Instant::now();
+        self.devices = devices;
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn refresh(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_diskstats_and_scheduler() {
+        let content = " 254       0 vda 6416 4902 2099602 6166 2529 6912 1898312 7136 3 2484 13521 893 0 747880 216 54 1\n";
+        let stats = parse_diskstats(content);
+        assert_eq!(stats[0].name, "vda");
+        assert_eq!(stats[0].sectors_read, 2099602);
+        assert_eq!(stats[0].sectors_written, 1898312);
+        assert_eq!(stats[0].in_flight, 3);
+        assert_eq!(stats[0].io_ticks, 2484);
+        assert_eq!(parse_scheduler("none [mq-deadline] kyber bfq\n"), Some("mq-deadline".to_string()));
+        assert_eq!(parse_scheduler("none\n"), None);
+    }
+
+    #[test]
+    fn test_mapper_kind_from_uuid() {
+        assert_eq!(MapperKind::from_uuid("LVM-abcDEF"), MapperKind::Lvm);
+        assert_eq!(MapperKind::from_uuid("CRYPT-LUKS2-0123-luks-root"), MapperKind::Crypt);
+        assert_eq!(MapperKind::from_uuid("mpath-3600"), MapperKind::Multipath);
+        assert_eq!(MapperKind::from_uuid(""), MapperKind::Other);
+    }
+}
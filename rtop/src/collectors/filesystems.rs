@@ -0,0 +1,203 @@
+// src/collectors/filesystems.rs
+use std::collections::HashSet;
+use sysinfo::{DiskExt, System, SystemExt};
+
+/// Where the data of a filesystem actually lives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Local,
+    Nfs,
+    CephFs,
+    CephRbd,
+    Iscsi,
+    Nbd,
+    Smb,
+    Gluster,
+}
+
+impl Backend {
+    pub fn label(self) -> &'static str {
+        match self {
+            Backend::Local => "local",
+            Backend::Nfs => "NFS",
+            Backend::CephFs => "CephFS",
+            Backend::CephRbd => "Ceph RBD",
+            Backend::Iscsi => "iSCSI",
+            Backend::Nbd => "NBD",
+            Backend::Smb => "SMB",
+            Backend::Gluster => "Gluster",
+        }
+    }
+
+    /// Whether I/O latency on this filesystem includes a network round trip.
+    pub fn is_remote(self) -> bool {
+        self != Backend::Local
+    }
+
+    fn from_fs_type(fs_type: &str) -> Option<Backend> {
+        match fs_type {
+            "nfs" | "nfs4" => Some(Backend::Nfs),
+            "ceph" | "fuse.ceph-fuse" => Some(Backend::CephFs),
+            "cifs" | "smb3" | "smbfs" => Some(Backend::Smb),
+            "glusterfs" | "fuse.glusterfs" => Some(Backend::Gluster),
+            _ => None,
+        }
+    }
+}
+
+/// One entry of `/proc/mounts`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mount {
+    pub device: String,
+    pub mount_point: String,
+    pub fs_type: String,
+}
+
+/// Undoes the octal escaping (`\040` for space, …) the kernel applies to mount fields.
+fn unescape(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let code: String = chars.by_ref().take(3).collect();
+            match u8::from_str_radix(&code, 8) {
+                Ok(byte) => out.push(byte as char),
+                Err(_) => {
+                    out.push(c);
+                    out.push_str(&code);
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+pub fn parse_mounts(content: &str) -> Vec<Mount> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(Mount {
+                device: unescape(fields.next()?),
+                mount_point: unescape(fields.next()?),
+                fs_type: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Classifies a block device by walking sysfs: the device itself, then whatever it is stacked on.
+#[cfg(target_os = "linux")]
+fn block_backend(kernel_name: &str) -> Backend {
+    if kernel_name.starts_with("rbd") {
+        return Backend::CephRbd;
+    }
+    if kernel_name.starts_with("nbd") {
+        return Backend::Nbd;
+    }
+    let sys_path = format!("/sys/class/block/{}", kernel_name);
+    // iSCSI disks hang off a SCSI host that belongs to an iSCSI session.
+    if let Ok(path) = std::fs::canonicalize(&sys_path) {
+        if path.to_string_lossy().contains("/session") {
+            return Backend::Iscsi;
+        }
+    }
+    std::fs::read_dir(format!("{}/slaves", sys_path))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| block_backend(&e.file_name().to_string_lossy()))
+                .find(|backend| backend.is_remote())
+                .unwrap_or(Backend::Local)
+        })
+        .unwrap_or(Backend::Local)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn block_backend(_kernel_name: &str) -> Backend {
+    Backend::Local
+}
+
+pub fn classify(mount: &Mount) -> Backend {
+    if let Some(backend) = Backend::from_fs_type(&mount.fs_type) {
+        return backend;
+    }
+    // Resolve /dev/mapper/* and /dev/disk/by-* symlinks to the kernel name.
+    let device = std::fs::canonicalize(&mount.device)
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| mount.device.trim_start_matches("/dev/").to_string());
+    block_backend(&device)
+}
+
+#[derive(Clone, Debug)]
+pub struct FilesystemInfo {
+    pub mount_point: String,
+    pub device: String,
+    pub fs_type: String,
+    pub backend: Backend,
+    /// Space figures come from sysinfo, which skips network filesystems because
+    /// `statvfs` on an unresponsive server can hang.
+    pub total: Option<u64>,
+    pub available: Option<u64>,
+}
+
+/// Lists block-device and network filesystems from `/proc/mounts`.
+pub fn collect(system: &System) -> Vec<FilesystemInfo> {
+    let Ok(content) = std::fs::read_to_string("/proc/mounts") else {
+        return system
+            .disks()
+            .iter()
+            .map(|disk| FilesystemInfo {
+                mount_point: disk.mount_point().display().to_string(),
+                device: disk.name().to_string_lossy().into_owned(),
+                fs_type: String::from_utf8_lossy(disk.file_system()).into_owned(),
+                backend: Backend::Local,
+                total: Some(disk.total_space()),
+                available: Some(disk.available_space()),
+            })
+            .collect();
+    };
+
+    let mut seen = HashSet::new();
+    parse_mounts(&content)
+        .into_iter()
+        .filter(|m| m.device.starts_with("/dev/") || Backend::from_fs_type(&m.fs_type).is_some())
+        .filter(|m| seen.insert(m.mount_point.clone()))
+        .map(|mount| {
+            let disk = system
+                .disks()
+                .iter()
+                .find(|d| d.mount_point().to_string_lossy() == mount.mount_point);
+            FilesystemInfo {
+                backend: classify(&mount),
+                total: disk.map(|d| d.total_space()),
+                available: disk.map(|d| d.available_space()),
+                mount_point: mount.mount_point,
+                device: mount.device,
+                fs_type: mount.fs_type,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_classify_mounts() {
+        let content = "\
+/dev/vda / ext4 rw,relatime 0 0
+nas:/export/media /mnt/my\\040media nfs4 rw,relatime 0 0
+10.0.0.1:6789:/ /mnt/ceph ceph rw,name=admin 0 0
+";
+        let mounts = parse_mounts(content);
+        assert_eq!(mounts[1].mount_point, "/mnt/my media");
+        assert_eq!(classify(&mounts[1]), Backend::Nfs);
+        assert_eq!(classify(&mounts[2]), Backend::CephFs);
+        assert!(!Backend::Local.is_remote());
+    }
+}
@@ -0,0 +1,500 @@
+// src/collectors/gpu.rs
+use std::collections::HashMap;
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
+#[cfg(target_os = "linux")]
+use std::time::Instant;
+use sysinfo::Pid;
+
+/// GPU temperatures, in °C, at which the panel turns yellow and red.
+pub const TEMPERATURE_WARNING: u32 = 80;
+pub const TEMPERATURE_CRITICAL: u32 = 90;
+
+/// One GPU as of the last refresh.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GpuInfo {
+    pub name: String,
+    /// Percent of the last sample period the GPU was busy.
+    pub utilization: Option<u32>,
+    pub memory_used: u64,
+    pub memory_total: u64,
+    /// In °C.
+    pub temperature: Option<u32>,
+    /// Board power draw in watts.
+    pub power: Option<f64>,
+}
+
+impl GpuInfo {
+    pub fn memory_ratio(&self) -> f64 {
+        if self.memory_total == 0 {
+            return 0.0;
+        }
+        self.memory_used as f64 / self.memory_total as f64
+    }
+}
+
+/// Samples NVIDIA GPUs through NVML (with the `nvidia` feature, when the
+/// driver library loads) and AMD/Intel GPUs through sysfs (Linux).
+pub struct GpuMonitor {
+    #[cfg(feature = "nvidia")]
+    nvml: Option<nvml_wrapper::Nvml>,
+    #[cfg(target_os = "linux")]
+    cards: Vec<DrmCard>,
+    #[cfg(feature = "nvidia")]
+    nvml_seen: u64,
+    #[cfg(target_os = "linux")]
+    clients: DrmClients,
+    pub gpus: Vec<GpuInfo>,
+    /// GPU memory in use by each process, over all GPUs.
+    pub process_memory: HashMap<Pid, u64>,
+    /// Percent of the last sample period each process kept a GPU busy,
+    /// summed over GPUs like CPU% over cores.
+    pub process_usage: HashMap<Pid, f64>,
+}
+
+impl Default for GpuMonitor {
+    fn default() -> GpuMonitor {
+        GpuMonitor::new()
+    }
+}
+
+impl GpuMonitor {
+    pub fn new() -> GpuMonitor {
+        let mut monitor = GpuMonitor {
+            #[cfg(feature = "nvidia")]
+            nvml: nvml_wrapper::Nvml::init().ok(),
+            #[cfg(target_os = "linux")]
+            cards: DrmCard::discover(Path::new("/sys/class/drm")),
+            #[cfg(feature = "nvidia")]
+            nvml_seen: 0,
+            #[cfg(target_os = "linux")]
+            clients: DrmClients::default(),
+            gpus: Vec::new(),
+            process_memory: HashMap::new(),
+            process_usage: HashMap::new(),
+        };
+        monitor.refresh();
+        monitor
+    }
+
+    pub fn is_available(&self) -> bool {
+        !self.gpus.is_empty()
+    }
+
+    pub fn refresh(&mut self) {
+        self.gpus.clear();
+        self.process_memory.clear();
+        #[cfg(feature = "nvidia")]
+        if let Some(nvml) = &self.nvml {
+            (self.gpus, self.process_memory) = sample(nvml);
+        }
+        #[cfg(target_os = "linux")]
+        self.gpus
+            .extend(self.cards.iter_mut().map(|card| card.sample()));
+    }
+
+    /// Samples per-process GPU usage, and memory on AMD/Intel GPUs. On Linux
+    /// this reads every process's `fdinfo`, so it only runs while a GPU
+    /// column is shown.
+    pub fn refresh_processes(&mut self) {
+        self.process_usage.clear();
+        #[cfg(feature = "nvidia")]
+        if let Some(nvml) = &self.nvml {
+            self.process_usage = process_usage(nvml, &mut self.nvml_seen);
+        }
+        #[cfg(target_os = "linux")]
+        if !self.cards.is_empty() {
+            let (usage, memory) = self.clients.sample(Path::new("/proc"));
+            for (pid, percent) in usage {
+                *self.process_usage.entry(pid).or_insert(0.0) += percent;
+            }
+            for (pid, bytes) in memory {
+                self.process_memory.entry(pid).or_insert(bytes);
+            }
+        }
+    }
+}
+
+/// SM (3D and compute) utilization per process since the last call.
+#[cfg(feature = "nvidia")]
+fn process_usage(nvml: &nvml_wrapper::Nvml, seen: &mut u64) -> HashMap<Pid, f64> {
+    let mut usage = HashMap::new();
+    let mut latest = *seen;
+    for index in 0..nvml.device_count().unwrap_or(0) {
+        let Ok(device) = nvml.device_by_index(index) else {
+            continue;
+        };
+        // Several samples of a process since `seen`; its busiest one counts.
+        let mut device_usage: HashMap<Pid, f64> = HashMap::new();
+        for sample in device.process_utilization_stats(*seen).unwrap_or_default() {
+            latest = latest.max(sample.timestamp);
+            let entry = device_usage
+                .entry(Pid::from(sample.pid as usize))
+                .or_insert(0.0);
+            *entry = entry.max(sample.sm_util as f64);
+        }
+        for (pid, percent) in device_usage {
+            *usage.entry(pid).or_insert(0.0) += percent;
+        }
+    }
+    *seen = latest;
+    usage
+}
+
+#[cfg(feature = "nvidia")]
+fn sample(nvml: &nvml_wrapper::Nvml) -> (Vec<GpuInfo>, HashMap<Pid, u64>) {
+    use nvml_wrapper::enum_wrappers::device::TemperatureSensor;
+    use nvml_wrapper::enums::device::UsedGpuMemory;
+
+    let mut gpus = Vec::new();
+    let mut processes = HashMap::new();
+    for index in 0..nvml.device_count().unwrap_or(0) {
+        let Ok(device) = nvml.device_by_index(index) else {
+            continue;
+        };
+        let memory = device.memory_info().ok();
+        gpus.push(GpuInfo {
+            name: device.name().unwrap_or_else(|_| format!("GPU {}", index)),
+            utilization: device.utilization_rates().ok().map(|rates| rates.gpu),
+            memory_used: memory.as_ref().map_or(0, |memory| memory.used),
+            memory_total: memory.as_ref().map_or(0, |memory| memory.total),
+            temperature: device.temperature(TemperatureSensor::Gpu).ok(),
+            power: device
+                .power_usage()
+                .ok()
+                .map(|milliwatts| milliwatts as f64 / 1000.0),
+        });
+        let compute = device.running_compute_processes().unwrap_or_default();
+        let graphics = device.running_graphics_processes().unwrap_or_default();
+        let used = compute.into_iter().chain(graphics).filter_map(|process| {
+            match process.used_gpu_memory {
+                UsedGpuMemory::Used(bytes) => Some((process.pid, bytes)),
+                UsedGpuMemory::Unavailable => None,
+            }
+        });
+        add_device_processes(&mut processes, used);
+    }
+    (gpus, processes)
+}
+
+/// A GPU driven by `amdgpu`, `i915` or `xe`, read from its DRM device in sysfs.
+#[cfg(target_os = "linux")]
+struct DrmCard {
+    /// `/sys/class/drm/cardN/device`.
+    device: PathBuf,
+    name: String,
+    /// Last `energy1_input` reading (µJ) and its time, for hwmons that only
+    /// report energy (i915).
+    energy: Option<(u64, Instant)>,
+}
+
+#[cfg(target_os = "linux")]
+impl DrmCard {
+    fn discover(drm: &Path) -> Vec<DrmCard> {
+        let Ok(entries) = std::fs::read_dir(drm) else {
+            return Vec::new();
+        };
+        let mut cards: Vec<DrmCard> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let card = entry.file_name().into_string().ok()?;
+                // Skip connectors such as `card0-DP-1`.
+                card.strip_prefix("card")?.parse::<u32>().ok()?;
+                DrmCard::open(entry.path().join("device"), &card)
+            })
+            .collect();
+        cards.sort_by(|a, b| a.device.cmp(&b.device));
+        cards
+    }
+
+    fn open(device: PathBuf, card: &str) -> Option<DrmCard> {
+        let driver = std::fs::read_link(device.join("driver")).ok()?;
+        let vendor = match driver.file_name()?.to_str()? {
+            "amdgpu" => "AMD",
+            "i915" | "xe" => "Intel",
+            _ => return None,
+        };
+        let name = read_string(&device.join("product_name"))
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| format!("{} GPU ({})", vendor, card));
+        Some(DrmCard {
+            device,
+            name,
+            energy: None,
+        })
+    }
+
+    fn sample(&mut self) -> GpuInfo {
+        let hwmon = std::fs::read_dir(self.device.join("hwmon"))
+            .ok()
+            .and_then(|mut entries| entries.next())
+            .and_then(|entry| entry.ok())
+            .map(|entry| entry.path());
+        let hwmon_value = |file: &str| hwmon.as_ref().and_then(|dir| read_u64(&dir.join(file)));
+
+        let mut power = hwmon_value("power1_average")
+            .or_else(|| hwmon_value("power1_input"))
+            .map(|microwatts| microwatts as f64 / 1e6);
+        if power.is_none() {
+            if let Some(energy) = hwmon_value("energy1_input") {
+                let now = Instant::now();
+                if let Some((previous, at)) = self.energy {
+                    let seconds = now.duration_since(at).as_secs_f64();
+                    if seconds > 0.0 && energy >= previous {
+                        power = Some((energy - previous) as f64 / 1e6 / seconds);
+                    }
+                }
+                self.energy = Some((energy, now));
+            }
+        }
+
+        GpuInfo {
+            name: self.name.clone(),
+            utilization: read_u64(&self.device.join("gpu_busy_percent")).map(|busy| busy as u32),
+            memory_used: read_u64(&self.device.join("mem_info_vram_used")).unwrap_or(0),
+            memory_total: read_u64(&self.device.join("mem_info_vram_total")).unwrap_or(0),
+            temperature: hwmon_value("temp1_input")
+                .map(|millidegrees| (millidegrees / 1000) as u32),
+            power,
+        }
+    }
+}
+
+/// One DRM client (an open GPU context) as listed in `/proc/PID/fdinfo`,
+/// following the kernel's DRM usage stats format.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default, PartialEq)]
+struct DrmClient {
+    /// `drm-pdev` and `drm-client-id`: duplicated file descriptors share it.
+    id: String,
+    /// Busy time per engine (`drm-engine-gfx` and so on), in ns.
+    engines: HashMap<String, u64>,
+    /// Device-local memory (VRAM), in bytes.
+    memory: u64,
+}
+
+#[cfg(target_os = "linux")]
+fn parse_fdinfo(content: &str) -> Option<DrmClient> {
+    let mut client = DrmClient::default();
+    let (mut pdev, mut id) = ("", None);
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let number = || value.split_whitespace().next()?.parse::<u64>().ok();
+        let bytes = || {
+            let unit = match value.split_whitespace().nth(1) {
+                Some("KiB") => 1 << 10,
+                Some("MiB") => 1 << 20,
+                _ => 1,
+            };
+            Some(number()? * unit)
+        };
+        match key {
+            "drm-pdev" => pdev = value,
+            "drm-client-id" => id = Some(value),
+            _ => {
+                if let Some(engine) = key.strip_prefix("drm-engine-") {
+                    // `drm-engine-capacity-*` is a count of engines, not a time.
+                    if !engine.starts_with("capacity-") {
+                        client.engines.insert(engine.to_string(), number()?);
+                    }
+                } else if let Some(region) = key
+                    .strip_prefix("drm-memory-")
+                    .or_else(|| key.strip_prefix("drm-resident-"))
+                {
+                    if region.starts_with("vram") || region.starts_with("local") {
+                        client.memory += bytes()?;
+                    }
+                }
+            }
+        }
+    }
+    client.id = format!("{} {}", pdev, id?);
+    Some(client)
+}
+
+/// Turns the busy times of DRM clients into per-process GPU%.
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+struct DrmClients {
+    /// Busy time per process and engine at the last sample.
+    previous: HashMap<Pid, HashMap<String, u64>>,
+    at: Option<Instant>,
+}
+
+#[cfg(target_os = "linux")]
+impl DrmClients {
+    /// GPU% (the busiest engine) and VRAM per process under `proc`. GPU% is
+    /// known from the second sample on.
+    fn sample(&mut self, proc: &Path) -> (HashMap<Pid, f64>, HashMap<Pid, u64>) {
+        let now = Instant::now();
+        let elapsed = self.at.map(|at| now.duration_since(at).as_nanos() as f64);
+        let mut busy: HashMap<Pid, HashMap<String, u64>> = HashMap::new();
+        let mut memory = HashMap::new();
+        for entry in std::fs::read_dir(proc).into_iter().flatten().flatten() {
+            let Some(pid) = entry
+                .file_name()
+                .to_str()
+                .and_then(|pid| pid.parse::<usize>().ok())
+            else {
+                continue;
+            };
+            let Ok(fds) = std::fs::read_dir(entry.path().join("fdinfo")) else {
+                continue;
+            };
+            let mut clients: HashMap<String, DrmClient> = HashMap::new();
+            for fd in fds.flatten() {
+                if let Some(client) = std::fs::read_to_string(fd.path())
+                    .ok()
+                    .and_then(|content| parse_fdinfo(&content))
+                {
+                    clients.insert(client.id.clone(), client);
+                }
+            }
+            if clients.is_empty() {
+                continue;
+            }
+            let pid = Pid::from(pid);
+            let engines = busy.entry(pid).or_default();
+            for client in clients.values() {
+                for (engine, ns) in &client.engines {
+                    *engines.entry(engine.clone()).or_insert(0) += ns;
+                }
+            }
+            memory.insert(pid, clients.values().map(|client| client.memory).sum());
+        }
+        let mut usage = HashMap::new();
+        if let Some(elapsed) = elapsed.filter(|elapsed| *elapsed > 0.0) {
+            for (pid, engines) in &busy {
+                let Some(previous) = self.previous.get(pid) else {
+                    continue;
+                };
+                let percent = engines
+                    .iter()
+                    .filter_map(|(engine, ns)| ns.checked_sub(*previous.get(engine)?))
+                    .map(|ns| ns as f64 / elapsed * 100.0)
+                    .fold(0.0, f64::max);
+                usage.insert(*pid, percent.min(100.0));
+            }
+        }
+        self.previous = busy;
+        self.at = Some(now);
+        (usage, memory)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_string(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|text| text.trim().to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn read_u64(path: &Path) -> Option<u64> {
+    read_string(path)?.parse().ok()
+}
+
+/// Adds one device's `(pid, bytes)` entries to the per-process totals. A
+/// process with both a compute and a graphics context is listed twice with
+/// the same allocation, so it is only counted once per device.
+#[cfg_attr(not(feature = "nvidia"), allow(dead_code))]
+fn add_device_processes(totals: &mut HashMap<Pid, u64>, used: impl Iterator<Item = (u32, u64)>) {
+    let mut device: HashMap<Pid, u64> = HashMap::new();
+    for (pid, bytes) in used {
+        let entry = device.entry(Pid::from(pid as usize)).or_insert(0);
+        *entry = (*entry).max(bytes);
+    }
+    for (pid, bytes) in device {
+        *totals.entry(pid).or_insert(0) += bytes;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_memory_totals() {
+        let mut totals = HashMap::new();
+        add_device_processes(&mut totals, [(10, 100), (10, 100), (11, 5)].into_iter());
+        add_device_processes(&mut totals, [(10, 50)].into_iter());
+        assert_eq!(totals[&Pid::from(10)], 150);
+        assert_eq!(totals[&Pid::from(11)], 5);
+
+        let gpu = GpuInfo {
+            memory_used: 1,
+            memory_total: 4,
+            ..GpuInfo::default()
+        };
+        assert_eq!(gpu.memory_ratio(), 0.25);
+        assert_eq!(GpuInfo::default().memory_ratio(), 0.0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_drm_clients() {
+        let fdinfo = "pos:\t0\nflags:\t02100002\ndrm-driver:\tamdgpu\n\
+                      drm-pdev:\t0000:03:00.0\ndrm-client-id:\t42\n\
+                      drm-memory-vram:\t2048 KiB\ndrm-memory-gtt:\t512 KiB\n\
+                      drm-engine-gfx:\t1000000000 ns\ndrm-engine-compute:\t0 ns\n\
+                      drm-engine-capacity-gfx:\t2\n";
+        let client = parse_fdinfo(fdinfo).unwrap();
+        assert_eq!(client.id, "0000:03:00.0 42");
+        assert_eq!(client.memory, 2 << 20);
+        assert_eq!(client.engines.len(), 2);
+        assert!(parse_fdinfo("pos:\t0\nflags:\t02\n").is_none());
+
+        let proc = std::env::temp_dir().join(format!("rtop-test-fdinfo-{}", std::process::id()));
+        let dir = proc.join("7").join("fdinfo");
+        std::fs::create_dir_all(&dir).unwrap();
+        // Two descriptors of one client are counted once.
+        std::fs::write(dir.join("5"), fdinfo).unwrap();
+        std::fs::write(dir.join("6"), fdinfo).unwrap();
+        let mut clients = DrmClients::default();
+        let (usage, memory) = clients.sample(&proc);
+        assert!(usage.is_empty());
+        assert_eq!(memory[&Pid::from(7)], 2 << 20);
+        // The busy time grows by as much as wall time passed: 100%.
+        clients.at = Some(Instant::now() - std::time::Duration::from_secs(1));
+        let busier = fdinfo.replace("1000000000 ns", "2000000000 ns");
+        std::fs::write(dir.join("5"), &busier).unwrap();
+        std::fs::write(dir.join("6"), &busier).unwrap();
+        let (usage, _) = clients.sample(&proc);
+        std::fs::remove_dir_all(&proc).unwrap();
+        assert!(usage[&Pid::from(7)] > 90.0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_drm_card() {
+        let drm = std::env::temp_dir().join(format!("rtop-test-drm-{}", std::process::id()));
+        let device = drm.join("card1").join("device");
+        let hwmon = device.join("hwmon").join("hwmon4");
+        std::fs::create_dir_all(&hwmon).unwrap();
+        std::fs::create_dir_all(drm.join("card1-DP-1")).unwrap();
+        std::os::unix::fs::symlink("/sys/bus/pci/drivers/amdgpu", device.join("driver")).unwrap();
+        for (path, value) in [
+            (device.join("gpu_busy_percent"), "37\n"),
+            (device.join("mem_info_vram_used"), "1073741824\n"),
+            (device.join("mem_info_vram_total"), "8589934592\n"),
+            (hwmon.join("temp1_input"), "54000\n"),
+            (hwmon.join("power1_average"), "31000000\n"),
+        ] {
+            std::fs::write(path, value).unwrap();
+        }
+
+        let mut cards = DrmCard::discover(&drm);
+        let gpu = cards.first_mut().map(|card| card.sample());
+        std::fs::remove_dir_all(&drm).unwrap();
+        assert_eq!(cards.len(), 1);
+        let gpu = gpu.unwrap();
+        assert_eq!(gpu.name, "AMD GPU (card1)");
+        assert_eq!(gpu.utilization, Some(37));
+        assert_eq!(gpu.memory_ratio(), 0.125);
+        assert_eq!(gpu.temperature, Some(54));
+        assert_eq!(gpu.power, Some(31.0));
+    }
+}
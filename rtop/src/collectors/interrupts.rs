@@ -0,0 +1,267 @@
+// src/collectors/interrupts.rs
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Cumulative counts for one row of `/proc/interrupts`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IrqCounters {
+    pub irq: String,
+    pub per_cpu: Vec<u64>,
+    pub description: String,
+}
+
+/// Parses `/proc/interrupts` (or `/proc/softirqs`, which shares the layout);
+/// the header line gives the number of CPU columns.
+pub fn parse_interrupts(content: &str) -> (usize, Vec<IrqCounters>) {
+    let mut lines = content.lines();
+    let cpu_count = lines
+        .next()
+        .map(|header| header.split_whitespace().count())
+        .unwrap_or(0);
+
+    let counters = lines
+        .filter_map(|line| {
+            let (irq, rest) = line.split_once(':')?;
+            let mut tokens = rest.split_whitespace().peekable();
+            let mut per_cpu = Vec::with_capacity(cpu_count);
+            // Summary rows such as ERR/MIS carry a single total instead of one count per CPU.
+            while per_cpu.len() < cpu_count {
+                match tokens.peek().and_then(|t| t.parse::<u64>().ok()) {
+                    Some(count) => {
+                        per_cpu.push(count);
+                        tokens.next();
+                    }
+                    None => break,
+                }
+            }
+            Some(IrqCounters {
+                irq: irq.trim().to_string(),
+                per_cpu,
+                description: tokens.collect::<Vec<_>>().join(" "),
+            })
+        })
+        .collect();
+    (cpu_count, counters)
+}
+
+/// Interrupts per second for one source over the last refresh interval.
+#[derive(Clone, Debug)]
+pub struct IrqRate {
+    pub irq: String,
+    pub description: String,
+    pub per_cpu: Vec<f64>,
+    pub total: f64,
+}
+
+impl IrqRate {
+    /// The CPU handling most of this source's interrupts and its share of the total.
+    pub fn busiest_cpu(&self) -> Option<(usize, f64)> {
+        if self.total <= 0.0 {
+            return None;
+        }
+        self.per_cpu
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(cpu, &rate)| (cpu, rate / self.total))
+    }
+}
+
+/// Jiffies a CPU spent in each state, from a `cpuN` line of `/proc/stat`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub softirq: u64,
+    pub total: u64,
+}
+
+/// Per-CPU times from `/proc/stat`, skipping the aggregate `cpu` line.
+pub fn parse_cpu_times(content: &str) -> Vec<CpuTimes> {
+    content
+        .lines()
+        .filter(|line| line.starts_with("cpu") && !line.starts_with("cpu "))
+        .map(|line| {
+            let values: Vec<u64> = line
+                .split_whitespace()
+                .skip(1)
+                .filter_map(|v| v.parse().ok())
+                .collect();
+            // user nice system idle iowait irq softirq steal guest guest_nice;
+            // guest time is already included in user/nice.
+            CpuTimes {
+                softirq: values.get(6).copied().unwrap_or(0),
+                total: values.iter().take(8).sum(),
+            }
+        })
+        .collect()
+}
+
+/// Softirq load of one CPU: time share plus the network softirq rates.
+#[derive(Clone, Debug)]
+pub struct CpuSoftirq {
+    pub cpu: usize,
+    /// Percentage of the CPU's time spent servicing softirqs.
+    pub percent: f64,
+    pub net_rx: f64,
+    pub net_tx: f64,
+    /// All other softirq types combined, per second.
+    pub other: f64,
+}
+
+fn compute_rates(
+    counters: &[IrqCounters],
+    previous: &HashMap<String, Vec<u64>>,
+    elapsed: f64,
+) -> Vec<IrqRate> {
+    counters
+        .iter()
+        .map(|counter| {
+            let previous = previous.get(&counter.irq);
+            let per_cpu: Vec<f64> = counter
+                .per_cpu
+                .iter()
+                .enumerate()
+                .map(|(cpu, &count)| {
+                    let before = previous.and_then(|p| p.get(cpu)).copied().unwrap_or(count);
+                    count.saturating_sub(before) as f64 / elapsed
+                })
+                .collect();
+            IrqRate {
+                irq: counter.irq.clone(),
+                description: counter.description.clone(),
+                total: per_cpu.iter().sum(),
+                per_cpu,
+            }
+        })
+        .collect()
+}
+
+fn to_previous(counters: Vec<IrqCounters>) -> HashMap<String, Vec<u64>> {
+    counters
+        .into_iter()
+        .map(|counter| (counter.irq, counter.per_cpu))
+        .collect()
+}
+
+/// Samples `/proc/interrupts`, `/proc/softirqs` and `/proc/stat`, turning the
+/// counters into per-second rates.
+#[derive(Clone)]
+pub struct InterruptMonitor {
+    previous: HashMap<String, Vec<u64>>,
+    previous_softirqs: HashMap<String, Vec<u64>>,
+    previous_cpu_times: Vec<CpuTimes>,
+    last_refresh: Instant,
+    pub cpu_count: usize,
+    /// Sorted hottest first.
+    pub rates: Vec<IrqRate>,
+    pub softirqs: Vec<CpuSoftirq>,
+}
+
+impl Default for InterruptMonitor {
+    fn default() -> InterruptMonitor {
+        InterruptMonitor::new()
+    }
+}
+
+impl InterruptMonitor {
+    pub fn new() -> InterruptMonitor {
+        let mut monitor = InterruptMonitor {
+            previous: HashMap::new(),
+            previous_softirqs: HashMap::new(),
+            previous_cpu_times: Vec::new(),
+            last_refresh: Instant::now(),
+            cpu_count: 0,
+            rates: Vec::new(),
+            softirqs: Vec::new(),
+        };
+        monitor.refresh();
+        monitor
+    }
+
+    pub fn refresh(&mut self) {
+        let Ok(content) = std::fs::read_to_string("/proc/interrupts") else {
+            return;
+        };
+        let elapsed = self.last_refresh.elapsed().as_secs_f64().max(0.001);
+        let (cpu_count, counters) = parse_interrupts(&content);
+
+        let mut rates = compute_rates(&counters, &self.previous, elapsed);
+        rates.sort_by(|a, b| b.total.total_cmp(&a.total));
+        self.previous = to_previous(counters);
+        self.cpu_count = cpu_count;
+        self.rates = rates;
+
+        self.refresh_softirqs(elapsed);
+        self.last_refresh = Instant::now();
+    }
+
+    fn refresh_softirqs(&mut self, elapsed: f64) {
+        let softirqs = std::fs::read_to_string("/proc/softirqs").unwrap_or_default();
+        let (_, counters) = parse_interrupts(&softirqs);
+        let rates = compute_rates(&counters, &self.previous_softirqs, elapsed);
+        self.previous_softirqs = to_previous(counters);
+
+        let stat = std::fs::read_to_string("/proc/stat").unwrap_or_default();
+        let cpu_times = parse_cpu_times(&stat);
+        let rate_of = |name: &str, cpu: usize| {
+            rates
+                .iter()
+                .find(|r| r.irq == name)
+                .and_then(|r| r.per_cpu.get(cpu))
+                .copied()
+                .unwrap_or(0.0)
+        };
+
+        self.softirqs = (0..self.cpu_count)
+            .map(|cpu| {
+                let percent = match (cpu_times.get(cpu), self.previous_cpu_times.get(cpu)) {
+                    (Some(now), Some(before)) if now.total > before.total => {
+                        now.softirq.saturating_sub(before.softirq) as f64
+                            / (now.total - before.total) as f64
+                            * 100.0
+                    }
+                    _ => 0.0,
+                };
+                let all: f64 = rates.iter().filter_map(|r| r.per_cpu.get(cpu)).sum();
+                let net_rx = rate_of("NET_RX", cpu);
+                let net_tx = rate_of("NET_TX", cpu);
+                CpuSoftirq {
+                    cpu,
+                    percent,
+                    net_rx,
+                    net_tx,
+                    other: all - net_rx - net_tx,
+                }
+            })
+            .collect();
+        self.previous_cpu_times = cpu_times;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interrupts() {
+        let content = "\
+           CPU0       CPU1
+ 24:         10          5  IO-APIC   5-edge      ACPI:Ged
+NMI:          0          0   Non-maskable interrupts
+ERR:          3
+";
+        let (cpus, counters) = parse_interrupts(content);
+        assert_eq!(cpus, 2);
+        assert_eq!(counters[0].irq, "24");
+        assert_eq!(counters[0].per_cpu, vec![10, 5]);
+        assert_eq!(counters[0].description, "IO-APIC 5-edge ACPI:Ged");
+        assert_eq!(counters[2].per_cpu, vec![3]);
+    }
+
+    #[test]
+    fn test_parse_cpu_times() {
+        let content = "cpu  20 0 10 100 0 0 4 0 0 0\ncpu0 10 0 5 50 0 0 2 0 0 0\ncpu1 10 0 5 50 0 0 2 0 0 0\nintr 0\n";
+        let times = parse_cpu_times(content);
+        assert_eq!(times.len(), 2);
+        assert_eq!(times[0], CpuTimes { softirq: 2, total: 67 });
+    }
+}
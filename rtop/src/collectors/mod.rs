@@ -0,0 +1,16 @@
+// src/collectors/mod.rs
+//! Readers for what the system reports beyond sysinfo: disks, GPUs,
+//! interrupts, network interfaces and per-process I/O and sockets, and the
+//! threads that run them in the background.
+pub mod background;
+pub mod battery;
+pub mod diskio;
+pub mod disks;
+pub mod filesystems;
+pub mod gpu;
+pub mod interrupts;
+pub mod netio;
+pub mod network;
+#[cfg(feature = "pagecache")]
+pub mod pagecache;
+pub mod sockets;
@@ -0,0 +1,348 @@
+// src/collectors/netio.rs
+use crate::collectors::sockets;
+use std::collections::HashMap;
+use std::time::Instant;
+use sysinfo::{Pid, System, SystemExt};
+
+/// Bytes per second one process sends and receives over TCP.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NetRate {
+    pub sent: f64,
+    pub received: f64,
+}
+
+/// Lifetime byte counters of one TCP socket, from its `tcp_info`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct SocketBytes {
+    inode: u64,
+    sent: u64,
+    received: u64,
+}
+
+/// Attributes TCP traffic to processes, like nethogs but without packet
+/// capture: the kernel's sock_diag interface reports how many bytes every
+/// socket has sent and received, and `/proc/PID/fd` tells which process holds
+/// it. Only sockets of rtop's own network namespace are seen.
+#[derive(Clone)]
+pub struct ProcessNet {
+    /// Sent and received totals per socket inode at the previous refresh.
+    sockets: HashMap<u64, (u64, u64)>,
+    rates: HashMap<Pid, NetRate>,
+    last: Option<Instant>,
+}
+
+impl Default for ProcessNet {
+    fn default() -> ProcessNet {
+        ProcessNet::new()
+    }
+}
+
+impl ProcessNet {
+    pub fn new() -> ProcessNet {
+        ProcessNet {
+            sockets: HashMap::new(),
+            rates: HashMap::new(),
+            last: None,
+        }
+    }
+
+    pub fn refresh(&mut self, system: &System) {
+        let sockets = tcp_socket_bytes();
+        let owners = socket_owners(system);
+        self.record(&sockets, &owners, Instant::now());
+    }
+
+    fn record(&mut self, sockets: &[SocketBytes], owners: &HashMap<u64, Pid>, now: Instant) {
+        let elapsed = self
+            .last
+            .map_or(0.0, |last| now.duration_since(last).as_secs_f64());
+        self.rates.clear();
+        if elapsed > 0.0 {
+            for socket in sockets {
+                let Some(&pid) = owners.get(&socket.inode) else {
+                    continue;
+                };
+                // Sockets opened since the previous refresh count from zero.
+                let (sent, received) = self.sockets.get(&socket.inode).copied().unwrap_or_default();
+                let rate = self.rates.entry(pid).or_default();
+                rate.sent += socket.sent.saturating_sub(sent) as f64 / elapsed;
+                rate.received += socket.received.saturating_sub(received) as f64 / elapsed;
+            }
+        }
+        self.sockets = sockets
+            .iter()
+            .map(|socket| (socket.inode, (socket.sent, socket.received)))
+            .collect();
+        self.last = Some(now);
+    }
+
+    pub fn rate(&self, pid: Pid) -> NetRate {
+        self.rates.get(&pid).copied().unwrap_or_default()
+    }
+}
+
+/// The process holding each socket inode. A socket shared after a fork is
+/// credited to the lowest PID, normally the parent.
+fn socket_owners(system: &System) -> HashMap<u64, Pid> {
+    let mut pids: Vec<Pid> = system.processes().keys().copied().collect();
+    pids.sort();
+    let mut owners = HashMap::new();
+    for pid in pids {
+        for inode in sockets::socket_inodes(pid) {
+            owners.entry(inode).or_insert(pid);
+        }
+    }
+    owners
+}
+
+const NLMSG_HEADER_LEN: usize = 16;
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+/// `struct inet_diag_msg`, which starts every SOCK_DIAG_BY_FAMILY reply.
+const INET_DIAG_MSG_LEN: usize = 72;
+const INET_DIAG_MSG_INODE: usize = 68;
+const INET_DIAG_INFO: u16 = 2;
+/// Offsets of `tcpi_bytes_acked` and `tcpi_bytes_received` in `struct
+/// tcp_info`; kernels before 4.2 send a shorter struct without them.
+const TCPI_BYTES_ACKED: usize = 120;
+const TCPI_BYTES_RECEIVED: usize = 128;
+
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_ne_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_ne_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_ne_bytes(
+        bytes.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// A dump request for every TCP socket of `family`, asking for `tcp_info`.
+#[cfg(target_os = "linux")]
+fn diag_request(family: u8) -> Vec<u8> {
+    const NLM_F_REQUEST: u16 = 0x1;
+    const NLM_F_DUMP: u16 = 0x300;
+    // `struct inet_diag_req_v2`: family, protocol, extensions, padding,
+    // state mask and a zeroed `struct inet_diag_sockid`.
+    let mut body = vec![
+        family,
+        libc::IPPROTO_TCP as u8,
+        1 << (INET_DIAG_INFO - 1),
+        0,
+    ];
+    body.extend(u32::MAX.to_ne_bytes());
+    body.extend([0; 48]);
+
+    let mut request = Vec::new();
+    request.extend(((NLMSG_HEADER_LEN + body.len()) as u32).to_ne_bytes());
+    request.extend(SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+    request.extend((NLM_F_REQUEST | NLM_F_DUMP).to_ne_bytes());
+    request.extend(1u32.to_ne_bytes());
+    request.extend(0u32.to_ne_bytes());
+    request.extend(body);
+    request
+}
+
+/// Parses one reply datagram into `sockets`; returns whether the dump is over.
+fn parse_diag_messages(buffer: &[u8], sockets: &mut Vec<SocketBytes>) -> bool {
+    let mut offset = 0;
+    while offset + NLMSG_HEADER_LEN <= buffer.len() {
+        let (Some(len), Some(kind)) = (read_u32(buffer, offset), read_u16(buffer, offset + 4))
+        else {
+            return true;
+        };
+        let len = len as usize;
+        if len < NLMSG_HEADER_LEN || offset + len > buffer.len() {
+            return true;
+        }
+        match kind {
+            NLMSG_DONE | NLMSG_ERROR => return true,
+            SOCK_DIAG_BY_FAMILY => {
+                sockets.extend(parse_socket(
+                    &buffer[offset + NLMSG_HEADER_LEN..offset + len],
+                ));
+            }
+            _ => {}
+        }
+        offset += align(len);
+    }
+    false
+}
+
+fn parse_socket(message: &[u8]) -> Option<SocketBytes> {
+    let inode = read_u32(message, INET_DIAG_MSG_INODE)?;
+    // TIME_WAIT sockets no longer belong to a process.
+    if inode == 0 {
+        return None;
+    }
+    let mut offset = INET_DIAG_MSG_LEN;
+    while let (Some(len), Some(kind)) = (read_u16(message, offset), read_u16(message, offset + 2)) {
+        let len = len as usize;
+        if len < 4 {
+            return None;
+        }
+        let payload = message.get(offset + 4..offset + len)?;
+        if kind == INET_DIAG_INFO {
+            return Some(SocketBytes {
+                inode: u64::from(inode),
+                sent: read_u64(payload, TCPI_BYTES_ACKED)?,
+                received: read_u64(payload, TCPI_BYTES_RECEIVED)?,
+            });
+        }
+        offset += align(len);
+    }
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn tcp_socket_bytes() -> Vec<SocketBytes> {
+    [libc::AF_INET, libc::AF_INET6]
+        .into_iter()
+        .flat_map(|family| dump_tcp_sockets(family as u8).unwrap_or_default())
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn tcp_socket_bytes() -> Vec<SocketBytes> {
+    Vec::new()
+}
+
+#[cfg(target_os = "linux")]
+fn dump_tcp_sockets(family: u8) -> std::io::Result<Vec<SocketBytes>> {
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    // SAFETY: the descriptor is checked and then owned (and closed) by `socket`;
+    // the buffers passed to send and recv outlive the calls.
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            libc::NETLINK_SOCK_DIAG,
+        )
+    };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    let request = diag_request(family);
+    // An unconnected netlink socket sends to the kernel.
+    let sent = unsafe {
+        libc::send(
+            socket.as_raw_fd(),
+            request.as_ptr().cast(),
+            request.len(),
+            0,
+        )
+    };
+    if sent < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut sockets = Vec::new();
+    loop {
+        let received = unsafe {
+            libc::recv(
+                socket.as_raw_fd(),
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+                0,
+            )
+        };
+        if received < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        if received == 0 || parse_diag_messages(&buffer[..received as usize], &mut sockets) {
+            return Ok(sockets);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn message(kind: u16, payload: &[u8]) -> Vec<u8> {
+        let mut message = Vec::new();
+        message.extend(((NLMSG_HEADER_LEN + payload.len()) as u32).to_ne_bytes());
+        message.extend(kind.to_ne_bytes());
+        message.extend([0; 10]);
+        message.extend(payload);
+        message
+    }
+
+    #[test]
+    fn test_parse_diag_messages() {
+        let mut info = vec![0u8; 232];
+        info[TCPI_BYTES_ACKED..TCPI_BYTES_ACKED + 8].copy_from_slice(&1500u64.to_ne_bytes());
+        info[TCPI_BYTES_RECEIVED..TCPI_BYTES_RECEIVED + 8].copy_from_slice(&9000u64.to_ne_bytes());
+        let mut payload = vec![0u8; INET_DIAG_MSG_LEN];
+        payload[INET_DIAG_MSG_INODE..INET_DIAG_MSG_INODE + 4].copy_from_slice(&42u32.to_ne_bytes());
+        // An attribute rtop does not ask about, then INET_DIAG_INFO.
+        payload.extend(8u16.to_ne_bytes());
+        payload.extend(5u16.to_ne_bytes());
+        payload.extend([0; 4]);
+        payload.extend(((4 + info.len()) as u16).to_ne_bytes());
+        payload.extend(INET_DIAG_INFO.to_ne_bytes());
+        payload.extend(&info);
+
+        let mut buffer = message(SOCK_DIAG_BY_FAMILY, &payload);
+        let mut sockets = Vec::new();
+        assert!(!parse_diag_messages(&buffer, &mut sockets));
+        buffer.extend(message(NLMSG_DONE, &[0; 4]));
+        sockets.clear();
+        assert!(parse_diag_messages(&buffer, &mut sockets));
+        assert_eq!(
+            sockets,
+            vec![SocketBytes {
+                inode: 42,
+                sent: 1500,
+                received: 9000
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rates_per_process() {
+        let mut net = ProcessNet::new();
+        let start = Instant::now();
+        let (one, two) = (Pid::from(1), Pid::from(2));
+        let owners = HashMap::from([(10, one), (11, one), (20, two)]);
+        let socket = |inode, sent, received| SocketBytes {
+            inode,
+            sent,
+            received,
+        };
+        net.record(&[socket(10, 100, 100)], &owners, start);
+        assert_eq!(net.rate(one), NetRate::default());
+
+        net.record(
+            &[socket(10, 2100, 100), socket(11, 0, 4000), socket(20, 0, 0)],
+            &owners,
+            start + Duration::from_secs(2),
+        );
+        // Both of process 1's sockets add up; socket 11 is new and counts from zero.
+        assert_eq!(
+            net.rate(one),
+            NetRate {
+                sent: 1000.0,
+                received: 2000.0
+            }
+        );
+        assert_eq!(net.rate(two), NetRate::default());
+    }
+}
@@ -0,0 +1,515 @@
+// src/collectors/network.rs
+use std::collections::{HashMap, VecDeque};
+use std::net::Ipv4Addr;
+use std::time::Instant;
+use sysinfo::{NetworkExt, NetworksExt, Pid, ProcessExt, System, SystemExt};
+
+/// Number of samples kept for each interface's sparkline.
+pub const HISTORY_LEN: usize = 120;
+
+/// What sort of device backs a network interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InterfaceKind {
+    Physical,
+    WireGuard,
+    Tun,
+    Tap,
+    Bridge,
+    Veth,
+    Loopback,
+    Virtual,
+}
+
+impl InterfaceKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            InterfaceKind::Physical => "physical",
+            InterfaceKind::WireGuard => "wireguard",
+            InterfaceKind::Tun => "tun",
+            InterfaceKind::Tap => "tap",
+            InterfaceKind::Bridge => "bridge",
+            InterfaceKind::Veth => "veth",
+            InterfaceKind::Loopback => "loopback",
+            InterfaceKind::Virtual => "virtual",
+        }
+    }
+
+    /// Software plumbing (bridges, veths, loopback, …) as opposed to physical
+    /// links and VPN tunnels.
+    pub fn is_virtual(self) -> bool {
+        matches!(
+            self,
+            InterfaceKind::Bridge
+                | InterfaceKind::Veth
+                | InterfaceKind::Loopback
+                | InterfaceKind::Virtual
+        )
+    }
+}
+
+/// The sysfs attributes of `/sys/class/net/NAME` that decide its kind.
+#[derive(Clone, Debug, Default)]
+pub struct InterfaceAttributes {
+    /// `DEVTYPE` from `uevent`, e.g. `bridge` or `wireguard`.
+    pub devtype: Option<String>,
+    /// Whether a `device` link to the underlying hardware exists.
+    pub has_device: bool,
+    /// ARP hardware type from `type`; 772 is loopback.
+    pub arp_type: u32,
+    pub tun_flags: Option<u32>,
+    pub ifindex: u32,
+    /// Index of the interface this one is linked to; differs from `ifindex` for veth peers.
+    pub iflink: u32,
+}
+
+pub fn classify_interface(attributes: &InterfaceAttributes) -> InterfaceKind {
+    const ARPHRD_LOOPBACK: u32 = 772;
+    const IFF_TAP: u32 = 0x0002;
+    match (attributes.devtype.as_deref(), attributes.tun_flags) {
+        (Some("bridge"), _) => InterfaceKind::Bridge,
+        (Some("wireguard"), _) => InterfaceKind::WireGuard,
+        (Some(_), _) => InterfaceKind::Virtual,
+        (None, Some(flags)) if flags & IFF_TAP != 0 => InterfaceKind::Tap,
+        (None, Some(_)) => InterfaceKind::Tun,
+        (None, None) if attributes.arp_type == ARPHRD_LOOPBACK => InterfaceKind::Loopback,
+        (None, None) if attributes.has_device => InterfaceKind::Physical,
+        (None, None) if attributes.iflink != attributes.ifindex => InterfaceKind::Veth,
+        (None, None) => InterfaceKind::Virtual,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_interface_attributes(name: &str) -> InterfaceAttributes {
+    let dir = format!("/sys/class/net/{}", name);
+    let read = |file: &str| std::fs::read_to_string(format!("{}/{}", dir, file)).ok();
+    let number = |file: &str| read(file).and_then(|v| v.trim().parse::<u32>().ok());
+    InterfaceAttributes {
+        devtype: read("uevent").and_then(|uevent| {
+            uevent
+                .lines()
+                .find_map(|line| line.strip_prefix("DEVTYPE=").map(str::to_string))
+        }),
+        has_device: std::path::Path::new(&dir).join("device").exists(),
+        arp_type: number("type").unwrap_or(0),
+        tun_flags: read("tun_flags")
+            .and_then(|v| u32::from_str_radix(v.trim().trim_start_matches("0x"), 16).ok()),
+        ifindex: number("ifindex").unwrap_or(0),
+        iflink: number("iflink").unwrap_or(0),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_interface_attributes(_name: &str) -> InterfaceAttributes {
+    InterfaceAttributes::default()
+}
+
+/// Interface indexes and names from `/proc/PID/net/if_inet6` (index in hex)
+/// and `/proc/PID/net/igmp` (index in decimal), which together list the
+/// interfaces of another network namespace.
+pub fn parse_interface_indexes(if_inet6: &str, igmp: &str) -> Vec<(u32, String)> {
+    let ipv6 = if_inet6.lines().filter_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        Some((
+            u32::from_str_radix(fields.get(1)?, 16).ok()?,
+            fields.get(5)?.to_string(),
+        ))
+    });
+    let ipv4 = igmp.lines().skip(1).filter_map(|line| {
+        let mut fields = line.split_whitespace();
+        let index = fields.next()?.parse().ok()?;
+        Some((index, fields.next()?.to_string()))
+    });
+    ipv6.chain(ipv4).collect()
+}
+
+/// The container side of a veth pair.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VethPeer {
+    pub interface: String,
+    /// The lowest PID in the peer's network namespace, normally the container's init.
+    pub pid: Pid,
+    pub process: String,
+}
+
+/// Finds which network namespace holds interface `iflink` by scanning one
+/// process per namespace other than rtop's own.
+#[cfg(target_os = "linux")]
+fn find_veth_peer(iflink: u32, system: &System) -> Option<VethPeer> {
+    let host = std::fs::read_link("/proc/self/ns/net").ok()?;
+    let mut pids: Vec<&Pid> = system.processes().keys().collect();
+    pids.sort();
+    let mut seen = std::collections::HashSet::new();
+    for &pid in pids {
+        let Ok(namespace) = std::fs::read_link(format!("/proc/{}/ns/net", pid)) else {
+            continue;
+        };
+        if namespace == host || !seen.insert(namespace) {
+            continue;
+        }
+        let read = |file: &str| {
+            std::fs::read_to_string(format!("/proc/{}/net/{}", pid, file)).unwrap_or_default()
+        };
+        let interface = parse_interface_indexes(&read("if_inet6"), &read("igmp"))
+            .into_iter()
+            .find(|(index, _)| *index == iflink);
+        if let Some((_, interface)) = interface {
+            return Some(VethPeer {
+                interface,
+                pid,
+                process: system
+                    .process(pid)
+                    .map(|p| p.name().to_string())
+                    .unwrap_or_default(),
+            });
+        }
+    }
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+fn find_veth_peer(_iflink: u32, _system: &System) -> Option<VethPeer> {
+    None
+}
+
+/// Throughput of one network interface over the last refresh interval.
+#[derive(Clone, Debug)]
+pub struct InterfaceStats {
+    pub name: String,
+    pub kind: InterfaceKind,
+    /// For veths whose other end lives in a container.
+    pub peer: Option<VethPeer>,
+    pub rx_bytes_per_sec: f64,
+    pub tx_bytes_per_sec: f64,
+    pub total_received: u64,
+    pub total_transmitted: u64,
+    /// Oldest first, at most `HISTORY_LEN` samples.
+    pub rx_history: VecDeque<f64>,
+    pub tx_history: VecDeque<f64>,
+}
+
+fn push_sample(history: &mut VecDeque<f64>, value: f64) {
+    if history.len() == HISTORY_LEN {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+/// Parses `/proc/net/snmp`, where each protocol has a header line of counter
+/// names followed by a line of values. Keys look like `Udp.InErrors`.
+pub fn parse_snmp(content: &str) -> HashMap<String, u64> {
+    let mut counters = HashMap::new();
+    let mut lines = content.lines();
+    while let (Some(names), Some(values)) = (lines.next(), lines.next()) {
+        let (Some((protocol, names)), Some((_, values))) =
+            (names.split_once(':'), values.split_once(':'))
+        else {
+            continue;
+        };
+        for (name, value) in names.split_whitespace().zip(values.split_whitespace()) {
+            if let Ok(value) = value.parse() {
+                counters.insert(format!("{}.{}", protocol, name), value);
+            }
+        }
+    }
+    counters
+}
+
+/// Parses `/proc/net/snmp6` (one `Udp6InErrors 0` pair per line) into the same
+/// keys as `parse_snmp`, so IPv4 and IPv6 counters can be added up.
+pub fn parse_snmp6(content: &str) -> HashMap<String, u64> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let value = fields.next()?.parse().ok()?;
+            let key = if let Some(rest) = name.strip_prefix("Udp6") {
+                format!("Udp.{}", rest)
+            } else if let Some(rest) = name.strip_prefix("Icmp6") {
+                format!("Icmp.{}", rest)
+            } else {
+                return None;
+            };
+            Some((key, value))
+        })
+        .collect()
+}
+
+/// The default route: the interface it leaves through and the gateway it targets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DefaultRoute {
+    pub interface: String,
+    pub gateway: Ipv4Addr,
+}
+
+/// Picks the lowest-metric default route from `/proc/net/route`, whose
+/// addresses are hex in host byte order.
+pub fn parse_default_route(content: &str) -> Option<DefaultRoute> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let hex = |i: usize| fields.get(i).and_then(|v| u32::from_str_radix(v, 16).ok());
+            let (destination, gateway, flags, metric, mask) =
+                (hex(1)?, hex(2)?, hex(3)?, fields.get(6)?, hex(7)?);
+            // RTF_UP (0x1) is required; a default route has an all-zero destination and mask.
+            if destination != 0 || mask != 0 || flags & 0x1 == 0 {
+                return None;
+            }
+            let route = DefaultRoute {
+                interface: fields[0].to_string(),
+                gateway: Ipv4Addr::from(gateway.to_ne_bytes()),
+            };
+            Some((metric.parse::<u32>().ok()?, route))
+        })
+        .min_by_key(|(metric, _)| *metric)
+        .map(|(_, route)| route)
+}
+
+/// Nameserver addresses from `/etc/resolv.conf`.
+pub fn parse_resolv_conf(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("nameserver") => fields.next().map(str::to_string),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Where traffic leaves this machine and who resolves its names.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NetworkIdentity {
+    pub default_route: Option<DefaultRoute>,
+    pub dns_servers: Vec<String>,
+}
+
+impl NetworkIdentity {
+    pub fn read() -> NetworkIdentity {
+        NetworkIdentity {
+            default_route: std::fs::read_to_string("/proc/net/route")
+                .ok()
+                .and_then(|content| parse_default_route(&content)),
+            dns_servers: std::fs::read_to_string("/etc/resolv.conf")
+                .map(|content| parse_resolv_conf(&content))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Turns sysinfo's per-refresh network deltas into rates and keeps a short history.
+#[derive(Clone)]
+pub struct NetworkMonitor {
+    last_refresh: Instant,
+    /// Sorted by kind, then name, so veths are listed together.
+    pub interfaces: Vec<InterfaceStats>,
+    pub hide_virtual: bool,
+    /// UDP and ICMP counters since boot, IPv4 and IPv6 combined.
+    pub protocol_totals: HashMap<String, u64>,
+    pub protocol_rates: HashMap<String, f64>,
+    pub identity: NetworkIdentity,
+    /// When the default route or DNS servers last changed, to spot flapping VPNs.
+    pub identity_changed: Option<Instant>,
+}
+
+impl Default for NetworkMonitor {
+    fn default() -> NetworkMonitor {
+        NetworkMonitor::new()
+    }
+}
+
+impl NetworkMonitor {
+    pub fn new() -> NetworkMonitor {
+        NetworkMonitor {
+            last_refresh: Instant::now(),
+            interfaces: Vec::new(),
+            hide_virtual: false,
+            protocol_totals: HashMap::new(),
+            protocol_rates: HashMap::new(),
+            identity: NetworkIdentity::read(),
+            identity_changed: None,
+        }
+    }
+
+    /// All interfaces, or only physical links and tunnels when `hide_virtual` is set.
+    pub fn visible(&self) -> impl Iterator<Item = &InterfaceStats> {
+        self.interfaces
+            .iter()
+            .filter(move |iface| !(self.hide_virtual && iface.kind.is_virtual()))
+    }
+
+    /// Total of a `parse_snmp` counter, e.g. `Udp.RcvbufErrors`.
+    pub fn protocol_total(&self, key: &str) -> u64 {
+        self.protocol_totals.get(key).copied().unwrap_or(0)
+    }
+
+    /// Per-second rate of a `parse_snmp` counter over the last refresh interval.
+    pub fn protocol_rate(&self, key: &str) -> f64 {
+        self.protocol_rates.get(key).copied().unwrap_or(0.0)
+    }
+
+    /// Samples the networks sysinfo refreshed along with the rest of `system`.
+    pub fn refresh(&mut self, system: &System) {
+        let elapsed = self.last_refresh.elapsed().as_secs_f64().max(0.001);
+        let mut previous = std::mem::take(&mut self.interfaces);
+        let mut interfaces: Vec<InterfaceStats> = system
+            .networks()
+            .iter()
+            .map(|(name, data)| {
+                let mut stats = match previous.iter().position(|i| &i.name == name) {
+                    Some(index) => previous.swap_remove(index),
+                    None => {
+                        // Classified once: an interface's kind does not change while it exists.
+                        let attributes = read_interface_attributes(name);
+                        let kind = classify_interface(&attributes);
+                        InterfaceStats {
+                            name: name.clone(),
+                            kind,
+                            peer: if kind == InterfaceKind::Veth {
+                                find_veth_peer(attributes.iflink, system)
+                            } else {
+                                None
+                            },
+                            rx_bytes_per_sec: 0.0,
+                            tx_bytes_per_sec: 0.0,
+                            total_received: 0,
+                            total_transmitted: 0,
+                            rx_history: VecDeque::with_capacity(HISTORY_LEN),
+                            tx_history: VecDeque::with_capacity(HISTORY_LEN),
+                        }
+                    }
+                };
+                stats.rx_bytes_per_sec = data.received() as f64 / elapsed;
+                stats.tx_bytes_per_sec = data.transmitted() as f64 / elapsed;
+                stats.total_received = data.total_received();
+                stats.total_transmitted = data.total_transmitted();
+                push_sample(&mut stats.rx_history, stats.rx_bytes_per_sec);
+                push_sample(&mut stats.tx_history, stats.tx_bytes_per_sec);
+                stats
+            })
+            .collect();
+        interfaces.sort_by(|a, b| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+        self.interfaces = interfaces;
+        self.refresh_protocols(elapsed);
+        let identity = NetworkIdentity::read();
+        if identity != self.identity {
+            self.identity = identity;
+            self.identity_changed = Some(Instant::now());
+        }
+        self.last_refresh = Instant::now();
+    }
+
+    fn refresh_protocols(&mut self, elapsed: f64) {
+        let mut totals = std::fs::read_to_string("/proc/net/snmp")
+            .map(|content| parse_snmp(&content))
+            .unwrap_or_default();
+        let v6 = std::fs::read_to_string("/proc/net/snmp6")
+            .map(|content| parse_snmp6(&content))
+            .unwrap_or_default();
+        for (key, value) in v6 {
+            *totals.entry(key).or_insert(0) += value;
+        }
+        self.protocol_rates = totals
+            .iter()
+            .map(|(key, &value)| {
+                let before = self.protocol_totals.get(key).copied().unwrap_or(value);
+                (key.clone(), value.saturating_sub(before) as f64 / elapsed)
+            })
+            .collect();
+        self.protocol_totals = totals;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut history = VecDeque::new();
+        for i in 0..HISTORY_LEN + 5 {
+            push_sample(&mut history, i as f64);
+        }
+        assert_eq!(history.len(), HISTORY_LEN);
+        assert_eq!(history.front(), Some(&5.0));
+    }
+
+    #[test]
+    fn test_classify_interface() {
+        let physical = InterfaceAttributes {
+            has_device: true,
+            ifindex: 2,
+            iflink: 2,
+            arp_type: 1,
+            ..Default::default()
+        };
+        assert_eq!(classify_interface(&physical), InterfaceKind::Physical);
+        let veth = InterfaceAttributes {
+            ifindex: 7,
+            iflink: 6,
+            arp_type: 1,
+            ..Default::default()
+        };
+        assert_eq!(classify_interface(&veth), InterfaceKind::Veth);
+        let tap = InterfaceAttributes {
+            tun_flags: Some(0x1002),
+            ..Default::default()
+        };
+        assert_eq!(classify_interface(&tap), InterfaceKind::Tap);
+        let wg = InterfaceAttributes {
+            devtype: Some("wireguard".to_string()),
+            ..Default::default()
+        };
+        assert!(!classify_interface(&wg).is_virtual());
+
+        let indexes = parse_interface_indexes(
+            "fe8000000000000000fc00fffe000001 0b 40 20 80     eth0\n",
+            "Idx\tDevice    : Count Querier\tGroup    Users Timer\tReporter\n1\tlo        :     1      V3\n\t\t\t\t010000E0     1 0:00000000\t\t0\n",
+        );
+        assert_eq!(
+            indexes,
+            vec![(11, "eth0".to_string()), (1, "lo".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_snmp() {
+        let content = "\
+Icmp: InMsgs InErrors OutMsgs
+Icmp: 5 1 7
+Udp: InDatagrams NoPorts InErrors OutDatagrams RcvbufErrors
+Udp: 8 2 3 8 3
+";
+        let counters = parse_snmp(content);
+        assert_eq!(counters["Icmp.InErrors"], 1);
+        assert_eq!(counters["Udp.RcvbufErrors"], 3);
+
+        let counters6 =
+            parse_snmp6("Icmp6InMsgs                     \t4\nUdp6InErrors 2\nIp6InReceives 9\n");
+        assert_eq!(counters6["Icmp.InMsgs"], 4);
+        assert_eq!(counters6["Udp.InErrors"], 2);
+        assert_eq!(counters6.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_default_route_and_resolv_conf() {
+        let content = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+tun0\t00000000\t0100080A\t0003\t0\t0\t50\t00000000\t0\t0\t0
+wlan0\t0001A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+";
+        let route = parse_default_route(content).unwrap();
+        assert_eq!(route.interface, "tun0");
+        if cfg!(target_endian = "little") {
+            assert_eq!(route.gateway, Ipv4Addr::new(10, 8, 0, 1));
+        }
+
+        let dns = parse_resolv_conf("# comment\nnameserver 1.1.1.1\nsearch lan\nnameserver ::1\n");
+        assert_eq!(dns, vec!["1.1.1.1", "::1"]);
+    }
+}
@@ -0,0 +1,231 @@
+// src/collectors/pagecache.rs
+use crate::export::Format;
+use crate::AppError;
+use clap::Args;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{self, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+#[derive(Args, Debug)]
+pub struct CacheArgs {
+    /// Files or directories (searched recursively) to check instead of the files processes have open or mapped
+    pub paths: Vec<PathBuf>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+
+    /// Number of files to list
+    #[arg(short = 'n', long, default_value_t = 20)]
+    pub limit: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct CachedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    /// Bytes of the file resident in the page cache.
+    pub cached: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    /// `Cached` of `/proc/meminfo`: the page cache as a whole.
+    page_cache: Option<u64>,
+    files: Vec<CachedFile>,
+}
+
+/// How much of `path` is in the page cache, like fincore(1): the file is
+/// mapped without being read and `mincore` tells which pages are resident.
+pub fn resident(path: &Path) -> io::Result<CachedFile> {
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let cached = |cached| CachedFile {
+        path: path.to_path_buf(),
+        size,
+        cached,
+    };
+    if size == 0 {
+        return Ok(cached(0));
+    }
+    let length = size as usize;
+    let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let map = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            length,
+            libc::PROT_READ,
+            libc::MAP_SHARED,
+            file.as_raw_fd(),
+            0,
+        )
+    };
+    if map == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    let mut pages = vec![0u8; length.div_ceil(page)];
+    let result = unsafe { libc::mincore(map, length, pages.as_mut_ptr()) };
+    let err = io::Error::last_os_error();
+    unsafe { libc::munmap(map, length) };
+    if result == -1 {
+        return Err(err);
+    }
+    let resident = pages.iter().filter(|page| *page & 1 == 1).count();
+    Ok(cached((resident as u64 * page as u64).min(size)))
+}
+
+/// Regular files that processes have open or mapped, from `/proc/PID/fd` and
+/// `/proc/PID/maps`. Other users' processes are only visible as root.
+fn open_files() -> BTreeSet<PathBuf> {
+    let mut files = BTreeSet::new();
+    let Ok(proc) = std::fs::read_dir("/proc") else {
+        return files;
+    };
+    for entry in proc.flatten() {
+        let dir = entry.path();
+        if !entry
+            .file_name()
+            .to_string_lossy()
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+        {
+            continue;
+        }
+        if let Ok(fds) = std::fs::read_dir(dir.join("fd")) {
+            files.extend(
+                fds.flatten()
+                    .filter_map(|fd| std::fs::read_link(fd.path()).ok()),
+            );
+        }
+        if let Ok(maps) = std::fs::read_to_string(dir.join("maps")) {
+            files.extend(mapped_files(&maps));
+        }
+    }
+    files.retain(|path| path.is_absolute() && path.is_file());
+    files
+}
+
+/// The paths in a `/proc/PID/maps` listing, without deleted files.
+fn mapped_files(maps: &str) -> impl Iterator<Item = PathBuf> + '_ {
+    maps.lines().filter_map(|line| {
+        let path = line.splitn(6, char::is_whitespace).nth(5)?.trim_start();
+        (path.starts_with('/') && !path.ends_with(" (deleted)")).then(|| PathBuf::from(path))
+    })
+}
+
+/// Regular files in `path`, searched recursively without following symlinks.
+fn walk(path: &Path, files: &mut BTreeSet<PathBuf>) {
+    let Ok(metadata) = path.symlink_metadata() else {
+        return;
+    };
+    if metadata.is_file() {
+        files.insert(path.to_path_buf());
+    } else if metadata.is_dir() {
+        for entry in std::fs::read_dir(path).into_iter().flatten().flatten() {
+            walk(&entry.path(), files);
+        }
+    }
+}
+
+fn page_cache() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|line| line.starts_with("Cached:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// The `limit` files with the most bytes in the page cache, largest first.
+pub fn largest(files: &BTreeSet<PathBuf>, limit: usize) -> Vec<CachedFile> {
+    let mut cached: Vec<CachedFile> = files
+        .iter()
+        .filter_map(|path| resident(path).ok())
+        .filter(|file| file.cached > 0)
+        .collect();
+    cached.sort_by(|a, b| b.cached.cmp(&a.cached).then_with(|| a.path.cmp(&b.path)));
+    cached.truncate(limit);
+    cached
+}
+
+fn size(bytes: u64) -> String {
+    if bytes >= 1 << 30 {
+        format!("{:.2} GiB", bytes as f64 / (1u64 << 30) as f64)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / (1u64 << 20) as f64)
+    }
+}
+
+fn write_text(report: &Report) -> String {
+    let listed: u64 = report.files.iter().map(|file| file.cached).sum();
+    let mut out = match report.page_cache {
+        Some(total) => format!(
+            "Page cache: {}, {} of it in the {} files below\n\n",
+            size(total),
+            size(listed),
+            report.files.len()
+        ),
+        None => String::new(),
+    };
+    out.push_str(&format!(
+        "{:>11} {:>11} {:>5}  File\n",
+        "Cached", "Size", "%"
+    ));
+    for file in &report.files {
+        out.push_str(&format!(
+            "{:>11} {:>11} {:>5.0}  {}\n",
+            size(file.cached),
+            size(file.size),
+            file.cached as f64 / file.size as f64 * 100.0,
+            file.path.display()
+        ));
+    }
+    out
+}
+
+pub fn run(args: CacheArgs) -> Result<(), AppError> {
+    let files = if args.paths.is_empty() {
+        open_files()
+    } else {
+        let mut files = BTreeSet::new();
+        for path in &args.paths {
+            walk(path, &mut files);
+        }
+        files
+    };
+    let report = Report {
+        page_cache: page_cache(),
+        files: largest(&files, args.limit),
+    };
+    let mut out = io::stdout().lock();
+    match args.format {
+        Format::Text => write!(out, "{}", write_text(&report))?,
+        Format::Json => writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resident_pages() {
+        let path = std::env::temp_dir().join(format!("rtop-pagecache-{}", std::process::id()));
+        std::fs::write(&path, vec![7u8; 64 * 1024]).unwrap();
+        // Just written, so the whole file is in the page cache.
+        let file = resident(&path).unwrap();
+        assert_eq!((file.size, file.cached), (64 * 1024, 64 * 1024));
+        let files = BTreeSet::from([path.clone()]);
+        assert_eq!(largest(&files, 5), vec![file]);
+        std::fs::remove_file(&path).unwrap();
+
+        let maps = "7f00-7f01 r--p 00000000 08:01 123    /usr/lib/libc.so.6\n\
+                    7f01-7f02 rw-p 00000000 00:00 0 \n\
+                    7f02-7f03 r--s 00000000 00:05 9      /memfd:x (deleted)\n\
+                    7f03-7f04 r--p 00000000 08:01 77     /tmp/old (deleted)\n";
+        let paths: Vec<PathBuf> = mapped_files(maps).collect();
+        assert_eq!(paths, vec![PathBuf::from("/usr/lib/libc.so.6")]);
+    }
+}
@@ -0,0 +1,212 @@
+// src/collectors/sockets.rs
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use sysinfo::Pid;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    Tcp,
+    Tcp6,
+    Udp,
+    Udp6,
+}
+
+impl Protocol {
+    pub const ALL: [Protocol; 4] = [Protocol::Tcp, Protocol::Tcp6, Protocol::Udp, Protocol::Udp6];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Tcp6 => "tcp6",
+            Protocol::Udp => "udp",
+            Protocol::Udp6 => "udp6",
+        }
+    }
+
+    pub fn is_tcp(self) -> bool {
+        matches!(self, Protocol::Tcp | Protocol::Tcp6)
+    }
+}
+
+/// One row of `/proc/net/{tcp,tcp6,udp,udp6}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SocketEntry {
+    pub protocol: Protocol,
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    /// Kernel state number, see `state_name`.
+    pub state: u8,
+    pub inode: u64,
+}
+
+impl SocketEntry {
+    pub fn state_name(&self) -> &'static str {
+        if !self.protocol.is_tcp() {
+            // UDP sockets only report whether they are connected.
+            return if self.state == 1 { "CONNECTED" } else { "-" };
+        }
+        match self.state {
+            0x01 => "ESTABLISHED",
+            0x02 => "SYN_SENT",
+            0x03 => "SYN_RECV",
+            0x04 => "FIN_WAIT1",
+            0x05 => "FIN_WAIT2",
+            0x06 => "TIME_WAIT",
+            0x07 => "CLOSE",
+            0x08 => "CLOSE_WAIT",
+            0x09 => "LAST_ACK",
+            0x0A => "LISTEN",
+            0x0B => "CLOSING",
+            _ => "UNKNOWN",
+        }
+    }
+}
+
+/// Decodes an `ADDR:PORT` pair as printed by the kernel: the address is hex in
+/// host byte order per 32-bit word, the port is big-endian hex.
+fn parse_address(field: &str) -> Option<SocketAddr> {
+    let (addr, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let ip = match addr.len() {
+        8 => {
+            let word = u32::from_str_radix(addr, 16).ok()?;
+            IpAddr::V4(Ipv4Addr::from(word.to_ne_bytes()))
+        }
+        32 => {
+            let mut bytes = [0u8; 16];
+            for (i, chunk) in bytes.chunks_mut(4).enumerate() {
+                let word = u32::from_str_radix(&addr[i * 8..i * 8 + 8], 16).ok()?;
+                chunk.copy_from_slice(&word.to_ne_bytes());
+            }
+            IpAddr::V6(Ipv6Addr::from(bytes))
+        }
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Parses the contents of a `/proc/net/{tcp,udp}[6]` table, skipping the header.
+pub fn parse_socket_table(protocol: Protocol, content: &str) -> Vec<SocketEntry> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            Some(SocketEntry {
+                protocol,
+                local: parse_address(fields.get(1)?)?,
+                remote: parse_address(fields.get(2)?)?,
+                state: u8::from_str_radix(fields.get(3)?, 16).ok()?,
+                inode: fields.get(9)?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn read_socket_tables(dir: &str, protocols: &[Protocol]) -> Vec<SocketEntry> {
+    protocols
+        .iter()
+        .flat_map(|&protocol| {
+            std::fs::read_to_string(format!("{}/{}", dir, protocol.name()))
+                .map(|content| parse_socket_table(protocol, &content))
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Reads every socket visible in the network namespace of `pid`.
+pub fn read_sockets(pid: Pid) -> Vec<SocketEntry> {
+    read_socket_tables(&format!("/proc/{}/net", pid), &Protocol::ALL)
+}
+
+/// Reads the TCP sockets of rtop's own network namespace.
+pub fn read_host_tcp_sockets() -> Vec<SocketEntry> {
+    read_socket_tables("/proc/net", &[Protocol::Tcp, Protocol::Tcp6])
+}
+
+/// TCP connection counts by state.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionSummary {
+    pub established: usize,
+    pub listen: usize,
+    pub time_wait: usize,
+    /// Connections the peer closed but the local application never did;
+    /// a growing count points at a socket leak.
+    pub close_wait: usize,
+    pub other: usize,
+}
+
+impl ConnectionSummary {
+    pub fn from_sockets(sockets: &[SocketEntry]) -> ConnectionSummary {
+        let mut summary = ConnectionSummary::default();
+        for socket in sockets.iter().filter(|s| s.protocol.is_tcp()) {
+            match socket.state_name() {
+                "ESTABLISHED" => summary.established += 1,
+                "LISTEN" => summary.listen += 1,
+                "TIME_WAIT" => summary.time_wait += 1,
+                "CLOSE_WAIT" => summary.close_wait += 1,
+                _ => summary.other += 1,
+            }
+        }
+        summary
+    }
+}
+
+/// Inodes of the sockets held open by `pid`, taken from its `/proc/PID/fd` links.
+#[cfg(target_os = "linux")]
+pub fn socket_inodes(pid: Pid) -> Vec<u64> {
+    let Ok(entries) = std::fs::read_dir(format!("/proc/{}/fd", pid)) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let target = std::fs::read_link(entry.ok()?.path()).ok()?;
+            let target = target.to_str()?;
+            target
+                .strip_prefix("socket:[")?
+                .strip_suffix(']')?
+                .parse()
+                .ok()
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn socket_inodes(_pid: Pid) -> Vec<u64> {
+    Vec::new()
+}
+
+/// The TCP and UDP sockets owned by `pid`.
+pub fn process_sockets(pid: Pid) -> Vec<SocketEntry> {
+    let inodes = socket_inodes(pid);
+    if inodes.is_empty() {
+        return Vec::new();
+    }
+    read_sockets(pid)
+        .into_iter()
+        .filter(|socket| inodes.contains(&socket.inode))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_socket_table() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0277 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 662 1 0000000000000000 100 0 0 10 0
+";
+        let sockets = parse_socket_table(Protocol::Tcp, table);
+        assert_eq!(sockets.len(), 1);
+        if cfg!(target_endian = "little") {
+            assert_eq!(sockets[0].local, "127.0.0.1:631".parse().unwrap());
+        }
+        assert_eq!(sockets[0].state_name(), "LISTEN");
+        assert_eq!(sockets[0].inode, 662);
+
+        let summary = ConnectionSummary::from_sockets(&sockets);
+        assert_eq!(summary.listen, 1);
+        assert_eq!(summary.established, 0);
+    }
+}
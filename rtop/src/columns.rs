@@ -0,0 +1,106 @@
+// src/columns.rs
+use crate::config::Column;
+
+/// The column setup screen: every column with whether it is shown, in
+/// display order, like htop's F2 screen.
+pub struct ColumnEditor {
+    pub entries: Vec<(Column, bool)>,
+    pub cursor: usize,
+}
+
+impl ColumnEditor {
+    /// Starts with the `shown` columns in their order, then the hidden ones
+    /// out of `available`.
+    pub fn new(shown: &[Column], available: impl Iterator<Item = Column>) -> ColumnEditor {
+        let mut entries: Vec<(Column, bool)> = shown.iter().map(|&column| (column, true)).collect();
+        entries.extend(
+            available
+                .filter(|column| !shown.contains(column))
+                .map(|column| (column, false)),
+        );
+        ColumnEditor { entries, cursor: 0 }
+    }
+
+    pub fn select(&mut self, down: bool) {
+        self.cursor = if down {
+            (self.cursor + 1).min(self.entries.len().saturating_sub(1))
+        } else {
+            self.cursor.saturating_sub(1)
+        };
+    }
+
+    /// Shows or hides the column under the cursor; the last shown column stays.
+    pub fn toggle(&mut self) {
+        let shown = self.columns().len();
+        if let Some((_, visible)) = self.entries.get_mut(self.cursor) {
+            if !*visible || shown > 1 {
+                *visible = !*visible;
+            }
+        }
+    }
+
+    /// Moves the column under the cursor one place left (up) or right (down).
+    pub fn move_selected(&mut self, down: bool) {
+        let target = if down {
+            self.cursor + 1
+        } else {
+            self.cursor.wrapping_sub(1)
+        };
+        if target < self.entries.len() {
+            self.entries.swap(self.cursor, target);
+            self.cursor = target;
+        }
+    }
+
+    /// The shown columns, in order.
+    pub fn columns(&self) -> Vec<Column> {
+        self.entries
+            .iter()
+            .filter(|(_, visible)| *visible)
+            .map(|(column, _)| *column)
+            .collect()
+    }
+
+    /// The `columns` line for the config file that keeps this setup.
+    pub fn config_line(&self) -> String {
+        let keys: Vec<String> = self
+            .columns()
+            .iter()
+            .map(|column| format!("\"{}\"", column.key()))
+            .collect();
+        format!("columns = [{}]", keys.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_columns() {
+        let mut editor = ColumnEditor::new(
+            &[Column::Pid, Column::Name],
+            [Column::Pid, Column::Name, Column::User].into_iter(),
+        );
+        assert_eq!(editor.entries.len(), 3);
+
+        // Show User and move it in front of Name.
+        editor.select(true);
+        editor.select(true);
+        editor.toggle();
+        editor.move_selected(false);
+        assert_eq!(editor.columns(), vec![Column::Pid, Column::User, Column::Name]);
+        assert_eq!(editor.config_line(), r#"columns = ["pid", "user", "name"]"#);
+
+        // The last shown column cannot be hidden.
+        editor.cursor = 0;
+        editor.toggle();
+        editor.select(true);
+        editor.toggle();
+        editor.select(true);
+        editor.toggle();
+        assert_eq!(editor.columns(), vec![Column::Name]);
+        editor.move_selected(true);
+        assert_eq!(editor.cursor, 2);
+    }
+}
@@ -0,0 +1,706 @@
+// src/config.rs
+use crate::actions::UserAction;
+use crate::alerts::AlertRule;
+use crate::capture::CaptureConfig;
+use crate::collectors::background::CollectorsConfig;
+use crate::control::PROTECTED;
+use crate::hooks::Hooks;
+use crate::keymap::{Action, KeyBinding};
+use crate::notify::NotifyConfig;
+use crate::plugins::PluginsConfig;
+use crate::rotate::RotateConfig;
+use crate::silence::MaintenanceWindow;
+use crate::theme::ColorDepth;
+use crate::watchdog::WatchdogConfig;
+use crate::{AppError, SortBy, Tab};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A column of the process table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    Pid,
+    Name,
+    /// Owner's user name.
+    User,
+    /// Value of the `group_env` environment variable.
+    Group,
+    /// One-letter state as in ps: R, S, D, Z, T...
+    State,
+    /// Kernel function the process sleeps in, from `/proc/PID/wchan` (Linux).
+    Wchan,
+    /// Nice value, -20 (highest priority) to 19.
+    Nice,
+    Threads,
+    Cpu,
+    /// Sparkline of recent CPU usage, to tell spiky from steady consumers.
+    #[serde(rename = "cpu_trend")]
+    CpuTrend,
+    Rss,
+    Virtual,
+    /// Anonymous resident memory (heap, stacks), which no other process or
+    /// file shares, from `RssAnon` in `/proc/PID/status`.
+    Private,
+    /// Swapped-out memory, from `VmSwap` in `/proc/PID/status`.
+    Swap,
+    /// GPU busy time in percent, like CPU%.
+    #[serde(rename = "gpu")]
+    GpuUsage,
+    /// GPU memory, with the `nvidia` feature and a supported GPU, or VRAM on
+    /// AMD and Intel GPUs.
+    #[serde(rename = "gpu_memory")]
+    GpuMemory,
+    /// Disk reads and writes, in bytes per second.
+    #[serde(rename = "disk_read")]
+    DiskRead,
+    #[serde(rename = "disk_write")]
+    DiskWrite,
+    /// TCP traffic, in bytes per second; see `netio`.
+    #[serde(rename = "net_sent")]
+    NetSent,
+    #[serde(rename = "net_received")]
+    NetReceived,
+    /// Full command line.
+    Command,
+}
+
+impl Column {
+    pub const ALL: [Column; 21] = [
+        Column::Pid,
+        Column::Name,
+        Column::User,
+        Column::Group,
+        Column::State,
+        Column::Wchan,
+        Column::Nice,
+        Column::Threads,
+        Column::Cpu,
+        Column::CpuTrend,
+        Column::Rss,
+        Column::Virtual,
+        Column::Private,
+        Column::Swap,
+        Column::GpuUsage,
+        Column::GpuMemory,
+        Column::DiskRead,
+        Column::DiskWrite,
+        Column::NetSent,
+        Column::NetReceived,
+        Column::Command,
+    ];
+
+    /// The name used in the config file and JSON exports.
+    pub fn key(self) -> &'static str {
+        match self {
+            Column::Pid => "pid",
+            Column::Name => "name",
+            Column::User => "user",
+            Column::Group => "group",
+            Column::State => "state",
+            Column::Wchan => "wchan",
+            Column::Nice => "nice",
+            Column::Threads => "threads",
+            Column::Cpu => "cpu",
+            Column::CpuTrend => "cpu_trend",
+            Column::Rss => "rss",
+            Column::Virtual => "virtual",
+            Column::Private => "private",
+            Column::Swap => "swap",
+            Column::GpuUsage => "gpu",
+            Column::GpuMemory => "gpu_memory",
+            Column::DiskRead => "disk_read",
+            Column::DiskWrite => "disk_write",
+            Column::NetSent => "net_sent",
+            Column::NetReceived => "net_received",
+            Column::Command => "command",
+        }
+    }
+
+    /// Whether the column holds a size in bytes (per second for disk I/O).
+    pub fn is_size(self) -> bool {
+        matches!(
+            self,
+            Column::Rss
+                | Column::Virtual
+                | Column::Private
+                | Column::Swap
+                | Column::GpuMemory
+                | Column::DiskRead
+                | Column::DiskWrite
+                | Column::NetSent
+                | Column::NetReceived
+        )
+    }
+
+    /// Network columns walk every process's file descriptors on each refresh,
+    /// so they are only sampled while shown and are off by default.
+    pub fn is_network(self) -> bool {
+        matches!(self, Column::NetSent | Column::NetReceived)
+    }
+
+    /// Columns only available while a GPU is found; on Linux they read every
+    /// process's `fdinfo` while shown.
+    pub fn is_gpu(self) -> bool {
+        matches!(self, Column::GpuUsage | Column::GpuMemory)
+    }
+
+    /// Columns read from `/proc/PID/status` on each refresh while shown.
+    pub fn reads_status(self) -> bool {
+        matches!(self, Column::Threads | Column::Private | Column::Swap)
+    }
+
+    /// The columns of the table when the config does not list them.
+    fn shown_by_default(self) -> bool {
+        !self.is_network()
+            && !matches!(
+                self,
+                Column::Group
+                    | Column::State
+                    | Column::Wchan
+                    | Column::CpuTrend
+                    | Column::Threads
+                    | Column::Swap
+                    | Column::Command
+            )
+    }
+
+    /// Precision and unit used when the config has no `[format]` entry.
+    fn default_format(self) -> (usize, Option<Unit>) {
+        match self {
+            Column::Cpu | Column::GpuUsage => (1, None),
+            Column::Rss | Column::Private | Column::Swap | Column::GpuMemory => {
+                (1, Some(Unit::Mib))
+            }
+            Column::Virtual => (2, Some(Unit::Gib)),
+            Column::DiskRead | Column::DiskWrite | Column::NetSent | Column::NetReceived => {
+                (0, Some(Unit::KiB))
+            }
+            Column::Pid
+            | Column::Name
+            | Column::User
+            | Column::Group
+            | Column::State
+            | Column::Wchan
+            | Column::CpuTrend
+            | Column::Nice
+            | Column::Threads
+            | Column::Command => (0, None),
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Column::Pid => "PID",
+            Column::Name => "Name",
+            Column::User => "User",
+            Column::Group => "Group",
+            Column::State => "S",
+            Column::Wchan => "WCHAN",
+            Column::Nice => "NI",
+            Column::Threads => "Thr",
+            Column::Cpu => "CPU%",
+            Column::CpuTrend => "CPU Trend",
+            Column::Rss => "RSS",
+            Column::Virtual => "Virtual",
+            Column::Private => "Private",
+            Column::Swap => "Swap",
+            Column::GpuUsage => "GPU%",
+            Column::GpuMemory => "GPU Mem",
+            Column::DiskRead => "Read/s",
+            Column::DiskWrite => "Write/s",
+            Column::NetSent => "Sent/s",
+            Column::NetReceived => "Recv/s",
+            Column::Command => "Command",
+        }
+    }
+}
+
+/// A unit for memory columns: binary (KiB, MiB, GiB) or decimal (KB, MB, GB).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Unit {
+    B,
+    KiB,
+    #[serde(rename = "MiB")]
+    Mib,
+    #[serde(rename = "GiB")]
+    Gib,
+    #[serde(rename = "KB")]
+    Kb,
+    #[serde(rename = "MB")]
+    Mb,
+    #[serde(rename = "GB")]
+    Gb,
+}
+
+impl Unit {
+    pub fn bytes(self) -> f64 {
+        match self {
+            Unit::B => 1.0,
+            Unit::KiB => 1024.0,
+            Unit::Mib => 1024.0 * 1024.0,
+            Unit::Gib => 1024.0 * 1024.0 * 1024.0,
+            Unit::Kb => 1e3,
+            Unit::Mb => 1e6,
+            Unit::Gb => 1e9,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Unit::B => "B",
+            Unit::KiB => "KiB",
+            Unit::Mib => "MiB",
+            Unit::Gib => "GiB",
+            Unit::Kb => "KB",
+            Unit::Mb => "MB",
+            Unit::Gb => "GB",
+        }
+    }
+}
+
+/// A `[format.COLUMN]` entry: decimals and, for memory columns, the unit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NumberFormat {
+    pub precision: Option<usize>,
+    pub unit: Option<Unit>,
+}
+
+/// Values at which usage turns yellow (warning) or red (critical).
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+    /// Per-process CPU%.
+    pub process_cpu_warning: f32,
+    pub process_cpu_critical: f32,
+    /// Per-process resident memory in MB.
+    pub process_memory_warning: f64,
+    pub process_memory_critical: f64,
+    /// Per-core gauges, in percent.
+    pub core_warning: f32,
+    pub core_critical: f32,
+    /// CLOSE_WAIT connections that raise the socket leak alert.
+    pub close_wait: usize,
+    /// Swap gauge, in percent of the swap space used.
+    pub swap_warning: f64,
+    pub swap_critical: f64,
+    /// Refreshes in a row in uninterruptible sleep (D state) after which a
+    /// process counts as stuck; 0 turns the check off.
+    pub d_state_refreshes: u32,
+    /// Standard deviations above its own recent norm at which a process's
+    /// CPU% or RSS is highlighted; 0 turns the check off.
+    pub anomaly_z: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Thresholds {
+        Thresholds {
+            process_cpu_warning: 20.0,
+            process_cpu_critical: 50.0,
+            process_memory_warning: 500.0,
+            process_memory_critical: 1000.0,
+            core_warning: 50.0,
+            core_critical: 80.0,
+            close_wait: 50,
+            swap_warning: 25.0,
+            swap_critical: 50.0,
+            d_state_refreshes: 20,
+            anomaly_z: 3.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    /// View shown at startup.
+    pub tab: Tab,
+    pub show_per_core: bool,
+    /// Chart of total CPU usage below the gauges; `h` toggles it.
+    pub cpu_history: bool,
+    /// Chart of used memory and swap next to it; `M` toggles it.
+    pub memory_history: bool,
+    /// Seconds the history charts span.
+    pub history_seconds: u64,
+    /// Alternate row backgrounds in the process table, in the theme's `stripe`.
+    pub zebra: bool,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> LayoutConfig {
+        LayoutConfig {
+            tab: Tab::Processes,
+            show_per_core: false,
+            cpu_history: false,
+            memory_history: false,
+            history_seconds: 60,
+            zebra: false,
+        }
+    }
+}
+
+/// Terminal sizes that can each have their own `[layouts.*]` entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SizeClass {
+    Small,
+    Medium,
+    Large,
+}
+
+impl SizeClass {
+    /// Small below 100x30 in either direction, large from 180x50, medium in between.
+    pub fn of(width: u16, height: u16) -> SizeClass {
+        if width < 100 || height < 30 {
+            SizeClass::Small
+        } else if width >= 180 && height >= 50 {
+            SizeClass::Large
+        } else {
+            SizeClass::Medium
+        }
+    }
+}
+
+/// A `[layouts.small|medium|large]` entry, applied while the terminal is of
+/// that size. Unset `columns`, `show_per_core` and `zebra` fall back to the
+/// top-level settings; the other keys hide parts of the screen.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SizeLayout {
+    pub columns: Option<Vec<Column>>,
+    /// Applied when the terminal enters this size; `1` still toggles it.
+    pub show_per_core: Option<bool>,
+    pub zebra: Option<bool>,
+    /// The uptime/load/tasks line.
+    pub summary: bool,
+    /// The CPU, memory, swap, connections and battery gauges.
+    pub gauges: bool,
+    pub help: bool,
+}
+
+impl Default for SizeLayout {
+    fn default() -> SizeLayout {
+        SizeLayout {
+            columns: None,
+            show_per_core: None,
+            zebra: None,
+            summary: true,
+            gauges: true,
+            help: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Layouts {
+    pub small: Option<SizeLayout>,
+    pub medium: Option<SizeLayout>,
+    pub large: Option<SizeLayout>,
+}
+
+impl Layouts {
+    pub fn get(&self, class: SizeClass) -> Option<&SizeLayout> {
+        match class {
+            SizeClass::Small => self.small.as_ref(),
+            SizeClass::Medium => self.medium.as_ref(),
+            SizeClass::Large => self.large.as_ref(),
+        }
+    }
+}
+
+/// Defaults read from `~/.config/rtop/config.toml`; command-line flags take precedence.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Refresh rate in milliseconds.
+    pub refresh_rate: u64,
+    pub sort: SortBy,
+    /// Process table columns, in display order.
+    pub columns: Vec<Column>,
+    /// Number formats of process table columns, in the UI and in exports.
+    pub format: HashMap<Column, NumberFormat>,
+    pub thresholds: Thresholds,
+    pub layout: LayoutConfig,
+    /// Overrides per terminal size, picked again whenever the terminal is resized.
+    pub layouts: Layouts,
+    /// Key bindings that replace the defaults of their action; see `rtop tui --dump-keymap`.
+    pub keys: HashMap<Action, Vec<KeyBinding>>,
+    /// Commands bound to keys, run against the selected process.
+    pub actions: Vec<UserAction>,
+    /// A built-in theme or one of `themes`.
+    pub theme: String,
+    /// Custom themes: color overrides on top of a `base` theme.
+    pub themes: HashMap<String, HashMap<String, String>>,
+    /// Colors the terminal supports, overriding the detection from
+    /// `NO_COLOR`, `COLORTERM` and `TERM`.
+    pub color_depth: Option<ColorDepth>,
+    /// Environment variable whose value groups processes, such as
+    /// `KUBERNETES_POD_NAME`; see the `group` column.
+    pub group_env: Option<String>,
+    /// How long `s` in the Alerts tab silences a rule, in minutes.
+    pub silence_minutes: u64,
+    /// Keys that unlock the session lock; without it, any key and Enter.
+    pub unlock_sequence: Option<String>,
+    /// Recurring windows in which alert rules are silenced.
+    pub maintenance: Vec<MaintenanceWindow>,
+    /// Records typical usage by hour of the day and compares against it.
+    pub baseline: bool,
+    /// Adds the `d_state` rule, firing on stuck processes, to the Alerts tab.
+    pub d_state_alert: bool,
+    /// User-defined rules, shown in the Alerts tab after the built-in ones.
+    pub alerts: Vec<AlertRule>,
+    /// Commands run when the UI starts and exits.
+    pub hooks: Hooks,
+    /// Samples recorded to a file when an alert starts firing.
+    pub capture: CaptureConfig,
+    /// Desktop notifications when an alert starts firing.
+    pub notify: NotifyConfig,
+    /// Flashes a full-screen banner when an alert turns critical, until
+    /// Enter is pressed.
+    pub alarm: bool,
+    /// Rules that signal runaway processes by themselves.
+    pub watchdog: WatchdogConfig,
+    /// Cycling through tabs on a timer.
+    pub rotate: RotateConfig,
+    /// Milliseconds between reads of disks, network and the like.
+    pub collectors: CollectorsConfig,
+    /// Panels of the Plugins tab.
+    pub plugins: PluginsConfig,
+    /// Process names that signals need a typed "yes" for; replaces
+    /// `control::PROTECTED`.
+    pub protected: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            refresh_rate: 250,
+            sort: SortBy::Cpu,
+            columns: Column::ALL
+                .into_iter()
+                .filter(|column| column.shown_by_default())
+                .collect(),
+            format: HashMap::new(),
+            thresholds: Thresholds::default(),
+            layout: LayoutConfig::default(),
+            layouts: Layouts::default(),
+            keys: HashMap::new(),
+            actions: Vec::new(),
+            theme: "default".to_string(),
+            themes: HashMap::new(),
+            color_depth: None,
+            group_env: None,
+            silence_minutes: 60,
+            unlock_sequence: None,
+            maintenance: Vec::new(),
+            baseline: true,
+            d_state_alert: false,
+            alerts: Vec::new(),
+            hooks: Hooks::default(),
+            capture: CaptureConfig::default(),
+            notify: NotifyConfig::default(),
+            alarm: false,
+            watchdog: WatchdogConfig::default(),
+            rotate: RotateConfig::default(),
+            collectors: CollectorsConfig::default(),
+            plugins: PluginsConfig::default(),
+            protected: PROTECTED.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/rtop/config.toml`, falling back to `~/.config`.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(base.join("rtop").join("config.toml"))
+    }
+
+    /// `$XDG_STATE_HOME/rtop`, falling back to `~/.local/state`: where the
+    /// baseline and alert captures are kept.
+    pub fn state_dir() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .map(|home| PathBuf::from(home).join(".local").join("state"))
+            })?;
+        Some(base.join("rtop"))
+    }
+
+    pub fn parse(content: &str) -> Result<Config, AppError> {
+        let config: Config = toml::from_str(content)?;
+        for (column, format) in &config.format {
+            if format.unit.is_some() && !column.is_size() {
+                return Err(AppError::Config(serde::de::Error::custom(format!(
+                    "format.{}: only memory columns have a unit",
+                    column.key()
+                ))));
+            }
+        }
+        for window in &config.maintenance {
+            window
+                .validate()
+                .map_err(|err| AppError::Config(serde::de::Error::custom(err)))?;
+        }
+        for (i, rule) in config.alerts.iter().enumerate() {
+            rule.validate()
+                .map_err(|err| AppError::Config(serde::de::Error::custom(err)))?;
+            if config.alerts[..i]
+                .iter()
+                .any(|other| other.name == rule.name)
+            {
+                return Err(AppError::Config(serde::de::Error::custom(format!(
+                    "alerts: '{}' is defined twice",
+                    rule.name
+                ))));
+            }
+        }
+        config
+            .watchdog
+            .validate()
+            .map_err(|err| AppError::Config(serde::de::Error::custom(err)))?;
+        Ok(config)
+    }
+
+    /// Precision and unit of `column` in the process table.
+    pub fn number_format(&self, column: Column) -> (usize, Option<Unit>) {
+        let (precision, unit) = column.default_format();
+        let custom = self.format.get(&column).copied().unwrap_or_default();
+        (custom.precision.unwrap_or(precision), custom.unit.or(unit))
+    }
+
+    /// Precision and unit of `column` in CSV/JSON exports. Sizes stay in bytes
+    /// unless the column has a `[format]` entry, so spreadsheets can do the math.
+    pub fn export_format(&self, column: Column) -> (usize, Option<Unit>) {
+        if column.is_size() && !self.format.contains_key(&column) {
+            return (0, None);
+        }
+        self.number_format(column)
+    }
+
+    /// Formats `value` (a percentage, or bytes for memory columns) for the process table.
+    pub fn format_number(&self, column: Column, value: f64) -> String {
+        match self.number_format(column) {
+            (precision, Some(unit)) => {
+                format!("{:.*} {}", precision, value / unit.bytes(), unit.label())
+            }
+            (precision, None) => format!("{:.*}", precision, value),
+        }
+    }
+
+    /// Loads `path`, or the default location when `None`. A missing default
+    /// file yields the built-in defaults; an explicitly given one must exist.
+    pub fn load(path: Option<&Path>) -> Result<Config, AppError> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Config::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Config::parse(&content),
+            Err(err) if !required && err.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Config::default())
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_format() {
+        let config = Config::parse(
+            r#"
+[format.cpu]
+precision = 0
+
+[format.rss]
+unit = "GiB"
+precision = 2
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.format_number(Column::Cpu, 12.46), "12");
+        assert_eq!(config.format_number(Column::Rss, 1610612736.0), "1.50 GiB");
+        assert_eq!(
+            config.format_number(Column::Virtual, 1610612736.0),
+            "1.50 GiB"
+        );
+        assert_eq!(config.format_number(Column::Private, 1572864.0), "1.5 MiB");
+        assert_eq!(config.export_format(Column::Rss), (2, Some(Unit::Gib)));
+        assert_eq!(config.export_format(Column::Private), (0, None));
+    }
+
+    #[test]
+    fn test_parse_partial_config() {
+        let config = Config::parse(
+            r#"
+refresh_rate = 1000
+sort = "memory"
+columns = ["pid", "name", "rss"]
+
+[thresholds]
+close_wait = 10
+
+[layout]
+tab = "network"
+
+[keys]
+down = ["j", "Down"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.refresh_rate, 1000);
+        assert_eq!(config.sort, SortBy::Memory);
+        assert_eq!(config.columns, vec![Column::Pid, Column::Name, Column::Rss]);
+        assert_eq!(config.thresholds.close_wait, 10);
+        assert_eq!(config.thresholds.core_critical, 80.0);
+        assert_eq!(config.layout.tab, Tab::Network);
+        assert!(!config.layout.show_per_core);
+        assert_eq!(config.keys[&Action::Down].len(), 2);
+
+        assert!(Config::parse("refresh = 5").is_err());
+        assert!(Config::parse("[layouts.tiny]").is_err());
+        assert!(Config::parse("[format.cpu]\nunit = \"MB\"").is_err());
+        assert!(Config::parse("[[maintenance]]\nstart = \"25:00\"\nend = \"01:00\"").is_err());
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_size_layouts() {
+        let config = Config::parse(
+            r#"
+[layouts.small]
+columns = ["pid", "name", "cpu"]
+gauges = false
+show_per_core = false
+zebra = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(SizeClass::of(80, 24), SizeClass::Small);
+        assert_eq!(SizeClass::of(200, 24), SizeClass::Small);
+        assert_eq!(SizeClass::of(120, 40), SizeClass::Medium);
+        assert_eq!(SizeClass::of(200, 60), SizeClass::Large);
+
+        let small = config.layouts.get(SizeClass::Small).unwrap();
+        assert_eq!(
+            small.columns.as_deref(),
+            Some(&[Column::Pid, Column::Name, Column::Cpu][..])
+        );
+        assert!(!small.gauges);
+        assert!(small.summary && small.help);
+        assert_eq!(small.zebra, Some(true));
+        assert_eq!(config.layouts.get(SizeClass::Medium), None);
+    }
+}
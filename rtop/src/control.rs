@@ -0,0 +1,385 @@
+// src/control.rs
+use std::io;
+use sysinfo::{Pid, PidExt, Signal, System, SystemExt};
+
+/// Process names a signal only goes to after typing "yes", as losing them
+/// can lock you out over SSH or end the desktop session. Init and rtop
+/// itself are always protected.
+pub const PROTECTED: [&str; 16] = [
+    "systemd",
+    "init",
+    "launchd",
+    "sshd",
+    "sshd-session",
+    "dbus-daemon",
+    "gdm",
+    "gdm3",
+    "sddm",
+    "lightdm",
+    "Xorg",
+    "Xwayland",
+    "gnome-shell",
+    "kwin_wayland",
+    "WindowServer",
+    "loginwindow",
+];
+
+/// Whether `pid`, called `name`, is init, rtop or in `protected`.
+pub fn is_protected(pid: Pid, name: &str, protected: &[String]) -> bool {
+    pid.as_u32() == 1 || pid.as_u32() == std::process::id() || protected.iter().any(|p| p == name)
+}
+
+/// Nice values the kernel accepts, from highest to lowest priority.
+pub const NICE_RANGE: std::ops::RangeInclusive<i32> = -20..=19;
+
+/// The nice value of `pid`.
+pub fn nice(pid: Pid) -> io::Result<i32> {
+    // -1 is a valid nice value, so only errno tells an error apart.
+    clear_errno();
+    let value = unsafe { libc::getpriority(libc::PRIO_PROCESS, pid.as_u32()) };
+    match io::Error::last_os_error() {
+        err if value == -1 && err.raw_os_error() != Some(0) => Err(err),
+        _ => Ok(value),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn clear_errno() {
+    unsafe { *libc::__errno_location() = 0 };
+}
+
+#[cfg(target_vendor = "apple")]
+fn clear_errno() {
+    unsafe { *libc::__error() = 0 };
+}
+
+/// Sets the nice value of `pid`, clamped to `NICE_RANGE`, and returns it.
+/// Lowering it below the current value needs root (or `CAP_SYS_NICE`).
+pub fn set_nice(pid: Pid, value: i32) -> io::Result<i32> {
+    let value = value.clamp(*NICE_RANGE.start(), *NICE_RANGE.end());
+    for_each_thread(pid, |tid| unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, tid, value)
+    })?;
+    Ok(value)
+}
+
+/// Which of the first `cpus` CPUs `pid` may run on.
+#[cfg(target_os = "linux")]
+pub fn affinity(pid: Pid, cpus: usize) -> io::Result<Vec<bool>> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::cpu_set_t>();
+    if unsafe { libc::sched_getaffinity(pid.as_u32() as libc::pid_t, size, &mut set) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok((0..cpus)
+        .map(|cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+        .collect())
+}
+
+/// Lets `pid` run only on the CPUs set in `allowed`, like `taskset -a -p`.
+#[cfg(target_os = "linux")]
+pub fn set_affinity(pid: Pid, allowed: &[bool]) -> io::Result<()> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for (cpu, _) in allowed.iter().enumerate().filter(|(_, allowed)| **allowed) {
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    let size = std::mem::size_of::<libc::cpu_set_t>();
+    for_each_thread(pid, |tid| unsafe {
+        libc::sched_setaffinity(tid as libc::pid_t, size, &set)
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn affinity(_pid: Pid, _cpus: usize) -> io::Result<Vec<bool>> {
+    Err(affinity_unsupported())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_affinity(_pid: Pid, _allowed: &[bool]) -> io::Result<()> {
+    Err(affinity_unsupported())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn affinity_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "CPU affinity is only supported on Linux",
+    )
+}
+
+/// Runs the syscall `call` for every thread of `pid`. Linux keeps nice values
+/// and affinity per thread, and calls with the PID only change the main one.
+fn for_each_thread(pid: Pid, call: impl Fn(u32) -> libc::c_int) -> io::Result<()> {
+    let mut threads: Vec<u32> = std::fs::read_dir(format!("/proc/{}/task", pid))
+        .map(|dir| {
+            dir.filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    if threads.is_empty() {
+        threads.push(pid.as_u32());
+    }
+    for tid in threads {
+        if call(tid) == -1 {
+            let err = io::Error::last_os_error();
+            // Threads may exit while we go.
+            if tid == pid.as_u32() || err.raw_os_error() != Some(libc::ESRCH) {
+                return Err(err);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// CPU numbers in `allowed` as a list like `0-3,6`, as `taskset -c` takes them.
+pub fn cpu_list(allowed: &[bool]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (cpu, _) in allowed.iter().enumerate().filter(|(_, allowed)| **allowed) {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == cpu => *end = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    let ranges: Vec<String> = ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect();
+    ranges.join(",")
+}
+
+/// The affinity editor of the process details: a checkbox per CPU.
+pub struct AffinityEditor {
+    pub pid: Pid,
+    pub allowed: Vec<bool>,
+    pub cursor: usize,
+}
+
+impl AffinityEditor {
+    pub fn new(pid: Pid, allowed: Vec<bool>) -> AffinityEditor {
+        AffinityEditor {
+            pid,
+            allowed,
+            cursor: 0,
+        }
+    }
+
+    /// Moves the cursor by `step` CPUs, staying on the list.
+    pub fn select(&mut self, step: isize) {
+        let last = self.allowed.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(step).min(last);
+    }
+
+    /// Allows or forbids the CPU under the cursor; one CPU always stays allowed.
+    pub fn toggle(&mut self) {
+        let count = self.allowed.iter().filter(|allowed| **allowed).count();
+        if let Some(allowed) = self.allowed.get_mut(self.cursor) {
+            if !*allowed || count > 1 {
+                *allowed = !*allowed;
+            }
+        }
+    }
+
+    pub fn allow_all(&mut self) {
+        self.allowed.iter_mut().for_each(|allowed| *allowed = true);
+    }
+}
+
+/// A signal of the kill menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SignalEntry {
+    pub signal: Signal,
+    pub name: &'static str,
+    pub number: i32,
+    pub description: &'static str,
+}
+
+/// The signal called `name`, such as `SIGTERM`, `TERM` or `term`.
+pub fn signal_named(name: &str) -> Option<Signal> {
+    let name = name.to_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    System::SUPPORTED_SIGNALS
+        .iter()
+        .copied()
+        .find(|&signal| describe(signal).0[3..] == *name)
+}
+
+/// The name of `signal`, like `SIGTERM`.
+pub fn signal_name(signal: Signal) -> &'static str {
+    describe(signal).0
+}
+
+/// Sends `signal` to `pid`, with the reason if it cannot.
+pub fn send_signal(pid: Pid, signal: Signal) -> io::Result<()> {
+    if unsafe { libc::kill(pid.as_u32() as libc::pid_t, describe(signal).1) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Name, number and use of `signal`.
+fn describe(signal: Signal) -> (&'static str, i32, &'static str) {
+    match signal {
+        Signal::Hangup => ("SIGHUP", libc::SIGHUP, "hangup; many daemons reload"),
+        Signal::Interrupt => ("SIGINT", libc::SIGINT, "interrupt, like Ctrl+C"),
+        Signal::Quit => ("SIGQUIT", libc::SIGQUIT, "quit with a core dump"),
+        Signal::Illegal => ("SIGILL", libc::SIGILL, "illegal instruction"),
+        Signal::Trap => ("SIGTRAP", libc::SIGTRAP, "breakpoint trap"),
+        Signal::Abort => ("SIGABRT", libc::SIGABRT, "abort with a core dump"),
+        Signal::IOT => ("SIGIOT", libc::SIGIOT, "abort (synonym)"),
+        Signal::Bus => ("SIGBUS", libc::SIGBUS, "bus error"),
+        Signal::FloatingPointException => ("SIGFPE", libc::SIGFPE, "arithmetic error"),
+        Signal::Kill => ("SIGKILL", libc::SIGKILL, "kill; cannot be caught"),
+        Signal::User1 => ("SIGUSR1", libc::SIGUSR1, "user-defined 1"),
+        Signal::Segv => ("SIGSEGV", libc::SIGSEGV, "invalid memory access"),
+        Signal::User2 => ("SIGUSR2", libc::SIGUSR2, "user-defined 2"),
+        Signal::Pipe => ("SIGPIPE", libc::SIGPIPE, "broken pipe"),
+        Signal::Alarm => ("SIGALRM", libc::SIGALRM, "timer"),
+        Signal::Term => ("SIGTERM", libc::SIGTERM, "terminate gracefully"),
+        Signal::Child => ("SIGCHLD", libc::SIGCHLD, "child stopped or exited"),
+        Signal::Continue => ("SIGCONT", libc::SIGCONT, "resume if stopped"),
+        Signal::Stop => ("SIGSTOP", libc::SIGSTOP, "suspend; cannot be caught"),
+        Signal::TSTP => ("SIGTSTP", libc::SIGTSTP, "suspend, like Ctrl+Z"),
+        Signal::TTIN => ("SIGTTIN", libc::SIGTTIN, "background terminal read"),
+        Signal::TTOU => ("SIGTTOU", libc::SIGTTOU, "background terminal write"),
+        Signal::Urgent => ("SIGURG", libc::SIGURG, "urgent socket data"),
+        Signal::XCPU => ("SIGXCPU", libc::SIGXCPU, "CPU time limit exceeded"),
+        Signal::XFSZ => ("SIGXFSZ", libc::SIGXFSZ, "file size limit exceeded"),
+        Signal::VirtualAlarm => ("SIGVTALRM", libc::SIGVTALRM, "virtual timer"),
+        Signal::Profiling => ("SIGPROF", libc::SIGPROF, "profiling timer"),
+        Signal::Winch => ("SIGWINCH", libc::SIGWINCH, "terminal resized"),
+        Signal::IO => ("SIGIO", libc::SIGIO, "I/O possible"),
+        #[cfg(target_os = "linux")]
+        Signal::Poll => ("SIGPOLL", libc::SIGPOLL, "I/O possible (synonym)"),
+        // Elsewhere SIGPOLL is SIGIO, as sysinfo sends it.
+        #[cfg(not(target_os = "linux"))]
+        Signal::Poll => ("SIGPOLL", libc::SIGIO, "I/O possible (synonym)"),
+        #[cfg(target_os = "linux")]
+        Signal::Power => ("SIGPWR", libc::SIGPWR, "power failure"),
+        // Not in `SUPPORTED_SIGNALS` elsewhere; kill(2) rejects -1.
+        #[cfg(not(target_os = "linux"))]
+        Signal::Power => ("SIGPWR", -1, "power failure"),
+        Signal::Sys => ("SIGSYS", libc::SIGSYS, "bad system call"),
+    }
+}
+
+/// The kill menu: every signal this system supports, by number, with one
+/// entry per number (synonyms such as SIGIOT are left out).
+pub struct SignalPicker {
+    pub entries: Vec<SignalEntry>,
+    pub selected: usize,
+}
+
+impl SignalPicker {
+    /// A picker with SIGTERM selected, the usual first try.
+    pub fn new() -> SignalPicker {
+        let mut entries: Vec<SignalEntry> = System::SUPPORTED_SIGNALS
+            .iter()
+            .map(|&signal| {
+                let (name, number, description) = describe(signal);
+                SignalEntry {
+                    signal,
+                    name,
+                    number,
+                    description,
+                }
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.number);
+        entries.dedup_by_key(|entry| entry.number);
+        let selected = entries
+            .iter()
+            .position(|entry| entry.signal == Signal::Term)
+            .unwrap_or(0);
+        SignalPicker { entries, selected }
+    }
+
+    /// Moves the selection by `step` entries, staying on the list.
+    pub fn select(&mut self, step: isize) {
+        let last = self.entries.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(step).min(last);
+    }
+
+    pub fn entry(&self) -> Option<SignalEntry> {
+        self.entries.get(self.selected).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renice_self() {
+        let pid = Pid::from_u32(std::process::id());
+        let before = nice(pid).unwrap();
+        // Raising the nice value is always allowed.
+        let after = set_nice(pid, before + 1).unwrap();
+        assert_eq!(after, (before + 1).min(19));
+        assert_eq!(nice(pid).unwrap(), after);
+        assert!(nice(Pid::from_u32(u32::MAX >> 1)).is_err());
+    }
+
+    #[test]
+    fn test_affinity_editor() {
+        let pid = Pid::from_u32(std::process::id());
+        #[cfg(target_os = "linux")]
+        {
+            let current = affinity(pid, libc::CPU_SETSIZE as usize).unwrap();
+            assert!(current.contains(&true));
+            set_affinity(pid, &current).unwrap();
+            assert_eq!(affinity(pid, current.len()).unwrap(), current);
+        }
+        #[cfg(not(target_os = "linux"))]
+        assert_eq!(
+            affinity(pid, 4).unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+
+        let mut editor = AffinityEditor::new(pid, vec![true, true, false, true]);
+        assert_eq!(cpu_list(&editor.allowed), "0-1,3");
+        editor.toggle();
+        editor.select(1);
+        editor.toggle();
+        assert_eq!(cpu_list(&editor.allowed), "3");
+        // The last allowed CPU stays.
+        editor.select(10);
+        editor.toggle();
+        assert_eq!(cpu_list(&editor.allowed), "3");
+        editor.allow_all();
+        assert_eq!(cpu_list(&editor.allowed), "0-3");
+    }
+
+    #[test]
+    fn test_signal_picker() {
+        let mut picker = SignalPicker::new();
+        assert_eq!(picker.entry().unwrap().name, "SIGTERM");
+        let numbers: Vec<i32> = picker.entries.iter().map(|entry| entry.number).collect();
+        assert_eq!(numbers[..3], [1, 2, 3]);
+        // Synonyms share a number and are listed once.
+        assert!(numbers.windows(2).all(|pair| pair[0] < pair[1]));
+        picker.select(-100);
+        assert_eq!(picker.entry().unwrap().signal, Signal::Hangup);
+        picker.select(100);
+        assert_eq!(picker.selected, picker.entries.len() - 1);
+
+        assert_eq!(signal_named("term"), Some(Signal::Term));
+        assert_eq!(signal_named("SIGKILL"), Some(Signal::Kill));
+        assert_eq!(signal_named("TERMINATE"), None);
+
+        let protected = vec!["sshd".to_string()];
+        assert!(is_protected(Pid::from_u32(1), "bash", &[]));
+        assert!(is_protected(
+            Pid::from_u32(std::process::id()),
+            "cargo",
+            &[]
+        ));
+        assert!(is_protected(Pid::from_u32(812), "sshd", &protected));
+        assert!(!is_protected(Pid::from_u32(812), "sshd2", &protected));
+    }
+}
@@ -0,0 +1,309 @@
+// src/details.rs
+use crate::dstate;
+use crate::limits::{self, ResourceLimit};
+use crate::collectors::sockets::{self, SocketEntry};
+use sysinfo::Pid;
+
+/// Resident memory of a process split by what backs it, in bytes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemoryComposition {
+    pub anon: u64,
+    /// Shared libraries, such as `libc.so.6`.
+    pub libraries: u64,
+    /// Other file-backed mappings.
+    pub file: u64,
+    pub shared: u64,
+    pub stack: u64,
+}
+
+impl MemoryComposition {
+    pub fn total(&self) -> u64 {
+        self.anon + self.libraries + self.file + self.shared + self.stack
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DetailsTab {
+    Info,
+    Overview,
+    Limits,
+    /// CPUs the process may run on, editable.
+    Affinity,
+}
+
+impl DetailsTab {
+    pub const ALL: [DetailsTab; 4] = [
+        DetailsTab::Info,
+        DetailsTab::Overview,
+        DetailsTab::Limits,
+        DetailsTab::Affinity,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            DetailsTab::Info => "Info",
+            DetailsTab::Overview => "Overview",
+            DetailsTab::Limits => "Limits",
+            DetailsTab::Affinity => "Affinity",
+        }
+    }
+
+    pub fn next(self) -> DetailsTab {
+        let index = DetailsTab::ALL.iter().position(|&t| t == self).unwrap_or(0);
+        DetailsTab::ALL[(index + 1) % DetailsTab::ALL.len()]
+    }
+}
+
+/// Fields of `/proc/PID/status` that sysinfo does not expose; sizes in bytes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProcessStatus {
+    pub threads: Option<u64>,
+    pub rss_anon: Option<u64>,
+    pub rss_file: Option<u64>,
+    pub rss_shmem: Option<u64>,
+    pub swap: Option<u64>,
+}
+
+pub fn parse_status(content: &str) -> ProcessStatus {
+    let mut status = ProcessStatus::default();
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let number = value.trim().trim_end_matches("kB").trim().parse::<u64>().ok();
+        let kb = number.map(|kb| kb * 1024);
+        match key {
+            "Threads" => status.threads = number,
+            "RssAnon" => status.rss_anon = kb,
+            "RssFile" => status.rss_file = kb,
+            "RssShmem" => status.rss_shmem = kb,
+            "VmSwap" => status.swap = kb,
+            _ => {}
+        }
+    }
+    status
+}
+
+#[cfg(target_os = "linux")]
+pub fn read_status(pid: Pid) -> ProcessStatus {
+    std::fs::read_to_string(format!("/proc/{}/status", pid))
+        .map(|content| parse_status(&content))
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read_status(_pid: Pid) -> ProcessStatus {
+    ProcessStatus::default()
+}
+
+/// Extra per-process information shown in the detail view, collected on demand
+/// for the selected process only since it is too expensive to gather for every row.
+pub struct ProcessDetails {
+    pub pid: Pid,
+    pub status: ProcessStatus,
+    pub memory: Option<MemoryComposition>,
+    pub sockets: Vec<SocketEntry>,
+    pub limits: Vec<ResourceLimit>,
+    pub open_files: Option<u64>,
+    /// Kernel function and system call the process is blocked in.
+    pub wchan: Option<String>,
+    pub syscall: Option<String>,
+    pub tab: DetailsTab,
+    /// Row highlighted in the children table.
+    pub selected_child: Option<usize>,
+}
+
+impl ProcessDetails {
+    pub fn collect(pid: Pid) -> ProcessDetails {
+        ProcessDetails {
+            pid,
+            status: read_status(pid),
+            memory: read_memory_composition(pid),
+            sockets: sockets::process_sockets(pid),
+            limits: limits::read_limits(pid),
+            open_files: limits::open_file_count(pid),
+            wchan: dstate::wchan(pid),
+            syscall: dstate::syscall(pid),
+            tab: DetailsTab::Info,
+            selected_child: None,
+        }
+    }
+
+    /// Re-reads the collected metrics while keeping the view state.
+    pub fn refresh(&mut self) {
+        self.status = read_status(self.pid);
+        self.memory = read_memory_composition(self.pid);
+        self.sockets = sockets::process_sockets(self.pid);
+        self.limits = limits::read_limits(self.pid);
+        self.open_files = limits::open_file_count(self.pid);
+        self.wchan = dstate::wchan(self.pid);
+        self.syscall = dstate::syscall(self.pid);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_memory_composition(pid: Pid) -> Option<MemoryComposition> {
+    let smaps = std::fs::read_to_string(format!("/proc/{}/smaps", pid)).ok()?;
+    Some(parse_smaps(&smaps))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_memory_composition(_pid: Pid) -> Option<MemoryComposition> {
+    None
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MappingKind {
+    Anon,
+    Library,
+    File,
+    Stack,
+}
+
+fn mapping_kind(header: &str) -> MappingKind {
+    // Fields: address perms offset dev inode [pathname]; the pathname is the
+    // rest of the line and may contain spaces.
+    let mut path = header;
+    for _ in 0..5 {
+        path = path
+            .trim_start()
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, rest)| rest);
+    }
+    let path = path.trim();
+    match path {
+        "" | "[heap]" => MappingKind::Anon,
+        _ if path.starts_with("[anon:") => MappingKind::Anon,
+        // `[stack:TID]` for thread stacks on older kernels.
+        "[stack]" => MappingKind::Stack,
+        _ if path.starts_with("[stack:") => MappingKind::Stack,
+        // `[vdso]`, `[vvar]` and the like are mapped by the kernel like files.
+        _ if path.starts_with('[') => MappingKind::File,
+        _ if is_library(path.trim_end_matches(" (deleted)")) => MappingKind::Library,
+        _ => MappingKind::File,
+    }
+}
+
+/// Whether the file name ends in `.so` or `.so.` and a version, like
+/// `libc.so.6` or `libssl.so.1.1`.
+fn is_library(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.ends_with(".so")
+        || name.split_once(".so.").is_some_and(|(_, version)| {
+            version
+                .split('.')
+                .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+        })
+}
+
+/// Splits the `Rss` of every mapping in `/proc/PID/smaps` into shared pages and
+/// private pages attributed to the kind of mapping they belong to.
+pub fn parse_smaps(content: &str) -> MemoryComposition {
+    let mut composition = MemoryComposition::default();
+    let mut kind = MappingKind::Anon;
+    let mut rss = 0;
+    let mut shared = 0;
+
+    let mut flush = |kind: MappingKind, rss: u64, shared: u64| {
+        let shared = shared.min(rss);
+        composition.shared += shared;
+        let private = rss - shared;
+        match kind {
+            MappingKind::Anon => composition.anon += private,
+            MappingKind::Library => composition.libraries += private,
+            MappingKind::File => composition.file += private,
+            MappingKind::Stack => composition.stack += private,
+        }
+    };
+
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Header lines also contain a colon (in the device field), but what
+        // precedes it is never a bare identifier.
+        if key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            let kb: u64 = value
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse()
+                .unwrap_or(0);
+            match key {
+                "Rss" => rss = kb * 1024,
+                "Shared_Clean" | "Shared_Dirty" => shared += kb * 1024,
+                _ => {}
+            }
+        } else {
+            flush(kind, rss, shared);
+            kind = mapping_kind(line);
+            rss = 0;
+            shared = 0;
+        }
+    }
+    flush(kind, rss, shared);
+    composition
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_smaps() {
+        let smaps = "\
+55d0c0a00000-55d0c0a21000 rw-p 00000000 00:00 0          [heap]
+Rss:                 100 kB
+Shared_Clean:          0 kB
+Shared_Dirty:          0 kB
+VmFlags: rd wr mr mw me ac
+7f1c2c000000-7f1c2c200000 r-xp 00000000 08:01 42         /usr/lib/libc.so.6
+Rss:                 300 kB
+Shared_Clean:        200 kB
+Shared_Dirty:          0 kB
+7ffd1a000000-7ffd1a021000 rw-p 00000000 00:00 0          [stack]
+Rss:                  20 kB
+Shared_Clean:          0 kB
+Shared_Dirty:          0 kB
+";
+        let composition = parse_smaps(smaps);
+        assert_eq!(composition.anon, 100 * 1024);
+        assert_eq!(composition.libraries, 100 * 1024);
+        assert_eq!(composition.file, 0);
+        assert_eq!(composition.shared, 200 * 1024);
+        assert_eq!(composition.stack, 20 * 1024);
+    }
+
+    #[test]
+    fn test_mapping_kind() {
+        use MappingKind::*;
+        for (path, expected) in [
+            ("", Anon),
+            ("[heap]", Anon),
+            ("[anon:libc_malloc]", Anon),
+            ("[stack]", Stack),
+            ("[stack:1234]", Stack),
+            ("[vdso]", File),
+            ("/usr/lib/libc.so.6", Library),
+            ("/usr/lib/libssl.so.1.1", Library),
+            ("/opt/app/plugin.so", Library),
+            ("/usr/lib/libfoo.so.6 (deleted)", Library),
+            ("/data/foo.socket", File),
+            ("/data/cache.so.bak", File),
+            ("/srv/my.so files/data.db", File),
+            ("/data.so/index", File),
+        ] {
+            let header = format!("7f1c2c000000-7f1c2c200000 r--p 00000000 08:01 42  {}", path);
+            assert_eq!(mapping_kind(header.trim_end()), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let status = parse_status("Name:\tbash\nRssAnon:\t    2048 kB\nVmSwap:\t       0 kB\nThreads:\t4\n");
+        assert_eq!(status.threads, Some(4));
+        assert_eq!(status.rss_anon, Some(2048 * 1024));
+        assert_eq!(status.swap, Some(0));
+        assert_eq!(status.rss_file, None);
+    }
+}
@@ -0,0 +1,293 @@
+// src/diff.rs
+use crate::export::{Format, Snapshot};
+use crate::{view, AppError};
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DiffSort {
+    /// Largest change in CPU usage first
+    Cpu,
+    /// Largest change in resident memory first
+    Memory,
+}
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// Snapshot or recording taken before the change
+    pub before: PathBuf,
+
+    /// Snapshot or recording taken after the change
+    pub after: PathBuf,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+
+    /// Order processes by the change in
+    #[arg(short, long, value_enum, default_value_t = DiffSort::Cpu)]
+    pub sort: DiffSort,
+
+    /// Number of processes to list in text output
+    #[arg(short = 'n', long, default_value_t = 20)]
+    pub limit: usize,
+}
+
+/// Averages over every snapshot of a recording. Processes are keyed by name
+/// since PIDs rarely survive between two runs.
+#[derive(Debug, Default, PartialEq)]
+struct Profile {
+    cpu: f64,
+    memory_used: f64,
+    swap_used: f64,
+    load: f64,
+    processes: f64,
+    /// Name to average CPU% and resident bytes of all processes of that name.
+    by_name: HashMap<String, (f64, f64)>,
+}
+
+impl Profile {
+    fn of(snapshots: &[Snapshot]) -> Profile {
+        let count = snapshots.len().max(1) as f64;
+        let mut profile = Profile::default();
+        for snapshot in snapshots {
+            profile.cpu += snapshot.cpu.usage as f64 / count;
+            profile.memory_used += snapshot.memory.used as f64 / count;
+            profile.swap_used += snapshot.memory.swap_used as f64 / count;
+            profile.load += snapshot.load_average[0] / count;
+            profile.processes += snapshot.processes.len() as f64 / count;
+            for process in &snapshot.processes {
+                let entry = profile.by_name.entry(process.name.clone()).or_default();
+                entry.0 += process.cpu_usage as f64 / count;
+                entry.1 += process.memory as f64 / count;
+            }
+        }
+        profile
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct MetricDiff {
+    pub metric: &'static str,
+    pub before: f64,
+    pub after: f64,
+    pub change: f64,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ProcessDiff {
+    pub name: String,
+    pub cpu_before: f64,
+    pub cpu_after: f64,
+    pub memory_before: u64,
+    pub memory_after: u64,
+}
+
+impl ProcessDiff {
+    fn cpu_change(&self) -> f64 {
+        self.cpu_after - self.cpu_before
+    }
+
+    fn memory_change(&self) -> i64 {
+        self.memory_after as i64 - self.memory_before as i64
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diff {
+    pub metrics: Vec<MetricDiff>,
+    /// Every process name seen on either side, largest change first.
+    pub processes: Vec<ProcessDiff>,
+}
+
+impl Diff {
+    pub fn between(before: &[Snapshot], after: &[Snapshot], sort: DiffSort) -> Diff {
+        let (a, b) = (Profile::of(before), Profile::of(after));
+        let mib = 1024.0 * 1024.0;
+        let metric = |metric, before: f64, after: f64| MetricDiff {
+            metric,
+            before,
+            after,
+            change: after - before,
+        };
+        let metrics = vec![
+            metric("cpu_percent", a.cpu, b.cpu),
+            metric("memory_used_mib", a.memory_used / mib, b.memory_used / mib),
+            metric("swap_used_mib", a.swap_used / mib, b.swap_used / mib),
+            metric("load_1m", a.load, b.load),
+            metric("processes", a.processes, b.processes),
+        ];
+        let mut names: Vec<&String> = a.by_name.keys().chain(b.by_name.keys()).collect();
+        names.sort();
+        names.dedup();
+        let mut processes: Vec<ProcessDiff> = names
+            .into_iter()
+            .map(|name| {
+                let before = a.by_name.get(name).copied().unwrap_or_default();
+                let after = b.by_name.get(name).copied().unwrap_or_default();
+                ProcessDiff {
+                    name: name.clone(),
+                    cpu_before: before.0,
+                    cpu_after: after.0,
+                    memory_before: before.1 as u64,
+                    memory_after: after.1 as u64,
+                }
+            })
+            .collect();
+        match sort {
+            DiffSort::Cpu => {
+                processes.sort_by(|x, y| y.cpu_change().abs().total_cmp(&x.cpu_change().abs()))
+            }
+            DiffSort::Memory => {
+                processes.sort_by_key(|p| std::cmp::Reverse(p.memory_change().unsigned_abs()))
+            }
+        }
+        Diff { metrics, processes }
+    }
+}
+
+/// `change` with its sign, and relative to `before` when that is not zero.
+fn change(before: f64, change: f64) -> String {
+    let absolute = format!("{:+.1}", change);
+    if before.abs() < f64::EPSILON {
+        return absolute;
+    }
+    format!("{} ({:+.0}%)", absolute, change / before * 100.0)
+}
+
+fn write_text(diff: &Diff, args: &DiffArgs, counts: (usize, usize)) -> String {
+    let mut out = format!(
+        "before: {} ({} snapshots)\nafter:  {} ({} snapshots)\n\n",
+        args.before.display(),
+        counts.0,
+        args.after.display(),
+        counts.1
+    );
+    out.push_str(&format!(
+        "{:<18} {:>12} {:>12}  Change\n",
+        "Metric", "Before", "After"
+    ));
+    for metric in &diff.metrics {
+        out.push_str(&format!(
+            "{:<18} {:>12.1} {:>12.1}  {}\n",
+            metric.metric,
+            metric.before,
+            metric.after,
+            change(metric.before, metric.change)
+        ));
+    }
+    out.push_str(&format!(
+        "\n{:<24} {:>8} {:>8}  {:<16} {:>10} {:>10}  Change MiB\n",
+        "Process", "CPU% was", "now", "Change", "MiB was", "now"
+    ));
+    let mib = |bytes: u64| bytes as f64 / 1024.0 / 1024.0;
+    for process in diff.processes.iter().take(args.limit) {
+        out.push_str(&format!(
+            "{:<24} {:>8.1} {:>8.1}  {:<16} {:>10.1} {:>10.1}  {}\n",
+            crate::text::truncate(&process.name, 24),
+            process.cpu_before,
+            process.cpu_after,
+            change(process.cpu_before, process.cpu_change()),
+            mib(process.memory_before),
+            mib(process.memory_after),
+            change(
+                mib(process.memory_before),
+                process.memory_change() as f64 / 1024.0 / 1024.0,
+            )
+        ));
+    }
+    out
+}
+
+pub fn run(args: DiffArgs) -> Result<(), AppError> {
+    let before = view::load(&args.before)?;
+    let after = view::load(&args.after)?;
+    let diff = Diff::between(&before, &after, args.sort);
+    let mut out = io::stdout().lock();
+    match args.format {
+        Format::Text => write!(
+            out,
+            "{}",
+            write_text(&diff, &args, (before.len(), after.len()))
+        )?,
+        Format::Json => writeln!(out, "{}", serde_json::to_string_pretty(&diff)?)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{CpuSnapshot, MemorySnapshot, ProcessSnapshot};
+
+    fn snapshot(cpu: f32, processes: &[(&str, f32, u64)]) -> Snapshot {
+        Snapshot {
+            timestamp: String::new(),
+            hostname: None,
+            uptime: 0,
+            load_average: [1.0, 0.0, 0.0],
+            cpu: CpuSnapshot {
+                usage: cpu,
+                cores: vec![cpu],
+            },
+            memory: MemorySnapshot {
+                total: 0,
+                used: 0,
+                available: 0,
+                swap_total: 0,
+                swap_used: 0,
+            },
+            processes: processes
+                .iter()
+                .enumerate()
+                .map(|(pid, &(name, cpu_usage, memory))| ProcessSnapshot {
+                    pid: pid as u32,
+                    parent: None,
+                    name: name.to_string(),
+                    command: String::new(),
+                    user: None,
+                    status: String::new(),
+                    cpu_usage,
+                    memory,
+                    virtual_memory: 0,
+                    start_time: 0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_diff_recordings() {
+        // Two workers at 20% each in both samples, then one at 10%.
+        let before = [
+            snapshot(50.0, &[("worker", 20.0, 100), ("worker", 20.0, 100)]),
+            snapshot(
+                30.0,
+                &[
+                    ("worker", 20.0, 100),
+                    ("worker", 20.0, 100),
+                    ("cron", 2.0, 10),
+                ],
+            ),
+        ];
+        let after = [snapshot(20.0, &[("worker", 10.0, 300)])];
+        let diff = Diff::between(&before, &after, DiffSort::Cpu);
+
+        assert_eq!(diff.metrics[0].before, 40.0);
+        assert_eq!(diff.metrics[0].change, -20.0);
+        assert_eq!(diff.metrics[4].before, 2.5);
+        let worker = &diff.processes[0];
+        assert_eq!(worker.name, "worker");
+        assert_eq!((worker.cpu_before, worker.cpu_after), (40.0, 10.0));
+        assert_eq!((worker.memory_before, worker.memory_after), (200, 300));
+        assert_eq!(diff.processes[1].cpu_before, 1.0);
+
+        let diff = Diff::between(&before, &after, DiffSort::Memory);
+        assert_eq!(diff.processes[0].memory_change(), 100);
+        assert_eq!(change(40.0, -30.0), "-30.0 (-75%)");
+        assert_eq!(change(0.0, 2.0), "+2.0");
+    }
+}
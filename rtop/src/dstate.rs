@@ -0,0 +1,164 @@
+// src/dstate.rs
+use std::collections::HashMap;
+use sysinfo::{Pid, ProcessStatus};
+
+/// Counts for how many refreshes in a row each process has been in
+/// uninterruptible sleep (D state), usually blocked on a disk or NFS server.
+#[derive(Default)]
+pub struct DStateTracker {
+    refreshes: HashMap<Pid, u32>,
+}
+
+impl DStateTracker {
+    pub fn record(&mut self, statuses: impl Iterator<Item = (Pid, ProcessStatus)>) {
+        let mut refreshes = HashMap::new();
+        for (pid, status) in statuses {
+            if status == ProcessStatus::UninterruptibleDiskSleep {
+                refreshes.insert(pid, self.refreshes.get(&pid).copied().unwrap_or(0) + 1);
+            }
+        }
+        self.refreshes = refreshes;
+    }
+
+    /// Refreshes `pid` has been in D state for, if it is now.
+    pub fn refreshes(&self, pid: Pid) -> Option<u32> {
+        self.refreshes.get(&pid).copied()
+    }
+
+    /// Processes in D state for at least `limit` refreshes, longest first.
+    /// A `limit` of 0 turns the rule off.
+    pub fn stuck(&self, limit: u32) -> Vec<(Pid, u32)> {
+        if limit == 0 {
+            return Vec::new();
+        }
+        let mut stuck: Vec<(Pid, u32)> = self
+            .refreshes
+            .iter()
+            .filter(|(_, refreshes)| **refreshes >= limit)
+            .map(|(pid, refreshes)| (*pid, *refreshes))
+            .collect();
+        stuck.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        stuck
+    }
+}
+
+/// The kernel function `pid` sleeps in, such as `nfs_wait_bit_killable`.
+pub fn wchan(pid: Pid) -> Option<String> {
+    let wchan = std::fs::read_to_string(format!("/proc/{}/wchan", pid)).ok()?;
+    let wchan = wchan.trim();
+    (!wchan.is_empty() && wchan != "0").then(|| wchan.to_string())
+}
+
+/// The system call `pid` is blocked in, from `/proc/PID/syscall` (readable
+/// by the owner or root). `None` while it runs or waits outside of one.
+pub fn syscall(pid: Pid) -> Option<String> {
+    parse_syscall(&std::fs::read_to_string(format!("/proc/{}/syscall", pid)).ok()?)
+}
+
+fn parse_syscall(content: &str) -> Option<String> {
+    // `running`, or `-1` when blocked in the kernel but not in a system call.
+    let number: i64 = content.split_whitespace().next()?.parse().ok()?;
+    if number < 0 {
+        return None;
+    }
+    Some(match syscall_name(number) {
+        Some(name) => format!("{} ({})", name, number),
+        None => format!("#{}", number),
+    })
+}
+
+/// Names of the system calls processes usually block or sleep in.
+#[cfg(target_os = "linux")]
+fn syscall_name(number: i64) -> Option<&'static str> {
+    Some(match number {
+        libc::SYS_read => "read",
+        libc::SYS_write => "write",
+        libc::SYS_pread64 => "pread64",
+        libc::SYS_pwrite64 => "pwrite64",
+        libc::SYS_readv => "readv",
+        libc::SYS_writev => "writev",
+        libc::SYS_openat => "openat",
+        libc::SYS_close => "close",
+        libc::SYS_fstat => "fstat",
+        libc::SYS_statx => "statx",
+        libc::SYS_getdents64 => "getdents64",
+        libc::SYS_unlinkat => "unlinkat",
+        libc::SYS_renameat2 => "renameat2",
+        libc::SYS_truncate => "truncate",
+        libc::SYS_ftruncate => "ftruncate",
+        libc::SYS_fallocate => "fallocate",
+        libc::SYS_fsync => "fsync",
+        libc::SYS_fdatasync => "fdatasync",
+        libc::SYS_sync => "sync",
+        libc::SYS_syncfs => "syncfs",
+        libc::SYS_msync => "msync",
+        libc::SYS_flock => "flock",
+        libc::SYS_fcntl => "fcntl",
+        libc::SYS_ioctl => "ioctl",
+        libc::SYS_mmap => "mmap",
+        libc::SYS_munmap => "munmap",
+        libc::SYS_sendfile => "sendfile",
+        libc::SYS_splice => "splice",
+        libc::SYS_mount => "mount",
+        libc::SYS_umount2 => "umount2",
+        libc::SYS_io_getevents => "io_getevents",
+        libc::SYS_io_uring_enter => "io_uring_enter",
+        libc::SYS_futex => "futex",
+        libc::SYS_nanosleep => "nanosleep",
+        libc::SYS_clock_nanosleep => "clock_nanosleep",
+        libc::SYS_wait4 => "wait4",
+        libc::SYS_waitid => "waitid",
+        libc::SYS_ppoll => "ppoll",
+        libc::SYS_pselect6 => "pselect6",
+        libc::SYS_epoll_pwait => "epoll_pwait",
+        libc::SYS_accept4 => "accept4",
+        libc::SYS_connect => "connect",
+        libc::SYS_recvfrom => "recvfrom",
+        libc::SYS_recvmsg => "recvmsg",
+        libc::SYS_sendto => "sendto",
+        libc::SYS_sendmsg => "sendmsg",
+        libc::SYS_exit_group => "exit_group",
+        _ => return None,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn syscall_name(_number: i64) -> Option<&'static str> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_d_state_tracker() {
+        let (a, b) = (Pid::from(1), Pid::from(2));
+        let d = ProcessStatus::UninterruptibleDiskSleep;
+        let mut tracker = DStateTracker::default();
+        tracker.record([(a, d), (b, d)].into_iter());
+        tracker.record([(a, d), (b, ProcessStatus::Sleep)].into_iter());
+        tracker.record([(a, d), (b, d)].into_iter());
+        assert_eq!(tracker.refreshes(a), Some(3));
+        // Waking up starts the count over.
+        assert_eq!(tracker.refreshes(b), Some(1));
+        assert_eq!(tracker.stuck(2), vec![(a, 3)]);
+        assert_eq!(tracker.stuck(1), vec![(a, 3), (b, 1)]);
+        assert!(tracker.stuck(0).is_empty());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_parse_syscall() {
+        let fsync = format!(
+            "{} 0x3 0x0 0x0 0x0 0x0 0x0 0x7ffd 0x7f12\n",
+            libc::SYS_fsync
+        );
+        assert_eq!(
+            parse_syscall(&fsync),
+            Some(format!("fsync ({})", libc::SYS_fsync))
+        );
+        assert_eq!(parse_syscall("running\n"), None);
+        assert_eq!(parse_syscall("-1 0x7ffd 0x7f12\n"), None);
+    }
+}
@@ -0,0 +1,115 @@
+// src/emulation.rs
+use std::path::Path;
+use sysinfo::Pid;
+
+/// A binary translator or emulator a process runs under. Its CPU usage
+/// includes the translation, so it costs more than a native build would.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Emulator {
+    /// Apple's Rosetta, on macOS or in Linux VMs on Apple silicon.
+    Rosetta,
+    /// qemu-user, with the emulated architecture (`x86_64`, `aarch64`, ...).
+    Qemu(String),
+    Fex,
+    /// box64 or box86, by name.
+    Box(String),
+}
+
+impl Emulator {
+    /// The marker shown after the process name.
+    pub fn label(&self) -> String {
+        match self {
+            Emulator::Rosetta => "rosetta".to_string(),
+            Emulator::Qemu(arch) => format!("qemu {}", arch),
+            Emulator::Fex => "FEX".to_string(),
+            Emulator::Box(name) => name.clone(),
+        }
+    }
+}
+
+/// The emulator behind `exe`. On Linux, binfmt_misc (and running the
+/// emulator by hand) makes `/proc/PID/exe` point at the emulator rather than
+/// the program.
+pub fn from_exe(exe: &Path) -> Option<Emulator> {
+    let name = exe.file_name()?.to_str()?;
+    if let Some(arch) = name.strip_prefix("qemu-") {
+        let arch = arch.trim_end_matches("-static");
+        // qemu-system-* runs whole VMs, qemu-img and friends are tools.
+        let tools = [
+            "system-",
+            "img",
+            "io",
+            "nbd",
+            "ga",
+            "storage-daemon",
+            "pr-helper",
+        ];
+        if tools.iter().any(|tool| arch.starts_with(tool)) {
+            return None;
+        }
+        return Some(Emulator::Qemu(arch.to_string()));
+    }
+    // Debian's qemu-user-binfmt registers `/usr/libexec/qemu-binfmt/ARCH-binfmt-P`.
+    if let Some(arch) = name.strip_suffix("-binfmt-P") {
+        return Some(Emulator::Qemu(arch.to_string()));
+    }
+    match name {
+        "rosetta" => Some(Emulator::Rosetta),
+        "FEXInterpreter" | "FEXLoader" => Some(Emulator::Fex),
+        "box64" | "box86" => Some(Emulator::Box(name.to_string())),
+        _ => None,
+    }
+}
+
+/// How `pid` is emulated, if at all.
+#[cfg(target_os = "macos")]
+pub fn detect(pid: Pid, _exe: &Path) -> Option<Emulator> {
+    use sysinfo::PidExt;
+    /// `P_TRANSLATED` from `<sys/proc.h>`: the process runs under Rosetta.
+    const P_TRANSLATED: u32 = 0x0002_0000;
+    let mut info: libc::proc_bsdinfo = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::proc_bsdinfo>() as libc::c_int;
+    let read = unsafe {
+        libc::proc_pidinfo(
+            pid.as_u32() as libc::c_int,
+            libc::PROC_PIDTBSDINFO,
+            0,
+            &mut info as *mut libc::proc_bsdinfo as *mut libc::c_void,
+            size,
+        )
+    };
+    (read == size && info.pbi_flags & P_TRANSLATED != 0).then_some(Emulator::Rosetta)
+}
+
+/// How `pid` is emulated, if at all.
+#[cfg(not(target_os = "macos"))]
+pub fn detect(_pid: Pid, exe: &Path) -> Option<Emulator> {
+    from_exe(exe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emulator_from_exe() {
+        let emulator = |path: &str| from_exe(Path::new(path));
+        assert_eq!(
+            emulator("/usr/bin/qemu-x86_64-static"),
+            Some(Emulator::Qemu("x86_64".to_string()))
+        );
+        assert_eq!(
+            emulator("/usr/libexec/qemu-binfmt/aarch64-binfmt-P"),
+            Some(Emulator::Qemu("aarch64".to_string()))
+        );
+        assert_eq!(
+            emulator("/usr/bin/qemu-aarch64").unwrap().label(),
+            "qemu aarch64"
+        );
+        assert_eq!(emulator("/usr/bin/qemu-system-x86_64"), None);
+        assert_eq!(emulator("/usr/bin/qemu-img"), None);
+        assert_eq!(emulator("/mnt/rosetta/rosetta"), Some(Emulator::Rosetta));
+        assert_eq!(emulator("/usr/bin/FEXInterpreter"), Some(Emulator::Fex));
+        assert_eq!(emulator("/usr/bin/bash"), None);
+    }
+}
@@ -0,0 +1,48 @@
+// src/envgroup.rs
+
+/// The value of variable `name` in a process environment of `KEY=value`
+/// entries. Environments of other users' processes are only readable as root,
+/// so those have no value either.
+pub fn value<'a>(environ: &'a [String], name: &str) -> Option<&'a str> {
+    environ.iter().find_map(|entry| {
+        let (key, value) = entry.split_once('=')?;
+        (key == name).then_some(value)
+    })
+}
+
+/// Stable-sorts `rows` so that rows of the same group are adjacent and keep
+/// their order, with groups by name and rows without a group last.
+pub fn group<T>(rows: &mut [T], key: impl Fn(&T) -> Option<&str>) {
+    rows.sort_by(|a, b| {
+        let (a, b) = (key(a), key(b));
+        (a.is_none(), a).cmp(&(b.is_none(), b))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_by_env() {
+        let environ = vec![
+            "PATH=/usr/bin".to_string(),
+            "RAILS_ENV=staging".to_string(),
+            "EMPTY=".to_string(),
+        ];
+        assert_eq!(value(&environ, "RAILS_ENV"), Some("staging"));
+        assert_eq!(value(&environ, "EMPTY"), Some(""));
+        assert_eq!(value(&environ, "RAILS"), None);
+
+        // Sorted by CPU before grouping; the order holds inside each group.
+        let mut rows = [
+            (90, None),
+            (70, Some("web")),
+            (50, Some("api")),
+            (30, Some("web")),
+            (10, Some("api")),
+        ];
+        group(&mut rows, |row| row.1);
+        assert_eq!(rows.map(|row| row.0), [50, 10, 70, 30, 90]);
+    }
+}
@@ -0,0 +1,563 @@
+// src/event.rs
+use crossterm::{
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event, KeyCode, MouseButton, MouseEventKind,
+    },
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::io::stdout;
+use std::time::{Duration, Instant};
+use sysinfo::SystemExt;
+use termion::raw::IntoRawMode;
+use crate::alarm::Alarm;
+use crate::cast::CastWriter;
+use crate::collectors::background;
+use crate::config::Config;
+use crate::keymap::{Action, Keymap};
+use crate::mirror::{MirrorClient, MirrorServer};
+use crate::redact::Redactor;
+use crate::sampler::Sampler;
+use crate::theme::{ColorDepth, Theme};
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    Terminal,
+};
+use crate::{baseline, cluster, export, mdns, metrics, mirror, notify, privilege, record, replay, view, watchdog};
+use crate::app::{App, AppState, SortBy};
+use crate::cli::TuiArgs;
+use crate::plugins::Plugins;
+use crate::ui::*;
+
+/// Runs the interactive UI, or `--batch` output, with the plugins registered
+/// in `plugins` besides those of the config.
+pub fn run_tui(args: TuiArgs, mut plugins: Plugins) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = load_config(args.config.as_deref());
+    // Command-line flags override the config file.
+    if let Some(refresh_rate) = args.refresh_rate {
+        config.refresh_rate = refresh_rate;
+    }
+    if let Some(sort) = args.sort {
+        config.sort = sort;
+    }
+    if let Some(theme) = args.theme {
+        config.theme = theme;
+    }
+    if let Err(err) = Theme::resolve(&config.theme, &config.themes) {
+        eprintln!("rtop: {}", err);
+        std::process::exit(1);
+    }
+    let refresh_rate = Duration::from_millis(config.refresh_rate);
+
+    if args.dump_keymap {
+        print!("{}", Keymap::new(&config.keys).dump());
+        return Ok(());
+    }
+    if let Some(path) = args.replay {
+        return run_replay(&path, config);
+    }
+
+    let mut app = App::new(config);
+    app.redact = Redactor::new(args.redact);
+    app.user_filter = args.user;
+    if args.batch || args.once {
+        drop_privileges(&mut app, &args.drop_privileges)?;
+        let iterations = if args.once { Some(1) } else { args.iterations };
+        let options = export::OutputOptions {
+            limit: args.limit,
+            format: args.format,
+            color: args.color.enabled(std::io::IsTerminal::is_terminal(&stdout()) && app.color_depth != ColorDepth::None),
+        };
+        return Ok(export::run_batch(app, refresh_rate, iterations, options)?);
+    }
+    // Started before dropping privileges, so on Linux they keep reading root-only data.
+    app.collectors = Some(background::Collectors::start(&app.config.collectors, refresh_rate));
+    let sampler = Sampler::start(&app.config.collectors, refresh_rate);
+    drop_privileges(&mut app, &args.drop_privileges)?;
+    serve_metrics(&mut app, &args.listen)?;
+    let mut agents = args.agent;
+    if args.discover_agents {
+        let found = mdns::discover(Duration::from_secs(1)).map_err(|err| format!("cannot discover agents: {}", err))?;
+        agents.extend(found.iter().map(mdns::Agent::url).filter(|url| !agents.contains(url)).collect::<Vec<_>>());
+    }
+    let token = match &args.agent_token_file {
+        Some(path) => Some(std::fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path.display(), err))?.trim().to_string()),
+        None => None,
+    };
+    // Even without agents, for those picked in the Cluster tab.
+    app.cluster = Some(cluster::Cluster::start(agents, token, agent_tls(args.agent_ca.as_deref())?, refresh_rate.max(Duration::from_secs(1))));
+    if let Some(path) = args.share {
+        app.mirror = Some(MirrorServer::bind(&path.unwrap_or_else(mirror::default_socket_path))?);
+    }
+    if let Some(path) = args.record {
+        let hostname = app.system.host_name().map(|name| app.redact.host(&name).into_owned());
+        let interval = Duration::try_from_secs_f64(args.record_interval).map_err(|_| "invalid --record-interval")?;
+        let recorder = record::Recorder::create(&path, hostname, interval).map_err(|err| format!("cannot record to {}: {}", path.display(), err))?;
+        app.recorder = Some((path, recorder));
+    }
+
+    if args.alarm || app.config.alarm {
+        app.alarm = Some(Alarm::default());
+    }
+    if args.notify || app.config.notify.on_alert {
+        app.notifier = Some(notify::Notifier::new(app.config.notify.clone()));
+    }
+    if args.watchdog || app.config.watchdog.enabled {
+        let log = app.config.watchdog.log.clone().or_else(|| Config::state_dir().map(|dir| dir.join("watchdog.log")));
+        app.watchdog = Some(watchdog::Watchdog::new(&app.config.watchdog, app.config.protected.clone(), log));
+    }
+    // Only now, so no plugin library is loaded as root.
+    if let Err(err) = app.config.plugins.register(&mut plugins) {
+        eprintln!("rtop: {}", err);
+        std::process::exit(1);
+    }
+    app.plugins = plugins;
+    app.plugins.start();
+    if let Some(seconds) = args.kiosk {
+        app.kiosk = true;
+        app.show_cpu_history = true;
+        app.show_memory_history = true;
+        app.start_rotation(Duration::from_secs(seconds.unwrap_or(app.config.rotate.seconds)));
+    } else if args.rotate || app.config.rotate.enabled {
+        app.start_rotation(Duration::from_secs(app.config.rotate.seconds));
+    }
+    if app.config.capture.on_alert {
+        app.capture_dir = app.config.capture.dir.clone().or_else(|| Config::state_dir().map(|dir| dir.join("captures")));
+    }
+    if app.config.baseline {
+        let host = app.system.host_name().unwrap_or_else(|| "localhost".to_string());
+        app.baseline = baseline::Baseline::default_path(&host).map(|path| baseline::Baseline::load(&path));
+    }
+
+    // A failing start hook is reported, but does not keep rtop from starting.
+    if let Err(err) = app.config.hooks.start() {
+        eprintln!("rtop: {}", err);
+    }
+    let (width, height) = crossterm::terminal::size()?;
+    let stdout = CastWriter::new(stdout(), args.cast.as_deref(), width, height)?;
+    enable_raw_mode()?;
+    let mut stdout = stdout.into_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let res = run_app(&mut terminal, &mut app, sampler);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), DisableBracketedPaste, DisableMouseCapture, LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    if let Err(err) = res {
+        println!("{:?}", err);
+    }
+    if let Some(Err(err)) = app.baseline.as_ref().map(baseline::Baseline::save) {
+        eprintln!("rtop: cannot save the baseline: {}", err);
+    }
+    if let Err(err) = app.config.hooks.exit(&app.session_summary()) {
+        eprintln!("rtop: {}", err);
+    }
+
+    Ok(())
+}
+
+/// Serves metrics for `rtop agent` until killed, without a terminal.
+pub(crate) fn run_agent(args: metrics::AgentArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = load_config(args.config.as_deref());
+    if let Some(refresh_rate) = args.refresh_rate {
+        config.refresh_rate = refresh_rate;
+    }
+    let refresh_rate = Duration::from_millis(config.refresh_rate);
+    let mut app = App::new(config);
+    app.redact = Redactor::new(args.redact);
+    app.collectors = Some(background::Collectors::start(&app.config.collectors, refresh_rate));
+    let sampler = Sampler::start(&app.config.collectors, refresh_rate);
+    drop_privileges(&mut app, &args.drop_privileges)?;
+    serve_metrics(&mut app, &args.listen)?;
+    loop {
+        sampler.want(app.wants());
+        if let Some(sample) = sampler.poll() {
+            sampler.give_back(app.update_from(sample));
+            if let Some(server) = &app.metrics {
+                server.publish(metrics::render(&app));
+            }
+        }
+        if let Some(advertiser) = &mut app.advertiser {
+            advertiser.serve();
+        }
+        std::thread::sleep(INPUT_POLL_INTERVAL);
+    }
+}
+
+/// Starts the `--listen` server, and the `--advertise` announcements, if asked for.
+fn serve_metrics(app: &mut App, args: &metrics::ListenArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(addr) = args.listen else {
+        return Ok(());
+    };
+    let mut server = metrics::MetricsServer::bind(addr).map_err(|err| format!("cannot listen on {}: {}", addr, err))?;
+    if let Some(path) = &args.listen_token_file {
+        let token = std::fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        let token = token.trim();
+        if token.is_empty() {
+            return Err(format!("{} is empty", path.display()).into());
+        }
+        server = server.with_token(token.to_string());
+    }
+    if let (Some(cert), Some(key)) = (&args.tls_cert, &args.tls_key) {
+        server = with_tls(server, cert, key)?;
+    }
+    if args.advertise {
+        let bound = server.local_addr()?;
+        let advertisement = mdns::Advertisement {
+            name: app.system.host_name().unwrap_or_else(|| "rtop".to_string()),
+            address: mdns::local_address(bound.ip()),
+            port: bound.port(),
+            tls: args.tls_cert.is_some(),
+            auth: args.listen_token_file.is_some(),
+        };
+        app.advertiser = Some(mdns::Advertiser::start(advertisement).map_err(|err| format!("cannot advertise via mDNS: {}", err))?);
+    }
+    let server = server.start();
+    server.publish(metrics::render(app));
+    app.metrics = Some(server);
+    Ok(())
+}
+
+/// Loads the config file, or exits with its error.
+pub(crate) fn load_config(path: Option<&std::path::Path>) -> Config {
+    match Config::load(path) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("rtop: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs the read-only viewer for `rtop attach`.
+pub(crate) fn run_attach(args: mirror::AttachArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let path = args.socket.unwrap_or_else(mirror::default_socket_path);
+    let client = MirrorClient::connect(&path)
+        .map_err(|err| format!("cannot attach to {}: {}", path.display(), err))?;
+
+    enable_raw_mode()?;
+    let mut stdout = stdout().into_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let res = (|| -> Result<(), Box<dyn std::error::Error>> {
+        loop {
+            terminal.draw(|f| render_mirror(f, &client, &path))?;
+            if crossterm::event::poll(Duration::from_millis(100))? {
+                if let Event::Key(key) = event::read()? {
+                    if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    })();
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    if let Err(err) = res {
+        println!("{:?}", err);
+    }
+
+    Ok(())
+}
+
+/// Runs the snapshot browser for `rtop view`.
+pub(crate) fn run_view(args: view::ViewArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshots = view::load(&args.file).map_err(|err| format!("cannot read {}: {}", args.file.display(), err))?;
+    let config = load_config(args.config.as_deref());
+    let depth = config.color_depth.unwrap_or_else(ColorDepth::detect);
+    let theme = Theme::resolve(&config.theme, &config.themes).unwrap_or_default().for_depth(depth);
+    let keymap = Keymap::new(&config.keys);
+    let mut viewer = view::Viewer::new(snapshots);
+
+    enable_raw_mode()?;
+    let mut stdout = stdout().into_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let res = (|| -> Result<(), Box<dyn std::error::Error>> {
+        loop {
+            let mut page = 1;
+            terminal.draw(|f| page = render_view(f, &mut viewer, &args.file, &keymap, &config, &theme))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            let page = page as isize;
+            // The first bound action that means something here wins, as in `run_app`.
+            for action in keymap.actions(&key) {
+                match action {
+                    Action::Quit | Action::Close => return Ok(()),
+                    Action::Down => viewer.select_by(1),
+                    Action::Up => viewer.select_by(-1),
+                    Action::PageDown => viewer.select_by(page),
+                    Action::PageUp => viewer.select_by(-page),
+                    Action::First => viewer.selected = 0,
+                    Action::Last => viewer.select_by(isize::MAX),
+                    Action::StepForward | Action::SeekForward => viewer.step(true),
+                    Action::StepBack | Action::SeekBack => viewer.step(false),
+                    Action::SortCpu => viewer.sort(SortBy::Cpu),
+                    Action::SortMemory => viewer.sort(SortBy::Memory),
+                    Action::SortName => viewer.sort(SortBy::Name),
+                    Action::SortPid => viewer.sort(SortBy::Pid),
+                    _ => continue,
+                }
+                break;
+            }
+        }
+    })();
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    if let Err(err) = res {
+        println!("{:?}", err);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn run_replay(path: &std::path::Path, config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshots = view::load(path).map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+    let depth = config.color_depth.unwrap_or_else(ColorDepth::detect);
+    let theme = Theme::resolve(&config.theme, &config.themes).unwrap_or_default().for_depth(depth);
+    let keymap = Keymap::new(&config.keys);
+    let mut replay = replay::Replay::new(snapshots);
+    let mut layout = config.layout.clone();
+
+    enable_raw_mode()?;
+    let mut stdout = stdout().into_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let res = (|| -> Result<(), Box<dyn std::error::Error>> {
+        loop {
+            replay.tick(Instant::now());
+            let mut page = 1;
+            terminal.draw(|f| page = render_replay(f, &mut replay, path, &layout, &keymap, &config, &theme))?;
+            // Wake up for the next sample while playing, else wait for a key.
+            let timeout = replay.next_due(Instant::now()).unwrap_or(Duration::from_secs(1));
+            if !crossterm::event::poll(timeout)? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            let page = page as isize;
+            for action in keymap.actions(&key) {
+                match action {
+                    Action::Quit | Action::Close => return Ok(()),
+                    Action::Down => replay.viewer.select_by(1),
+                    Action::Up => replay.viewer.select_by(-1),
+                    Action::PageDown => replay.viewer.select_by(page),
+                    Action::PageUp => replay.viewer.select_by(-page),
+                    Action::Play => replay.toggle(),
+                    Action::StepForward => replay.step(true),
+                    Action::StepBack => replay.step(false),
+                    Action::SeekForward => replay.seek(60),
+                    Action::SeekBack => replay.seek(-60),
+                    Action::SeekForwardFar => replay.seek(600),
+                    Action::SeekBackFar => replay.seek(-600),
+                    Action::First => replay.seek_to_end(false),
+                    Action::Last => replay.seek_to_end(true),
+                    Action::Faster => replay.change_speed(true),
+                    Action::Slower => replay.change_speed(false),
+                    Action::TogglePerCore => layout.show_per_core = !layout.show_per_core,
+                    Action::ToggleCpuHistory => layout.cpu_history = !layout.cpu_history,
+                    Action::ToggleMemoryHistory => layout.memory_history = !layout.memory_history,
+                    Action::SortCpu => replay.viewer.sort(SortBy::Cpu),
+                    Action::SortMemory => replay.viewer.sort(SortBy::Memory),
+                    Action::SortName => replay.viewer.sort(SortBy::Name),
+                    Action::SortPid => replay.viewer.sort(SortBy::Pid),
+                    _ => continue,
+                }
+                break;
+            }
+        }
+    })();
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    if let Err(err) = res {
+        println!("{:?}", err);
+    }
+
+    Ok(())
+}
+
+fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    sampler: Sampler,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut redraw = true;
+    let mut flash = false;
+    loop {
+        if redraw {
+            terminal.draw(|f| draw(f, app))?;
+            redraw = false;
+        }
+
+        // Input is polled independently of the refresh rate, so keys are
+        // handled right away even when samples are seconds apart.
+        if crossterm::event::poll(INPUT_POLL_INTERVAL)? {
+            // Handle everything already queued before drawing again, so a
+            // held-down key never falls behind the screen.
+            loop {
+                if handle_event(app, event::read()?) {
+                    return Ok(());
+                }
+                if !crossterm::event::poll(Duration::ZERO)? {
+                    break;
+                }
+            }
+            redraw = true;
+        }
+        if let Some(tab) = app.rotation.as_mut().and_then(|rotation| rotation.next(Instant::now(), app.tab)) {
+            if app.state == AppState::Main {
+                app.tab = tab;
+                redraw = true;
+            }
+        }
+        if let Some(alarm) = app.alarm.as_ref().filter(|alarm| alarm.is_ringing()) {
+            redraw |= alarm.flash(Instant::now()) != flash;
+            flash = alarm.flash(Instant::now());
+        }
+        if let Some(advertiser) = &mut app.advertiser {
+            advertiser.serve();
+        }
+        if let Some(picker) = &mut app.host_picker {
+            redraw |= picker.poll();
+        }
+        let was_running = app.actions.running().is_some();
+        app.actions.poll();
+        if was_running && app.actions.running().is_none() {
+            redraw = true;
+        }
+        if app.status_message.as_ref().is_some_and(|(at, _)| at.elapsed() >= STATUS_MESSAGE_TIME) {
+            app.status_message = None;
+            redraw = true;
+        }
+        sampler.want(app.wants());
+        if let Some(sample) = sampler.poll() {
+            // While paused, the sample is dropped and the thread refreshes
+            // it again for the next one.
+            sampler.give_back(if app.paused { sample.system } else { app.update_from(sample) });
+            if let Some(mut server) = app.mirror.take() {
+                server.publish(|| mirror::MirrorSnapshot::capture(app));
+                app.mirror = Some(server);
+            }
+            if let Some((path, mut recorder)) = app.recorder.take() {
+                if app.paused || !recorder.due() {
+                    app.recorder = Some((path, recorder));
+                } else if let Err(err) = recorder.record(export::Snapshot::capture(app, None)) {
+                    // A full disk or a removed file ends the recording, not the session.
+                    app.status_message = Some((Instant::now(), Err(format!("Stopped recording to {}: {}", path.display(), err))));
+                } else {
+                    app.recorder = Some((path, recorder));
+                }
+            }
+            if let Some(server) = &app.metrics {
+                server.publish(metrics::render(app));
+            }
+            redraw = true;
+        }
+    }
+}
+
+/// Applies one terminal event and returns whether the user quit.
+fn handle_event(app: &mut App, event: Event) -> bool {
+    if let Some(rotation) = app.rotation.as_mut().filter(|_| matches!(event, Event::Key(_) | Event::Mouse(_) | Event::Paste(_))) {
+        rotation.interact(Instant::now());
+    }
+    match event {
+        Event::Key(key) if app.keymap.actions(&key).contains(&Action::Acknowledge) && app.alarm.as_ref().is_some_and(Alarm::is_ringing) => {
+            if let Some(alarm) = &mut app.alarm {
+                alarm.acknowledge();
+            }
+        }
+        _ if app.alarm.as_ref().is_some_and(Alarm::is_ringing) => {}
+        Event::Key(key) if app.lock.is_some() => {
+            let unlocked = app.lock.as_mut().is_some_and(|lock| lock.key(&key));
+            if unlocked {
+                app.lock = None;
+            }
+        }
+        _ if app.lock.is_some() => {}
+        Event::Key(key) if app.state == AppState::Input => app.edit_input(&key),
+        Event::Key(key) if app.state == AppState::Columns => app.edit_columns(&key),
+        Event::Key(key) if app.state == AppState::Hosts => app.pick_hosts(&key),
+        Event::Key(key) if app.state == AppState::Affinity => app.edit_affinity(&key),
+        Event::Paste(text) if app.state == AppState::Input => app.paste(&text),
+        Event::Key(key) => {
+            let mut handled = false;
+            for action in app.keymap.actions(&key) {
+                if action == Action::Quit {
+                    return true;
+                }
+                if app.perform(action) {
+                    handled = true;
+                    break;
+                }
+            }
+            if !handled && app.state == AppState::Main && !app.kiosk {
+                app.run_user_action(&key);
+            }
+        }
+        Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+            app.click(mouse.column, mouse.row);
+        }
+        Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::ScrollDown | MouseEventKind::ScrollUp) => {
+            app.scroll(mouse.kind == MouseEventKind::ScrollDown);
+        }
+        _ => {}
+    }
+    false
+}
+
+/// Longest the UI loop waits for input before checking on running actions,
+/// expiring status messages and taking new samples.
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Applies --drop-privileges; the samples taken so far ran with full privileges.
+fn drop_privileges(app: &mut App, user: &Option<Option<String>>) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(user) = user {
+        app.privilege = privilege::drop_root(user.as_deref()).map_err(|err| format!("cannot drop privileges: {}", err))?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "tls")]
+fn with_tls(server: metrics::MetricsServer, cert: &std::path::Path, key: &std::path::Path) -> Result<metrics::MetricsServer, Box<dyn std::error::Error>> {
+    Ok(server.with_tls(cert, key).map_err(|err| format!("cannot set up TLS: {}", err))?)
+}
+
+#[cfg(not(feature = "tls"))]
+fn with_tls(_server: metrics::MetricsServer, _cert: &std::path::Path, _key: &std::path::Path) -> Result<metrics::MetricsServer, Box<dyn std::error::Error>> {
+    Err("--tls-cert needs rtop built with `--features tls`".into())
+}
+
+#[cfg(feature = "tls")]
+fn agent_tls(ca: Option<&std::path::Path>) -> Result<cluster::Tls, Box<dyn std::error::Error>> {
+    Ok(cluster::tls(ca).map_err(|err| format!("cannot read CA certificates: {}", err))?)
+}
+
+#[cfg(not(feature = "tls"))]
+fn agent_tls(ca: Option<&std::path::Path>) -> Result<cluster::Tls, Box<dyn std::error::Error>> {
+    match ca {
+        Some(_) => Err("--agent-ca needs rtop built with `--features tls`".into()),
+        None => Ok(cluster::Tls::default()),
+    }
+}
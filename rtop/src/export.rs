@@ -0,0 +1,570 @@
+// src/export.rs
+use crate::config::{Column, Config, Unit};
+use crate::redact::Redactor;
+use crate::text;
+use crate::theme::ColorDepth;
+use crate::{App, AppError, SortBy};
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use sysinfo::{CpuExt, Pid, PidExt, ProcessExt, System, SystemExt, UserExt};
+use tui::style::Color;
+
+/// Processes in a text snapshot unless `--limit` says otherwise.
+const DEFAULT_TEXT_LIMIT: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    /// One JSON object per snapshot and line
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when writing to a color terminal and `NO_COLOR` is not set
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color, `terminal` being whether the output is a terminal
+    /// that takes colors.
+    pub fn enabled(self, terminal: bool) -> bool {
+        match self {
+            ColorChoice::Auto => terminal,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// How headless snapshots are printed.
+#[derive(Clone, Copy, Debug)]
+pub struct OutputOptions {
+    /// Processes per snapshot; `None` means 20 for text and all for JSON.
+    pub limit: Option<usize>,
+    pub format: Format,
+    /// ANSI colors in text output, using the TUI's thresholds and theme.
+    pub color: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    /// Column to sort the process list by
+    #[arg(short, long, value_enum, default_value_t = SortBy::Cpu)]
+    pub sort: SortBy,
+
+    /// Number of processes to include [default: 20 for text, all for JSON]
+    #[arg(short = 'n', long)]
+    pub limit: Option<usize>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+
+    /// Color text output
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Write the snapshot to a file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Replace process names with hashes
+    #[arg(long)]
+    pub redact: bool,
+
+    /// Write the whole process table to a timestamped CSV file in DIR (default: the current directory)
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["output", "format", "limit"])]
+    pub csv: Option<Option<PathBuf>>,
+}
+
+pub fn run(args: ExportArgs) -> Result<(), AppError> {
+    let mut app = App::new_sampled(Config::default());
+    app.sort_by = args.sort.name().to_string();
+    app.redact = Redactor::new(args.redact);
+
+    if let Some(dir) = args.csv {
+        let dir = dir.unwrap_or_else(|| PathBuf::from("."));
+        let (path, _) = export_table(&app, &dir, TableFormat::Csv)?;
+        println!("{}", path.display());
+        return Ok(());
+    }
+    let mut options = OutputOptions {
+        limit: args.limit,
+        format: args.format,
+        color: false,
+    };
+    match args.output {
+        Some(path) => write_output(&mut File::create(path)?, &app, options)?,
+        None => {
+            let terminal = io::stdout().is_terminal() && ColorDepth::detect() != ColorDepth::None;
+            options.color = args.color.enabled(terminal);
+            write_output(&mut io::stdout().lock(), &app, options)?
+        }
+    }
+    Ok(())
+}
+
+/// `--batch` and `--once`: prints a snapshot every `interval` until `iterations` have been
+/// printed (forever if `None`) or stdout is closed.
+pub fn run_batch(
+    mut app: App,
+    interval: Duration,
+    iterations: Option<u64>,
+    options: OutputOptions,
+) -> Result<(), AppError> {
+    let mut delay = System::MINIMUM_CPU_UPDATE_INTERVAL;
+    let mut printed = 0;
+    while iterations.is_none_or(|n| printed < n) {
+        std::thread::sleep(delay);
+        delay = interval;
+        app.update();
+        let mut out = io::stdout().lock();
+        let separator = if options.format == Format::Text {
+            "\n"
+        } else {
+            ""
+        };
+        let result = write_output(&mut out, &app, options)
+            .and_then(|_| write!(out, "{}", separator))
+            .and_then(|_| out.flush());
+        match result {
+            // Piped into `head` or similar, which has seen enough.
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            result => result?,
+        }
+        printed += 1;
+    }
+    Ok(())
+}
+
+fn write_output<W: Write>(out: &mut W, app: &App, options: OutputOptions) -> io::Result<()> {
+    match options.format {
+        Format::Text => write_snapshot(
+            out,
+            app,
+            options.limit.unwrap_or(DEFAULT_TEXT_LIMIT),
+            options.color,
+        ),
+        Format::Json => {
+            serde_json::to_writer(&mut *out, &Snapshot::capture(app, options.limit))?;
+            writeln!(out)
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CpuSnapshot {
+    pub usage: f32,
+    pub cores: Vec<f32>,
+}
+
+/// Bytes.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct MemorySnapshot {
+    pub total: u64,
+    pub used: u64,
+    pub available: u64,
+    pub swap_total: u64,
+    pub swap_used: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProcessSnapshot {
+    pub pid: u32,
+    pub parent: Option<u32>,
+    pub name: String,
+    pub command: String,
+    pub user: Option<String>,
+    pub status: String,
+    pub cpu_usage: f32,
+    /// Resident and virtual memory in bytes.
+    pub memory: u64,
+    pub virtual_memory: u64,
+    /// Seconds since the epoch.
+    pub start_time: u64,
+}
+
+/// Everything `--format json` prints for one sample.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub timestamp: String,
+    pub hostname: Option<String>,
+    pub uptime: u64,
+    pub load_average: [f64; 3],
+    pub cpu: CpuSnapshot,
+    pub memory: MemorySnapshot,
+    pub processes: Vec<ProcessSnapshot>,
+}
+
+impl Snapshot {
+    /// Captures global usage and the first `limit` processes in the current sort order (all if `None`).
+    pub fn capture(app: &App, limit: Option<usize>) -> Snapshot {
+        let system = &app.system;
+        let load = system.load_average();
+        let processes = app
+            .get_sorted_processes()
+            .into_iter()
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|(pid, process)| ProcessSnapshot {
+                pid: pid.as_u32(),
+                parent: process.parent().map(|parent| parent.as_u32()),
+                name: app.redact.process(process.name()).into_owned(),
+                command: app.redact.command(&process.cmd().join(" ")).into_owned(),
+                user: process
+                    .user_id()
+                    .and_then(|uid| system.get_user_by_id(uid))
+                    .map(|user| app.redact.user(user.name()).into_owned()),
+                status: process.status().to_string(),
+                cpu_usage: process.cpu_usage(),
+                memory: process.memory(),
+                virtual_memory: process.virtual_memory(),
+                start_time: process.start_time(),
+            })
+            .collect();
+        Snapshot {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            hostname: system
+                .host_name()
+                .map(|name| app.redact.host(&name).into_owned()),
+            uptime: system.uptime(),
+            load_average: [load.one, load.five, load.fifteen],
+            cpu: CpuSnapshot {
+                usage: system.global_cpu_info().cpu_usage(),
+                cores: system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect(),
+            },
+            memory: MemorySnapshot {
+                total: system.total_memory(),
+                used: system.used_memory(),
+                available: system.available_memory(),
+                swap_total: system.total_swap(),
+                swap_used: system.used_swap(),
+            },
+            processes,
+        }
+    }
+}
+
+/// The SGR parameters that set `color` as the foreground.
+fn ansi_foreground(color: Color) -> String {
+    match color {
+        Color::Black => "30".to_string(),
+        Color::Red => "31".to_string(),
+        Color::Green => "32".to_string(),
+        Color::Yellow => "33".to_string(),
+        Color::Blue => "34".to_string(),
+        Color::Magenta => "35".to_string(),
+        Color::Cyan => "36".to_string(),
+        Color::Gray => "37".to_string(),
+        Color::DarkGray => "90".to_string(),
+        Color::LightRed => "91".to_string(),
+        Color::LightGreen => "92".to_string(),
+        Color::LightYellow => "93".to_string(),
+        Color::LightBlue => "94".to_string(),
+        Color::LightMagenta => "95".to_string(),
+        Color::LightCyan => "96".to_string(),
+        Color::White => "97".to_string(),
+        Color::Indexed(index) => format!("38;5;{}", index),
+        Color::Rgb(r, g, b) => format!("38;2;{};{};{}", r, g, b),
+        Color::Reset => "39".to_string(),
+    }
+}
+
+/// Writes a plain-text snapshot of global usage followed by the top `limit`
+/// processes, with usage colored like in the TUI when `color` is set.
+pub fn write_snapshot<W: Write>(
+    out: &mut W,
+    app: &App,
+    limit: usize,
+    color: bool,
+) -> io::Result<()> {
+    let system = &app.system;
+    let (theme, thresholds) = (&app.theme, &app.config.thresholds);
+    let paint = |text: String, critical: bool, warning: bool| {
+        if !color {
+            return text;
+        }
+        let fg = if critical {
+            theme.critical
+        } else if warning {
+            theme.warning
+        } else {
+            theme.good
+        };
+        format!("\x1b[{}m{}\x1b[0m", ansi_foreground(fg), text)
+    };
+    let cpu = system.global_cpu_info().cpu_usage();
+    let swap_percent = system.used_swap() as f64 / system.total_swap().max(1) as f64 * 100.0;
+    writeln!(
+        out,
+        "rtop snapshot {}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+    )?;
+    writeln!(
+        out,
+        "CPU: {}  Memory: {:.1} / {:.1} MB  Swap: {}",
+        paint(
+            format!("{:.1}%", cpu),
+            cpu > thresholds.core_critical,
+            cpu > thresholds.core_warning
+        ),
+        system.used_memory() as f64 / 1024.0 / 1024.0,
+        system.total_memory() as f64 / 1024.0 / 1024.0,
+        paint(
+            format!(
+                "{:.1} / {:.1} MB",
+                system.used_swap() as f64 / 1024.0 / 1024.0,
+                system.total_swap() as f64 / 1024.0 / 1024.0
+            ),
+            swap_percent > thresholds.swap_critical,
+            swap_percent > thresholds.swap_warning
+        ),
+    )?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{:>8}  {:<24} {:>6} {:>12} {:>12}",
+        "PID", "NAME", "CPU%", "RSS", "VIRTUAL"
+    )?;
+    for (pid, process) in app.get_sorted_processes().into_iter().take(limit) {
+        let cpu = process.cpu_usage();
+        let memory = process.memory() as f64 / 1024.0 / 1024.0;
+        let memory_critical = memory > thresholds.process_memory_critical;
+        let memory_warning = memory > thresholds.process_memory_warning;
+        writeln!(
+            out,
+            "{:>8}  {} {} {} {}",
+            pid.to_string(),
+            text::pad(&app.redact.process(process.name()), 24),
+            paint(
+                format!("{:>6.1}", cpu),
+                cpu > thresholds.process_cpu_critical,
+                cpu > thresholds.process_cpu_warning
+            ),
+            paint(
+                format!("{:>9.1} MB", memory),
+                memory_critical,
+                memory_warning
+            ),
+            paint(
+                format!(
+                    "{:>9.2} GB",
+                    process.virtual_memory() as f64 / 1024.0 / 1024.0 / 1024.0
+                ),
+                memory_critical,
+                memory_warning
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// File formats for exporting the process table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableFormat {
+    Csv,
+    Json,
+}
+
+impl TableFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            TableFormat::Csv => "csv",
+            TableFormat::Json => "json",
+        }
+    }
+}
+
+/// Writes the rows the process table currently shows, in display order, with
+/// the configured columns and `[format]` entries; see `Config::export_format`.
+pub fn write_table<W: Write>(out: &mut W, app: &App, format: TableFormat) -> io::Result<()> {
+    let columns = app.columns();
+    let rows = app.visible_processes();
+    // Each field as a JSON value and as CSV text.
+    let field = |column: Column, pid: Pid, process: &sysinfo::Process| {
+        let number = match column {
+            Column::Pid => return (serde_json::Value::from(pid.as_u32()), pid.to_string()),
+            Column::Name => {
+                let name = app.redact.process(process.name());
+                return (serde_json::Value::from(name.as_ref()), csv_field(&name));
+            }
+            Column::User => {
+                let user = app.user_name(process);
+                return (serde_json::Value::from(user.as_str()), csv_field(&user));
+            }
+            Column::Group => {
+                let group = app.env_group(process).unwrap_or_default();
+                return (serde_json::Value::from(group), csv_field(group));
+            }
+            Column::State => {
+                let state = crate::summary::state_letter(process.status()).to_string();
+                return (serde_json::Value::from(state.as_str()), state);
+            }
+            Column::Wchan => {
+                let wchan = app.per_process.wchans.get(&pid).map_or("", String::as_str);
+                return (serde_json::Value::from(wchan), csv_field(wchan));
+            }
+            Column::CpuTrend => {
+                let samples = app.cpu_trends.samples(pid);
+                let text: Vec<String> = samples.iter().map(|usage| format!("{:.1}", usage)).collect();
+                return (serde_json::Value::from(samples), text.join(" "));
+            }
+            Column::Command => {
+                let command = app.redact.command(&process.cmd().join(" ")).into_owned();
+                return (serde_json::Value::from(command.as_str()), csv_field(&command));
+            }
+            Column::Nice => match crate::control::nice(pid) {
+                Ok(nice) => nice as f64,
+                Err(_) => return (serde_json::Value::Null, String::new()),
+            },
+            Column::Threads => match app.per_process.statuses.get(&pid).and_then(|status| status.threads) {
+                Some(threads) => threads as f64,
+                None => return (serde_json::Value::Null, String::new()),
+            },
+            Column::Swap => match app.per_process.statuses.get(&pid).and_then(|status| status.swap) {
+                Some(bytes) => bytes as f64,
+                None => return (serde_json::Value::Null, String::new()),
+            },
+            Column::Cpu => f64::from(process.cpu_usage()),
+            Column::Rss => process.memory() as f64,
+            Column::Private => app.private_memory(pid, process) as f64,
+            Column::Virtual => process.virtual_memory() as f64,
+            Column::GpuUsage => app.gpu.process_usage.get(&pid).copied().unwrap_or(0.0),
+            Column::GpuMemory => app.gpu.process_memory.get(&pid).copied().unwrap_or(0) as f64,
+            Column::DiskRead => app.per_process.disk_io.rate(pid).read,
+            Column::DiskWrite => app.per_process.disk_io.rate(pid).write,
+            Column::NetSent => app.per_process.net_io.rate(pid).sent,
+            Column::NetReceived => app.per_process.net_io.rate(pid).received,
+        };
+        let (precision, unit) = app.config.export_format(column);
+        let scaled = number / unit.map_or(1.0, Unit::bytes);
+        let value = if precision == 0 {
+            serde_json::Value::from(scaled.round() as u64)
+        } else {
+            let factor = 10f64.powi(precision as i32);
+            serde_json::Value::from((scaled * factor).round() / factor)
+        };
+        (value, format!("{:.*}", precision, scaled))
+    };
+    match format {
+        TableFormat::Csv => {
+            let header: Vec<String> = columns
+                .iter()
+                .map(|&column| match app.config.export_format(column) {
+                    (_, Some(unit)) => format!("{} ({})", column.title(), unit.label()),
+                    _ if column.is_size() => format!("{} (bytes)", column.title()),
+                    _ => column.title().to_string(),
+                })
+                .collect();
+            writeln!(out, "{}", header.join(","))?;
+            for (pid, process) in rows {
+                let fields: Vec<String> = columns
+                    .iter()
+                    .map(|&column| field(column, pid, process).1)
+                    .collect();
+                writeln!(out, "{}", fields.join(","))?;
+            }
+        }
+        TableFormat::Json => {
+            let rows: Vec<serde_json::Map<String, serde_json::Value>> = rows
+                .into_iter()
+                .map(|(pid, process)| {
+                    columns
+                        .iter()
+                        .map(|&column| (column.key().to_string(), field(column, pid, process).0))
+                        .collect()
+                })
+                .collect();
+            serde_json::to_writer_pretty(&mut *out, &rows)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the process table to `rtop-processes-<timestamp>.<ext>` in `dir`
+/// and returns the path and the number of rows.
+pub fn export_table(app: &App, dir: &Path, format: TableFormat) -> io::Result<(PathBuf, usize)> {
+    let name = format!(
+        "rtop-processes-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    );
+    let path = dir.join(name);
+    let mut file = io::BufWriter::new(File::create(&path)?);
+    write_table(&mut file, app, format)?;
+    file.flush()?;
+    Ok((path, app.visible_processes().len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_json_snapshot() {
+        let mut app = App::new(Config::default());
+        app.redact = Redactor::new(true);
+        let mut out = Vec::new();
+        let options = OutputOptions {
+            limit: Some(3),
+            format: Format::Json,
+            color: false,
+        };
+        write_output(&mut out, &app, options).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(out.iter().filter(|&&b| b == b'\n').count(), 1);
+        assert!(json["memory"]["total"].as_u64().unwrap() > 0);
+        let processes = json["processes"].as_array().unwrap();
+        assert!(!processes.is_empty() && processes.len() <= 3);
+        assert!(processes[0]["name"].as_str().unwrap().starts_with("proc-"));
+    }
+
+    #[test]
+    fn test_ansi_foreground() {
+        assert_eq!(ansi_foreground(Color::Red), "31");
+        assert_eq!(ansi_foreground(Color::Indexed(208)), "38;5;208");
+        assert_eq!(ansi_foreground(Color::Rgb(1, 2, 3)), "38;2;1;2;3");
+        assert!(ColorChoice::Auto.enabled(true) && !ColorChoice::Auto.enabled(false));
+    }
+
+    #[test]
+    fn test_table_export() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+
+        let mut app = App::new(Config::default());
+        app.config.columns = vec![Column::Pid, Column::Rss];
+        let mut out = Vec::new();
+        write_table(&mut out, &app, TableFormat::Csv).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("PID,RSS (bytes)"));
+        assert_eq!(lines.count(), app.visible_processes().len());
+
+        let mut out = Vec::new();
+        write_table(&mut out, &app, TableFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let rows = json.as_array().unwrap();
+        assert_eq!(rows.len(), app.visible_processes().len());
+        assert!(rows[0]["pid"].is_u64() && rows[0]["rss"].is_u64());
+        assert!(rows[0].get("name").is_none());
+    }
+}
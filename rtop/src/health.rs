@@ -0,0 +1,230 @@
+// src/health.rs
+use crate::config::Config;
+use crate::silence::Silences;
+use crate::{App, AppError};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use sysinfo::{CpuExt, DiskExt, ProcessExt, ProcessStatus, System, SystemExt};
+
+#[derive(Args, Debug)]
+pub struct CheckArgs {
+    /// Print the findings as JSON instead of plain text
+    #[arg(long)]
+    pub json: bool,
+
+    /// Config file with [[alerts]] rules to check and maintenance windows that silence findings [default: ~/.config/rtop/config.toml]
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Ok,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    /// Exit code following the Nagios plugin convention (0 ok, 1 warning, 2 critical).
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Severity::Ok => 0,
+            Severity::Warning => 1,
+            Severity::Critical => 2,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Ok => "OK",
+            Severity::Warning => "WARN",
+            Severity::Critical => "CRIT",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Finding {
+    pub check: String,
+    pub severity: Severity,
+    pub message: String,
+    /// Why the finding does not count, if its rule is silenced.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub silenced: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    status: Severity,
+    findings: &'a [Finding],
+}
+
+/// Maps `value` onto a severity given inclusive warning and critical thresholds.
+fn classify(value: f64, warn: f64, crit: f64) -> Severity {
+    if value >= crit {
+        Severity::Critical
+    } else if value >= warn {
+        Severity::Warning
+    } else {
+        Severity::Ok
+    }
+}
+
+fn percent(used: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        used as f64 / total as f64 * 100.0
+    }
+}
+
+/// Runs every health heuristic against an already refreshed `System`.
+pub fn evaluate(system: &System) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    let cpu = system.global_cpu_info().cpu_usage() as f64;
+    findings.push(Finding {
+        check: "cpu".to_string(),
+        severity: classify(cpu, 90.0, 98.0),
+        message: format!("{:.1}% busy", cpu),
+        silenced: None,
+    });
+
+    let mem = percent(system.used_memory(), system.total_memory());
+    findings.push(Finding {
+        check: "memory".to_string(),
+        severity: classify(mem, 90.0, 95.0),
+        message: format!("{:.1}% used", mem),
+        silenced: None,
+    });
+
+    if system.total_swap() > 0 {
+        let swap = percent(system.used_swap(), system.total_swap());
+        findings.push(Finding {
+            check: "swap".to_string(),
+            severity: classify(swap, 50.0, 80.0),
+            message: format!("{:.1}% used", swap),
+            silenced: None,
+        });
+    }
+
+    let cores = system.cpus().len().max(1) as f64;
+    let load = system.load_average();
+    findings.push(Finding {
+        check: "load".to_string(),
+        severity: classify(load.five / cores, 1.0, 2.0),
+        message: format!("5 min load {:.2} on {} cores", load.five, cores),
+        silenced: None,
+    });
+
+    let zombies = system
+        .processes()
+        .values()
+        .filter(|p| p.status() == ProcessStatus::Zombie)
+        .count();
+    findings.push(Finding {
+        check: "zombies".to_string(),
+        severity: classify(zombies as f64, 1.0, 50.0),
+        message: format!("{} zombie processes", zombies),
+        silenced: None,
+    });
+
+    for disk in system.disks() {
+        let used = disk.total_space().saturating_sub(disk.available_space());
+        let usage = percent(used, disk.total_space());
+        findings.push(Finding {
+            check: format!("disk:{}", disk.mount_point().display()),
+            severity: classify(usage, 90.0, 95.0),
+            message: format!("{:.1}% full", usage),
+            silenced: None,
+        });
+    }
+
+    findings
+}
+
+pub fn run(args: CheckArgs) -> Result<i32, AppError> {
+    let config = Config::load(args.config.as_deref())?;
+    let (status, findings) = check(config);
+
+    if args.json {
+        let report = Report {
+            status,
+            findings: &findings,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for finding in &findings {
+            let silenced = finding
+                .silenced
+                .as_ref()
+                .map_or(String::new(), |reason| format!(" ({})", reason));
+            println!(
+                "{:<4} {:<20} {}{}",
+                finding.severity.label(),
+                finding.check,
+                finding.message,
+                silenced
+            );
+        }
+    }
+    Ok(status.exit_code())
+}
+
+/// Samples the system once and runs what the Alerts tab runs with `config`:
+/// the built-in checks and the `[[alerts]]` rules.
+fn check(mut config: Config) -> (Severity, Vec<Finding>) {
+    // One sample cannot tell how long a value has been above its threshold,
+    // so a check reports what is above it now.
+    for rule in &mut config.alerts {
+        rule.for_seconds = 0;
+    }
+    let silences = Silences::new(config.maintenance.clone());
+    let app = App::new_sampled(config);
+    let mut findings = app.alerts;
+    let now = chrono::Local::now();
+    for finding in &mut findings {
+        finding.silenced = silences
+            .status(&finding.check, now)
+            .map(|silenced| silenced.describe());
+    }
+    let status = findings
+        .iter()
+        .filter(|f| f.silenced.is_none())
+        .map(|f| f.severity)
+        .max()
+        .unwrap_or(Severity::Ok);
+    (status, findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_thresholds() {
+        assert_eq!(classify(10.0, 90.0, 95.0), Severity::Ok);
+        assert_eq!(classify(90.0, 90.0, 95.0), Severity::Warning);
+        assert_eq!(classify(99.0, 90.0, 95.0), Severity::Critical);
+    }
+
+    #[test]
+    fn test_check_runs_configured_rules() {
+        let (_, findings) = check(Config::default());
+        assert!(!findings.iter().any(|finding| finding.check == "memory_used"));
+
+        let config: Config = toml::from_str(
+            "[[alerts]]\nname = \"memory_used\"\nmetric = \"memory\"\nabove = 0.0\nfor_seconds = 60\nseverity = \"critical\"\n",
+        )
+        .unwrap();
+        let (status, findings) = check(config);
+        let finding = findings
+            .iter()
+            .find(|finding| finding.check == "memory_used")
+            .unwrap();
+        assert_eq!(finding.severity, Severity::Critical);
+        assert_eq!(status.exit_code(), 2);
+    }
+}
@@ -0,0 +1,92 @@
+// src/history.rs
+use crate::source::Source;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// A ring buffer of timestamped samples covering the last `window`, oldest
+/// first, for the charts in the header.
+pub struct History {
+    window: Duration,
+    samples: VecDeque<(Instant, f64)>,
+}
+
+impl History {
+    pub fn new(window: Duration) -> History {
+        History {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Adds a sample taken at `at` and drops those that fell out of the window.
+    pub fn push(&mut self, at: Instant, value: f64) {
+        self.samples.push_back((at, value));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(time, _)| at.duration_since(*time) > self.window)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Chart points: seconds before the newest sample (negative) and the
+    /// value. Relative to the newest sample, the chart holds still while
+    /// refreshing is paused.
+    pub fn points(&self) -> Vec<(f64, f64)> {
+        let Some(&(now, _)) = self.samples.back() else {
+            return Vec::new();
+        };
+        self.samples
+            .iter()
+            .map(|(time, value)| (-now.duration_since(*time).as_secs_f64(), *value))
+            .collect()
+    }
+}
+
+/// The series of the history charts.
+pub struct Histories {
+    pub cpu: History,
+    /// Used memory and swap, in percent.
+    pub memory: History,
+    pub swap: History,
+}
+
+impl Histories {
+    pub fn new(window: Duration) -> Histories {
+        Histories {
+            cpu: History::new(window),
+            memory: History::new(window),
+            swap: History::new(window),
+        }
+    }
+
+    pub fn push(&mut self, at: Instant, source: &dyn Source) {
+        self.cpu.push(at, source.cpu_usage() as f64);
+        self.memory.push(at, source.memory_percent());
+        self.swap.push(at, source.swap_percent());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_window() {
+        let start = Instant::now();
+        let mut history = History::new(Duration::from_secs(10));
+        for (second, value) in [(0, 10.0), (5, 50.0), (10, 20.0), (12, 30.0)] {
+            history.push(start + Duration::from_secs(second), value);
+        }
+        // The sample from second 0 is 12 s old, out of the 10 s window.
+        assert_eq!(
+            history.points(),
+            vec![(-7.0, 50.0), (-2.0, 20.0), (0.0, 30.0)]
+        );
+    }
+}
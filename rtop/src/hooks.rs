@@ -0,0 +1,177 @@
+// src/hooks.rs
+use crate::actions::{self, Outcome};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+/// Commands run when the interactive UI starts and exits, from the config's
+/// `[hooks]` table.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Hooks {
+    /// Program and arguments, run without a shell before the UI appears.
+    pub on_start: Vec<String>,
+    /// Run after the UI is gone, with the session summary as JSON on stdin.
+    pub on_exit: Vec<String>,
+    /// Seconds before a hook and its children are killed.
+    pub timeout: u64,
+}
+
+impl Default for Hooks {
+    fn default() -> Hooks {
+        Hooks {
+            on_start: Vec::new(),
+            on_exit: Vec::new(),
+            timeout: 10,
+        }
+    }
+}
+
+impl Hooks {
+    pub fn start(&self) -> Result<(), String> {
+        self.run("on_start", &self.on_start, None)
+    }
+
+    pub fn exit(&self, summary: &SessionSummary) -> Result<(), String> {
+        let json = serde_json::to_vec(summary).map_err(|err| err.to_string())?;
+        self.run("on_exit", &self.on_exit, Some(&json))
+    }
+
+    fn run(&self, name: &str, argv: &[String], input: Option<&[u8]>) -> Result<(), String> {
+        if argv.is_empty() {
+            return Ok(());
+        }
+        let env = [("RTOP_HOOK", name.trim_start_matches("on_").to_string())];
+        let timeout = Duration::from_secs(self.timeout);
+        let execution = actions::execute(name, argv, &env, input, timeout);
+        let failure = match execution.outcome {
+            Outcome::Exited(0) => return Ok(()),
+            Outcome::Exited(code) => format!("exited with {}", code),
+            Outcome::Signaled => "killed by a signal".to_string(),
+            Outcome::TimedOut => format!("timed out after {}s", self.timeout),
+            Outcome::FailedToStart(err) => format!("could not start: {}", err),
+        };
+        match execution.stderr.trim() {
+            "" => Err(format!("{} hook {}", name, failure)),
+            stderr => Err(format!("{} hook {}: {}", name, failure, stderr)),
+        }
+    }
+}
+
+/// A process with the most CPU time over the session.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TopProcess {
+    pub pid: u32,
+    pub name: String,
+    pub cpu_seconds: f64,
+}
+
+/// What the exit hook receives on stdin.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SessionSummary {
+    pub host: String,
+    /// RFC 3339 timestamps.
+    pub started: String,
+    pub ended: String,
+    pub duration_secs: u64,
+    pub samples: usize,
+    pub cpu_average: f64,
+    pub cpu_peak: f64,
+    pub memory_peak: u64,
+    pub memory_total: u64,
+    /// Alert rules that fired unsilenced at some point.
+    pub alerts: Vec<String>,
+    pub top_processes: Vec<TopProcess>,
+}
+
+/// Accumulates the session summary, one sample per refresh.
+pub struct Session {
+    started: DateTime<Local>,
+    samples: usize,
+    cpu_total: f64,
+    cpu_peak: f64,
+    memory_peak: u64,
+    alerts: BTreeSet<String>,
+}
+
+impl Session {
+    pub fn new() -> Session {
+        Session {
+            started: Local::now(),
+            samples: 0,
+            cpu_total: 0.0,
+            cpu_peak: 0.0,
+            memory_peak: 0,
+            alerts: BTreeSet::new(),
+        }
+    }
+
+    pub fn record<'a>(&mut self, cpu: f64, memory: u64, firing: impl IntoIterator<Item = &'a str>) {
+        self.samples += 1;
+        self.cpu_total += cpu;
+        self.cpu_peak = self.cpu_peak.max(cpu);
+        self.memory_peak = self.memory_peak.max(memory);
+        self.alerts.extend(firing.into_iter().map(str::to_string));
+    }
+
+    pub fn summary(
+        &self,
+        host: String,
+        memory_total: u64,
+        top_processes: Vec<TopProcess>,
+        ended: DateTime<Local>,
+    ) -> SessionSummary {
+        SessionSummary {
+            host,
+            started: self.started.to_rfc3339(),
+            ended: ended.to_rfc3339(),
+            duration_secs: (ended - self.started).num_seconds().max(0) as u64,
+            samples: self.samples,
+            cpu_average: self.cpu_total / self.samples.max(1) as f64,
+            cpu_peak: self.cpu_peak,
+            memory_peak: self.memory_peak,
+            memory_total,
+            alerts: self.alerts.iter().cloned().collect(),
+            top_processes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_summary() {
+        let mut session = Session::new();
+        session.record(20.0, 100, ["memory"]);
+        session.record(60.0, 300, ["cpu", "memory"]);
+        let ended = session.started + chrono::Duration::seconds(90);
+        let summary = session.summary("web1".to_string(), 1000, Vec::new(), ended);
+        assert_eq!(summary.samples, 2);
+        assert_eq!(summary.cpu_average, 40.0);
+        assert_eq!(summary.cpu_peak, 60.0);
+        assert_eq!(summary.memory_peak, 300);
+        assert_eq!(summary.alerts, vec!["cpu", "memory"]);
+        assert_eq!(summary.duration_secs, 90);
+    }
+
+    #[test]
+    fn test_exit_hook_reads_summary() {
+        let summary = Session::new().summary("web1".to_string(), 0, Vec::new(), Local::now());
+        let script = |script: &str| Hooks {
+            on_exit: ["sh", "-c", script].map(str::to_string).to_vec(),
+            ..Hooks::default()
+        };
+        assert_eq!(
+            script(r#"grep -q '"host":"web1"' && test "$RTOP_HOOK" = exit"#).exit(&summary),
+            Ok(())
+        );
+        assert_eq!(
+            script("echo nope >&2; exit 4").exit(&summary),
+            Err("on_exit hook exited with 4: nope".to_string())
+        );
+        assert_eq!(Hooks::default().start(), Ok(()));
+    }
+}
@@ -0,0 +1,158 @@
+// src/hosts.rs
+use crate::mdns::{self, Agent};
+use std::io;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+/// How long the picker listens for answers.
+const DISCOVERY_TIME: Duration = Duration::from_secs(2);
+
+/// The host picker of the Cluster tab: the agents mDNS finds, checked when
+/// the Cluster tab polls them.
+pub struct HostPicker {
+    /// `None` while discovery runs.
+    pub entries: Option<Result<Vec<(Agent, bool)>, String>>,
+    pub cursor: usize,
+    /// URLs the Cluster tab polled when the picker opened.
+    polled: Vec<String>,
+    receiver: Receiver<io::Result<Vec<Agent>>>,
+}
+
+impl HostPicker {
+    /// Opens the picker and starts looking for agents in the background.
+    pub fn start(polled: Vec<String>) -> HostPicker {
+        let mut picker = HostPicker::new(polled, mpsc::channel().1);
+        picker.rescan();
+        picker
+    }
+
+    fn new(polled: Vec<String>, receiver: Receiver<io::Result<Vec<Agent>>>) -> HostPicker {
+        HostPicker {
+            entries: None,
+            cursor: 0,
+            polled,
+            receiver,
+        }
+    }
+
+    /// Looks for agents again, keeping what is checked.
+    pub fn rescan(&mut self) {
+        if let Some(Ok(entries)) = &self.entries {
+            self.polled = checked(entries);
+        }
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(mdns::discover(DISCOVERY_TIME));
+        });
+        self.receiver = receiver;
+        self.entries = None;
+    }
+
+    /// Takes the agents found, once discovery is done; returns whether it
+    /// just finished.
+    pub fn poll(&mut self) -> bool {
+        let Ok(found) = self.receiver.try_recv() else {
+            return false;
+        };
+        self.entries = Some(
+            found
+                .map(|agents| {
+                    agents
+                        .into_iter()
+                        .map(|agent| {
+                            let polled = self.polled.contains(&agent.url());
+                            (agent, polled)
+                        })
+                        .collect()
+                })
+                .map_err(|err| err.to_string()),
+        );
+        self.cursor = 0;
+        true
+    }
+
+    pub fn select(&mut self, down: bool) {
+        let len = match &self.entries {
+            Some(Ok(entries)) => entries.len(),
+            _ => 0,
+        };
+        self.cursor = if down {
+            (self.cursor + 1).min(len.saturating_sub(1))
+        } else {
+            self.cursor.saturating_sub(1)
+        };
+    }
+
+    /// Checks or unchecks the agent under the cursor.
+    pub fn toggle(&mut self) {
+        if let Some(Ok(entries)) = &mut self.entries {
+            if let Some((_, checked)) = entries.get_mut(self.cursor) {
+                *checked = !*checked;
+            }
+        }
+    }
+
+    /// URLs to start and to stop polling. Agents given with `--agent` but
+    /// not found stay as they are.
+    pub fn changes(&self) -> (Vec<String>, Vec<String>) {
+        let Some(Ok(entries)) = &self.entries else {
+            return (Vec::new(), Vec::new());
+        };
+        let (mut add, mut remove) = (Vec::new(), Vec::new());
+        for (agent, checked) in entries {
+            let url = agent.url();
+            match (*checked, self.polled.contains(&url)) {
+                (true, false) => add.push(url),
+                (false, true) => remove.push(url),
+                _ => {}
+            }
+        }
+        (add, remove)
+    }
+}
+
+fn checked(entries: &[(Agent, bool)]) -> Vec<String> {
+    entries
+        .iter()
+        .filter(|(_, checked)| *checked)
+        .map(|(agent, _)| agent.url())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn agent(name: &str, last: u8) -> Agent {
+        Agent {
+            name: name.to_string(),
+            host: format!("{}.local", name),
+            address: Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, last))),
+            port: 9898,
+            scheme: "http".to_string(),
+            auth: false,
+        }
+    }
+
+    #[test]
+    fn test_host_picker_changes() {
+        let (sender, receiver) = mpsc::channel();
+        let mut picker = HostPicker::new(vec!["http://10.0.0.1:9898/metrics".to_string()], receiver);
+        assert!(!picker.poll());
+        sender.send(Ok(vec![agent("a", 1), agent("b", 2)])).unwrap();
+        assert!(picker.poll());
+        assert_eq!(picker.changes(), (Vec::new(), Vec::new()));
+
+        picker.toggle();
+        picker.select(true);
+        picker.toggle();
+        assert_eq!(
+            picker.changes(),
+            (
+                vec!["http://10.0.0.2:9898/metrics".to_string()],
+                vec!["http://10.0.0.1:9898/metrics".to_string()],
+            )
+        );
+    }
+}
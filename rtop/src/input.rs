@@ -0,0 +1,109 @@
+// src/input.rs
+
+/// The one-line prompts shown in place of the controls line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Prompt {
+    /// Narrows the process table to matching names and command lines.
+    Filter,
+    /// Shows only the processes of one user, by name or UID.
+    User,
+    JumpToPid,
+    /// A hypothetical CPU or memory limit for `whatif`.
+    Limit,
+    /// "yes" before a signal goes to a protected process.
+    Confirm,
+}
+
+impl Prompt {
+    pub fn label(self) -> &'static str {
+        match self {
+            Prompt::Filter => "Filter",
+            Prompt::User => "User",
+            Prompt::JumpToPid => "Go to PID",
+            Prompt::Limit => "What if limited to (150%, 1.5 cores, 512M)",
+            Prompt::Confirm => "Type yes to confirm",
+        }
+    }
+
+    /// Whether a typed character goes into the prompt.
+    pub fn accepts(self, c: char) -> bool {
+        match self {
+            Prompt::Filter => !c.is_control(),
+            Prompt::User => !c.is_control() && !c.is_whitespace(),
+            Prompt::JumpToPid => c.is_ascii_digit(),
+            Prompt::Limit => c.is_ascii_alphanumeric() || matches!(c, '.' | '%' | ' '),
+            Prompt::Confirm => c.is_alphabetic(),
+        }
+    }
+
+    /// What pasting `text` adds: its first non-empty line without control
+    /// characters, for users its first word, or for PIDs the first number in
+    /// it, so `pid=1234` copied from a log works as is.
+    pub fn paste(self, text: &str) -> String {
+        match self {
+            Prompt::Filter | Prompt::Limit => text
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .unwrap_or("")
+                .chars()
+                .filter(|c| !c.is_control())
+                .collect(),
+            Prompt::User | Prompt::Confirm => text
+                .split_whitespace()
+                .next()
+                .unwrap_or("")
+                .chars()
+                .filter(|c| !c.is_control())
+                .collect(),
+            Prompt::JumpToPid => text
+                .chars()
+                .skip_while(|c| !c.is_ascii_digit())
+                .take_while(|c| c.is_ascii_digit())
+                .collect(),
+        }
+    }
+}
+
+/// A prompt being edited. `original` is the filter to restore on cancel.
+pub struct LineInput {
+    pub prompt: Prompt,
+    pub text: String,
+    pub original: String,
+}
+
+/// Case-insensitive substring match of `filter` against a process name or
+/// command line; an empty filter matches everything.
+pub fn matches(filter: &str, name: &str, command: &str) -> bool {
+    let filter = filter.to_lowercase();
+    name.to_lowercase().contains(&filter) || command.to_lowercase().contains(&filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paste() {
+        assert_eq!(
+            Prompt::Filter.paste("java -jar app.jar\n"),
+            "java -jar app.jar"
+        );
+        assert_eq!(
+            Prompt::Filter.paste("\n  postgres\nsecond line"),
+            "postgres"
+        );
+        assert_eq!(Prompt::Filter.paste("a\tb"), "ab");
+        assert_eq!(Prompt::User.paste("  postgres 1234\n"), "postgres");
+        assert_eq!(Prompt::JumpToPid.paste("worker pid=1234 exited"), "1234");
+        assert_eq!(Prompt::JumpToPid.paste("no number"), "");
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("JAVA", "java", ""));
+        assert!(matches("app.jar", "java", "/usr/bin/java -jar app.jar"));
+        assert!(matches("", "anything", ""));
+        assert!(!matches("nginx", "java", "java -jar app.jar"));
+    }
+}
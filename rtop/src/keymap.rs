@@ -0,0 +1,493 @@
+// src/keymap.rs
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Everything a key can be bound to. What an action does can depend on the
+/// current view; see `App::perform`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Down,
+    Up,
+    PageDown,
+    PageUp,
+    First,
+    Last,
+    Open,
+    Close,
+    Parent,
+    NextTab,
+    PreviousTab,
+    SortCpu,
+    SortMemory,
+    SortName,
+    SortPid,
+    SortDiskRead,
+    SortDiskWrite,
+    TogglePerCore,
+    ToggleCpuHistory,
+    ToggleMemoryHistory,
+    TogglePartitions,
+    ToggleVirtualInterfaces,
+    KillMenu,
+    ExportCsv,
+    ExportJson,
+    ToggleTree,
+    Collapse,
+    Expand,
+    Tag,
+    UntagAll,
+    Filter,
+    FilterUser,
+    GroupEnv,
+    FilterGroup,
+    NiceDown,
+    NiceUp,
+    WhatIf,
+    Silence,
+    JumpToPid,
+    ColumnSetup,
+    /// Opens the host picker of the Cluster tab.
+    PickAgents,
+    SignalInterrupt,
+    SignalQuit,
+    SignalTerm,
+    SignalKill,
+    SignalStop,
+    SignalContinue,
+    /// Stops refreshing the data, so the rows hold still.
+    Pause,
+    /// Blanks the screen's content and ignores keys until unlocked.
+    Lock,
+    /// Silences the `--alarm` banner.
+    Acknowledge,
+    /// Starts or pauses `--replay` playback.
+    Play,
+    /// Steps to the next recorded sample, or the next snapshot in `rtop view`.
+    StepForward,
+    StepBack,
+    /// Jumps a minute through a replay.
+    SeekForward,
+    SeekBack,
+    /// Jumps ten minutes through a replay.
+    SeekForwardFar,
+    SeekBackFar,
+    /// Changes the replay speed.
+    Faster,
+    Slower,
+}
+
+impl Action {
+    pub const ALL: [Action; 60] = [
+        Action::Quit,
+        Action::Down,
+        Action::Up,
+        Action::PageDown,
+        Action::PageUp,
+        Action::First,
+        Action::Last,
+        Action::Open,
+        Action::Close,
+        Action::Parent,
+        Action::NextTab,
+        Action::PreviousTab,
+        Action::SortCpu,
+        Action::SortMemory,
+        Action::SortName,
+        Action::SortPid,
+        Action::SortDiskRead,
+        Action::SortDiskWrite,
+        Action::TogglePerCore,
+        Action::ToggleCpuHistory,
+        Action::ToggleMemoryHistory,
+        Action::TogglePartitions,
+        Action::ToggleVirtualInterfaces,
+        Action::KillMenu,
+        Action::ExportCsv,
+        Action::ExportJson,
+        Action::ToggleTree,
+        Action::Collapse,
+        Action::Expand,
+        Action::Tag,
+        Action::UntagAll,
+        Action::Filter,
+        Action::FilterUser,
+        Action::GroupEnv,
+        Action::FilterGroup,
+        Action::NiceDown,
+        Action::NiceUp,
+        Action::WhatIf,
+        Action::Silence,
+        Action::JumpToPid,
+        Action::ColumnSetup,
+        Action::PickAgents,
+        Action::SignalInterrupt,
+        Action::SignalQuit,
+        Action::SignalTerm,
+        Action::SignalKill,
+        Action::SignalStop,
+        Action::SignalContinue,
+        Action::Pause,
+        Action::Lock,
+        Action::Acknowledge,
+        Action::Play,
+        Action::StepForward,
+        Action::StepBack,
+        Action::SeekForward,
+        Action::SeekBack,
+        Action::SeekForwardFar,
+        Action::SeekBackFar,
+        Action::Faster,
+        Action::Slower,
+    ];
+
+    /// The name used in the `[keys]` table of the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Down => "down",
+            Action::Up => "up",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::Open => "open",
+            Action::Close => "close",
+            Action::Parent => "parent",
+            Action::NextTab => "next_tab",
+            Action::PreviousTab => "previous_tab",
+            Action::SortCpu => "sort_cpu",
+            Action::SortMemory => "sort_memory",
+            Action::SortName => "sort_name",
+            Action::SortPid => "sort_pid",
+            Action::SortDiskRead => "sort_disk_read",
+            Action::SortDiskWrite => "sort_disk_write",
+            Action::TogglePerCore => "toggle_per_core",
+            Action::ToggleCpuHistory => "toggle_cpu_history",
+            Action::ToggleMemoryHistory => "toggle_memory_history",
+            Action::TogglePartitions => "toggle_partitions",
+            Action::ToggleVirtualInterfaces => "toggle_virtual_interfaces",
+            Action::KillMenu => "kill_menu",
+            Action::ExportCsv => "export_csv",
+            Action::ExportJson => "export_json",
+            Action::ToggleTree => "toggle_tree",
+            Action::Collapse => "collapse",
+            Action::Expand => "expand",
+            Action::Tag => "tag",
+            Action::UntagAll => "untag_all",
+            Action::Filter => "filter",
+            Action::FilterUser => "filter_user",
+            Action::GroupEnv => "group_env",
+            Action::FilterGroup => "filter_group",
+            Action::NiceDown => "nice_down",
+            Action::NiceUp => "nice_up",
+            Action::WhatIf => "what_if",
+            Action::Silence => "silence",
+            Action::JumpToPid => "jump_to_pid",
+            Action::ColumnSetup => "column_setup",
+            Action::PickAgents => "pick_agents",
+            Action::SignalInterrupt => "signal_interrupt",
+            Action::SignalQuit => "signal_quit",
+            Action::SignalTerm => "signal_term",
+            Action::SignalKill => "signal_kill",
+            Action::SignalStop => "signal_stop",
+            Action::SignalContinue => "signal_continue",
+            Action::Pause => "pause",
+            Action::Lock => "lock",
+            Action::Acknowledge => "acknowledge",
+            Action::Play => "play",
+            Action::StepForward => "step_forward",
+            Action::StepBack => "step_back",
+            Action::SeekForward => "seek_forward",
+            Action::SeekBack => "seek_back",
+            Action::SeekForwardFar => "seek_forward_far",
+            Action::SeekBackFar => "seek_back_far",
+            Action::Faster => "faster",
+            Action::Slower => "slower",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q"],
+            Action::Down => &["Down"],
+            Action::Up => &["Up"],
+            Action::PageDown => &["PageDown"],
+            Action::PageUp => &["PageUp"],
+            Action::First => &["Home"],
+            Action::Last => &["End"],
+            Action::Open => &["Enter"],
+            Action::Close => &["Esc"],
+            Action::Parent => &["Backspace"],
+            Action::NextTab => &["Tab"],
+            Action::PreviousTab => &["BackTab"],
+            Action::SortCpu => &["c"],
+            Action::SortMemory => &["m"],
+            Action::SortName => &["n"],
+            Action::SortPid => &["p"],
+            Action::SortDiskRead => &["r"],
+            Action::SortDiskWrite => &["w"],
+            Action::TogglePerCore => &["1"],
+            Action::ToggleCpuHistory => &["h"],
+            Action::ToggleMemoryHistory => &["M"],
+            Action::TogglePartitions => &["d"],
+            Action::ToggleVirtualInterfaces => &["v"],
+            Action::KillMenu => &["k"],
+            Action::ExportCsv => &["e"],
+            Action::ExportJson => &["E"],
+            Action::ToggleTree => &["t"],
+            Action::Collapse => &["Left"],
+            Action::Expand => &["Right"],
+            Action::Tag => &["Space"],
+            Action::UntagAll => &["U"],
+            Action::Filter => &["/"],
+            Action::FilterUser => &["u"],
+            Action::GroupEnv => &["G"],
+            Action::FilterGroup => &["="],
+            Action::NiceDown => &["F7", "-"],
+            Action::NiceUp => &["F8", "+"],
+            Action::WhatIf => &["L"],
+            Action::Silence => &["s"],
+            Action::JumpToPid => &["g"],
+            Action::ColumnSetup => &["F2"],
+            Action::PickAgents => &["a"],
+            Action::SignalInterrupt => &["1"],
+            Action::SignalQuit => &["2"],
+            Action::SignalTerm => &["3"],
+            Action::SignalKill => &["9"],
+            Action::SignalStop => &["z"],
+            Action::SignalContinue => &["f"],
+            Action::Pause => &["z"],
+            Action::Lock => &["ctrl+l"],
+            Action::Acknowledge => &["Enter"],
+            Action::Play => &["Space"],
+            Action::StepForward => &["Right"],
+            Action::StepBack => &["Left"],
+            Action::SeekForward => &["]"],
+            Action::SeekBack => &["["],
+            Action::SeekForwardFar => &["}"],
+            Action::SeekBackFar => &["{"],
+            Action::Faster => &["+", "="],
+            Action::Slower => &["-"],
+        }
+    }
+}
+
+/// A key plus the Ctrl/Alt modifiers, written like `ctrl+c`, `Down` or `F5`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub fn from_event(event: &KeyEvent) -> KeyBinding {
+        // Shift is already part of the character (`K`) or the code (BackTab).
+        KeyBinding {
+            code: event.code,
+            modifiers: event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<KeyBinding, String> {
+        text.parse()
+    }
+}
+
+impl std::str::FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<KeyBinding, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut key = text;
+        // `+` on its own is a key, not a separator.
+        while let Some((modifier, rest)) = key.split_once('+').filter(|(_, rest)| !rest.is_empty())
+        {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" if rest.eq_ignore_ascii_case("tab") => {
+                    key = "BackTab";
+                    break;
+                }
+                _ => return Err(format!("unknown modifier '{}' in '{}'", modifier, text)),
+            };
+            key = rest;
+        }
+        let code = match key.to_ascii_lowercase().as_str() {
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "delete" => KeyCode::Delete,
+            "space" => KeyCode::Char(' '),
+            lower => match (
+                lower.strip_prefix('f').and_then(|n| n.parse().ok()),
+                key.chars().count(),
+            ) {
+                (Some(n), _) if (1..=12).contains(&n) => KeyCode::F(n),
+                (_, 1) => KeyCode::Char(key.chars().next().unwrap_or_default()),
+                _ => return Err(format!("unknown key '{}'", text)),
+            },
+        };
+        Ok(KeyBinding { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::BackTab => write!(f, "BackTab"),
+            KeyCode::Backspace => write!(f, "Backspace"),
+            KeyCode::Up => write!(f, "Up"),
+            KeyCode::Down => write!(f, "Down"),
+            KeyCode::Left => write!(f, "Left"),
+            KeyCode::Right => write!(f, "Right"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            KeyCode::Delete => write!(f, "Delete"),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// The active key bindings: the defaults with the config file's `[keys]` applied.
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<KeyBinding>)>,
+}
+
+impl Keymap {
+    /// Bindings in `overrides` replace the defaults of their action.
+    pub fn new(overrides: &HashMap<Action, Vec<KeyBinding>>) -> Keymap {
+        let bindings = Action::ALL
+            .iter()
+            .map(|&action| {
+                let keys = overrides.get(&action).cloned().unwrap_or_else(|| {
+                    action
+                        .default_keys()
+                        .iter()
+                        .filter_map(|key| key.parse().ok())
+                        .collect()
+                });
+                (action, keys)
+            })
+            .collect();
+        Keymap { bindings }
+    }
+
+    /// Every action bound to the pressed key, in `Action::ALL` order.
+    pub fn actions(&self, event: &KeyEvent) -> Vec<Action> {
+        let pressed = KeyBinding::from_event(event);
+        self.bindings
+            .iter()
+            .filter(|(_, keys)| keys.contains(&pressed))
+            .map(|&(action, _)| action)
+            .collect()
+    }
+
+    /// The first key bound to `action`, for help texts.
+    pub fn key(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .and_then(|(_, keys)| keys.first())
+            .map_or("unbound".to_string(), |key| key.to_string())
+    }
+
+    /// The keymap as a `[keys]` table that can be pasted into the config file.
+    pub fn dump(&self) -> String {
+        let mut out = String::from("[keys]\n");
+        for (action, keys) in &self.bindings {
+            let keys: Vec<String> = keys.iter().map(|key| format!("\"{}\"", key)).collect();
+            out.push_str(&format!("{} = [{}]\n", action.name(), keys.join(", ")));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key_bindings() {
+        let binding: KeyBinding = "ctrl+c".parse().unwrap();
+        assert_eq!(binding.code, KeyCode::Char('c'));
+        assert_eq!(binding.modifiers, KeyModifiers::CONTROL);
+        assert_eq!("F5".parse::<KeyBinding>().unwrap().code, KeyCode::F(5));
+        assert_eq!("+".parse::<KeyBinding>().unwrap().code, KeyCode::Char('+'));
+        assert_eq!(
+            "PageDown".parse::<KeyBinding>().unwrap().to_string(),
+            "PageDown"
+        );
+        assert_eq!(
+            "shift+tab".parse::<KeyBinding>().unwrap().code,
+            KeyCode::BackTab
+        );
+        assert!("hyper+x".parse::<KeyBinding>().is_err());
+        assert!("nope".parse::<KeyBinding>().is_err());
+    }
+
+    #[test]
+    fn test_overrides_replace_defaults() {
+        let overrides = HashMap::from([(Action::Down, vec!["j".parse().unwrap()])]);
+        let keymap = Keymap::new(&overrides);
+        let j = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(keymap.actions(&j), vec![Action::Down]);
+        let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        assert!(keymap.actions(&down).is_empty());
+        let one = KeyEvent::new(KeyCode::Char('1'), KeyModifiers::NONE);
+        assert_eq!(
+            keymap.actions(&one),
+            vec![Action::TogglePerCore, Action::SignalInterrupt]
+        );
+        assert_eq!(keymap.key(Action::Quit), "q");
+        assert!(keymap.dump().contains("down = [\"j\"]\n"));
+    }
+
+    #[test]
+    fn test_replay_keys_share_defaults() {
+        let overrides = HashMap::from([(Action::Play, vec!["p".parse().unwrap()])]);
+        let keymap = Keymap::new(&overrides);
+        let minus = KeyEvent::new(KeyCode::Char('-'), KeyModifiers::NONE);
+        assert_eq!(
+            keymap.actions(&minus),
+            vec![Action::NiceDown, Action::Slower]
+        );
+        let space = KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE);
+        assert_eq!(keymap.actions(&space), vec![Action::Tag]);
+        assert_eq!(keymap.key(Action::Play), "p");
+        assert_eq!(keymap.key(Action::SeekBackFar), "{");
+    }
+}
@@ -0,0 +1,37 @@
+// src/kiosk.rs
+use crate::keymap::Action;
+
+/// Whether `action` is left enabled on a `--kiosk` wallboard: everything
+/// that only changes what is shown. Signals, renicing, exports and
+/// silences are not, nor picking the agents of the Cluster tab.
+pub fn allows(action: Action) -> bool {
+    !matches!(
+        action,
+        Action::KillMenu
+            | Action::SignalInterrupt
+            | Action::SignalQuit
+            | Action::SignalTerm
+            | Action::SignalKill
+            | Action::SignalStop
+            | Action::SignalContinue
+            | Action::NiceDown
+            | Action::NiceUp
+            | Action::ExportCsv
+            | Action::ExportJson
+            | Action::Silence
+            | Action::PickAgents
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kiosk_allows() {
+        assert!(allows(Action::NextTab));
+        assert!(allows(Action::Lock));
+        assert!(!allows(Action::SignalKill));
+        assert!(!allows(Action::NiceDown));
+    }
+}
@@ -0,0 +1,75 @@
+// src/lib.rs
+//! rtop as a library: an `App` holds the state the UI shows and refreshes it
+//! from the system, `collectors` read what sysinfo does not cover,
+//! `ui::draw` draws an `App` into any `tui` backend and `event::run_tui`
+//! runs the terminal loop around both.
+use thiserror::Error;
+
+mod actions;
+mod alarm;
+mod alerts;
+mod anomaly;
+pub mod app;
+mod baseline;
+mod capture;
+mod cast;
+pub mod cli;
+mod cluster;
+pub mod collectors;
+mod columns;
+pub mod config;
+mod control;
+mod details;
+mod diff;
+mod dstate;
+mod emulation;
+mod envgroup;
+pub mod event;
+pub mod export;
+pub mod health;
+mod history;
+mod hooks;
+mod hosts;
+mod input;
+mod keymap;
+mod kiosk;
+mod limits;
+mod lock;
+mod mdns;
+mod metrics;
+mod mirror;
+mod notify;
+pub mod plugins;
+mod privilege;
+mod race;
+mod record;
+mod redact;
+mod repeat;
+mod replay;
+mod rotate;
+pub mod sampler;
+mod shade;
+mod silence;
+mod sort;
+pub mod source;
+mod sparkline;
+mod summary;
+mod text;
+mod theme;
+mod tree;
+pub mod ui;
+mod view;
+mod watchdog;
+mod whatif;
+
+pub use app::{App, SortBy, Tab};
+
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Config error: {0}")]
+    Config(#[from] toml::de::Error),
+}
@@ -0,0 +1,90 @@
+// src/limits.rs
+use sysinfo::Pid;
+
+/// One row of `/proc/PID/limits`; `None` means unlimited.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResourceLimit {
+    pub name: String,
+    pub soft: Option<u64>,
+    pub hard: Option<u64>,
+    pub units: String,
+}
+
+impl ResourceLimit {
+    /// Fraction of the soft limit consumed by `usage`, if the limit is finite.
+    pub fn utilization(&self, usage: u64) -> Option<f64> {
+        match self.soft {
+            Some(0) | None => None,
+            Some(soft) => Some(usage as f64 / soft as f64),
+        }
+    }
+}
+
+fn parse_value(value: &str) -> Option<u64> {
+    value.parse().ok()
+}
+
+/// Parses `/proc/PID/limits`, whose limit names are padded to a fixed 26 columns.
+pub fn parse_limits(content: &str) -> Vec<ResourceLimit> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let name = line.get(..26)?.trim();
+            let mut values = line.get(26..)?.split_whitespace();
+            Some(ResourceLimit {
+                name: name.to_string(),
+                soft: parse_value(values.next()?),
+                hard: parse_value(values.next()?),
+                units: values.next().unwrap_or("").to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+pub fn read_limits(pid: Pid) -> Vec<ResourceLimit> {
+    std::fs::read_to_string(format!("/proc/{}/limits", pid))
+        .map(|content| parse_limits(&content))
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read_limits(_pid: Pid) -> Vec<ResourceLimit> {
+    Vec::new()
+}
+
+/// Number of file descriptors `pid` currently holds, the usage behind "Max open files".
+#[cfg(target_os = "linux")]
+pub fn open_file_count(pid: Pid) -> Option<u64> {
+    std::fs::read_dir(format!("/proc/{}/fd", pid))
+        .ok()
+        .map(|entries| entries.count() as u64)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn open_file_count(_pid: Pid) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_limits() {
+        let content = "\
+Limit                     Soft Limit           Hard Limit           Units
+Max stack size            8388608              unlimited            bytes
+Max open files            1024                 524288               files
+Max nice priority         0                    0
+";
+        let limits = parse_limits(content);
+        assert_eq!(limits.len(), 3);
+        assert_eq!(limits[0].name, "Max stack size");
+        assert_eq!(limits[0].hard, None);
+        assert_eq!(limits[1].soft, Some(1024));
+        assert_eq!(limits[1].utilization(512), Some(0.5));
+        assert_eq!(limits[2].units, "");
+    }
+}
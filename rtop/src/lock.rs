@@ -0,0 +1,103 @@
+// src/lock.rs
+use chrono::{DateTime, Local};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// The session lock: while it is on, the tab content is blank and keys
+/// only go here until they unlock.
+pub struct Lock {
+    /// Keys to type to unlock; without one, any key asks to confirm.
+    sequence: Option<String>,
+    /// The last keys typed, as long as the sequence at most.
+    typed: String,
+    /// Whether a key was pressed and Enter now unlocks.
+    confirming: bool,
+    pub since: DateTime<Local>,
+}
+
+impl Lock {
+    pub fn new(sequence: Option<String>) -> Lock {
+        Lock {
+            sequence: sequence.filter(|sequence| !sequence.is_empty()),
+            typed: String::new(),
+            confirming: false,
+            since: Local::now(),
+        }
+    }
+
+    /// Takes a key pressed while locked and returns whether it unlocks.
+    pub fn key(&mut self, key: &KeyEvent) -> bool {
+        let Some(sequence) = &self.sequence else {
+            let unlock = self.confirming && key.code == KeyCode::Enter;
+            self.confirming = !self.confirming && !unlock;
+            return unlock;
+        };
+        match key.code {
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                self.typed.push(c);
+                let excess = self
+                    .typed
+                    .chars()
+                    .count()
+                    .saturating_sub(sequence.chars().count());
+                self.typed = self.typed.chars().skip(excess).collect();
+                self.typed == *sequence
+            }
+            _ => {
+                self.typed.clear();
+                false
+            }
+        }
+    }
+
+    /// What to do to unlock, without telling the sequence.
+    pub fn hint(&self) -> String {
+        let since = self.since.format("%H:%M");
+        match (&self.sequence, self.confirming) {
+            (Some(_), _) => format!("Locked since {}. Type the unlock sequence", since),
+            (None, false) => format!("Locked since {}. Press any key to unlock", since),
+            (None, true) => format!(
+                "Locked since {}. Press Enter to unlock, any other key to stay locked",
+                since
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(lock: &mut Lock, keys: &str) -> bool {
+        keys.chars()
+            .map(|c| {
+                let code = if c == '\n' {
+                    KeyCode::Enter
+                } else {
+                    KeyCode::Char(c)
+                };
+                lock.key(&KeyEvent::new(code, KeyModifiers::NONE))
+            })
+            .last()
+            .unwrap_or(false)
+    }
+
+    #[test]
+    fn test_unlock() {
+        let mut lock = Lock::new(Some("4711".to_string()));
+        assert!(!press(&mut lock, "47"));
+        assert!(!press(&mut lock, "\n11"));
+        // Stray keys before the sequence do not matter.
+        assert!(press(&mut lock, "9994711"));
+
+        let mut lock = Lock::new(None);
+        assert!(!press(&mut lock, "x"));
+        assert!(lock.hint().ends_with("any other key to stay locked"));
+        assert!(!press(&mut lock, "y"));
+        assert!(!press(&mut lock, "\n"));
+        assert!(press(&mut lock, "\n"));
+    }
+}
@@ -0,0 +1,533 @@
+// src/mdns.rs
+use clap::Args;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// DNS-SD service type under which `--listen` instances advertise themselves.
+const SERVICE: &str = "_rtop._tcp.local";
+const MDNS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const MDNS_PORT: u16 = 5353;
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+const TYPE_ANY: u16 = 255;
+const CLASS_IN: u16 = 1;
+/// Set on records only this host answers for, so caches replace old copies.
+const CACHE_FLUSH: u16 = 0x8000;
+const TTL: u32 = 120;
+
+#[derive(Args, Debug)]
+pub struct DiscoverArgs {
+    /// Seconds to wait for answers
+    #[arg(long, default_value_t = 2)]
+    pub timeout: u64,
+
+    /// Print the agents as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// An rtop instance found on the local network.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Agent {
+    pub name: String,
+    pub host: String,
+    pub address: Option<IpAddr>,
+    pub port: u16,
+    /// `http` or `https`.
+    pub scheme: String,
+    /// Whether the endpoint wants a bearer token.
+    pub auth: bool,
+}
+
+impl Agent {
+    pub fn url(&self) -> String {
+        let host = match self.address {
+            Some(address) => SocketAddr::new(address, self.port).to_string(),
+            None => format!("{}:{}", self.host, self.port),
+        };
+        format!("{}://{}/metrics", self.scheme, host)
+    }
+}
+
+/// What an advertised instance tells the network about itself.
+#[derive(Clone, Debug)]
+pub struct Advertisement {
+    /// Instance name, normally the host name.
+    pub name: String,
+    pub address: Ipv4Addr,
+    pub port: u16,
+    pub tls: bool,
+    pub auth: bool,
+}
+
+impl Advertisement {
+    fn host(&self) -> String {
+        format!("{}.local", self.name.replace('.', "-"))
+    }
+
+    fn instance(&self) -> Vec<String> {
+        let mut labels = vec![self.name.clone()];
+        labels.extend(SERVICE.split('.').map(str::to_string));
+        labels
+    }
+
+    /// A response carrying the PTR, SRV, TXT and A records of this instance.
+    fn response(&self, id: u16) -> Vec<u8> {
+        let instance = self.instance();
+        let host: Vec<String> = self.host().split('.').map(str::to_string).collect();
+        let mut out = Vec::new();
+        for value in [id, 0x8400, 0, 4, 0, 0] {
+            out.extend(value.to_be_bytes());
+        }
+
+        let mut record = |name: &[String], kind: u16, class: u16, rdata: Vec<u8>| {
+            write_name(&mut out, name);
+            out.extend(kind.to_be_bytes());
+            out.extend(class.to_be_bytes());
+            out.extend(TTL.to_be_bytes());
+            out.extend((rdata.len() as u16).to_be_bytes());
+            out.extend(rdata);
+        };
+
+        let service: Vec<String> = SERVICE.split('.').map(str::to_string).collect();
+        let mut ptr = Vec::new();
+        write_name(&mut ptr, &instance);
+        record(&service, TYPE_PTR, CLASS_IN, ptr);
+
+        let mut srv = Vec::new();
+        srv.extend([0, 0, 0, 0]);
+        srv.extend(self.port.to_be_bytes());
+        write_name(&mut srv, &host);
+        record(&instance, TYPE_SRV, CLASS_IN | CACHE_FLUSH, srv);
+
+        let mut txt = Vec::new();
+        let scheme = if self.tls {
+            "scheme=https"
+        } else {
+            "scheme=http"
+        };
+        let auth = if self.auth {
+            "auth=bearer"
+        } else {
+            "auth=none"
+        };
+        for entry in [scheme, auth, "path=/metrics"] {
+            txt.push(entry.len() as u8);
+            txt.extend(entry.as_bytes());
+        }
+        record(&instance, TYPE_TXT, CLASS_IN | CACHE_FLUSH, txt);
+
+        record(
+            &host,
+            TYPE_A,
+            CLASS_IN | CACHE_FLUSH,
+            self.address.octets().to_vec(),
+        );
+        out
+    }
+}
+
+fn write_name(out: &mut Vec<u8>, labels: &[String]) {
+    for label in labels {
+        let label = &label.as_bytes()[..label.len().min(63)];
+        out.push(label.len() as u8);
+        out.extend(label);
+    }
+    out.push(0);
+}
+
+/// Reads a possibly compressed name at `*pos` and moves past it.
+fn read_name(message: &[u8], pos: &mut usize) -> Option<String> {
+    let mut labels = Vec::new();
+    let mut at = *pos;
+    let mut jumped = false;
+    // Bounds the number of pointers so a malicious loop cannot hang us.
+    for _ in 0..128 {
+        let len = *message.get(at)? as usize;
+        if len == 0 {
+            if !jumped {
+                *pos = at + 1;
+            }
+            return Some(labels.join("."));
+        }
+        if len & 0xC0 == 0xC0 {
+            let target = (len & 0x3F) << 8 | *message.get(at + 1)? as usize;
+            if !jumped {
+                *pos = at + 2;
+            }
+            jumped = true;
+            at = target;
+            continue;
+        }
+        let label = message.get(at + 1..at + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).into_owned());
+        at += 1 + len;
+    }
+    None
+}
+
+fn read_u16(message: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        message.get(pos..pos + 2)?.try_into().ok()?,
+    ))
+}
+
+/// Whether a query asks for rtop instances; returns its ID.
+fn asks_for_service(message: &[u8]) -> Option<u16> {
+    let flags = read_u16(message, 2)?;
+    if flags & 0x8000 != 0 {
+        return None;
+    }
+    let questions = read_u16(message, 4)?;
+    let mut pos = 12;
+    for _ in 0..questions {
+        let name = read_name(message, &mut pos)?;
+        let kind = read_u16(message, pos)?;
+        pos += 4;
+        if name.eq_ignore_ascii_case(SERVICE) && (kind == TYPE_PTR || kind == TYPE_ANY) {
+            return read_u16(message, 0);
+        }
+    }
+    None
+}
+
+/// Adds the records of one response to `found`.
+fn read_response(message: &[u8], found: &mut Discovered) -> Option<()> {
+    if read_u16(message, 2)? & 0x8000 == 0 {
+        return None;
+    }
+    let questions = read_u16(message, 4)?;
+    let records: usize = [6, 8, 10]
+        .iter()
+        .map(|&offset| read_u16(message, offset).unwrap_or(0) as usize)
+        .sum();
+    let mut pos = 12;
+    for _ in 0..questions {
+        read_name(message, &mut pos)?;
+        pos += 4;
+    }
+    for _ in 0..records {
+        let name = read_name(message, &mut pos)?;
+        let kind = read_u16(message, pos)?;
+        let len = read_u16(message, pos + 8)? as usize;
+        let start = pos + 10;
+        let rdata = message.get(start..start + len)?;
+        match kind {
+            TYPE_PTR if name.eq_ignore_ascii_case(SERVICE) => {
+                let mut at = start;
+                found.instances.push(read_name(message, &mut at)?);
+            }
+            TYPE_SRV => {
+                let mut at = start + 6;
+                let target = read_name(message, &mut at)?;
+                found.services.insert(name, (target, read_u16(rdata, 4)?));
+            }
+            TYPE_TXT => {
+                let mut entries = Vec::new();
+                let mut at = 0;
+                while let Some(&entry_len) = rdata.get(at) {
+                    let entry = rdata.get(at + 1..at + 1 + entry_len as usize)?;
+                    entries.push(String::from_utf8_lossy(entry).into_owned());
+                    at += 1 + entry_len as usize;
+                }
+                found.texts.insert(name, entries);
+            }
+            TYPE_A if len == 4 => {
+                let address = Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3]);
+                found.addresses.insert(name.to_ascii_lowercase(), address);
+            }
+            _ => {}
+        }
+        pos = start + len;
+    }
+    Some(())
+}
+
+/// Records collected from all responses of one discovery run.
+#[derive(Default)]
+struct Discovered {
+    instances: Vec<String>,
+    services: HashMap<String, (String, u16)>,
+    texts: HashMap<String, Vec<String>>,
+    addresses: HashMap<String, Ipv4Addr>,
+}
+
+impl Discovered {
+    fn agents(&self) -> Vec<Agent> {
+        let mut agents: Vec<Agent> = Vec::new();
+        for instance in &self.instances {
+            let Some((host, port)) = self.services.get(instance) else {
+                continue;
+            };
+            let texts = self.texts.get(instance).cloned().unwrap_or_default();
+            let text = |key: &str| {
+                texts
+                    .iter()
+                    .find_map(|entry| entry.strip_prefix(key)?.strip_prefix('='))
+                    .map(str::to_string)
+            };
+            let agent = Agent {
+                name: instance
+                    .strip_suffix(SERVICE)
+                    .map_or(instance.as_str(), |name| name.trim_end_matches('.'))
+                    .to_string(),
+                host: host.clone(),
+                address: self
+                    .addresses
+                    .get(&host.to_ascii_lowercase())
+                    .map(|&address| IpAddr::V4(address)),
+                port: *port,
+                scheme: text("scheme").unwrap_or_else(|| "http".to_string()),
+                auth: text("auth").is_some_and(|auth| auth != "none"),
+            };
+            if !agents.contains(&agent) {
+                agents.push(agent);
+            }
+        }
+        agents.sort_by(|a, b| a.name.cmp(&b.name));
+        agents
+    }
+}
+
+/// Asks the local network for rtop instances and collects answers for `timeout`.
+pub fn discover(timeout: Duration) -> io::Result<Vec<Agent>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    let mut query = Vec::new();
+    for value in [0u16, 0, 1, 0, 0, 0] {
+        query.extend(value.to_be_bytes());
+    }
+    let service: Vec<String> = SERVICE.split('.').map(str::to_string).collect();
+    write_name(&mut query, &service);
+    query.extend(TYPE_PTR.to_be_bytes());
+    query.extend(CLASS_IN.to_be_bytes());
+    // Sent from an ephemeral port, responders answer us directly (RFC 6762, 6.7).
+    socket.send_to(&query, (MDNS_GROUP, MDNS_PORT))?;
+
+    let mut found = Discovered::default();
+    let deadline = Instant::now() + timeout;
+    let mut buffer = [0u8; 9000];
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        if left.is_zero() {
+            break;
+        }
+        socket.set_read_timeout(Some(left))?;
+        match socket.recv_from(&mut buffer) {
+            Ok((len, _)) => {
+                read_response(&buffer[..len], &mut found);
+            }
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                break
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(found.agents())
+}
+
+/// Answers mDNS queries for `_rtop._tcp` from the UI loop.
+pub struct Advertiser {
+    socket: UdpSocket,
+    advertisement: Advertisement,
+}
+
+impl Advertiser {
+    /// Joins the mDNS group (sharing port 5353 with avahi and the like) and
+    /// announces the instance once.
+    pub fn start(advertisement: Advertisement) -> io::Result<Advertiser> {
+        let socket = bind_shared(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, MDNS_PORT))?;
+        socket.join_multicast_v4(&MDNS_GROUP, &Ipv4Addr::UNSPECIFIED)?;
+        socket.set_nonblocking(true)?;
+        let advertiser = Advertiser {
+            socket,
+            advertisement,
+        };
+        advertiser.socket.send_to(
+            &advertiser.advertisement.response(0),
+            (MDNS_GROUP, MDNS_PORT),
+        )?;
+        Ok(advertiser)
+    }
+
+    /// Answers every pending query for rtop instances.
+    pub fn serve(&mut self) {
+        let mut buffer = [0u8; 9000];
+        while let Ok((len, from)) = self.socket.recv_from(&mut buffer) {
+            let Some(id) = asks_for_service(&buffer[..len]) else {
+                continue;
+            };
+            let response =
+                self.advertisement
+                    .response(if from.port() == MDNS_PORT { 0 } else { id });
+            // One-shot queries from other ports get a direct answer.
+            let to = if from.port() == MDNS_PORT {
+                SocketAddr::from((MDNS_GROUP, MDNS_PORT))
+            } else {
+                from
+            };
+            let _ = self.socket.send_to(&response, to);
+        }
+    }
+}
+
+/// The address other hosts reach us on: the one `listen` is bound to, or
+/// else the source address of the route to the mDNS group.
+pub fn local_address(listen: IpAddr) -> Ipv4Addr {
+    match listen {
+        IpAddr::V4(address) if !address.is_unspecified() => address,
+        _ => UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+            .and_then(|socket| {
+                socket.connect((MDNS_GROUP, MDNS_PORT))?;
+                socket.local_addr()
+            })
+            .ok()
+            .and_then(|addr| match addr.ip() {
+                IpAddr::V4(address) => Some(address),
+                IpAddr::V6(_) => None,
+            })
+            .unwrap_or(Ipv4Addr::LOCALHOST),
+    }
+}
+
+/// Binds a UDP socket with SO_REUSEADDR/SO_REUSEPORT so other mDNS
+/// responders on the host keep working.
+#[cfg(unix)]
+fn bind_shared(addr: SocketAddrV4) -> io::Result<UdpSocket> {
+    use std::os::fd::{FromRawFd, OwnedFd};
+
+    // SAFETY: the descriptor is checked and immediately owned; the option
+    // value and address point to live locals of the right size.
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    // Not SOCK_CLOEXEC, which macOS lacks.
+    if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let enable: libc::c_int = 1;
+    for option in [libc::SO_REUSEADDR, libc::SO_REUSEPORT] {
+        let result = unsafe {
+            libc::setsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                option,
+                (&enable as *const libc::c_int).cast(),
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    let mut address: libc::sockaddr_in = unsafe { std::mem::zeroed() };
+    address.sin_family = libc::AF_INET as libc::sa_family_t;
+    #[cfg(target_vendor = "apple")]
+    {
+        address.sin_len = std::mem::size_of::<libc::sockaddr_in>() as u8;
+    }
+    address.sin_port = addr.port().to_be();
+    address.sin_addr.s_addr = u32::from(*addr.ip()).to_be();
+    let result = unsafe {
+        libc::bind(
+            fd,
+            (&address as *const libc::sockaddr_in).cast(),
+            std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        )
+    };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(UdpSocket::from(socket))
+}
+
+#[cfg(not(unix))]
+fn bind_shared(addr: SocketAddrV4) -> io::Result<UdpSocket> {
+    UdpSocket::bind(addr)
+}
+
+/// `rtop discover`: lists the agents that answer within the timeout.
+pub fn run(args: DiscoverArgs) -> io::Result<()> {
+    let agents = discover(Duration::from_secs(args.timeout))?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&agents)?);
+        return Ok(());
+    }
+    if agents.is_empty() {
+        eprintln!("No rtop agents answered; start them with `--listen ADDR --advertise`.");
+    }
+    for agent in &agents {
+        let auth = if agent.auth { "  (bearer token)" } else { "" };
+        println!("{:<24} {}{}", agent.name, agent.url(), auth);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_round_trip() {
+        let advertisement = Advertisement {
+            name: "nas.lan".to_string(),
+            address: Ipv4Addr::new(192, 168, 1, 20),
+            port: 9898,
+            tls: true,
+            auth: true,
+        };
+        let mut query = Vec::new();
+        for value in [7u16, 0, 1, 0, 0, 0] {
+            query.extend(value.to_be_bytes());
+        }
+        let service: Vec<String> = SERVICE.split('.').map(str::to_string).collect();
+        write_name(&mut query, &service);
+        query.extend(TYPE_PTR.to_be_bytes());
+        query.extend(CLASS_IN.to_be_bytes());
+        assert_eq!(asks_for_service(&query), Some(7));
+
+        let mut found = Discovered::default();
+        read_response(&advertisement.response(7), &mut found).unwrap();
+        assert_eq!(
+            found.agents(),
+            vec![Agent {
+                name: "nas.lan".to_string(),
+                host: "nas-lan.local".to_string(),
+                address: Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20))),
+                port: 9898,
+                scheme: "https".to_string(),
+                auth: true,
+            }]
+        );
+        assert_eq!(found.agents()[0].url(), "https://192.168.1.20:9898/metrics");
+        // A response is not a query.
+        assert_eq!(asks_for_service(&advertisement.response(7)), None);
+    }
+
+    #[test]
+    fn test_compressed_names() {
+        // "_rtop._tcp.local" at 0, then "a" followed by a pointer to it.
+        let mut message = Vec::new();
+        write_name(
+            &mut message,
+            &["_rtop".into(), "_tcp".into(), "local".into()],
+        );
+        let start = message.len();
+        message.extend([1, b'a', 0xC0, 0]);
+        let mut pos = start;
+        assert_eq!(read_name(&message, &mut pos).unwrap(), "a._rtop._tcp.local");
+        assert_eq!(pos, start + 4);
+        // A pointer to itself must not loop forever.
+        assert_eq!(read_name(&[0xC0, 0], &mut 0), None);
+    }
+}
@@ -0,0 +1,532 @@
+// src/metrics.rs
+use crate::App;
+use clap::Args;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+#[cfg(feature = "tls")]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use sysinfo::{CpuExt, Pid, PidExt, ProcessExt, SystemExt};
+
+/// Processes exported per ranking (CPU and memory).
+const TOP_PROCESSES: usize = 10;
+
+// How `--listen` serves metrics, in the UI and in `rtop agent`.
+#[derive(Args, Debug)]
+pub struct ListenArgs {
+    /// Serve Prometheus metrics at http://ADDR/metrics, e.g. 0.0.0.0:9898
+    #[arg(long, value_name = "ADDR")]
+    pub listen: Option<SocketAddr>,
+
+    /// Require `Authorization: Bearer TOKEN` on --listen, with TOKEN read from FILE
+    #[arg(long, value_name = "FILE", requires = "listen")]
+    pub listen_token_file: Option<PathBuf>,
+
+    /// Serve --listen over HTTPS with this PEM certificate chain (needs the `tls` feature)
+    #[arg(long, value_name = "FILE", requires_all = ["listen", "tls_key"])]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key for --tls-cert
+    #[arg(long, value_name = "FILE", requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+
+    /// Announce --listen on the local network via mDNS, for `rtop discover`
+    #[arg(long, requires = "listen")]
+    pub advertise: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(mut_arg("listen", |arg| arg.required(true)))]
+pub struct AgentArgs {
+    /// Config file to use instead of ~/.config/rtop/config.toml
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Refresh rate in milliseconds [default: 250]
+    #[arg(short, long)]
+    pub refresh_rate: Option<u64>,
+
+    /// When started as root, switch to USER (default: the sudo user, else nobody) once the collectors have started
+    #[arg(long, value_name = "USER")]
+    pub drop_privileges: Option<Option<String>>,
+
+    /// Replace process names in the metrics with hashes
+    #[arg(long)]
+    pub redact: bool,
+
+    #[command(flatten)]
+    pub listen: ListenArgs,
+}
+
+/// Escapes a Prometheus label value.
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn gauge(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+}
+
+/// The current sample in the Prometheus text exposition format.
+pub fn render(app: &App) -> String {
+    let system = &app.system;
+    let mut out = String::new();
+
+    gauge(&mut out, "rtop_cpu_usage_percent", "Total CPU usage.");
+    let _ = writeln!(
+        out,
+        "rtop_cpu_usage_percent {}",
+        system.global_cpu_info().cpu_usage()
+    );
+    gauge(
+        &mut out,
+        "rtop_cpu_core_usage_percent",
+        "Usage of each CPU core.",
+    );
+    for (i, cpu) in system.cpus().iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "rtop_cpu_core_usage_percent{{core=\"{}\"}} {}",
+            i,
+            cpu.cpu_usage()
+        );
+    }
+    let load = system.load_average();
+    gauge(&mut out, "rtop_load_average", "System load average.");
+    for (period, value) in [("1m", load.one), ("5m", load.five), ("15m", load.fifteen)] {
+        let _ = writeln!(out, "rtop_load_average{{period=\"{}\"}} {}", period, value);
+    }
+    for (name, help, value) in [
+        (
+            "rtop_memory_total_bytes",
+            "Total memory.",
+            system.total_memory(),
+        ),
+        (
+            "rtop_memory_used_bytes",
+            "Used memory.",
+            system.used_memory(),
+        ),
+        (
+            "rtop_memory_available_bytes",
+            "Available memory.",
+            system.available_memory(),
+        ),
+        ("rtop_swap_total_bytes", "Total swap.", system.total_swap()),
+        ("rtop_swap_used_bytes", "Used swap.", system.used_swap()),
+    ] {
+        gauge(&mut out, name, help);
+        let _ = writeln!(out, "{} {}", name, value);
+    }
+
+    let connections = &app.connections;
+    gauge(
+        &mut out,
+        "rtop_tcp_connections",
+        "TCP connections by state.",
+    );
+    for (state, count) in [
+        ("established", connections.established),
+        ("listen", connections.listen),
+        ("time_wait", connections.time_wait),
+        ("close_wait", connections.close_wait),
+        ("other", connections.other),
+    ] {
+        let _ = writeln!(out, "rtop_tcp_connections{{state=\"{}\"}} {}", state, count);
+    }
+
+    // The busiest processes by CPU and by memory; one set so both series exist for each.
+    let mut processes: Vec<_> = system.processes().iter().collect();
+    let mut top: BTreeSet<Pid> = BTreeSet::new();
+    processes.sort_by(|a, b| b.1.cpu_usage().total_cmp(&a.1.cpu_usage()));
+    top.extend(processes.iter().take(TOP_PROCESSES).map(|(&pid, _)| pid));
+    processes.sort_by_key(|p| std::cmp::Reverse(p.1.memory()));
+    top.extend(processes.iter().take(TOP_PROCESSES).map(|(&pid, _)| pid));
+
+    let series = |out: &mut String, name: &str, value: &dyn Fn(&sysinfo::Process) -> String| {
+        for (pid, process) in top
+            .iter()
+            .filter_map(|&pid| Some((pid, system.process(pid)?)))
+        {
+            let _ = writeln!(
+                out,
+                "{}{{pid=\"{}\",name=\"{}\"}} {}",
+                name,
+                pid.as_u32(),
+                label(&app.redact.process(process.name())),
+                value(process)
+            );
+        }
+    };
+    gauge(
+        &mut out,
+        "rtop_process_cpu_usage_percent",
+        "CPU usage of the busiest processes.",
+    );
+    series(&mut out, "rtop_process_cpu_usage_percent", &|p| {
+        p.cpu_usage().to_string()
+    });
+    gauge(
+        &mut out,
+        "rtop_process_resident_memory_bytes",
+        "Resident memory of the busiest processes.",
+    );
+    series(&mut out, "rtop_process_resident_memory_bytes", &|p| {
+        p.memory().to_string()
+    });
+    out
+}
+
+/// How long one client may take for its whole request and response.
+const REQUEST_DEADLINE: Duration = Duration::from_secs(2);
+/// Bytes of request line and headers read at most.
+const MAX_HEAD: u64 = 8 * 1024;
+/// Connections served at once; more are closed right away, so idle clients
+/// cannot pile up threads.
+const MAX_CONNECTIONS: usize = 8;
+
+/// Serves `/metrics` on a thread of its own from the sample the UI loop
+/// last published, so a slow or hostile client never holds up the UI.
+pub struct MetricsServer {
+    listener: Option<TcpListener>,
+    local_addr: SocketAddr,
+    /// Required as `Authorization: Bearer TOKEN` when set.
+    token: Option<String>,
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<rustls::ServerConfig>>,
+    latest: Arc<Mutex<Option<String>>>,
+}
+
+impl MetricsServer {
+    pub fn bind(addr: SocketAddr) -> io::Result<MetricsServer> {
+        let listener = TcpListener::bind(addr)?;
+        Ok(MetricsServer {
+            local_addr: listener.local_addr()?,
+            listener: Some(listener),
+            token: None,
+            #[cfg(feature = "tls")]
+            tls: None,
+            latest: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    pub fn with_token(mut self, token: String) -> MetricsServer {
+        self.token = Some(token);
+        self
+    }
+
+    /// Serves HTTPS with the PEM certificate chain and private key at `cert` and `key`.
+    #[cfg(feature = "tls")]
+    pub fn with_tls(mut self, cert: &Path, key: &Path) -> io::Result<MetricsServer> {
+        use rustls::pki_types::pem::PemObject;
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+        let certs = CertificateDer::pem_file_iter(cert)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|err| io::Error::other(format!("{}: {}", cert.display(), err)))?;
+        let key = PrivateKeyDer::from_pem_file(key)
+            .map_err(|err| io::Error::other(format!("{}: {}", key.display(), err)))?;
+        let config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map_err(io::Error::other)?;
+        self.tls = Some(Arc::new(config));
+        Ok(self)
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.local_addr)
+    }
+
+    /// Starts answering requests, each on a thread of its own and within
+    /// `REQUEST_DEADLINE`, up to `MAX_CONNECTIONS` at once.
+    pub fn start(mut self) -> MetricsServer {
+        if let Some(listener) = self.listener.take() {
+            let handler = Arc::new(Handler {
+                token: self.token.clone(),
+                #[cfg(feature = "tls")]
+                tls: self.tls.clone(),
+                latest: Arc::downgrade(&self.latest),
+            });
+            let active = Arc::new(AtomicUsize::new(0));
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    // Gone once rtop quits.
+                    if handler.latest.strong_count() == 0 {
+                        return;
+                    }
+                    if active.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                        active.fetch_sub(1, Ordering::SeqCst);
+                        continue;
+                    }
+                    let (handler, active) = (handler.clone(), active.clone());
+                    std::thread::spawn(move || {
+                        if let Some(latest) = handler.latest.upgrade() {
+                            let _ = handler.handle(stream, &latest);
+                        }
+                        active.fetch_sub(1, Ordering::SeqCst);
+                    });
+                }
+            });
+        }
+        self
+    }
+
+    /// Makes `metrics` what `/metrics` answers from now on.
+    pub fn publish(&self, metrics: String) {
+        if let Ok(mut latest) = self.latest.lock() {
+            *latest = Some(metrics);
+        }
+    }
+}
+
+/// What the serving thread needs of the `MetricsServer`.
+struct Handler {
+    token: Option<String>,
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<rustls::ServerConfig>>,
+    latest: Weak<Mutex<Option<String>>>,
+}
+
+impl Handler {
+    /// The TLS handshake happens on the serving thread too, within the
+    /// same deadline as the request.
+    #[cfg(feature = "tls")]
+    fn handle(&self, stream: TcpStream, latest: &Mutex<Option<String>>) -> io::Result<()> {
+        let stream = Deadline::new(stream);
+        let Some(config) = &self.tls else {
+            return respond(&mut { stream }, self.token.as_deref(), latest);
+        };
+        let connection = rustls::ServerConnection::new(config.clone()).map_err(io::Error::other)?;
+        let mut stream = rustls::StreamOwned::new(connection, stream);
+        respond(&mut stream, self.token.as_deref(), latest)?;
+        stream.conn.send_close_notify();
+        stream.flush()
+    }
+
+    #[cfg(not(feature = "tls"))]
+    fn handle(&self, stream: TcpStream, latest: &Mutex<Option<String>>) -> io::Result<()> {
+        respond(&mut Deadline::new(stream), self.token.as_deref(), latest)
+    }
+}
+
+/// A connection whose reads and writes fail once `REQUEST_DEADLINE` has
+/// passed since it was accepted, however slowly the client trickles.
+struct Deadline {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+impl Deadline {
+    fn new(stream: TcpStream) -> Deadline {
+        Deadline {
+            stream,
+            deadline: Instant::now() + REQUEST_DEADLINE,
+        }
+    }
+
+    fn left(&self) -> io::Result<Duration> {
+        self.deadline
+            .checked_duration_since(Instant::now())
+            .filter(|left| !left.is_zero())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::TimedOut))
+    }
+}
+
+impl Read for Deadline {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.set_read_timeout(Some(self.left()?))?;
+        self.stream.read(buf)
+    }
+}
+
+impl Write for Deadline {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.set_write_timeout(Some(self.left()?))?;
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// Compares in time that depends only on the length of `expected`, so
+/// response timing reveals neither how much of a guessed token was right
+/// nor whether its length was.
+fn token_matches(given: &str, expected: &str) -> bool {
+    let given = given.as_bytes();
+    let diff = expected
+        .bytes()
+        .enumerate()
+        .fold(given.len() ^ expected.len(), |diff, (i, b)| {
+            diff | usize::from(given.get(i).copied().unwrap_or(0) ^ b)
+        });
+    diff == 0
+}
+
+fn respond<S: Read + Write>(
+    stream: &mut S,
+    token: Option<&str>,
+    latest: &Mutex<Option<String>>,
+) -> io::Result<()> {
+    let mut request_line = String::new();
+    let mut authorized = token.is_none();
+    let too_large = {
+        let mut reader = BufReader::new(Read::take(&mut *stream, MAX_HEAD));
+        reader.read_line(&mut request_line)?;
+        // Only Authorization matters, but reading all headers keeps clients from seeing a reset.
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            if let (Some(token), Some((name, value))) = (token, header.split_once(':')) {
+                if name.eq_ignore_ascii_case("authorization") {
+                    authorized = value
+                        .trim()
+                        .strip_prefix("Bearer ")
+                        .is_some_and(|given| token_matches(given.trim(), token));
+                }
+            }
+            header.clear();
+        }
+        reader.into_inner().limit() == 0
+    };
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        _ if too_large => ("431 Request Header Fields Too Large", String::new()),
+        _ if !authorized => (
+            "401 Unauthorized",
+            "A bearer token is required\n".to_string(),
+        ),
+        (Some("GET"), Some("/metrics")) => {
+            match latest.lock().ok().and_then(|latest| latest.clone()) {
+                Some(metrics) => ("200 OK", metrics),
+                None => ("503 Service Unavailable", "No sample yet\n".to_string()),
+            }
+        }
+        (Some("GET"), Some(_)) => ("404 Not Found", "Metrics are at /metrics\n".to_string()),
+        _ => ("405 Method Not Allowed", String::new()),
+    };
+    let challenge = if authorized {
+        ""
+    } else {
+        "WWW-Authenticate: Bearer\r\n"
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        challenge,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_label_escaping() {
+        assert_eq!(label(r#"a "b" \c"#), r#"a \"b\" \\c"#);
+        assert_eq!(label("x\ny"), "x\\ny");
+    }
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches("s3cret", "s3cret"));
+        assert!(!token_matches("s3cre", "s3cret"));
+        assert!(!token_matches("s3cret\0", "s3cret"));
+        assert!(!token_matches("", "s3cret"));
+        assert!(!token_matches("s3creT", "s3cret"));
+    }
+
+    fn get(addr: SocketAddr, headers: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "GET /metrics HTTP/1.1\r\nHost: x\r\n{}\r\n",
+            headers
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        let server = MetricsServer::bind("127.0.0.1:0".parse().unwrap())
+            .unwrap()
+            .with_token("s3cret".to_string())
+            .start();
+        server.publish("rtop_up 1\n".to_string());
+        let addr = server.local_addr().unwrap();
+        let [missing, wrong, right] = [
+            get(addr, ""),
+            get(addr, "Authorization: Bearer wrong!\r\n"),
+            get(addr, "authorization: Bearer s3cret\r\n"),
+        ];
+        assert!(missing.starts_with("HTTP/1.1 401 Unauthorized"));
+        assert!(missing.contains("WWW-Authenticate: Bearer"));
+        assert!(wrong.starts_with("HTTP/1.1 401 Unauthorized"));
+        assert!(right.ends_with("\r\n\r\nrtop_up 1\n"));
+    }
+
+    #[test]
+    fn test_serve_metrics() {
+        let server = MetricsServer::bind("127.0.0.1:0".parse().unwrap())
+            .unwrap()
+            .start();
+        let addr = server.local_addr().unwrap();
+        assert!(get(addr, "").starts_with("HTTP/1.1 503 Service Unavailable"));
+        server.publish("rtop_up 1\n".to_string());
+
+        // A client that never finishes its request holds no one else up.
+        let _stalled = TcpStream::connect(addr).unwrap();
+        let started = Instant::now();
+        let response = get(addr, "");
+        assert!(started.elapsed() < REQUEST_DEADLINE / 2);
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("\r\n\r\nrtop_up 1\n"));
+
+        // Exactly the cap, so nothing is left unread to reset the connection.
+        let head = "GET /metrics HTTP/1.1\r\nHost: x\r\nX-Padding: \r\n\r\n".len();
+        let flood = format!("X-Padding: {}\r\n", "x".repeat(MAX_HEAD as usize - head));
+        assert!(get(addr, &flood).starts_with("HTTP/1.1 431"));
+    }
+
+    #[test]
+    fn test_connection_limit() {
+        let server = MetricsServer::bind("127.0.0.1:0".parse().unwrap())
+            .unwrap()
+            .start();
+        server.publish("rtop_up 1\n".to_string());
+        let addr = server.local_addr().unwrap();
+        let _stalled: Vec<_> = (0..MAX_CONNECTIONS)
+            .map(|_| TcpStream::connect(addr).unwrap())
+            .collect();
+        // Closed without an answer rather than kept waiting.
+        let started = Instant::now();
+        let mut refused = TcpStream::connect(addr).unwrap();
+        let mut response = String::new();
+        refused.read_to_string(&mut response).unwrap();
+        assert_eq!(response, "");
+        assert!(started.elapsed() < REQUEST_DEADLINE / 2);
+        // Served again once the stalled clients ran out of time.
+        std::thread::sleep(REQUEST_DEADLINE + Duration::from_millis(500));
+        assert!(get(addr, "").starts_with("HTTP/1.1 200 OK"));
+    }
+}
@@ -0,0 +1,223 @@
+// src/mirror.rs
+use crate::collectors::sockets::ConnectionSummary;
+use crate::{App, SortBy};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use sysinfo::{CpuExt, ProcessExt, SystemExt};
+
+/// Processes included in each snapshot, busiest first.
+const SNAPSHOT_PROCESSES: usize = 100;
+
+#[derive(Args, Debug)]
+pub struct AttachArgs {
+    /// Socket of the instance started with `--share` (defaults to the same path `--share` uses)
+    pub socket: Option<PathBuf>,
+}
+
+/// `$XDG_RUNTIME_DIR/rtop.sock`, or a per-user socket in the temp dir.
+pub fn default_socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join("rtop.sock"),
+        None => {
+            let user = std::env::var("USER").unwrap_or_default();
+            std::env::temp_dir().join(format!("rtop-{}.sock", user))
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MirrorProcess {
+    pub pid: u32,
+    pub name: String,
+    pub cpu_usage: f32,
+    pub memory: u64,
+}
+
+/// What a sharing instance sends to attached viewers after every refresh.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MirrorSnapshot {
+    pub cpu_usage: f32,
+    pub per_core: Vec<f32>,
+    pub used_memory: u64,
+    pub total_memory: u64,
+    /// Absent from older sharing instances.
+    #[serde(default)]
+    pub used_swap: u64,
+    #[serde(default)]
+    pub total_swap: u64,
+    pub connections: ConnectionSummary,
+    pub processes: Vec<MirrorProcess>,
+}
+
+impl MirrorSnapshot {
+    pub fn capture(app: &App) -> MirrorSnapshot {
+        let system = &app.system;
+        let mut processes = app.get_sorted_processes();
+        if app.sort_by != SortBy::Cpu.name() || app.sort_reversed {
+            processes.sort_by(|a, b| b.1.cpu_usage().total_cmp(&a.1.cpu_usage()));
+        }
+        MirrorSnapshot {
+            cpu_usage: system.global_cpu_info().cpu_usage(),
+            per_core: system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect(),
+            used_memory: system.used_memory(),
+            total_memory: system.total_memory(),
+            used_swap: system.used_swap(),
+            total_swap: system.total_swap(),
+            connections: app.connections.clone(),
+            processes: processes
+                .iter()
+                .take(SNAPSHOT_PROCESSES)
+                .map(|(pid, process)| MirrorProcess {
+                    pid: usize::from(*pid) as u32,
+                    name: app.redact.process(process.name()).into_owned(),
+                    cpu_usage: process.cpu_usage(),
+                    memory: process.memory(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Streams snapshots as JSON lines to every viewer connected to a Unix socket.
+pub struct MirrorServer {
+    path: PathBuf,
+    listener: UnixListener,
+    clients: Vec<UnixStream>,
+}
+
+impl MirrorServer {
+    pub fn bind(path: &Path) -> io::Result<MirrorServer> {
+        // A socket file nobody answers on is left over from an instance that crashed.
+        if path.exists() && UnixStream::connect(path).is_err() {
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+        // Snapshots include process names and command details of every user.
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        Ok(MirrorServer {
+            path: path.to_path_buf(),
+            listener,
+            clients: Vec::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Accepts pending viewers and sends them a snapshot; `capture` only runs
+    /// when someone is attached.
+    pub fn publish(&mut self, capture: impl FnOnce() -> MirrorSnapshot) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            // A viewer that stops reading must not stall the sampler.
+            if stream.set_write_timeout(Some(Duration::from_millis(100))).is_ok() {
+                self.clients.push(stream);
+            }
+        }
+        if self.clients.is_empty() {
+            return;
+        }
+        let Ok(mut line) = serde_json::to_vec(&capture()) else {
+            return;
+        };
+        line.push(b'\n');
+        self.clients.retain_mut(|client| client.write_all(&line).is_ok());
+    }
+}
+
+impl Drop for MirrorServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// The viewer side: a background thread keeps the latest snapshot received.
+pub struct MirrorClient {
+    latest: Arc<Mutex<Option<MirrorSnapshot>>>,
+    connected: Arc<Mutex<bool>>,
+}
+
+impl MirrorClient {
+    pub fn connect(path: &Path) -> io::Result<MirrorClient> {
+        let stream = UnixStream::connect(path)?;
+        let latest = Arc::new(Mutex::new(None));
+        let connected = Arc::new(Mutex::new(true));
+        let (thread_latest, thread_connected) = (latest.clone(), connected.clone());
+        std::thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if let Ok(snapshot) = serde_json::from_str(&line) {
+                    *thread_latest.lock().unwrap_or_else(PoisonError::into_inner) = Some(snapshot);
+                }
+            }
+            *thread_connected
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = false;
+        });
+        Ok(MirrorClient { latest, connected })
+    }
+
+    pub fn latest(&self) -> Option<MirrorSnapshot> {
+        self.latest
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        *self
+            .connected
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_to_attached_viewer() {
+        let path = std::env::temp_dir().join(format!("rtop-test-{}.sock", std::process::id()));
+        let mut server = MirrorServer::bind(&path).unwrap();
+        let snapshot = MirrorSnapshot {
+            cpu_usage: 12.5,
+            per_core: vec![10.0, 15.0],
+            used_memory: 1,
+            total_memory: 2,
+            used_swap: 0,
+            total_swap: 0,
+            connections: ConnectionSummary::default(),
+            processes: vec![MirrorProcess {
+                pid: 1,
+                name: "init".to_string(),
+                cpu_usage: 0.5,
+                memory: 4096,
+            }],
+        };
+
+        let viewer = UnixStream::connect(&path).unwrap();
+        server.publish(|| snapshot.clone());
+        assert_eq!(server.client_count(), 1);
+
+        let mut line = String::new();
+        BufReader::new(viewer).read_line(&mut line).unwrap();
+        assert_eq!(serde_json::from_str::<MirrorSnapshot>(&line).unwrap(), snapshot);
+
+        drop(server);
+        assert!(!path.exists());
+    }
+}
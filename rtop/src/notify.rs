@@ -0,0 +1,263 @@
+// src/notify.rs
+use crate::health::{Finding, Severity};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// The `[notify]` table: desktop notifications when alerts start firing.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifyConfig {
+    pub on_alert: bool,
+    /// Seconds before a rule that fired can notify again, so a flapping
+    /// rule does not notify on every refresh.
+    pub cooldown_secs: u64,
+    /// Notifications per minute at most; alerts beyond that are named in
+    /// the next one.
+    pub per_minute: usize,
+    /// Program and arguments to run instead of `notify-send` or `osascript`,
+    /// with `{title}`, `{body}` and `{severity}` replaced.
+    pub command: Vec<String>,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> NotifyConfig {
+        NotifyConfig {
+            on_alert: false,
+            cooldown_secs: 300,
+            per_minute: 3,
+            command: Vec::new(),
+        }
+    }
+}
+
+/// What a notification says.
+#[derive(Debug, PartialEq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    pub severity: Severity,
+}
+
+/// Turns alerts that start firing into notifications, debounced per rule
+/// and rate limited overall.
+pub struct Notifier {
+    config: NotifyConfig,
+    /// When each rule last notified.
+    notified: HashMap<String, Instant>,
+    /// When the notifications of the last minute went out.
+    sent: VecDeque<Instant>,
+    /// Rules held back by the rate limit since the last notification.
+    held_back: Vec<String>,
+}
+
+impl Notifier {
+    pub fn new(config: NotifyConfig) -> Notifier {
+        Notifier {
+            config,
+            notified: HashMap::new(),
+            sent: VecDeque::new(),
+            held_back: Vec::new(),
+        }
+    }
+
+    /// The notification for the alerts in `started`, if any is due.
+    pub fn notification(
+        &mut self,
+        now: Instant,
+        host: &str,
+        started: &[&Finding],
+    ) -> Option<Notification> {
+        let cooldown = Duration::from_secs(self.config.cooldown_secs);
+        let due: Vec<&Finding> = started
+            .iter()
+            .copied()
+            .filter(|alert| {
+                self.notified
+                    .get(&alert.check)
+                    .is_none_or(|at| now.saturating_duration_since(*at) >= cooldown)
+            })
+            .collect();
+        if due.is_empty() {
+            return None;
+        }
+        while self
+            .sent
+            .front()
+            .is_some_and(|at| now.saturating_duration_since(*at) >= Duration::from_secs(60))
+        {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= self.config.per_minute {
+            // Not stamped, so the rules can notify as soon as there is room.
+            for alert in due {
+                if !self.held_back.contains(&alert.check) {
+                    self.held_back.push(alert.check.clone());
+                }
+            }
+            return None;
+        }
+        self.sent.push_back(now);
+        for alert in &due {
+            self.notified.insert(alert.check.clone(), now);
+        }
+
+        let severity = due.iter().map(|alert| alert.severity).max()?;
+        let title = match due.len() {
+            1 => format!("rtop: {} on {}", due[0].check, host),
+            n => format!("rtop: {} alerts on {}", n, host),
+        };
+        let mut lines: Vec<String> = due
+            .iter()
+            .map(|alert| {
+                format!(
+                    "{} {}: {}",
+                    alert.severity.label(),
+                    alert.check,
+                    alert.message
+                )
+            })
+            .collect();
+        self.held_back
+            .retain(|check| !due.iter().any(|alert| alert.check == *check));
+        if !self.held_back.is_empty() {
+            for check in &self.held_back {
+                self.notified.insert(check.clone(), now);
+            }
+            lines.push(format!("Held back: {}", self.held_back.join(", ")));
+            self.held_back.clear();
+        }
+        Some(Notification {
+            title,
+            body: lines.join("\n"),
+            severity,
+        })
+    }
+
+    /// Shows `notification` on the desktop without waiting for it.
+    pub fn send(&self, notification: &Notification) -> io::Result<()> {
+        let argv = if self.config.command.is_empty() {
+            default_command(notification)?
+        } else {
+            let severity = notification.severity.label().to_lowercase();
+            self.config
+                .command
+                .iter()
+                .map(|arg| {
+                    arg.replace("{title}", &notification.title)
+                        .replace("{body}", &notification.body)
+                        .replace("{severity}", &severity)
+                })
+                .collect()
+        };
+        let (program, args) = argv
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+        // Unlike actions, the notifier needs the session's environment
+        // (DBUS_SESSION_BUS_ADDRESS, DISPLAY) to reach the desktop.
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn default_command(notification: &Notification) -> io::Result<Vec<String>> {
+    let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    Ok(vec![
+        "osascript".to_string(),
+        "-e".to_string(),
+        format!(
+            "display notification {} with title {}",
+            quote(&notification.body),
+            quote(&notification.title)
+        ),
+    ])
+}
+
+#[cfg(not(target_os = "macos"))]
+fn default_command(notification: &Notification) -> io::Result<Vec<String>> {
+    if !cfg!(unix) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "no notifier on this platform; set command in [notify]",
+        ));
+    }
+    let urgency = match notification.severity {
+        Severity::Critical => "critical",
+        _ => "normal",
+    };
+    Ok(vec![
+        "notify-send".to_string(),
+        "--app-name=rtop".to_string(),
+        format!("--urgency={}", urgency),
+        notification.title.clone(),
+        notification.body.clone(),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(check: &str) -> Finding {
+        Finding {
+            check: check.to_string(),
+            severity: Severity::Critical,
+            message: "99.0% used".to_string(),
+            silenced: None,
+        }
+    }
+
+    #[test]
+    fn test_notifications_are_debounced() {
+        let mut notifier = Notifier::new(NotifyConfig {
+            per_minute: 2,
+            ..NotifyConfig::default()
+        });
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        let (memory, swap, disk) = (alert("memory"), alert("swap"), alert("disk:/"));
+
+        let first = notifier.notification(at(0), "web1", &[&memory]).unwrap();
+        assert_eq!(first.title, "rtop: memory on web1");
+        assert_eq!(first.body, "CRIT memory: 99.0% used");
+        // A flapping rule stays quiet until its cooldown is over.
+        assert_eq!(notifier.notification(at(10), "web1", &[&memory]), None);
+
+        let second = notifier.notification(at(20), "web1", &[&swap, &memory]).unwrap();
+        assert_eq!(second.title, "rtop: swap on web1");
+        // Over the rate limit, alerts are held back and named later.
+        assert_eq!(notifier.notification(at(30), "web1", &[&disk]), None);
+        let later = notifier
+            .notification(at(400), "web1", &[&memory, &swap])
+            .unwrap();
+        assert_eq!(later.title, "rtop: 2 alerts on web1");
+        assert!(later.body.ends_with("\nHeld back: disk:/"));
+    }
+
+    #[test]
+    fn test_held_back_rule_is_not_in_cooldown() {
+        let mut notifier = Notifier::new(NotifyConfig {
+            per_minute: 1,
+            ..NotifyConfig::default()
+        });
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        let (memory, disk) = (alert("memory"), alert("disk:/"));
+
+        assert!(notifier.notification(at(0), "web1", &[&memory]).is_some());
+        assert_eq!(notifier.notification(at(10), "web1", &[&disk]), None);
+        // Once the minute is over the held-back rule goes out by itself.
+        let next = notifier.notification(at(70), "web1", &[&disk]).unwrap();
+        assert_eq!(next.title, "rtop: disk:/ on web1");
+        assert_eq!(next.body, "CRIT disk:/: 99.0% used");
+    }
+}
@@ -0,0 +1,125 @@
+// src/plugins/dynamic.rs
+//! Plugins loaded from shared libraries, which export:
+//!
+//! ```c
+//! const char *rtop_plugin_name(void);
+//! /* Writes "label\tvalue\n" lines to buf; returns their length, or -1. */
+//! int rtop_plugin_collect(char *buf, size_t len);
+//! ```
+use super::{Collector, Fields, FieldsPanel};
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::time::Duration;
+
+type NameFn = unsafe extern "C" fn() -> *const c_char;
+type CollectFn = unsafe extern "C" fn(*mut c_char, usize) -> c_int;
+
+/// Room given to `rtop_plugin_collect`.
+const BUFFER: usize = 16 * 1024;
+
+pub struct LibraryCollector {
+    collect: CollectFn,
+}
+
+impl Collector for LibraryCollector {
+    type Output = Fields;
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(2)
+    }
+
+    fn collect(&mut self) -> Result<Fields, String> {
+        let mut buf = vec![0u8; BUFFER];
+        let len = unsafe { (self.collect)(buf.as_mut_ptr() as *mut c_char, buf.len()) };
+        let len = usize::try_from(len).map_err(|_| "the plugin failed".to_string())?;
+        buf.truncate(len.min(BUFFER));
+        Ok(parse(&String::from_utf8_lossy(&buf)))
+    }
+}
+
+/// Loads the library at `path`; it stays loaded while rtop runs.
+pub fn load(path: &Path) -> Result<(LibraryCollector, FieldsPanel), String> {
+    let error = |what: &str| format!("{}: {}", path.display(), what);
+    let name = CString::new(path.as_os_str().as_bytes()).map_err(|_| error("bad path"))?;
+    let handle = unsafe { libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+    if handle.is_null() {
+        let reason = unsafe { libc::dlerror() };
+        return Err(if reason.is_null() {
+            error("cannot load")
+        } else {
+            error(&unsafe { CStr::from_ptr(reason) }.to_string_lossy())
+        });
+    }
+    let symbol = |symbol: &CStr| {
+        let address = unsafe { libc::dlsym(handle, symbol.as_ptr()) };
+        (!address.is_null())
+            .then_some(address)
+            .ok_or_else(|| error(&format!("no {}", symbol.to_string_lossy())))
+    };
+    let name_fn: NameFn = unsafe { std::mem::transmute(symbol(c"rtop_plugin_name")?) };
+    let collect: CollectFn = unsafe { std::mem::transmute(symbol(c"rtop_plugin_collect")?) };
+    let title = unsafe { name_fn() };
+    let title = if title.is_null() {
+        path.display().to_string()
+    } else {
+        unsafe { CStr::from_ptr(title) }
+            .to_string_lossy()
+            .into_owned()
+    };
+    Ok((LibraryCollector { collect }, FieldsPanel { title }))
+}
+
+fn parse(output: &str) -> Fields {
+    output
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(label, value)| (label.to_string(), value.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_missing_library() {
+        let err = load(Path::new("/nonexistent/librtop_plugin.so"))
+            .err()
+            .unwrap();
+        assert!(err.starts_with("/nonexistent/librtop_plugin.so: "));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("hits\t10\nno tab\nmisses\t2\n"),
+            vec![
+                ("hits".to_string(), "10".to_string()),
+                ("misses".to_string(), "2".to_string()),
+            ]
+        );
+    }
+
+    unsafe extern "C" fn failing(_: *mut c_char, _: usize) -> c_int {
+        -1
+    }
+
+    unsafe extern "C" fn overlong(buf: *mut c_char, len: usize) -> c_int {
+        let line = b"hits\t10\n";
+        std::ptr::copy_nonoverlapping(line.as_ptr() as *const c_char, buf, line.len().min(len));
+        BUFFER as c_int * 2
+    }
+
+    #[test]
+    fn test_collect_error_and_oversized_length() {
+        let mut collector = LibraryCollector { collect: failing };
+        assert_eq!(collector.collect(), Err("the plugin failed".to_string()));
+        let mut collector = LibraryCollector { collect: overlong };
+        assert_eq!(
+            collector.collect(),
+            Ok(vec![("hits".to_string(), "10".to_string())])
+        );
+    }
+}
@@ -0,0 +1,294 @@
+// src/plugins/mod.rs
+//! Panels beyond what rtop reads itself, shown in the Plugins tab. A plugin
+//! is a `Collector`, run on a thread of its own, and a `Panel` that draws
+//! what it collected. Built-in ones are enabled with cargo features; others
+//! can be registered by a binary built on the `rtop` library, or loaded from
+//! a shared library with the `dynamic-plugins` feature.
+#[cfg(all(unix, feature = "dynamic-plugins"))]
+pub mod dynamic;
+#[cfg(feature = "redis")]
+pub mod redis;
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Paragraph, Widget, Wrap};
+
+/// Reads what a panel shows; runs on a thread of its own.
+pub trait Collector: Send + 'static {
+    type Output: Send + 'static;
+
+    /// Time between reads.
+    fn interval(&self) -> Duration {
+        Duration::from_secs(2)
+    }
+
+    fn collect(&mut self) -> Result<Self::Output, String>;
+}
+
+/// Draws what a `Collector` read, inside a block titled `title`.
+pub trait Panel: 'static {
+    type Input;
+
+    fn title(&self) -> String;
+
+    /// Rows wanted in the Plugins tab, borders included.
+    fn height(&self) -> u16 {
+        10
+    }
+
+    fn render(&self, input: &Self::Input, area: Rect, buf: &mut Buffer);
+}
+
+/// Label and value rows, the output of most simple collectors.
+pub type Fields = Vec<(String, String)>;
+
+/// Shows `Fields` as aligned label: value rows.
+pub struct FieldsPanel {
+    pub title: String,
+}
+
+impl Panel for FieldsPanel {
+    type Input = Fields;
+
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    fn render(&self, fields: &Fields, area: Rect, buf: &mut Buffer) {
+        let width = fields
+            .iter()
+            .map(|(label, _)| label.len())
+            .max()
+            .unwrap_or(0);
+        let lines: Vec<Spans> = fields
+            .iter()
+            .map(|(label, value)| {
+                Spans::from(vec![
+                    Span::styled(
+                        format!("{:width$}  ", label, width = width),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(value.clone()),
+                ])
+            })
+            .collect();
+        Paragraph::new(lines).render(area, buf);
+    }
+}
+
+/// Draws a panel into the area inside its block.
+type Draw = dyn Fn(Rect, &mut Buffer);
+
+type Latest<T> = Arc<Mutex<Option<Result<T, String>>>>;
+
+/// A registered plugin, with its types erased.
+struct Entry {
+    title: String,
+    height: u16,
+    start: Option<Box<dyn FnOnce() + Send>>,
+    draw: Box<Draw>,
+}
+
+/// The registered plugins, in the order they are shown.
+#[derive(Default)]
+pub struct Plugins {
+    entries: Vec<Entry>,
+}
+
+impl Plugins {
+    /// Adds a plugin; its collector starts with `start`.
+    pub fn register<C, P>(&mut self, collector: C, panel: P)
+    where
+        C: Collector,
+        P: Panel<Input = C::Output>,
+    {
+        let latest: Latest<C::Output> = Arc::new(Mutex::new(None));
+        let start = {
+            let latest = Arc::downgrade(&latest);
+            let mut collector = collector;
+            move || {
+                std::thread::spawn(move || loop {
+                    let value = collector.collect();
+                    let Some(latest) = latest.upgrade() else {
+                        return;
+                    };
+                    if let Ok(mut latest) = latest.lock() {
+                        *latest = Some(value);
+                    }
+                    drop(latest);
+                    std::thread::sleep(collector.interval());
+                });
+            }
+        };
+        let (title, height) = (panel.title(), panel.height());
+        let draw = move |area: Rect, buf: &mut Buffer| {
+            let Ok(latest) = latest.lock() else {
+                return;
+            };
+            match &*latest {
+                Some(Ok(input)) => panel.render(input, area, buf),
+                Some(Err(err)) => message(&format!("Error: {}", err), area, buf),
+                None => message("Waiting for the first sample", area, buf),
+            }
+        };
+        self.entries.push(Entry {
+            title,
+            height,
+            start: Some(Box::new(start)),
+            draw: Box::new(draw),
+        });
+    }
+
+    /// Starts the collectors of the plugins registered so far.
+    pub fn start(&mut self) {
+        for entry in &mut self.entries {
+            if let Some(start) = entry.start.take() {
+                start();
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Title, height and drawing of every panel, for the Plugins tab.
+    pub(crate) fn panels(&self) -> impl Iterator<Item = (&str, u16, &Draw)> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.title.as_str(), entry.height, entry.draw.as_ref()))
+    }
+}
+
+fn message(text: &str, area: Rect, buf: &mut Buffer) {
+    Paragraph::new(text.to_string())
+        .wrap(Wrap { trim: true })
+        .render(area, buf);
+}
+
+/// The `[plugins]` table.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginsConfig {
+    /// `[plugins.redis]`: a panel with the stats of a Redis server; needs
+    /// the `redis` feature.
+    pub redis: Option<RedisConfig>,
+    /// Shared libraries exporting `rtop_plugin_name` and
+    /// `rtop_plugin_collect`; needs the `dynamic-plugins` feature.
+    pub libraries: Vec<PathBuf>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RedisConfig {
+    /// Such as `127.0.0.1:6379`.
+    pub address: String,
+    pub password: Option<String>,
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+}
+
+fn default_interval_ms() -> u64 {
+    2000
+}
+
+impl PluginsConfig {
+    /// Registers the plugins the config asks for with `plugins`.
+    pub fn register(&self, plugins: &mut Plugins) -> Result<(), String> {
+        if let Some(config) = &self.redis {
+            register_redis(plugins, config)?;
+        }
+        for path in &self.libraries {
+            register_library(plugins, path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "redis")]
+fn register_redis(plugins: &mut Plugins, config: &RedisConfig) -> Result<(), String> {
+    plugins.register(
+        redis::RedisCollector::new(config.clone()),
+        redis::panel(config),
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "redis"))]
+fn register_redis(_: &mut Plugins, config: &RedisConfig) -> Result<(), String> {
+    Err(format!(
+        "plugins.redis ({}): rtop was built without the redis feature",
+        config.address
+    ))
+}
+
+#[cfg(all(unix, feature = "dynamic-plugins"))]
+fn register_library(plugins: &mut Plugins, path: &Path) -> Result<(), String> {
+    let (collector, panel) = dynamic::load(path)?;
+    plugins.register(collector, panel);
+    Ok(())
+}
+
+#[cfg(not(all(unix, feature = "dynamic-plugins")))]
+fn register_library(_: &mut Plugins, path: &Path) -> Result<(), String> {
+    Err(format!(
+        "plugins.libraries ({}): rtop was built without the dynamic-plugins feature",
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    struct Counter(u32);
+
+    impl Collector for Counter {
+        type Output = Fields;
+
+        fn interval(&self) -> Duration {
+            Duration::from_millis(10)
+        }
+
+        fn collect(&mut self) -> Result<Fields, String> {
+            self.0 += 1;
+            Ok(vec![("count".to_string(), self.0.to_string())])
+        }
+    }
+
+    #[test]
+    fn test_registered_plugin_draws_collected() {
+        let mut plugins = Plugins::default();
+        plugins.register(
+            Counter(0),
+            FieldsPanel {
+                title: "Counter".to_string(),
+            },
+        );
+        let area = Rect::new(0, 0, 20, 1);
+        let draw = |plugins: &Plugins| {
+            let mut buf = Buffer::empty(area);
+            let (title, _, draw) = plugins.panels().next().unwrap();
+            assert_eq!(title, "Counter");
+            draw(area, &mut buf);
+            buf.content
+                .iter()
+                .map(|cell| cell.symbol.as_str())
+                .collect::<String>()
+        };
+        assert!(draw(&plugins).starts_with("Waiting"));
+        plugins.start();
+        let start = Instant::now();
+        while !draw(&plugins).starts_with("count  ") {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+}
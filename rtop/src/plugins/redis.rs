@@ -0,0 +1,158 @@
+// src/plugins/redis.rs
+use super::{Collector, Fields, FieldsPanel, RedisConfig};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Longest bulk reply read; `INFO` is a few KiB.
+const MAX_BULK: usize = 1024 * 1024;
+
+/// `INFO` fields the panel shows, with their labels.
+const SHOWN: &[(&str, &str)] = &[
+    ("redis_version", "Version"),
+    ("role", "Role"),
+    ("uptime_in_seconds", "Uptime (s)"),
+    ("connected_clients", "Clients"),
+    ("blocked_clients", "Blocked"),
+    ("used_memory_human", "Memory"),
+    ("maxmemory_human", "Max memory"),
+    ("instantaneous_ops_per_sec", "Ops/s"),
+    ("keyspace_hits", "Hits"),
+    ("keyspace_misses", "Misses"),
+    ("evicted_keys", "Evicted"),
+    ("db0", "db0"),
+];
+
+/// Reads `INFO` from a Redis server.
+pub struct RedisCollector {
+    config: RedisConfig,
+}
+
+impl RedisCollector {
+    pub fn new(config: RedisConfig) -> RedisCollector {
+        RedisCollector { config }
+    }
+
+    fn info(&self) -> Result<String, String> {
+        let address = self
+            .config
+            .address
+            .to_socket_addrs()
+            .map_err(|e| e.to_string())?
+            .next()
+            .ok_or_else(|| format!("{}: no address", self.config.address))?;
+        let stream = TcpStream::connect_timeout(&address, TIMEOUT).map_err(|e| e.to_string())?;
+        stream
+            .set_read_timeout(Some(TIMEOUT))
+            .map_err(|e| e.to_string())?;
+        let mut reader = BufReader::new(stream);
+        if let Some(password) = &self.config.password {
+            send(reader.get_mut(), &["AUTH", password])?;
+            reply(&mut reader)?;
+        }
+        send(reader.get_mut(), &["INFO"])?;
+        reply(&mut reader)
+    }
+}
+
+impl Collector for RedisCollector {
+    type Output = Fields;
+
+    fn interval(&self) -> Duration {
+        Duration::from_millis(self.config.interval_ms)
+    }
+
+    fn collect(&mut self) -> Result<Fields, String> {
+        self.info().map(|info| fields(&info))
+    }
+}
+
+pub fn panel(config: &RedisConfig) -> FieldsPanel {
+    FieldsPanel {
+        title: format!("Redis {}", config.address),
+    }
+}
+
+fn send(stream: &mut TcpStream, args: &[&str]) -> Result<(), String> {
+    let mut command = format!("*{}\r\n", args.len());
+    for arg in args {
+        command.push_str(&format!("${}\r\n{}\r\n", arg.len(), arg));
+    }
+    stream
+        .write_all(command.as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+/// Reads a simple, error or bulk string reply.
+fn reply(reader: &mut impl BufRead) -> Result<String, String> {
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|e| e.to_string())?;
+    let line = line.trim_end();
+    match line.as_bytes().first() {
+        Some(b'+') => Ok(line[1..].to_string()),
+        Some(b'-') => Err(line[1..].to_string()),
+        Some(b'$') => {
+            let len: usize = line[1..]
+                .parse()
+                .map_err(|_| format!("bad reply: {}", line))?;
+            if len > MAX_BULK {
+                return Err(format!("reply too long: {} bytes", len));
+            }
+            let mut body = vec![0; len + 2];
+            reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+            body.truncate(len);
+            String::from_utf8(body).map_err(|e| e.to_string())
+        }
+        _ => Err(format!("bad reply: {}", line)),
+    }
+}
+
+/// The shown fields of an `INFO` reply, in `SHOWN` order.
+fn fields(info: &str) -> Fields {
+    let values: Vec<(&str, &str)> = info
+        .lines()
+        .filter_map(|line| line.trim_end().split_once(':'))
+        .collect();
+    SHOWN
+        .iter()
+        .filter_map(|(key, label)| {
+            let (_, value) = values.iter().find(|(k, _)| k == key)?;
+            Some((label.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redis_info_reply() {
+        let info = "# Server\r\nredis_version:7.2.4\r\nuptime_in_seconds:360\r\n\r\n# Clients\r\nconnected_clients:3\r\n# Keyspace\r\ndb0:keys=10,expires=0,avg_ttl=0\r\n";
+        let raw = format!("${}\r\n{}\r\n", info.len(), info);
+        let info = reply(&mut raw.as_bytes()).unwrap();
+        assert_eq!(
+            fields(&info),
+            vec![
+                ("Version".to_string(), "7.2.4".to_string()),
+                ("Uptime (s)".to_string(), "360".to_string()),
+                ("Clients".to_string(), "3".to_string()),
+                ("db0".to_string(), "keys=10,expires=0,avg_ttl=0".to_string()),
+            ]
+        );
+        assert_eq!(
+            reply(&mut "-NOAUTH Authentication required.\r\n".as_bytes()),
+            Err("NOAUTH Authentication required.".to_string())
+        );
+        assert_eq!(
+            reply(&mut "éOK\r\n".as_bytes()),
+            Err("bad reply: éOK".to_string())
+        );
+        assert_eq!(
+            reply(&mut "$1073741824\r\n".as_bytes()),
+            Err("reply too long: 1073741824 bytes".to_string())
+        );
+    }
+}
@@ -0,0 +1,152 @@
+// src/privilege.rs
+use std::ffi::CString;
+use std::io;
+
+/// The privilege level rtop runs at, shown in the status bar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Privilege {
+    Root,
+    User {
+        uid: u32,
+    },
+    /// Started as root, then switched to `uid` once the collectors were started.
+    Dropped {
+        uid: u32,
+    },
+}
+
+impl Privilege {
+    pub fn current() -> Privilege {
+        match unsafe { libc::geteuid() } {
+            0 => Privilege::Root,
+            uid => Privilege::User { uid },
+        }
+    }
+}
+
+/// The invoking user's IDs when running under `sudo`.
+pub fn sudo_ids(uid: Option<&str>, gid: Option<&str>) -> Option<(u32, u32)> {
+    Some((uid?.parse().ok()?, gid?.parse().ok()?))
+}
+
+fn lookup_user(name: &str) -> io::Result<(u32, u32)> {
+    let c_name =
+        CString::new(name).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    // The reentrant variant, as the collectors already run and look up
+    // the owners of processes.
+    let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        let err = unsafe {
+            libc::getpwnam_r(
+                c_name.as_ptr(),
+                &mut entry,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+        match err {
+            0 => break,
+            libc::ERANGE if buf.len() < 1 << 20 => buf.resize(buf.len() * 2, 0),
+            err => return Err(io::Error::from_raw_os_error(err)),
+        }
+    }
+    if result.is_null() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("unknown user '{}'", name),
+        ));
+    }
+    Ok((entry.pw_uid, entry.pw_gid))
+}
+
+/// Switches from root to `user`, or to the user who ran `sudo`, or to
+/// `nobody`. Does nothing when not running as root.
+///
+/// On Linux only the calling thread switches, and the threads it starts
+/// afterwards: threads started before keep running as root. Elsewhere the
+/// whole process switches.
+pub fn drop_root(user: Option<&str>) -> io::Result<Privilege> {
+    if Privilege::current() != Privilege::Root {
+        return Ok(Privilege::current());
+    }
+    let (uid, gid) = match user {
+        Some(name) => lookup_user(name)?,
+        None => match sudo_ids(
+            std::env::var("SUDO_UID").ok().as_deref(),
+            std::env::var("SUDO_GID").ok().as_deref(),
+        ) {
+            Some(ids) => ids,
+            None => lookup_user("nobody")?,
+        },
+    };
+    if uid == 0 {
+        return Ok(Privilege::Root);
+    }
+    if !unsafe { switch(uid, gid) } {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Privilege::Dropped { uid })
+}
+
+#[cfg(all(
+    target_os = "linux",
+    not(any(target_arch = "x86", target_arch = "arm"))
+))]
+use libc::{
+    SYS_setgroups as SYS_SETGROUPS, SYS_setresgid as SYS_SETRESGID, SYS_setresuid as SYS_SETRESUID,
+};
+#[cfg(all(target_os = "linux", any(target_arch = "x86", target_arch = "arm")))]
+use libc::{
+    SYS_setgroups32 as SYS_SETGROUPS, SYS_setresgid32 as SYS_SETRESGID,
+    SYS_setresuid32 as SYS_SETRESUID,
+};
+
+/// Linux keeps credentials per thread, and glibc's `setuid` and friends
+/// switch every thread; the system calls themselves switch only this one.
+/// Supplementary groups go first and the user ID last, since giving up root
+/// gives up the right to change the others.
+#[cfg(target_os = "linux")]
+unsafe fn switch(uid: u32, gid: u32) -> bool {
+    libc::syscall(SYS_SETGROUPS, 0, std::ptr::null::<libc::gid_t>()) == 0
+        && libc::syscall(SYS_SETRESGID, gid, gid, gid) == 0
+        && libc::syscall(SYS_SETRESUID, uid, uid, uid) == 0
+}
+
+#[cfg(not(target_os = "linux"))]
+unsafe fn switch(uid: u32, gid: u32) -> bool {
+    libc::setgroups(0, std::ptr::null()) == 0 && libc::setgid(gid) == 0 && libc::setuid(uid) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sudo_ids() {
+        assert_eq!(sudo_ids(Some("1000"), Some("100")), Some((1000, 100)));
+        assert_eq!(sudo_ids(Some("1000"), None), None);
+        assert_eq!(sudo_ids(Some("x"), Some("100")), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_drop_root_keeps_earlier_threads() {
+        if Privilege::current() != Privilege::Root {
+            return;
+        }
+        let (uid, _) = lookup_user("nobody").unwrap();
+        let (dropped, spawned) = std::thread::spawn(move || {
+            let dropped = drop_root(Some("nobody")).unwrap();
+            let spawned = std::thread::spawn(Privilege::current).join().unwrap();
+            (dropped, spawned)
+        })
+        .join()
+        .unwrap();
+        assert_eq!(dropped, Privilege::Dropped { uid });
+        assert_eq!(spawned, Privilege::User { uid });
+        assert_eq!(Privilege::current(), Privilege::Root);
+    }
+}
@@ -0,0 +1,102 @@
+// src/race.rs
+use std::collections::HashMap;
+use std::time::Instant;
+use sysinfo::{Pid, ProcessExt, System, SystemExt};
+
+/// Fraction of the remaining distance a bar moves towards its real value per frame.
+const EASING: f64 = 0.35;
+
+/// CPU time one process accumulated since rtop started.
+#[derive(Clone, Debug)]
+pub struct RaceEntry {
+    pub pid: Pid,
+    pub name: String,
+    pub cpu_seconds: f64,
+    /// What the bar currently shows; trails `cpu_seconds` so growth animates.
+    pub displayed: f64,
+    pub exited: bool,
+}
+
+/// Accumulates per-process CPU time over the session for the race chart.
+pub struct CpuRace {
+    /// Keyed by PID and start time so a reused PID starts a new entry.
+    entries: HashMap<(Pid, u64), RaceEntry>,
+    last_update: Instant,
+}
+
+impl CpuRace {
+    pub fn new() -> CpuRace {
+        CpuRace {
+            entries: HashMap::new(),
+            last_update: Instant::now(),
+        }
+    }
+
+    /// Adds the CPU each process used since the previous call.
+    pub fn record(&mut self, system: &System) {
+        let elapsed = self.last_update.elapsed().as_secs_f64();
+        self.last_update = Instant::now();
+        for entry in self.entries.values_mut() {
+            entry.exited = true;
+        }
+        for (&pid, process) in system.processes() {
+            let entry = self
+                .entries
+                .entry((pid, process.start_time()))
+                .or_insert_with(|| RaceEntry {
+                    pid,
+                    name: process.name().to_string(),
+                    cpu_seconds: 0.0,
+                    displayed: 0.0,
+                    exited: false,
+                });
+            entry.cpu_seconds += process.cpu_usage() as f64 / 100.0 * elapsed;
+            entry.exited = false;
+        }
+        for entry in self.entries.values_mut() {
+            entry.displayed += (entry.cpu_seconds - entry.displayed) * EASING;
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn insert(&mut self, pid: u32, name: &str, cpu_seconds: f64) {
+        self.entries.insert(
+            (Pid::from(pid as usize), 0),
+            RaceEntry {
+                pid: Pid::from(pid as usize),
+                name: name.to_string(),
+                cpu_seconds,
+                displayed: 0.0,
+                exited: false,
+            },
+        );
+    }
+
+    /// The `limit` processes with the most CPU time, highest first.
+    pub fn ranking(&self, limit: usize) -> Vec<&RaceEntry> {
+        let mut ranking: Vec<&RaceEntry> = self
+            .entries
+            .values()
+            .filter(|e| e.cpu_seconds > 0.0)
+            .collect();
+        ranking.sort_by(|a, b| b.cpu_seconds.total_cmp(&a.cpu_seconds));
+        ranking.truncate(limit);
+        ranking
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranking_orders_by_cpu_time() {
+        let mut race = CpuRace::new();
+        for (pid, seconds) in [(1, 2.0), (2, 5.0), (3, 0.0)] {
+            race.insert(pid, &format!("p{}", pid), seconds);
+        }
+        let ranking = race.ranking(5);
+        assert_eq!(ranking.len(), 2);
+        assert_eq!(ranking[0].name, "p2");
+    }
+}
@@ -0,0 +1,339 @@
+// src/record.rs
+use crate::export::{CpuSnapshot, MemorySnapshot, ProcessSnapshot, Snapshot};
+use crate::AppError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Format version in each session header.
+const VERSION: u32 = 1;
+
+/// First line of every session in a recording. `--record` appends, so a
+/// file can hold several sessions, each starting from scratch.
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    rtop_session: u32,
+    hostname: Option<String>,
+}
+
+/// What a process is, written once when it is first seen or execs.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Started(u32, Option<u32>, String, String, Option<String>, u64);
+
+/// How a process is doing: pid, CPU%, resident and virtual memory, and
+/// its status if it changed.
+#[derive(Debug, Serialize, Deserialize)]
+struct Sample(u32, f32, u64, u64, Option<String>);
+
+/// One sample, holding only the processes that started, exited or changed
+/// since the previous frame.
+#[derive(Debug, Serialize, Deserialize)]
+struct Frame {
+    /// Milliseconds since the epoch.
+    t: i64,
+    uptime: u64,
+    load: [f64; 3],
+    cpu: CpuSnapshot,
+    memory: MemorySnapshot,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    new: Vec<Started>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    gone: Vec<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    changed: Vec<Sample>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Header(Header),
+    Frame(Frame),
+}
+
+fn started(process: &ProcessSnapshot) -> Started {
+    Started(
+        process.pid,
+        process.parent,
+        process.name.clone(),
+        process.command.clone(),
+        process.user.clone(),
+        process.start_time,
+    )
+}
+
+/// Tenths of a percent are all the UI shows, and round numbers keep the
+/// file small.
+fn round(usage: f32) -> f32 {
+    (usage * 10.0).round() / 10.0
+}
+
+/// Appends the samples of a session to a file for `--record`.
+pub struct Recorder {
+    out: BufWriter<File>,
+    interval: Duration,
+    last: Option<Instant>,
+    /// The processes as of the last frame, by PID.
+    previous: HashMap<u32, ProcessSnapshot>,
+}
+
+impl Recorder {
+    /// Opens `path` for appending, and records at most one frame per `interval`.
+    pub fn create(
+        path: &Path,
+        hostname: Option<String>,
+        interval: Duration,
+    ) -> io::Result<Recorder> {
+        let mut out = BufWriter::new(OpenOptions::new().create(true).append(true).open(path)?);
+        let header = Header {
+            rtop_session: VERSION,
+            hostname,
+        };
+        writeln!(out, "{}", serde_json::to_string(&header)?)?;
+        out.flush()?;
+        Ok(Recorder {
+            out,
+            interval,
+            last: None,
+            previous: HashMap::new(),
+        })
+    }
+
+    /// Whether the next frame is due.
+    pub fn due(&self) -> bool {
+        self.last.is_none_or(|last| last.elapsed() >= self.interval)
+    }
+
+    /// Writes `snapshot` as the next frame, flushed so that a crash or a
+    /// reboot loses nothing already recorded.
+    pub fn record(&mut self, snapshot: Snapshot) -> io::Result<()> {
+        let mut frame = Frame {
+            t: chrono::DateTime::parse_from_rfc3339(&snapshot.timestamp).map_or_else(
+                |_| chrono::Utc::now().timestamp_millis(),
+                |t| t.timestamp_millis(),
+            ),
+            uptime: snapshot.uptime,
+            load: snapshot.load_average,
+            cpu: CpuSnapshot {
+                usage: round(snapshot.cpu.usage),
+                cores: snapshot.cpu.cores.iter().copied().map(round).collect(),
+            },
+            memory: snapshot.memory,
+            new: Vec::new(),
+            gone: Vec::new(),
+            changed: Vec::new(),
+        };
+        let mut current = HashMap::new();
+        for mut process in snapshot.processes {
+            process.cpu_usage = round(process.cpu_usage);
+            let before = self.previous.remove(&process.pid);
+            let is_new = before
+                .as_ref()
+                .is_none_or(|before| started(before) != started(&process));
+            if is_new {
+                frame.new.push(started(&process));
+            }
+            let status_changed = before
+                .as_ref()
+                .is_none_or(|before| before.status != process.status);
+            let changed = is_new
+                || before.as_ref().is_some_and(|before| {
+                    status_changed
+                        || before.cpu_usage != process.cpu_usage
+                        || before.memory != process.memory
+                        || before.virtual_memory != process.virtual_memory
+                });
+            if changed {
+                frame.changed.push(Sample(
+                    process.pid,
+                    process.cpu_usage,
+                    process.memory,
+                    process.virtual_memory,
+                    status_changed.then(|| process.status.clone()),
+                ));
+            }
+            current.insert(process.pid, process);
+        }
+        frame.gone = self.previous.keys().copied().collect();
+        frame.gone.sort_unstable();
+        self.previous = current;
+        self.last = Some(Instant::now());
+        writeln!(self.out, "{}", serde_json::to_string(&frame)?)?;
+        self.out.flush()
+    }
+}
+
+/// Whether `content` looks like a recording rather than JSON snapshots.
+pub fn is_recording(content: &str) -> bool {
+    content.trim_start().starts_with("{\"rtop_session\"")
+}
+
+/// Reads every frame of a recording back as a full snapshot, oldest first.
+pub fn parse(content: &str) -> Result<Vec<Snapshot>, AppError> {
+    let mut snapshots = Vec::new();
+    let mut hostname = None;
+    let mut processes: HashMap<u32, ProcessSnapshot> = HashMap::new();
+    let lines: Vec<&str> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    for (index, line) in lines.iter().enumerate() {
+        let frame = match serde_json::from_str(line) {
+            Ok(Line::Header(header)) => {
+                hostname = header.hostname;
+                processes.clear();
+                continue;
+            }
+            Ok(Line::Frame(frame)) => frame,
+            // The last line of a recording cut short by a crash.
+            Err(_) if index + 1 == lines.len() => break,
+            Err(err) => return Err(err.into()),
+        };
+        for pid in frame.gone {
+            processes.remove(&pid);
+        }
+        for Started(pid, parent, name, command, user, start_time) in frame.new {
+            processes.insert(
+                pid,
+                ProcessSnapshot {
+                    pid,
+                    parent,
+                    name,
+                    command,
+                    user,
+                    status: String::new(),
+                    cpu_usage: 0.0,
+                    memory: 0,
+                    virtual_memory: 0,
+                    start_time,
+                },
+            );
+        }
+        for Sample(pid, cpu_usage, memory, virtual_memory, status) in frame.changed {
+            if let Some(process) = processes.get_mut(&pid) {
+                process.cpu_usage = cpu_usage;
+                process.memory = memory;
+                process.virtual_memory = virtual_memory;
+                if let Some(status) = status {
+                    process.status = status;
+                }
+            }
+        }
+        let timestamp = chrono::DateTime::from_timestamp_millis(frame.t)
+            .unwrap_or_default()
+            .with_timezone(&chrono::Local);
+        let mut list: Vec<ProcessSnapshot> = processes.values().cloned().collect();
+        list.sort_by_key(|process| process.pid);
+        snapshots.push(Snapshot {
+            timestamp: timestamp.to_rfc3339(),
+            hostname: hostname.clone(),
+            uptime: frame.uptime,
+            load_average: frame.load,
+            cpu: frame.cpu,
+            memory: frame.memory,
+            processes: list,
+        });
+    }
+    if snapshots.is_empty() {
+        return Err(
+            io::Error::new(io::ErrorKind::InvalidData, "no samples in the recording").into(),
+        );
+    }
+    Ok(snapshots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str, cpu_usage: f32, memory: u64) -> ProcessSnapshot {
+        ProcessSnapshot {
+            pid,
+            parent: Some(1),
+            name: name.to_string(),
+            command: format!("/usr/bin/{} --serve", name),
+            user: Some("www".to_string()),
+            status: "Sleeping".to_string(),
+            cpu_usage,
+            memory,
+            virtual_memory: 4 * memory,
+            start_time: 1_700_000_000,
+        }
+    }
+
+    fn snapshot(second: i64, processes: Vec<ProcessSnapshot>) -> Snapshot {
+        let at = chrono::DateTime::from_timestamp(1_700_000_000 + second, 0).unwrap();
+        Snapshot {
+            timestamp: at.to_rfc3339(),
+            hostname: Some("web1".to_string()),
+            uptime: 3600 + second as u64,
+            load_average: [0.5, 0.4, 0.3],
+            cpu: CpuSnapshot {
+                usage: 12.34,
+                cores: vec![10.0, 14.68],
+            },
+            memory: MemorySnapshot {
+                total: 8 << 30,
+                used: 2 << 30,
+                available: 6 << 30,
+                swap_total: 0,
+                swap_used: 0,
+            },
+            processes,
+        }
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let path = std::env::temp_dir().join(format!("rtop-record-{}.rtop", std::process::id()));
+        let mut recorder =
+            Recorder::create(&path, Some("web1".to_string()), Duration::ZERO).unwrap();
+        recorder
+            .record(snapshot(
+                0,
+                vec![
+                    process(10, "nginx", 1.04, 100),
+                    process(20, "cron", 0.0, 50),
+                ],
+            ))
+            .unwrap();
+        let mut exiting = process(10, "nginx", 1.0, 100);
+        exiting.status = "Zombie".to_string();
+        recorder
+            .record(snapshot(2, vec![exiting, process(20, "cron", 0.0, 50)]))
+            .unwrap();
+        recorder
+            .record(snapshot(
+                4,
+                vec![process(20, "cron", 0.0, 50), process(30, "sh", 80.0, 10)],
+            ))
+            .unwrap();
+        drop(recorder);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(is_recording(&content));
+        let lines: Vec<&str> = content.lines().collect();
+        // cron never changed after its first frame, so it is not repeated.
+        assert_eq!(lines.len(), 4);
+        assert!(!lines[2].contains("cron") && !lines[3].contains("cron"));
+
+        let snapshots = parse(&content).unwrap();
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!(snapshots[0].hostname.as_deref(), Some("web1"));
+        assert_eq!(snapshots[0].cpu.usage, 12.3);
+        assert_eq!(snapshots[0].processes[0].cpu_usage, 1.0);
+        assert_eq!(snapshots[0].processes[0].command, "/usr/bin/nginx --serve");
+        assert_eq!(snapshots[1].processes[0].status, "Zombie");
+        let pids: Vec<u32> = snapshots[2].processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![20, 30]);
+        assert_eq!(snapshots[2].processes[0].status, "Sleeping");
+        assert_eq!(snapshots[2].processes[1].cpu_usage, 80.0);
+
+        // A frame cut short by a crash is dropped.
+        let truncated = &content[..content.len() - 10];
+        assert_eq!(parse(truncated).unwrap().len(), 2);
+    }
+}
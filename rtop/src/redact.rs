@@ -0,0 +1,72 @@
+// src/redact.rs
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+/// Replaces process names, command lines, paths and user names with short
+/// hashes for `--redact`. The key is random per run, so the same name always
+/// maps to the same token within a session but tokens cannot be looked up
+/// by hashing well-known names.
+#[derive(Clone)]
+pub struct Redactor {
+    key: Option<RandomState>,
+}
+
+impl Redactor {
+    pub fn new(enabled: bool) -> Redactor {
+        Redactor {
+            key: enabled.then(RandomState::new),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.key.is_some()
+    }
+
+    fn token<'a>(&self, prefix: &str, value: &'a str) -> Cow<'a, str> {
+        match &self.key {
+            Some(key) if !value.is_empty() => {
+                Cow::Owned(format!("{}-{:08x}", prefix, key.hash_one(value) as u32))
+            }
+            _ => Cow::Borrowed(value),
+        }
+    }
+
+    pub fn process<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        self.token("proc", name)
+    }
+
+    pub fn command<'a>(&self, command: &'a str) -> Cow<'a, str> {
+        self.token("cmd", command)
+    }
+
+    pub fn path<'a>(&self, path: &'a str) -> Cow<'a, str> {
+        self.token("path", path)
+    }
+
+    pub fn host<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        self.token("host", name)
+    }
+
+    pub fn user<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        self.token("user", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redaction_is_stable_within_a_session() {
+        let redactor = Redactor::new(true);
+        let token = redactor.process("postgres");
+        assert!(token.starts_with("proc-"));
+        assert_ne!(token, "postgres");
+        assert_eq!(redactor.process("postgres"), token);
+        assert_ne!(redactor.process("nginx"), token);
+        assert_eq!(redactor.process(""), "");
+
+        assert_eq!(Redactor::new(false).process("postgres"), "postgres");
+    }
+}
@@ -0,0 +1,68 @@
+// src/repeat.rs
+use std::time::{Duration, Instant};
+
+/// Terminals report a held key as repeated presses, so a press that follows
+/// the previous one within this gap counts as the key still being held.
+const REPEAT_GAP: Duration = Duration::from_millis(150);
+
+/// Rows to move per press, by how long the key has been held.
+const STEPS: [(Duration, usize); 4] = [
+    (Duration::from_millis(500), 1),
+    (Duration::from_millis(1500), 2),
+    (Duration::from_millis(3000), 5),
+    (Duration::from_millis(5000), 10),
+];
+const MAX_STEP: usize = 25;
+
+/// Accelerates navigation while a key is held down.
+pub struct KeyRepeat<K> {
+    last: Option<(K, Instant)>,
+    held_since: Instant,
+}
+
+impl<K: PartialEq> KeyRepeat<K> {
+    pub fn new() -> KeyRepeat<K> {
+        KeyRepeat {
+            last: None,
+            held_since: Instant::now(),
+        }
+    }
+
+    /// Records a press of `key` at `now` and returns how many rows it moves.
+    pub fn step(&mut self, key: K, now: Instant) -> usize {
+        let held = matches!(&self.last, Some((last, at)) if *last == key && now.duration_since(*at) <= REPEAT_GAP);
+        if !held {
+            self.held_since = now;
+        }
+        self.last = Some((key, now));
+        let held_for = now.duration_since(self.held_since);
+        STEPS
+            .iter()
+            .find(|(limit, _)| held_for < *limit)
+            .map_or(MAX_STEP, |(_, step)| *step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_grows_while_held() {
+        let mut repeat = KeyRepeat::new();
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        assert_eq!(repeat.step('j', at(0)), 1);
+        // Held: presses every 30 ms.
+        let mut last = 1;
+        for ms in (30..=6000).step_by(30) {
+            last = repeat.step('j', at(ms));
+        }
+        assert_eq!(last, MAX_STEP);
+        // A different key or a pause starts over.
+        assert_eq!(repeat.step('k', at(6030)), 1);
+        assert_eq!(repeat.step('k', at(6060)), 1);
+        assert_eq!(repeat.step('k', at(7000)), 1);
+        assert_eq!(repeat.step('k', at(8600)), 1);
+    }
+}
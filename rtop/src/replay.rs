@@ -0,0 +1,228 @@
+// src/replay.rs
+use crate::export::Snapshot;
+use crate::history::Histories;
+use crate::view::Viewer;
+use clap::Args;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Playback speeds `+` and `-` step through, as multiples of real time.
+const SPEEDS: [u32; 9] = [1, 2, 5, 10, 30, 60, 120, 300, 600];
+
+/// Longest pause between two samples during playback, so the gap between
+/// two sessions of a file or a suspended machine is skipped quickly.
+const MAX_WAIT: Duration = Duration::from_secs(3);
+
+#[derive(Args, Debug)]
+pub struct ReplayArgs {
+    /// A file written by `rtop --record`, or anything `rtop view` opens
+    pub file: PathBuf,
+
+    /// Config file to use instead of ~/.config/rtop/config.toml
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+/// Playback of a `--record` session for `--replay`: which sample is shown,
+/// whether it advances by itself and how fast.
+pub struct Replay {
+    pub viewer: Viewer,
+    /// When each sample was taken, in milliseconds since the epoch.
+    times: Vec<i64>,
+    pub playing: bool,
+    speed: usize,
+    /// When the shown sample came up, to know when the next one is due.
+    shown_at: Instant,
+}
+
+impl Replay {
+    pub fn new(snapshots: Vec<Snapshot>) -> Replay {
+        let times = snapshots
+            .iter()
+            .enumerate()
+            .map(|(i, snapshot)| {
+                chrono::DateTime::parse_from_rfc3339(&snapshot.timestamp)
+                    .map_or(i as i64 * 1000, |time| time.timestamp_millis())
+            })
+            .collect();
+        Replay {
+            viewer: Viewer::new(snapshots),
+            times,
+            playing: false,
+            speed: 0,
+            shown_at: Instant::now(),
+        }
+    }
+
+    pub fn speed(&self) -> u32 {
+        SPEEDS[self.speed]
+    }
+
+    pub fn change_speed(&mut self, faster: bool) {
+        self.speed = if faster {
+            (self.speed + 1).min(SPEEDS.len() - 1)
+        } else {
+            self.speed.saturating_sub(1)
+        };
+    }
+
+    /// Starts or pauses playback; playing at the end starts over.
+    pub fn toggle(&mut self) {
+        self.playing = !self.playing;
+        if self.playing && self.viewer.index + 1 == self.times.len() {
+            self.go_to(0);
+        }
+        self.shown_at = Instant::now();
+    }
+
+    fn go_to(&mut self, index: usize) {
+        self.viewer.go_to(index);
+        self.shown_at = Instant::now();
+    }
+
+    /// Moves to the next or previous sample.
+    pub fn step(&mut self, forward: bool) {
+        let index = self.viewer.index;
+        self.go_to(if forward {
+            index + 1
+        } else {
+            index.saturating_sub(1)
+        });
+    }
+
+    /// Moves by `seconds` of recorded time, negative for back.
+    pub fn seek(&mut self, seconds: i64) {
+        let target = self.times[self.viewer.index] + seconds * 1000;
+        let index = if seconds >= 0 {
+            self.times.partition_point(|&time| time < target)
+        } else {
+            self.times
+                .partition_point(|&time| time <= target)
+                .saturating_sub(1)
+        };
+        self.go_to(index);
+    }
+
+    pub fn seek_to_end(&mut self, end: bool) {
+        self.go_to(if end { self.times.len() - 1 } else { 0 });
+    }
+
+    /// How long after `shown_at` the next sample is due while playing.
+    fn wait(&self) -> Option<Duration> {
+        let index = self.viewer.index;
+        let gap = self.times.get(index + 1)? - self.times[index];
+        let wait = Duration::from_millis(gap.max(0) as u64) / self.speed();
+        Some(wait.min(MAX_WAIT))
+    }
+
+    /// Time until the next sample is due, if playing.
+    pub fn next_due(&self, now: Instant) -> Option<Duration> {
+        let due = self.shown_at + self.wait()?;
+        self.playing.then(|| due.saturating_duration_since(now))
+    }
+
+    /// Advances playback to `now` and returns whether the sample changed.
+    /// Playback stops at the end of the recording.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        if !self.playing {
+            return false;
+        }
+        let start = self.viewer.index;
+        while let Some(wait) = self.wait() {
+            if now.duration_since(self.shown_at) < wait {
+                break;
+            }
+            self.shown_at += wait;
+            self.viewer.go_to(self.viewer.index + 1);
+        }
+        if self.wait().is_none() {
+            self.playing = false;
+        }
+        self.viewer.index != start
+    }
+
+    /// Recorded time from the first sample to the shown one and to the last.
+    pub fn position(&self) -> (Duration, Duration) {
+        let since_start = |time: i64| Duration::from_millis((time - self.times[0]).max(0) as u64);
+        (
+            since_start(self.times[self.viewer.index]),
+            since_start(self.times[self.times.len() - 1]),
+        )
+    }
+
+    /// The history charts as they looked at the shown sample: the samples
+    /// of the `window` before it.
+    pub fn histories(&self, window: Duration) -> Histories {
+        let mut histories = Histories::new(window);
+        let now = self.times[self.viewer.index];
+        let start = Instant::now();
+        for (snapshot, &time) in self
+            .viewer
+            .snapshots
+            .iter()
+            .zip(&self.times)
+            .take(self.viewer.index + 1)
+        {
+            if now - time <= window.as_millis() as i64 {
+                let offset =
+                    Duration::from_millis((time - (now - window.as_millis() as i64)).max(0) as u64);
+                histories.push(start + offset, snapshot);
+            }
+        }
+        histories
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recording(seconds: &[i64]) -> Vec<Snapshot> {
+        seconds
+            .iter()
+            .map(|second| {
+                let at = chrono::DateTime::from_timestamp(1_700_000_000 + second, 0).unwrap();
+                let json = format!(
+                    r#"{{"timestamp":"{}","hostname":"h","uptime":1,"load_average":[0.0,0.0,0.0],"cpu":{{"usage":{},"cores":[1.0]}},"memory":{{"total":100,"used":50,"available":50,"swap_total":0,"swap_used":0}},"processes":[]}}"#,
+                    at.to_rfc3339(),
+                    second
+                );
+                serde_json::from_str(&json).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_replay_playback() {
+        // Samples every 2 s, then a session started an hour later.
+        let mut replay = Replay::new(recording(&[0, 2, 4, 6, 3600]));
+        replay.seek(3);
+        assert_eq!(replay.viewer.index, 2);
+        replay.seek(-3);
+        assert_eq!(replay.viewer.index, 0);
+        replay.seek_to_end(true);
+        assert_eq!(
+            replay.position(),
+            (Duration::from_secs(3600), Duration::from_secs(3600))
+        );
+
+        // Playing at the end starts over; at 2x a 2 s gap takes 1 s.
+        replay.toggle();
+        replay.change_speed(true);
+        assert_eq!(replay.viewer.index, 0);
+        let start = replay.shown_at;
+        assert!(!replay.tick(start + Duration::from_millis(900)));
+        assert!(replay.tick(start + Duration::from_millis(2100)));
+        assert_eq!(replay.viewer.index, 2);
+        // The hour between the sessions is cut short.
+        assert!(replay.tick(start + Duration::from_millis(3000) + MAX_WAIT));
+        assert_eq!(replay.viewer.index, 4);
+        assert!(!replay.playing);
+
+        let histories = replay.histories(Duration::from_secs(60));
+        assert_eq!(histories.cpu.points(), vec![(0.0, 3600.0)]);
+        replay.step(false);
+        let points = replay.histories(Duration::from_secs(5)).cpu.points();
+        assert_eq!(points, vec![(-4.0, 2.0), (-2.0, 4.0), (0.0, 6.0)]);
+    }
+}
@@ -0,0 +1,99 @@
+// src/rotate.rs
+use crate::app::Tab;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+/// The `[rotate]` table: cycling through tabs on a timer.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RotateConfig {
+    /// Rotates without `--rotate`.
+    pub enabled: bool,
+    /// Tabs to cycle through, in order; all of them when empty.
+    pub tabs: Vec<Tab>,
+    pub seconds: u64,
+    /// Seconds after the last key or click before rotation goes on.
+    pub idle_seconds: u64,
+}
+
+impl Default for RotateConfig {
+    fn default() -> RotateConfig {
+        RotateConfig {
+            enabled: false,
+            tabs: Vec::new(),
+            seconds: 10,
+            idle_seconds: 30,
+        }
+    }
+}
+
+/// Switches tabs every `interval`, but not while someone is using rtop.
+pub struct Rotation {
+    tabs: Vec<Tab>,
+    interval: Duration,
+    idle: Duration,
+    switched: Instant,
+    interacted: Option<Instant>,
+}
+
+impl Rotation {
+    pub fn new(tabs: Vec<Tab>, interval: Duration, idle: Duration) -> Rotation {
+        Rotation {
+            tabs,
+            interval: interval.max(Duration::from_secs(1)),
+            idle,
+            switched: Instant::now(),
+            interacted: None,
+        }
+    }
+
+    /// Notes a key or click, which holds rotation for the idle time.
+    pub fn interact(&mut self, now: Instant) {
+        self.interacted = Some(now);
+    }
+
+    /// The tab to switch to from `current`, if it is time.
+    pub fn next(&mut self, now: Instant, current: Tab) -> Option<Tab> {
+        let idle_from = self.interacted.map(|at| at + self.idle);
+        if idle_from.is_some_and(|from| now < from) {
+            return None;
+        }
+        // A full interval once the user is done, not a switch right away.
+        let since = idle_from.map_or(self.switched, |from| from.max(self.switched));
+        if now.saturating_duration_since(since) < self.interval {
+            return None;
+        }
+        self.switched = now;
+        let next = match self.tabs.iter().position(|tab| *tab == current) {
+            Some(index) => self.tabs[(index + 1) % self.tabs.len()],
+            None => *self.tabs.first()?,
+        };
+        Some(next).filter(|tab| *tab != current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_waits_for_idle() {
+        let mut rotation = Rotation::new(
+            vec![Tab::Processes, Tab::Alerts],
+            Duration::from_secs(10),
+            Duration::from_secs(30),
+        );
+        let start = rotation.switched;
+        let at = |seconds| start + Duration::from_secs(seconds);
+        assert_eq!(rotation.next(at(5), Tab::Processes), None);
+        assert_eq!(rotation.next(at(10), Tab::Processes), Some(Tab::Alerts));
+        assert_eq!(rotation.next(at(20), Tab::Alerts), Some(Tab::Processes));
+        // A tab outside the list goes back to its start.
+        assert_eq!(rotation.next(at(30), Tab::Disks), Some(Tab::Processes));
+
+        rotation.interact(at(31));
+        assert_eq!(rotation.next(at(50), Tab::Processes), None);
+        assert_eq!(rotation.next(at(65), Tab::Processes), None);
+        assert_eq!(rotation.next(at(75), Tab::Processes), Some(Tab::Alerts));
+    }
+}
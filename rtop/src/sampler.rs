@@ -0,0 +1,259 @@
+// src/sampler.rs
+use crate::collectors::background::{self, CollectorsConfig};
+use crate::collectors::diskio::ProcessIo;
+use crate::collectors::netio::ProcessNet;
+use crate::details::{self, ProcessStatus};
+use crate::{anomaly, dstate, emulation};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessExt, System, SystemExt};
+
+/// What the UI needs read per process besides what sysinfo reads; it
+/// depends on the columns shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Wants {
+    pub network: bool,
+    pub statuses: bool,
+    pub wchans: bool,
+    /// `anomaly_z` of the thresholds; 0 turns anomaly detection off.
+    pub anomaly_z: f64,
+}
+
+/// Per-process figures rtop reads itself, from `/proc` and sock_diag.
+#[derive(Default)]
+pub struct ProcessReads {
+    pub(crate) disk_io: ProcessIo,
+    pub(crate) net_io: ProcessNet,
+    /// `/proc/PID/status` of every process, read while a column needs it.
+    pub(crate) statuses: HashMap<Pid, ProcessStatus>,
+    /// Kernel functions processes sleep in, read while the WCHAN column is shown.
+    pub(crate) wchans: HashMap<Pid, String>,
+    /// Processes running under Rosetta, qemu-user and the like.
+    pub(crate) emulated: HashMap<Pid, emulation::Emulator>,
+    /// Processes using far more CPU or memory than they recently did.
+    pub(crate) anomalies: anomaly::Anomalies,
+}
+
+impl ProcessReads {
+    /// Reads what `wants` asks for, for every process of `system`.
+    pub fn refresh(&mut self, system: &System, wants: Wants) {
+        self.disk_io.refresh(system);
+        if wants.network {
+            self.net_io.refresh(system);
+        }
+        if wants.statuses {
+            self.statuses = system
+                .processes()
+                .keys()
+                .map(|&pid| (pid, details::read_status(pid)))
+                .collect();
+        }
+        if wants.wchans {
+            self.wchans = system
+                .processes()
+                .keys()
+                .filter_map(|&pid| Some((pid, dstate::wchan(pid)?)))
+                .collect();
+        }
+        if wants.anomaly_z > 0.0 {
+            let samples = system
+                .processes()
+                .iter()
+                .map(|(&pid, process)| (pid, process.cpu_usage() as f64, process.memory() as f64));
+            self.anomalies.record(samples, wants.anomaly_z);
+        }
+        self.emulated = system
+            .processes()
+            .iter()
+            .filter_map(|(&pid, process)| Some((pid, emulation::detect(pid, process.exe())?)))
+            .collect();
+    }
+
+    /// What the UI shows of these reads; the history anomalies are judged
+    /// against stays here.
+    fn share(&self) -> ProcessReads {
+        ProcessReads {
+            disk_io: self.disk_io.clone(),
+            net_io: self.net_io.clone(),
+            statuses: self.statuses.clone(),
+            wchans: self.wchans.clone(),
+            emulated: self.emulated.clone(),
+            anomalies: self.anomalies.flags(),
+        }
+    }
+}
+
+/// What the sampler refreshes of a `System`, each at its own interval.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Part {
+    Cpu,
+    Memory,
+    Processes,
+    /// Space used, for the health checks; at the `filesystems` interval.
+    Disks,
+}
+
+impl Part {
+    fn refresh(self, system: &mut System) {
+        match self {
+            Part::Cpu => system.refresh_cpu(),
+            Part::Memory => system.refresh_memory(),
+            Part::Processes => system.refresh_processes(),
+            Part::Disks => system.refresh_disks(),
+        }
+    }
+}
+
+/// A refreshed `System` and what was read per process along with it.
+pub struct Sample {
+    pub system: System,
+    pub(crate) processes: ProcessReads,
+}
+
+/// Refreshes a `System`, and reads per process what sysinfo does not, on a
+/// thread of its own, so a slow `/proc` scan never holds up drawing or keys.
+///
+/// CPU, memory, processes and disk space are each refreshed at their
+/// interval from `[collectors]`, and a sample goes out whenever one was.
+///
+/// `System` cannot be cloned, so two of them take turns: the thread
+/// refreshes one while the UI shows the other, and gets that one back in
+/// exchange for the next sample. It refreshes the one it gets back right
+/// away as well, so both stay current and CPU figures, which sysinfo takes
+/// from the change since a `System` was last refreshed, cover exactly one
+/// interval.
+pub struct Sampler {
+    samples: Receiver<Sample>,
+    returned: Sender<System>,
+    wants: Arc<Mutex<Wants>>,
+}
+
+impl Sampler {
+    pub fn start(config: &CollectorsConfig, refresh_rate: Duration) -> Sampler {
+        let (sender, samples) = mpsc::channel();
+        let (returned, spare) = mpsc::channel::<System>();
+        let wants = Arc::new(Mutex::new(Wants::default()));
+        let thread_wants = wants.clone();
+        let start = Instant::now();
+        let mut schedule = [
+            (Part::Cpu, config.cpu),
+            (Part::Memory, config.memory),
+            (Part::Processes, config.processes),
+            (Part::Disks, config.filesystems),
+        ]
+        .map(|(part, ms)| {
+            let interval = background::interval(ms, refresh_rate);
+            (part, interval, start + interval)
+        });
+        std::thread::spawn(move || {
+            let mut system = System::new_all();
+            let mut processes = ProcessReads::default();
+            loop {
+                let next = schedule
+                    .iter()
+                    .map(|(_, _, due)| *due)
+                    .min()
+                    .unwrap_or(start);
+                std::thread::sleep(next.saturating_duration_since(Instant::now()));
+                let now = Instant::now();
+                let mut due = Vec::new();
+                for (part, interval, at) in &mut schedule {
+                    if *at <= now {
+                        due.push(*part);
+                        *at = now + *interval;
+                    }
+                }
+                for part in &due {
+                    part.refresh(&mut system);
+                }
+                if due.contains(&Part::Processes) {
+                    let wants = thread_wants
+                        .lock()
+                        .map_or_else(|_| Wants::default(), |wants| *wants);
+                    processes.refresh(&system, wants);
+                }
+                let sample = Sample {
+                    system,
+                    processes: processes.share(),
+                };
+                // The receivers are gone once rtop quits.
+                if sender.send(sample).is_err() {
+                    return;
+                }
+                system = match spare.recv() {
+                    Ok(system) => system,
+                    Err(_) => return,
+                };
+                // It missed what the sample just got.
+                for part in &due {
+                    part.refresh(&mut system);
+                }
+            }
+        });
+        Sampler {
+            samples,
+            returned,
+            wants,
+        }
+    }
+
+    /// Sets what the next samples read per process.
+    pub fn want(&self, wants: Wants) {
+        if let Ok(mut current) = self.wants.lock() {
+            *current = wants;
+        }
+    }
+
+    /// The sample that arrived since the last call, if any.
+    pub fn poll(&self) -> Option<Sample> {
+        self.samples.try_recv().ok()
+    }
+
+    /// Gives back a `System` to be refreshed for the next sample: the one
+    /// the new sample replaced, or the sample's own if it was not used.
+    pub fn give_back(&self, system: System) {
+        // Only fails once the thread is gone, and then nothing is sampled.
+        let _ = self.returned.send(system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_sampler_takes_turns() {
+        let config = CollectorsConfig {
+            memory: Some(60_000),
+            ..CollectorsConfig::default()
+        };
+        let sampler = Sampler::start(&config, Duration::from_millis(10));
+        sampler.want(Wants {
+            statuses: true,
+            ..Wants::default()
+        });
+        let next = || {
+            let start = Instant::now();
+            loop {
+                if let Some(sample) = sampler.poll() {
+                    return sample;
+                }
+                assert!(start.elapsed() < Duration::from_secs(10));
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        };
+        let first = next();
+        assert!(!first.system.processes().is_empty());
+        // Nothing more comes until a `System` is given back.
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(sampler.poll().is_none());
+        sampler.give_back(System::new());
+        let second = next();
+        assert!(!second.system.processes().is_empty());
+        let own = Pid::from(std::process::id() as usize);
+        assert!(second.processes.statuses.contains_key(&own));
+    }
+}
@@ -0,0 +1,92 @@
+// src/shade.rs
+use crate::theme::gradient;
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::Color;
+use tui::widgets::Widget;
+
+/// A gauge drawn in `fill` whose bar is shaded along a theme's gradient
+/// instead, so the color of each cell shows how far along the track it is.
+pub struct Shade<W> {
+    widget: W,
+    fill: Color,
+    stops: Option<Vec<Color>>,
+    left: u16,
+    right: u16,
+}
+
+impl<W: Widget> Shade<W> {
+    /// Shades `widget` along `stops`; without stops it is drawn as it is.
+    pub fn new(widget: W, fill: Color, stops: Option<Vec<Color>>) -> Shade<W> {
+        Shade {
+            widget,
+            fill,
+            stops,
+            left: 0,
+            right: 0,
+        }
+    }
+
+    /// Columns on either side that are not part of the track, such as
+    /// borders or a label.
+    pub fn inset(mut self, left: u16, right: u16) -> Shade<W> {
+        self.left = left;
+        self.right = right;
+        self
+    }
+}
+
+impl<W: Widget> Widget for Shade<W> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.widget.render(area, buf);
+        let Some(stops) = self.stops.filter(|stops| !stops.is_empty()) else {
+            return;
+        };
+        let start = area.left() + self.left.min(area.width);
+        let end = area.right().saturating_sub(self.right).max(start);
+        let length = (end - start).saturating_sub(1).max(1) as f64;
+        for x in start..end {
+            let color = gradient(&stops, (x - start) as f64 / length);
+            for y in area.top()..area.bottom() {
+                let cell = buf.get_mut(x, y);
+                if cell.fg == self.fill {
+                    cell.fg = color;
+                }
+                if cell.bg == self.fill {
+                    cell.bg = color;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::widgets::{Block, Borders, Gauge};
+
+    #[test]
+    fn test_shaded_gauge() {
+        let (green, red) = (Color::Rgb(0, 255, 0), Color::Rgb(255, 0, 0));
+        let area = Rect::new(0, 0, 12, 3);
+        let gauge = || {
+            Gauge::default()
+                .block(Block::default().borders(Borders::ALL))
+                .gauge_style(tui::style::Style::default().fg(Color::Yellow))
+                .ratio(0.5)
+        };
+        let mut buf = Buffer::empty(area);
+        Shade::new(gauge(), Color::Yellow, Some(vec![green, red]))
+            .inset(1, 1)
+            .render(area, &mut buf);
+        // The bar starts green and is under half way to red where it ends.
+        assert_eq!(buf.get(1, 1).bg, green);
+        assert_eq!(buf.get(5, 1).bg, Color::Rgb(113, 142, 0));
+        assert_eq!(buf.get(6, 1).bg, Color::Reset);
+        assert_eq!(buf.get(0, 0).fg, Color::Reset);
+
+        let mut plain = Buffer::empty(area);
+        Shade::new(gauge(), Color::Yellow, None).render(area, &mut plain);
+        assert_eq!(plain.get(1, 1).bg, Color::Yellow);
+    }
+}
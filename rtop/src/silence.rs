@@ -0,0 +1,181 @@
+// src/silence.rs
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, Weekday};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A recurring period in which matching alert rules stay quiet, from the
+/// config's `[[maintenance]]` tables.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MaintenanceWindow {
+    /// Rule names; a trailing `*` matches a prefix (`disk:*`). Empty means all rules.
+    #[serde(default)]
+    pub rules: Vec<String>,
+    /// Days the window starts on (`mon` to `sun`); empty means every day.
+    #[serde(default)]
+    pub days: Vec<String>,
+    /// Local start and end time, `HH:MM`. A window that ends before it
+    /// starts runs past midnight.
+    pub start: String,
+    pub end: String,
+}
+
+impl MaintenanceWindow {
+    /// Checks the times and day names, for config errors at startup.
+    pub fn validate(&self) -> Result<(), String> {
+        self.times()?;
+        for day in &self.days {
+            day.parse::<Weekday>()
+                .map_err(|_| format!("maintenance: unknown day '{}'", day))?;
+        }
+        Ok(())
+    }
+
+    fn times(&self) -> Result<(NaiveTime, NaiveTime), String> {
+        let time = |text: &str| {
+            NaiveTime::parse_from_str(text, "%H:%M")
+                .map_err(|_| format!("maintenance: '{}' is not a time like 02:30", text))
+        };
+        Ok((time(&self.start)?, time(&self.end)?))
+    }
+
+    fn starts_on(&self, day: Weekday) -> bool {
+        self.days.is_empty()
+            || self
+                .days
+                .iter()
+                .any(|name| name.parse::<Weekday>() == Ok(day))
+    }
+
+    fn covers(&self, rule: &str) -> bool {
+        self.rules.is_empty()
+            || self
+                .rules
+                .iter()
+                .any(|pattern| match pattern.strip_suffix('*') {
+                    Some(prefix) => rule.starts_with(prefix),
+                    None => rule == pattern,
+                })
+    }
+
+    fn is_open(&self, now: DateTime<Local>) -> bool {
+        let Ok((start, end)) = self.times() else {
+            return false;
+        };
+        let time = now.time();
+        if start <= end {
+            self.starts_on(now.weekday()) && time >= start && time < end
+        } else {
+            (self.starts_on(now.weekday()) && time >= start)
+                || (self.starts_on(now.weekday().pred()) && time < end)
+        }
+    }
+}
+
+/// Why an alert rule is quiet.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Silenced {
+    /// Silenced by hand until then.
+    Until(DateTime<Local>),
+    /// Inside a maintenance window, shown as `start-end`.
+    Maintenance(String),
+}
+
+impl Silenced {
+    pub fn describe(&self) -> String {
+        match self {
+            Silenced::Until(until) => format!("silenced until {}", until.format("%H:%M")),
+            Silenced::Maintenance(window) => format!("maintenance {}", window),
+        }
+    }
+}
+
+/// Alert rules silenced by hand, plus the configured maintenance windows.
+#[derive(Default)]
+pub struct Silences {
+    windows: Vec<MaintenanceWindow>,
+    until: HashMap<String, DateTime<Local>>,
+}
+
+impl Silences {
+    pub fn new(windows: Vec<MaintenanceWindow>) -> Silences {
+        Silences {
+            windows,
+            until: HashMap::new(),
+        }
+    }
+
+    /// Silences `rule` for `duration`, or lifts a silence still running.
+    /// Returns whether the rule is now silenced.
+    pub fn toggle(&mut self, rule: &str, duration: Duration, now: DateTime<Local>) -> bool {
+        if self.until.get(rule).is_some_and(|until| *until > now) {
+            self.until.remove(rule);
+            return false;
+        }
+        self.until.insert(rule.to_string(), now + duration);
+        true
+    }
+
+    pub fn status(&self, rule: &str, now: DateTime<Local>) -> Option<Silenced> {
+        if let Some(until) = self.until.get(rule).filter(|until| **until > now) {
+            return Some(Silenced::Until(*until));
+        }
+        self.windows
+            .iter()
+            .find(|window| window.covers(rule) && window.is_open(now))
+            .map(|window| Silenced::Maintenance(format!("{}-{}", window.start, window.end)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_maintenance_window() {
+        // Saturday 23:30 to Sunday 01:00, for disks only.
+        let window = MaintenanceWindow {
+            rules: vec!["disk:*".to_string()],
+            days: vec!["sat".to_string()],
+            start: "23:30".to_string(),
+            end: "01:00".to_string(),
+        };
+        assert!(window.validate().is_ok());
+        let silences = Silences::new(vec![window]);
+        let at = |day, hour, minute| {
+            Local
+                .with_ymd_and_hms(2024, 6, day, hour, minute, 0)
+                .unwrap()
+        };
+        // 2024-06-01 is a Saturday.
+        assert!(silences.status("disk:/", at(1, 23, 45)).is_some());
+        assert!(silences.status("disk:/", at(2, 0, 30)).is_some());
+        assert!(silences.status("disk:/", at(2, 1, 0)).is_none());
+        assert!(silences.status("disk:/", at(1, 0, 30)).is_none());
+        assert!(silences.status("memory", at(1, 23, 45)).is_none());
+
+        let bad = MaintenanceWindow {
+            rules: Vec::new(),
+            days: vec!["someday".to_string()],
+            start: "2am".to_string(),
+            end: "03:00".to_string(),
+        };
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_manual_silence() {
+        let mut silences = Silences::default();
+        let now = Local.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        assert!(silences.toggle("cpu", Duration::minutes(30), now));
+        assert_eq!(
+            silences.status("cpu", now + Duration::minutes(10)),
+            Some(Silenced::Until(now + Duration::minutes(30)))
+        );
+        assert_eq!(silences.status("cpu", now + Duration::minutes(30)), None);
+        // Pressing again while silenced lifts it.
+        assert!(!silences.toggle("cpu", Duration::minutes(30), now));
+        assert_eq!(silences.status("cpu", now), None);
+    }
+}
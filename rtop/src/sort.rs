@@ -0,0 +1,160 @@
+// src/sort.rs
+use crate::config::Column;
+use crate::App;
+use std::cmp::Ordering;
+use sysinfo::{Pid, PidExt, Process, ProcessExt};
+
+/// What a comparator orders processes by.
+#[derive(Clone, Debug)]
+pub enum Key {
+    Number(f64),
+    Text(String),
+}
+
+impl Ord for Key {
+    /// Numbers come before text, so a key that is sometimes missing can
+    /// be given as text (`""`) and sorts last.
+    fn cmp(&self, other: &Key) -> Ordering {
+        match (self, other) {
+            (Key::Number(a), Key::Number(b)) => a.total_cmp(b),
+            (Key::Text(a), Key::Text(b)) => a.cmp(b),
+            (Key::Number(_), Key::Text(_)) => Ordering::Less,
+            (Key::Text(_), Key::Number(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Key) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Key) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Key {}
+
+type KeyFn = Box<dyn Fn(&App, Pid, &Process) -> Key>;
+
+/// A named order of the process table, for `--sort`, the sort keys and
+/// clicks on the header of `column`.
+pub struct Comparator {
+    pub name: String,
+    pub column: Option<Column>,
+    /// Biggest first in the natural order, as for usage.
+    pub descending: bool,
+    key: KeyFn,
+}
+
+/// The orders the process table and exports can be sorted in. Besides
+/// the built-in ones, anything that can compute a key per process can
+/// register one.
+pub struct Comparators {
+    comparators: Vec<Comparator>,
+}
+
+impl Comparators {
+    /// The orders of the `SortBy` keys.
+    pub fn builtin() -> Comparators {
+        let mut comparators = Comparators {
+            comparators: Vec::new(),
+        };
+        comparators.register("cpu", Some(Column::Cpu), true, |_, _, process| {
+            Key::Number(process.cpu_usage() as f64)
+        });
+        comparators.register("memory", Some(Column::Rss), true, |_, _, process| {
+            Key::Number(process.memory() as f64)
+        });
+        comparators.register("name", Some(Column::Name), false, |_, _, process| {
+            Key::Text(process.name().to_string())
+        });
+        comparators.register("pid", Some(Column::Pid), false, |_, pid, _| {
+            Key::Number(pid.as_u32() as f64)
+        });
+        comparators.register("disk_read", Some(Column::DiskRead), true, |app, pid, _| {
+            Key::Number(app.per_process.disk_io.rate(pid).read)
+        });
+        comparators.register(
+            "disk_write",
+            Some(Column::DiskWrite),
+            true,
+            |app, pid, _| Key::Number(app.per_process.disk_io.rate(pid).write),
+        );
+        comparators
+    }
+
+    /// Adds an order, replacing any of the same name.
+    pub fn register(
+        &mut self,
+        name: &str,
+        column: Option<Column>,
+        descending: bool,
+        key: impl Fn(&App, Pid, &Process) -> Key + 'static,
+    ) {
+        self.comparators
+            .retain(|comparator| comparator.name != name);
+        self.comparators.push(Comparator {
+            name: name.to_string(),
+            column,
+            descending,
+            key: Box::new(key),
+        });
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Comparator> {
+        self.comparators
+            .iter()
+            .find(|comparator| comparator.name == name)
+    }
+
+    /// The order a click on the header of `column` selects, if any.
+    pub fn of_column(&self, column: Column) -> Option<&Comparator> {
+        self.comparators
+            .iter()
+            .find(|comparator| comparator.column == Some(column))
+    }
+
+    /// Sorts `processes` of `app` in the order `name`, inverted if
+    /// `reversed`. Unknown orders leave them as they are.
+    pub fn sort(&self, app: &App, name: &str, reversed: bool, processes: &mut [(Pid, &Process)]) {
+        let Some(comparator) = self.get(name) else {
+            return;
+        };
+        // Keys are computed once per process, as registered ones may be
+        // expensive.
+        processes.sort_by_cached_key(|(pid, process)| (comparator.key)(app, *pid, process));
+        if comparator.descending != reversed {
+            processes.reverse();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_registered_comparator() {
+        let mut app = App::new(Config::default());
+        app.comparators
+            .register("name_length", None, true, |_, _, process| {
+                Key::Number(process.name().len() as f64)
+            });
+        app.sort("name_length");
+        let lengths: Vec<usize> = app
+            .get_sorted_processes()
+            .iter()
+            .map(|(_, process)| process.name().len())
+            .collect();
+        assert!(lengths.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert!(app.comparators.of_column(Column::Rss).is_some());
+
+        assert!(Key::Number(2.0) > Key::Number(-1.0));
+        assert!(Key::Number(f64::MAX) < Key::Text(String::new()));
+    }
+}
@@ -0,0 +1,126 @@
+// src/source.rs
+use crate::export::{MemorySnapshot, Snapshot};
+use crate::summary::TaskCounts;
+use sysinfo::{CpuExt, ProcessExt, ProcessStatus, System, SystemExt};
+
+/// Where the system-wide figures of the header (summary line, gauges,
+/// per-core bars, history charts) come from: the running system, or a
+/// sample of a `--record` session being replayed.
+pub trait Source {
+    fn uptime(&self) -> u64;
+    fn load_average(&self) -> [f64; 3];
+    /// Total CPU usage, in percent.
+    fn cpu_usage(&self) -> f32;
+    /// Usage of each core, in percent.
+    fn cores(&self) -> Vec<f32>;
+    fn memory(&self) -> MemorySnapshot;
+    fn task_counts(&self) -> TaskCounts;
+
+    /// Used memory, in percent of the total.
+    fn memory_percent(&self) -> f64 {
+        let memory = self.memory();
+        memory.used as f64 / memory.total.max(1) as f64 * 100.0
+    }
+
+    /// Used swap, in percent of the total (0 without swap).
+    fn swap_percent(&self) -> f64 {
+        let memory = self.memory();
+        memory.swap_used as f64 / memory.swap_total.max(1) as f64 * 100.0
+    }
+}
+
+impl Source for System {
+    fn uptime(&self) -> u64 {
+        SystemExt::uptime(self)
+    }
+
+    fn load_average(&self) -> [f64; 3] {
+        let load = SystemExt::load_average(self);
+        [load.one, load.five, load.fifteen]
+    }
+
+    fn cpu_usage(&self) -> f32 {
+        self.global_cpu_info().cpu_usage()
+    }
+
+    fn cores(&self) -> Vec<f32> {
+        self.cpus().iter().map(|cpu| cpu.cpu_usage()).collect()
+    }
+
+    fn memory(&self) -> MemorySnapshot {
+        MemorySnapshot {
+            total: self.total_memory(),
+            used: self.used_memory(),
+            available: self.available_memory(),
+            swap_total: self.total_swap(),
+            swap_used: self.used_swap(),
+        }
+    }
+
+    fn task_counts(&self) -> TaskCounts {
+        TaskCounts::count(self.processes().values().map(|process| process.status()))
+    }
+}
+
+impl Source for Snapshot {
+    fn uptime(&self) -> u64 {
+        self.uptime
+    }
+
+    fn load_average(&self) -> [f64; 3] {
+        self.load_average
+    }
+
+    fn cpu_usage(&self) -> f32 {
+        self.cpu.usage
+    }
+
+    fn cores(&self) -> Vec<f32> {
+        self.cpu.cores.clone()
+    }
+
+    fn memory(&self) -> MemorySnapshot {
+        self.memory
+    }
+
+    fn task_counts(&self) -> TaskCounts {
+        TaskCounts::count(self.processes.iter().map(|process| status(&process.status)))
+    }
+}
+
+/// The status a snapshot wrote with sysinfo's `Display`, read back.
+fn status(name: &str) -> ProcessStatus {
+    match name {
+        "Runnable" => ProcessStatus::Run,
+        "Sleeping" => ProcessStatus::Sleep,
+        "Idle" => ProcessStatus::Idle,
+        "UninterruptibleDiskSleep" => ProcessStatus::UninterruptibleDiskSleep,
+        "Stopped" => ProcessStatus::Stop,
+        "Tracing" => ProcessStatus::Tracing,
+        "Zombie" => ProcessStatus::Zombie,
+        "Dead" => ProcessStatus::Dead,
+        "Wakekill" => ProcessStatus::Wakekill,
+        "Waking" => ProcessStatus::Waking,
+        "Parked" => ProcessStatus::Parked,
+        _ => ProcessStatus::Unknown(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::App;
+
+    #[test]
+    fn test_snapshot_matches_live_source() {
+        let app = App::new(Config::default());
+        let snapshot = Snapshot::capture(&app, None);
+        let live: &dyn Source = &app.system;
+        let recorded: &dyn Source = &snapshot;
+        assert_eq!(recorded.task_counts(), live.task_counts());
+        assert_eq!(recorded.cores().len(), live.cores().len());
+        assert_eq!(recorded.memory().total, live.memory().total);
+        assert!((0.0..=100.0).contains(&recorded.memory_percent()));
+    }
+}
@@ -0,0 +1,81 @@
+// src/sparkline.rs
+use std::collections::{HashMap, VecDeque};
+use sysinfo::Pid;
+
+/// Samples kept per process, one character each in the CPU Trend column.
+pub const WIDTH: usize = 10;
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The last `WIDTH` CPU usage samples of every process, oldest first.
+#[derive(Default)]
+pub struct CpuTrends {
+    samples: HashMap<Pid, VecDeque<f32>>,
+}
+
+impl CpuTrends {
+    pub fn record(&mut self, usage: impl Iterator<Item = (Pid, f32)>) {
+        let mut samples = HashMap::new();
+        for (pid, cpu_usage) in usage {
+            let mut window = self.samples.remove(&pid).unwrap_or_default();
+            if window.len() == WIDTH {
+                window.pop_front();
+            }
+            window.push_back(cpu_usage);
+            samples.insert(pid, window);
+        }
+        // Exited processes drop out.
+        self.samples = samples;
+    }
+
+    pub fn samples(&self, pid: Pid) -> Vec<f32> {
+        self.samples
+            .get(&pid)
+            .map_or_else(Vec::new, |window| window.iter().copied().collect())
+    }
+
+    /// `pid`'s samples as a sparkline `WIDTH` characters wide, newest on
+    /// the right.
+    pub fn sparkline(&self, pid: Pid) -> String {
+        sparkline(&self.samples(pid))
+    }
+}
+
+/// Bars scaled to one full core, or to the peak of `samples` if a
+/// multi-threaded process went above that; idle samples are blank.
+fn sparkline(samples: &[f32]) -> String {
+    let scale = samples.iter().copied().fold(100.0, f32::max);
+    let bars = samples.iter().map(|&usage| {
+        if usage < 0.05 {
+            ' '
+        } else {
+            let level = (usage / scale * BARS.len() as f32).ceil() as usize;
+            BARS[level.clamp(1, BARS.len()) - 1]
+        }
+    });
+    let padding = WIDTH.saturating_sub(samples.len());
+    std::iter::repeat_n(' ', padding).chain(bars).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_trends() {
+        let (spiky, steady) = (Pid::from(1), Pid::from(2));
+        let mut trends = CpuTrends::default();
+        for i in 0..WIDTH + 2 {
+            let spike = if i % 3 == 0 { 100.0 } else { 0.0 };
+            trends.record([(spiky, spike), (steady, 50.0)].into_iter());
+        }
+        assert_eq!(trends.samples(spiky).len(), WIDTH);
+        assert_eq!(trends.sparkline(spiky), " █  █  █  ");
+        assert_eq!(trends.sparkline(steady), "▄".repeat(WIDTH));
+        // Above one core, bars are relative to the peak.
+        assert_eq!(sparkline(&[400.0, 100.0]), "        █▂");
+
+        trends.record([(steady, 50.0)].into_iter());
+        assert_eq!(trends.sparkline(spiky), " ".repeat(WIDTH));
+    }
+}
@@ -0,0 +1,128 @@
+// src/summary.rs
+use sysinfo::ProcessStatus;
+
+/// Processes by state, as in the "Tasks:" line of top.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TaskCounts {
+    pub total: usize,
+    pub running: usize,
+    pub sleeping: usize,
+    pub stopped: usize,
+    pub zombie: usize,
+}
+
+impl TaskCounts {
+    pub fn count(statuses: impl Iterator<Item = ProcessStatus>) -> TaskCounts {
+        let mut counts = TaskCounts::default();
+        for status in statuses {
+            counts.total += 1;
+            match status {
+                ProcessStatus::Run => counts.running += 1,
+                ProcessStatus::Sleep
+                | ProcessStatus::Idle
+                | ProcessStatus::UninterruptibleDiskSleep => counts.sleeping += 1,
+                ProcessStatus::Stop | ProcessStatus::Tracing => counts.stopped += 1,
+                ProcessStatus::Zombie => counts.zombie += 1,
+                _ => {}
+            }
+        }
+        counts
+    }
+}
+
+/// The one-letter state ps and top show for `status`.
+pub fn state_letter(status: ProcessStatus) -> char {
+    match status {
+        ProcessStatus::Run => 'R',
+        ProcessStatus::Sleep => 'S',
+        ProcessStatus::Idle => 'I',
+        ProcessStatus::UninterruptibleDiskSleep => 'D',
+        ProcessStatus::Stop => 'T',
+        ProcessStatus::Tracing => 't',
+        ProcessStatus::Zombie => 'Z',
+        ProcessStatus::Dead => 'X',
+        ProcessStatus::Wakekill => 'K',
+        ProcessStatus::Waking => 'W',
+        ProcessStatus::Parked => 'P',
+        ProcessStatus::LockBlocked => 'L',
+        ProcessStatus::Unknown(_) => '?',
+    }
+}
+
+/// Uptime the way uptime(1) prints it: `3 days, 4:05` or `17 min`.
+pub fn format_uptime(seconds: u64) -> String {
+    let days = seconds / 86400;
+    let hours = seconds % 86400 / 3600;
+    let minutes = seconds % 3600 / 60;
+    let time = if hours == 0 {
+        format!("{} min", minutes)
+    } else {
+        format!("{}:{:02}", hours, minutes)
+    };
+    match days {
+        0 => time,
+        1 => format!("1 day, {}", time),
+        _ => format!("{} days, {}", days, time),
+    }
+}
+
+/// Login sessions recorded in utmp, the "users" count of top and uptime(1).
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn logged_in_users() -> Option<usize> {
+    let mut users = 0;
+    // SAFETY: the utmpx functions are only used from the UI thread, and each
+    // entry is read before the next getutxent call reuses its buffer.
+    unsafe {
+        libc::setutxent();
+        loop {
+            let entry = libc::getutxent();
+            if entry.is_null() {
+                break;
+            }
+            if (*entry).ut_type == libc::USER_PROCESS {
+                users += 1;
+            }
+        }
+        libc::endutxent();
+    }
+    Some(users)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn logged_in_users() -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(format_uptime(17 * 60 + 5), "17 min");
+        assert_eq!(format_uptime(4 * 3600 + 5 * 60), "4:05");
+        assert_eq!(format_uptime(86400 + 60), "1 day, 1 min");
+        assert_eq!(format_uptime(3 * 86400 + 4 * 3600 + 5 * 60), "3 days, 4:05");
+
+        let counts = TaskCounts::count(
+            [
+                ProcessStatus::Run,
+                ProcessStatus::Sleep,
+                ProcessStatus::Idle,
+                ProcessStatus::Zombie,
+                ProcessStatus::Stop,
+            ]
+            .into_iter(),
+        );
+        assert_eq!(
+            counts,
+            TaskCounts {
+                total: 5,
+                running: 1,
+                sleeping: 2,
+                stopped: 1,
+                zombie: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,60 @@
+// src/text.rs
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Shortens `text` to at most `width` terminal columns, ending in `…` when
+/// it is cut. Wide (CJK) characters take two columns, combining marks stay
+/// with the character they modify, and control characters become `?` so a
+/// process name cannot move the cursor.
+pub fn truncate(text: &str, width: usize) -> String {
+    let text: String = text
+        .chars()
+        .map(|c| if c.is_control() { '?' } else { c })
+        .collect();
+    if text.width() <= width {
+        return text;
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width > width - 1 {
+            break;
+        }
+        out.push(c);
+        used += char_width;
+    }
+    out.push('…');
+    out
+}
+
+/// `text` truncated and then padded with spaces to exactly `width` columns,
+/// for aligned plain-text output where `{:<N}` would count characters.
+pub fn pad(text: &str, width: usize) -> String {
+    let text = truncate(text, width);
+    let padding = width.saturating_sub(text.width());
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("bash", 8), "bash");
+        assert_eq!(truncate("postgres-writer", 8), "postgre…");
+        // Two columns per character: a wide one never straddles the edge.
+        assert_eq!(truncate("日本語のプロセス", 8), "日本語…");
+        assert_eq!(truncate("日本語のプロセス", 7), "日本語…");
+        // The combining accent stays with its `e`.
+        assert_eq!(truncate("cafe\u{301}-daemon", 5), "cafe\u{301}…");
+        assert_eq!(truncate("evil\x1b[2J", 20), "evil?[2J");
+        assert_eq!(truncate("bash", 0), "");
+
+        assert_eq!(pad("日本", 6), "日本  ");
+        assert_eq!(pad("日本語のプロセス", 8).width(), 8);
+    }
+}
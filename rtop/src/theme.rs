@@ -0,0 +1,589 @@
+// src/theme.rs
+use serde::Deserialize;
+use std::collections::HashMap;
+use tui::style::{Color, Modifier, Style};
+
+/// Names accepted by `--theme` and `theme = "..."` besides the config file's `[themes]`.
+pub const BUILTIN: [&str; 4] = ["default", "gruvbox", "nord", "solarized"];
+
+/// The 16 ANSI colors with the values xterm gives them.
+const ANSI: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// How many colors the terminal can show; `color_depth` in the config.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum ColorDepth {
+    /// No colors at all, as `NO_COLOR` asks for.
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "16")]
+    Basic,
+    #[serde(rename = "256")]
+    Indexed,
+    /// 24-bit RGB, which also turns on the theme's gradient.
+    #[serde(rename = "truecolor")]
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Reads `NO_COLOR`, `COLORTERM` and `TERM`.
+    pub fn detect() -> ColorDepth {
+        let var = |name| std::env::var(name).ok();
+        ColorDepth::from_env(var("NO_COLOR"), var("COLORTERM"), var("TERM"))
+    }
+
+    fn from_env(
+        no_color: Option<String>,
+        colorterm: Option<String>,
+        term: Option<String>,
+    ) -> ColorDepth {
+        // https://no-color.org: set and not empty.
+        if no_color.is_some_and(|value| !value.is_empty()) {
+            return ColorDepth::None;
+        }
+        if matches!(colorterm.as_deref(), Some("truecolor" | "24bit")) {
+            return ColorDepth::TrueColor;
+        }
+        match term.as_deref() {
+            Some("dumb") => ColorDepth::None,
+            Some(term) if term.contains("256color") => ColorDepth::Indexed,
+            _ => ColorDepth::Basic,
+        }
+    }
+
+    /// `color` as close as this depth can show it.
+    pub fn convert(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorDepth::None, _) => Color::Reset,
+            (ColorDepth::TrueColor, _) | (_, Color::Reset) => color,
+            (ColorDepth::Indexed, Color::Rgb(..)) => nearest(color, (16..=255).map(Color::Indexed)),
+            (ColorDepth::Indexed, _) => color,
+            (ColorDepth::Basic, Color::Rgb(..) | Color::Indexed(_)) => {
+                nearest(color, ANSI.iter().map(|(ansi, _)| *ansi))
+            }
+            (ColorDepth::Basic, _) => color,
+        }
+    }
+}
+
+/// RGB value of `color`, taking the ANSI colors as xterm shows them.
+fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Reset => None,
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(index @ 0..=15) => Some(ANSI[index as usize].1),
+        Color::Indexed(index @ 16..=231) => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + 40 * n };
+            let index = index - 16;
+            Some((level(index / 36), level(index / 6 % 6), level(index % 6)))
+        }
+        Color::Indexed(index) => {
+            let gray = 8 + 10 * (index - 232);
+            Some((gray, gray, gray))
+        }
+        named => ANSI
+            .iter()
+            .find(|(ansi, _)| *ansi == named)
+            .map(|(_, rgb)| *rgb),
+    }
+}
+
+/// The candidate closest to `color`.
+fn nearest(color: Color, candidates: impl Iterator<Item = Color>) -> Color {
+    let Some((r, g, b)) = rgb(color) else {
+        return color;
+    };
+    let distance = |candidate: &Color| {
+        let (cr, cg, cb) = rgb(*candidate).unwrap_or_default();
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, cr) + d(g, cg) + d(b, cb)
+    };
+    candidates.min_by_key(distance).unwrap_or(color)
+}
+
+/// The color `fraction` (0 to 1) of the way along `stops`, blended in RGB.
+pub fn gradient(stops: &[Color], fraction: f64) -> Color {
+    let position = fraction.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let index = (position.floor() as usize).min(stops.len().saturating_sub(2));
+    let (Some(from), Some(to)) = (
+        rgb(stops[index]),
+        stops.get(index + 1).and_then(|c| rgb(*c)),
+    ) else {
+        return stops[position.round() as usize];
+    };
+    let t = position - index as f64;
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    Color::Rgb(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+/// Colors used by the UI, selected with `--theme` or the config file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub cpu_gauge: Color,
+    pub memory_gauge: Color,
+    /// Active tabs, field labels and popup borders.
+    pub highlight: Color,
+    /// Background of the selected row in the table that has the keyboard.
+    pub focus: Color,
+    /// Background of the selected row of a table behind a popup.
+    pub selection: Color,
+    /// Background of every other row with `zebra` on.
+    pub stripe: Color,
+    /// Help line and secondary text.
+    pub muted: Color,
+    /// Exited processes and virtual interfaces.
+    pub dim: Color,
+    pub good: Color,
+    pub warning: Color,
+    pub critical: Color,
+    /// Remote filesystems.
+    pub accent: Color,
+    pub rx: Color,
+    pub tx: Color,
+    /// Series colors for the CPU race and memory composition bars.
+    pub palette: [Color; 6],
+    /// Stops gauges and heatmaps shade along on truecolor terminals: empty
+    /// for `good`, `warning` and `critical`, `None` for plain colors.
+    pub gradient: Option<Vec<Color>>,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            cpu_gauge: Color::Yellow,
+            memory_gauge: Color::Cyan,
+            highlight: Color::Yellow,
+            focus: Color::Blue,
+            selection: Color::DarkGray,
+            stripe: Color::Indexed(235),
+            muted: Color::Gray,
+            dim: Color::DarkGray,
+            good: Color::Green,
+            warning: Color::Yellow,
+            critical: Color::Red,
+            accent: Color::Magenta,
+            rx: Color::Green,
+            tx: Color::Cyan,
+            palette: [
+                Color::Green,
+                Color::Cyan,
+                Color::Magenta,
+                Color::Yellow,
+                Color::Blue,
+                Color::Red,
+            ],
+            gradient: Some(Vec::new()),
+        }
+    }
+}
+
+impl Theme {
+    /// Every color converted to what `depth` can show.
+    pub fn for_depth(&self, depth: ColorDepth) -> Theme {
+        let c = |color| depth.convert(color);
+        Theme {
+            cpu_gauge: c(self.cpu_gauge),
+            memory_gauge: c(self.memory_gauge),
+            highlight: c(self.highlight),
+            focus: c(self.focus),
+            selection: c(self.selection),
+            stripe: c(self.stripe),
+            muted: c(self.muted),
+            dim: c(self.dim),
+            good: c(self.good),
+            warning: c(self.warning),
+            critical: c(self.critical),
+            accent: c(self.accent),
+            rx: c(self.rx),
+            tx: c(self.tx),
+            palette: self.palette.map(c),
+            gradient: self
+                .gradient
+                .clone()
+                .filter(|_| depth == ColorDepth::TrueColor),
+        }
+    }
+
+    /// The selected row of the table that has the keyboard: the focus
+    /// background, or bold reversed video without colors.
+    pub fn focused(&self) -> Style {
+        match self.focus {
+            Color::Reset => Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            focus => Style::default().bg(focus),
+        }
+    }
+
+    /// The selected row of a table behind a popup: the selection
+    /// background, or reversed video without colors.
+    pub fn selected(&self) -> Style {
+        match self.selection {
+            Color::Reset => Style::default().add_modifier(Modifier::REVERSED),
+            selection => Style::default().bg(selection),
+        }
+    }
+
+    /// Row `index` of a table, striped with `zebra`.
+    pub fn row(&self, index: usize, zebra: bool) -> Style {
+        match self.stripe {
+            Color::Reset => Style::default(),
+            stripe if zebra && index % 2 == 1 => Style::default().bg(stripe),
+            _ => Style::default(),
+        }
+    }
+
+    /// The stops to shade along, or `None` for plain colors.
+    pub fn shading(&self) -> Option<Vec<Color>> {
+        let stops = self.gradient.as_ref()?;
+        Some(if stops.is_empty() {
+            vec![self.good, self.warning, self.critical]
+        } else {
+            stops.clone()
+        })
+    }
+
+    /// The color `fraction` (0 to 1) of the way along the shading, or from
+    /// `good` over `warning` to `critical` without one.
+    pub fn level(&self, fraction: f64) -> Color {
+        let stops = self
+            .shading()
+            .unwrap_or_else(|| vec![self.good, self.warning, self.critical]);
+        gradient(&stops, fraction)
+    }
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        let rgb = |hex: u32| Color::Rgb((hex >> 16) as u8, (hex >> 8) as u8, hex as u8);
+        match name {
+            "default" => Some(Theme::default()),
+            "gruvbox" => Some(Theme {
+                cpu_gauge: rgb(0xfabd2f),
+                memory_gauge: rgb(0x83a598),
+                highlight: rgb(0xfabd2f),
+                focus: rgb(0x504945),
+                selection: rgb(0x3c3836),
+                stripe: rgb(0x32302f),
+                muted: rgb(0xa89984),
+                dim: rgb(0x665c54),
+                good: rgb(0xb8bb26),
+                warning: rgb(0xfe8019),
+                critical: rgb(0xfb4934),
+                accent: rgb(0xd3869b),
+                rx: rgb(0xb8bb26),
+                tx: rgb(0x83a598),
+                palette: [
+                    rgb(0xb8bb26),
+                    rgb(0x83a598),
+                    rgb(0xd3869b),
+                    rgb(0xfabd2f),
+                    rgb(0x8ec07c),
+                    rgb(0xfe8019),
+                ],
+                gradient: Some(Vec::new()),
+            }),
+            "nord" => Some(Theme {
+                cpu_gauge: rgb(0x88c0d0),
+                memory_gauge: rgb(0x81a1c1),
+                highlight: rgb(0x88c0d0),
+                focus: rgb(0x434c5e),
+                selection: rgb(0x3b4252),
+                stripe: rgb(0x333946),
+                muted: rgb(0xd8dee9),
+                dim: rgb(0x4c566a),
+                good: rgb(0xa3be8c),
+                warning: rgb(0xebcb8b),
+                critical: rgb(0xbf616a),
+                accent: rgb(0xb48ead),
+                rx: rgb(0xa3be8c),
+                tx: rgb(0x88c0d0),
+                palette: [
+                    rgb(0x8fbcbb),
+                    rgb(0x88c0d0),
+                    rgb(0x81a1c1),
+                    rgb(0xb48ead),
+                    rgb(0xa3be8c),
+                    rgb(0xd08770),
+                ],
+                gradient: Some(Vec::new()),
+            }),
+            "solarized" => Some(Theme {
+                cpu_gauge: rgb(0xb58900),
+                memory_gauge: rgb(0x2aa198),
+                highlight: rgb(0x268bd2),
+                focus: rgb(0x073642),
+                selection: rgb(0x04313c),
+                stripe: rgb(0x022f3a),
+                muted: rgb(0x93a1a1),
+                dim: rgb(0x586e75),
+                good: rgb(0x859900),
+                warning: rgb(0xb58900),
+                critical: rgb(0xdc322f),
+                accent: rgb(0xd33682),
+                rx: rgb(0x859900),
+                tx: rgb(0x2aa198),
+                palette: [
+                    rgb(0x859900),
+                    rgb(0x2aa198),
+                    rgb(0x268bd2),
+                    rgb(0x6c71c4),
+                    rgb(0xd33682),
+                    rgb(0xcb4b16),
+                ],
+                gradient: Some(Vec::new()),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Resolves `name` against the built-in themes and the config file's
+    /// `[themes.<name>]` tables. A custom theme starts from its `base`
+    /// (default: `default`) and overrides individual colors.
+    pub fn resolve(
+        name: &str,
+        custom: &HashMap<String, HashMap<String, String>>,
+    ) -> Result<Theme, String> {
+        Theme::resolve_chain(name, custom, &mut Vec::new())
+    }
+
+    fn resolve_chain<'a>(
+        name: &'a str,
+        custom: &'a HashMap<String, HashMap<String, String>>,
+        chain: &mut Vec<&'a str>,
+    ) -> Result<Theme, String> {
+        let Some(entries) = custom.get(name) else {
+            return Theme::builtin(name).ok_or_else(|| {
+                format!(
+                    "unknown theme '{}' (built-in: {})",
+                    name,
+                    BUILTIN.join(", ")
+                )
+            });
+        };
+        if chain.contains(&name) {
+            return Err(format!("theme '{}' is its own base", name));
+        }
+        chain.push(name);
+        let base = entries.get("base").map_or("default", String::as_str);
+        let mut theme = Theme::resolve_chain(base, custom, chain)?;
+        for (slot, value) in entries.iter().filter(|(slot, _)| *slot != "base") {
+            theme
+                .set(slot, value)
+                .map_err(|err| format!("theme '{}': {}", name, err))?;
+        }
+        Ok(theme)
+    }
+
+    fn set(&mut self, slot: &str, value: &str) -> Result<(), String> {
+        if let Some(index) = slot.strip_prefix("palette") {
+            let index: usize = index
+                .parse()
+                .ok()
+                .filter(|&i| (1..=self.palette.len()).contains(&i))
+                .ok_or_else(|| {
+                    format!(
+                        "palette colors are palette1 to palette{}",
+                        self.palette.len()
+                    )
+                })?;
+            self.palette[index - 1] = parse_color(value)?;
+            return Ok(());
+        }
+        if slot == "gradient" {
+            self.gradient = parse_gradient(value)?;
+            return Ok(());
+        }
+        let color = match slot {
+            "cpu_gauge" => &mut self.cpu_gauge,
+            "memory_gauge" => &mut self.memory_gauge,
+            "highlight" => &mut self.highlight,
+            "focus" => &mut self.focus,
+            "selection" => &mut self.selection,
+            "stripe" => &mut self.stripe,
+            "muted" => &mut self.muted,
+            "dim" => &mut self.dim,
+            "good" => &mut self.good,
+            "warning" => &mut self.warning,
+            "critical" => &mut self.critical,
+            "accent" => &mut self.accent,
+            "rx" => &mut self.rx,
+            "tx" => &mut self.tx,
+            _ => return Err(format!("unknown color '{}'", slot)),
+        };
+        *color = parse_color(value)?;
+        Ok(())
+    }
+}
+
+/// Parses `none` or at least two comma-separated colors.
+fn parse_gradient(value: &str) -> Result<Option<Vec<Color>>, String> {
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    let stops = value
+        .split(',')
+        .map(|stop| parse_color(stop.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if stops.len() < 2 {
+        return Err("a gradient needs at least two colors, or none".to_string());
+    }
+    Ok(Some(stops))
+}
+
+/// Parses a color name (`yellow`, `light_blue`, `dark_gray`), `#rrggbb` or a
+/// 256-color index.
+pub fn parse_color(value: &str) -> Result<Color, String> {
+    if let Some(hex) = value.strip_prefix('#') {
+        return match u32::from_str_radix(hex, 16) {
+            Ok(rgb) if hex.len() == 6 => {
+                Ok(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+            }
+            _ => Err(format!("invalid hex color '{}'", value)),
+        };
+    }
+    if let Ok(index) = value.parse::<u8>() {
+        return Ok(Color::Indexed(index));
+    }
+    let name: String = value
+        .chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .collect::<String>()
+        .to_ascii_lowercase();
+    Ok(match name.as_str() {
+        "reset" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return Err(format!("unknown color '{}'", value)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("dark_gray"), Ok(Color::DarkGray));
+        assert_eq!(parse_color("Light-Blue"), Ok(Color::LightBlue));
+        assert_eq!(parse_color("#fe8019"), Ok(Color::Rgb(0xfe, 0x80, 0x19)));
+        assert_eq!(parse_color("208"), Ok(Color::Indexed(208)));
+        assert!(parse_color("#fff").is_err());
+        assert!(parse_color("chartreuse").is_err());
+    }
+
+    #[test]
+    fn test_color_depth() {
+        let env = |value: &str| Some(value.to_string());
+        let detect = ColorDepth::from_env;
+        assert_eq!(
+            detect(env("1"), env("truecolor"), env("xterm")),
+            ColorDepth::None
+        );
+        assert_eq!(
+            detect(env(""), env("truecolor"), None),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            detect(None, None, env("xterm-256color")),
+            ColorDepth::Indexed
+        );
+        assert_eq!(detect(None, None, env("xterm")), ColorDepth::Basic);
+        assert_eq!(detect(None, None, env("dumb")), ColorDepth::None);
+
+        let orange = Color::Rgb(0xfe, 0x80, 0x19);
+        assert_eq!(ColorDepth::Indexed.convert(orange), Color::Indexed(208));
+        assert_eq!(ColorDepth::Basic.convert(orange), Color::Yellow);
+        assert_eq!(
+            ColorDepth::Basic.convert(Color::Indexed(196)),
+            Color::LightRed
+        );
+        let plain = Theme::default().for_depth(ColorDepth::None);
+        assert_eq!(plain.critical, Color::Reset);
+        assert_eq!(
+            plain.selected(),
+            Style::default().add_modifier(Modifier::REVERSED)
+        );
+        assert_ne!(plain.focused(), plain.selected());
+        // Without colors there are no stripes.
+        assert_eq!(plain.row(1, true), Style::default());
+        assert_eq!(
+            Theme::default().row(1, true),
+            Style::default().bg(Color::Indexed(235))
+        );
+        assert_eq!(Theme::default().row(2, true), Style::default());
+
+        let (red, blue) = (Color::Rgb(255, 0, 0), Color::Rgb(0, 0, 255));
+        assert_eq!(gradient(&[red, blue], 0.5), Color::Rgb(128, 0, 128));
+        assert_eq!(gradient(&[red, blue], 2.0), blue);
+        assert_eq!(Theme::default().level(0.0), Color::Rgb(0, 205, 0));
+        assert_eq!(
+            Theme::default().for_depth(ColorDepth::Indexed).shading(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_resolve_custom_theme() {
+        let custom = HashMap::from([(
+            "mine".to_string(),
+            HashMap::from([
+                ("base".to_string(), "nord".to_string()),
+                ("warning".to_string(), "magenta".to_string()),
+                ("palette2".to_string(), "white".to_string()),
+                ("gradient".to_string(), "#00ff00, blue".to_string()),
+            ]),
+        )]);
+        let theme = Theme::resolve("mine", &custom).unwrap();
+        assert_eq!(theme.warning, Color::Magenta);
+        assert_eq!(theme.palette[1], Color::White);
+        assert_eq!(
+            theme.shading(),
+            Some(vec![Color::Rgb(0, 255, 0), Color::Blue])
+        );
+        assert_eq!(theme.critical, Theme::builtin("nord").unwrap().critical);
+
+        assert!(Theme::resolve("nope", &custom).is_err());
+        let broken = HashMap::from([(
+            "x".to_string(),
+            HashMap::from([("warn".to_string(), "red".to_string())]),
+        )]);
+        assert!(Theme::resolve("x", &broken).is_err());
+        let plain = HashMap::from([(
+            "x".to_string(),
+            HashMap::from([("gradient".to_string(), "none".to_string())]),
+        )]);
+        assert_eq!(Theme::resolve("x", &plain).unwrap().shading(), None);
+        let looped = HashMap::from([(
+            "a".to_string(),
+            HashMap::from([("base".to_string(), "a".to_string())]),
+        )]);
+        assert!(Theme::resolve("a", &looped).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+// src/tree.rs
+use std::collections::{HashMap, HashSet};
+use sysinfo::Pid;
+
+/// One row of the process tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TreeRow {
+    pub pid: Pid,
+    pub depth: usize,
+    pub has_children: bool,
+    pub collapsed: bool,
+}
+
+impl TreeRow {
+    /// Indentation and fold marker drawn before the process name.
+    pub fn prefix(&self) -> String {
+        let marker = match (self.has_children, self.collapsed) {
+            (false, _) => "  ",
+            (true, false) => "▾ ",
+            (true, true) => "▸ ",
+        };
+        format!("{}{}", "  ".repeat(self.depth), marker)
+    }
+
+    /// Columns, relative to the start of the name cell, covered by the fold marker.
+    pub fn marker_columns(&self) -> std::ops::Range<u16> {
+        let start = (self.depth * 2) as u16;
+        start..start + 2
+    }
+}
+
+/// Flattens `(pid, parent)` pairs, given in display order, into a depth-first
+/// tree. Siblings keep their relative order, processes whose parent is not
+/// listed become roots, and descendants of `collapsed` PIDs are left out.
+pub fn flatten(entries: &[(Pid, Option<Pid>)], collapsed: &HashSet<Pid>) -> Vec<TreeRow> {
+    let known: HashSet<Pid> = entries.iter().map(|entry| entry.0).collect();
+    let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+    let mut roots = Vec::new();
+    for &(pid, parent) in entries {
+        match parent.filter(|parent| *parent != pid && known.contains(parent)) {
+            Some(parent) => children.entry(parent).or_default().push(pid),
+            None => roots.push(pid),
+        }
+    }
+
+    let mut rows = Vec::with_capacity(entries.len());
+    let mut stack: Vec<(Pid, usize)> = roots.into_iter().rev().map(|pid| (pid, 0)).collect();
+    while let Some((pid, depth)) = stack.pop() {
+        let kids = children.get(&pid);
+        let is_collapsed = kids.is_some() && collapsed.contains(&pid);
+        rows.push(TreeRow {
+            pid,
+            depth,
+            has_children: kids.is_some(),
+            collapsed: is_collapsed,
+        });
+        if let Some(kids) = kids.filter(|_| !is_collapsed) {
+            stack.extend(kids.iter().rev().map(|&kid| (kid, depth + 1)));
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pid(n: usize) -> Pid {
+        Pid::from(n)
+    }
+
+    #[test]
+    fn test_flatten() {
+        // 1 ─┬─ 3 ── 4
+        //    └─ 2
+        // 5 (parent not listed)
+        let entries = [
+            (pid(3), Some(pid(1))),
+            (pid(1), None),
+            (pid(5), Some(pid(99))),
+            (pid(2), Some(pid(1))),
+            (pid(4), Some(pid(3))),
+        ];
+        let rows = flatten(&entries, &HashSet::new());
+        let order: Vec<(usize, usize)> = rows
+            .iter()
+            .map(|row| (usize::from(row.pid), row.depth))
+            .collect();
+        assert_eq!(order, [(1, 0), (3, 1), (4, 2), (2, 1), (5, 0)]);
+        assert_eq!(rows[0].prefix(), "▾ ");
+        assert_eq!(rows[2].prefix(), "      ");
+
+        let rows = flatten(&entries, &HashSet::from([pid(3)]));
+        let order: Vec<usize> = rows.iter().map(|row| usize::from(row.pid)).collect();
+        assert_eq!(order, [1, 3, 2, 5]);
+        assert!(rows[1].collapsed);
+        assert_eq!(rows[1].prefix(), "  ▸ ");
+        assert_eq!(rows[1].marker_columns(), 2..4);
+    }
+}
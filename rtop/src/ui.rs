@@ -0,0 +1,2626 @@
+// src/ui.rs
+use std::time::Duration;
+use chrono::TimeZone;
+use sysinfo::{CpuExt, ProcessExt, SystemExt, Pid, PidExt, UserExt};
+use crate::actions::{ActionRunner, Outcome};
+use crate::alarm::Alarm;
+use crate::collectors::battery::{BatteryInfo, BatteryState};
+use crate::config::{Column, Config, Thresholds};
+use crate::details::{DetailsTab, MemoryComposition, ProcessDetails};
+use crate::input::Prompt;
+use crate::keymap::{Action, Keymap};
+use crate::limits::ResourceLimit;
+use crate::mirror::MirrorClient;
+use crate::privilege::Privilege;
+use crate::redact::Redactor;
+use crate::shade::Shade;
+use crate::collectors::sockets::ConnectionSummary;
+use crate::source::Source;
+use crate::theme::{ColorDepth, Theme};
+/// # Terminal UI Components
+///
+/// This module imports the necessary components from the `tui` crate to create a terminal user interface.
+///
+/// ## Imports
+///
+/// - `backend`: Terminal backends, specifically `CrosstermBackend` for cross-platform terminal handling
+/// - `layout`: Layout components for arranging UI elements in different constraints and directions
+/// - `style`: Styling options for UI elements, including colors and text modifications
+/// - `text`: Text components for displaying and formatting text in the UI
+/// - `widgets`: UI widgets including:
+///   - `Block`: Basic rectangular UI element that can have borders
+///   - `Borders`: Border styles for blocks
+///   - `Gauge`: Progress bar or meter visualization
+///   - `List` and `ListItem`: Components for displaying lists of items
+///   - `Paragraph`: Text display with various formatting options
+///   - `Table`, `Row`, `Cell`: Table display components
+/// - `Terminal`: Main terminal handling component that manages the UI
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    symbols,
+    widgets::{Axis, Block, Borders, Cell, Chart, Clear, Dataset, Gauge, GraphType, LineGauge, Row, Table, TableState, Tabs, Paragraph, Wrap},
+};
+use crate::collectors::gpu;
+use crate::{alerts, cluster, columns, config, control, health, history, hosts, replay, silence, sparkline, summary, text, view, watchdog, whatif};
+use crate::app::{App, AppState, Tab, AFFINITY_COLUMNS};
+
+/// Battery charge below which the widget turns yellow and red while discharging.
+const BATTERY_WARNING: f64 = 25.0;
+const BATTERY_CRITICAL: f64 = 10.0;
+
+/// Width of one per-core gauge cell, including its label.
+const CORE_CELL_WIDTH: u16 = 24;
+
+/// Height of the history charts, borders and axis labels included.
+const HISTORY_HEIGHT: u16 = 8;
+/// Their height on a `--kiosk` wallboard, to be read from across the room.
+const KIOSK_HISTORY_HEIGHT: u16 = 16;
+
+/// How long a status message stays in the tab bar.
+pub(crate) const STATUS_MESSAGE_TIME: Duration = Duration::from_secs(5);
+
+/// Draws `app` into the whole frame, as the interactive UI does each refresh.
+pub fn draw<B: Backend>(f: &mut tui::Frame<B>, app: &mut App) {
+    app.resize(f.size());
+    let (show_summary, show_gauges, show_help) = app
+        .size_layout()
+        .map_or((true, true, true), |layout| (layout.summary, layout.gauges, layout.help));
+    // Prompts are typed into the help line.
+    let show_help = (show_help && !app.kiosk) || app.state == AppState::Input;
+    let history_height = if app.kiosk { KIOSK_HISTORY_HEIGHT } else { HISTORY_HEIGHT };
+    let per_core_height = if app.show_per_core { per_core_height(app.system.cpus().len(), f.size().width) } else { 0 };
+    let gpu_height = if app.gpu.is_available() { app.gpu.gpus.len() as u16 + 2 } else { 0 };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(if show_summary { 1 } else { 0 }),  // Summary line
+            Constraint::Length(if show_gauges { 3 } else { 0 }),  // Top gauges
+            Constraint::Length(per_core_height),  // Per-core gauges
+            Constraint::Length(if app.show_cpu_history || app.show_memory_history { history_height } else { 0 }),  // History charts
+            Constraint::Length(gpu_height),  // GPU panel
+            Constraint::Length(1),  // Tab bar
+            Constraint::Min(10),    // Tab content
+            Constraint::Length(if show_help { 1 } else { 0 }),  // Help text
+        ].as_ref())
+        .split(f.size());
+
+    if show_summary {
+        render_summary(f, chunks[0], &app.system, summary::logged_in_users(), &app.theme);
+    }
+
+    if show_gauges {
+        render_gauges(f, chunks[1], app);
+    }
+
+    let titles = Tab::ALL.iter().map(|tab| Spans::from(tab.title())).collect();
+    let tabs = Tabs::new(titles)
+        .select(app.tab.index())
+        .highlight_style(Style::default().fg(app.theme.highlight));
+    f.render_widget(tabs, chunks[5]);
+
+    let user_name = |uid: u32| {
+        app.system
+            .users()
+            .iter()
+            .find(|user| **user.id() == uid)
+            .map_or(format!("uid {}", uid), |user| app.redact.user(user.name()).into_owned())
+    };
+    let mut status = match &app.status_message {
+        Some((at, Ok(message))) if at.elapsed() < STATUS_MESSAGE_TIME => {
+            vec![Span::styled(format!("{} | ", message), Style::default().fg(app.theme.good))]
+        }
+        Some((at, Err(message))) if at.elapsed() < STATUS_MESSAGE_TIME => {
+            vec![Span::styled(format!("{} | ", message), Style::default().fg(app.theme.critical))]
+        }
+        _ => Vec::new(),
+    };
+    if app.paused {
+        let style = Style::default().fg(app.theme.warning).add_modifier(Modifier::BOLD);
+        status.push(Span::styled(format!("PAUSED ({} to resume) | ", app.keymap.key(Action::Pause)), style));
+    }
+    if !app.tagged.is_empty() {
+        status.push(Span::styled(format!("{} tagged | ", app.tagged.len()), Style::default().fg(app.theme.accent)));
+    }
+    status.extend(match app.privilege {
+        Privilege::Root => vec![Span::styled("root", Style::default().fg(app.theme.critical))],
+        Privilege::User { uid } => vec![Span::styled(user_name(uid), Style::default().fg(app.theme.muted))],
+        Privilege::Dropped { uid } => vec![Span::styled(
+            format!("root → {}", user_name(uid)),
+            Style::default().fg(app.theme.good),
+        )],
+    });
+    if let Some(server) = &app.mirror {
+        status.push(Span::styled(
+            format!(
+                " | Sharing on {} ({} viewers)",
+                server.path().display(),
+                server.client_count()
+            ),
+            Style::default().fg(app.theme.muted),
+        ));
+    }
+    if let Some(addr) = app.metrics.as_ref().and_then(|server| server.local_addr().ok()) {
+        status.push(Span::styled(format!(" | Metrics on {}", addr), Style::default().fg(app.theme.muted)));
+    }
+    if let Some((path, _)) = &app.recorder {
+        status.push(Span::styled(format!(" | Recording to {}", path.display()), Style::default().fg(app.theme.muted)));
+    }
+    if app.redact.is_enabled() {
+        status.push(Span::styled(" | Redacted", Style::default().fg(app.theme.muted)));
+    }
+    f.render_widget(Paragraph::new(Spans::from(status)).alignment(Alignment::Right), chunks[5]);
+
+    if app.show_per_core {
+        render_per_core(f, chunks[2], &app.system, &app.config.thresholds, &app.theme);
+    }
+    if app.show_cpu_history || app.show_memory_history {
+        render_history(f, chunks[3], &app.history, (app.show_cpu_history, app.show_memory_history), app.system.total_swap() > 0, &app.theme);
+    }
+    if app.gpu.is_available() {
+        render_gpus(f, chunks[4], app);
+    }
+
+    match app.tab {
+        _ if app.lock.is_some() => render_lock(f, chunks[6], app),
+        Tab::Processes => {
+            app.process_table_area = chunks[6];
+            app.process_table_offset = scroll_offset(app.process_table_offset, app.selected_process, app.process_table_rows());
+            render_process_table(f, chunks[6], app)
+        }
+        Tab::Disks => render_disks(f, chunks[6], app),
+        Tab::Interrupts => render_interrupts(f, chunks[6], app),
+        Tab::Network => render_network(f, chunks[6], app),
+        Tab::Race => render_race(f, chunks[6], app),
+        Tab::Cluster => render_cluster(f, chunks[6], app),
+        Tab::Alerts => render_alerts(f, chunks[6], app),
+        Tab::Plugins => render_plugins(f, chunks[6], app),
+    }
+
+    let help_text = Paragraph::new(help_text(app))
+    .style(Style::default().fg(app.theme.muted))
+    .block(Block::default().borders(Borders::NONE));
+
+    if show_help {
+        f.render_widget(help_text, chunks[7]);
+    }
+
+    if app.state == AppState::ProcessMenu {
+        render_signal_picker(f, centered_rect(50, 60, f.size()), app);
+    }
+
+    if matches!(app.state, AppState::Details | AppState::Affinity) {
+        if let Some(details) = &app.details {
+            render_details(f, centered_rect(80, 70, f.size()), app, details);
+        }
+    }
+
+    if app.state == AppState::ActionOutput {
+        render_action_output(f, centered_rect(80, 60, f.size()), &app.actions, &app.theme, &app.redact);
+    }
+
+    if let Some(limit) = app.what_if {
+        render_what_if(f, centered_rect(80, 70, f.size()), app, limit);
+    }
+
+    if let Some(editor) = &app.column_editor {
+        render_column_editor(f, centered_rect(40, 70, f.size()), editor, &app.theme);
+    }
+
+    if let Some(picker) = &app.host_picker {
+        render_host_picker(f, centered_rect(60, 60, f.size()), picker, &app.theme);
+    }
+
+    if let Some(alarm) = app.alarm.as_ref().filter(|alarm| alarm.is_ringing()) {
+        render_alarm(f, f.size(), alarm, app);
+    }
+}
+
+/// The `--alarm` banner over the whole screen, flashing in the critical color.
+fn render_alarm<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, alarm: &Alarm, app: &App) {
+    let theme = &app.theme;
+    let (fg, bg) = if alarm.flash(std::time::Instant::now()) { (Color::White, theme.critical) } else { (theme.critical, Color::Reset) };
+    let style = Style::default().fg(fg).bg(bg).add_modifier(Modifier::BOLD);
+    let lines = 4 + alarm.ringing.len() as u16;
+    let top = area.height.saturating_sub(lines) / 2;
+    let text: Vec<Spans> = vec![Spans::from(""); top as usize]
+        .into_iter()
+        .chain([Spans::from("CRITICAL ALERT"), Spans::from("")])
+        .chain(alarm.ringing.iter().map(|alert| Spans::from(format!("{}: {}", alert.check, alert.message))))
+        .chain([Spans::from(""), Spans::from(format!("Press {} to acknowledge", app.keymap.key(Action::Acknowledge)))])
+        .collect();
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(text).alignment(Alignment::Center).style(style).block(Block::default().borders(Borders::ALL)), area);
+}
+
+/// The kill menu: every supported signal, sent to the selected or tagged processes.
+fn render_signal_picker<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, app: &App) {
+    let picker = &app.signal_picker;
+    let rows: Vec<Row> = picker
+        .entries
+        .iter()
+        .map(|entry| Row::new(vec![entry.number.to_string(), entry.name.to_string(), entry.description.to_string()]))
+        .collect();
+    let target = match app.targets().as_slice() {
+        [] => "no process".to_string(),
+        [pid] => match app.system.process(*pid) {
+            Some(process) => format!("{} ({})", pid, app.redact.process(process.name())),
+            None => pid.to_string(),
+        },
+        targets => format!("{} tagged processes", targets.len()),
+    };
+    let table = Table::new(rows)
+        .block(
+            Block::default()
+                .title(format!("Send signal to {}", target))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(app.theme.highlight)),
+        )
+        .widths(&[
+            Constraint::Length(3),  // Number
+            Constraint::Length(10), // Name
+            Constraint::Length(28), // Description
+        ])
+        .highlight_style(app.theme.focused());
+    let mut state = TableState::default();
+    state.select(Some(picker.selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(table, area, &mut state);
+}
+
+/// The column setup screen: a checklist in display order.
+fn render_column_editor<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, editor: &columns::ColumnEditor, theme: &Theme) {
+    let lines: Vec<Spans> = editor
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(i, (column, shown))| {
+            let mark = if *shown { "[x]" } else { "[ ]" };
+            let line = format!("{} {:<8} {}", mark, column.title(), column.key());
+            let style = if i == editor.cursor {
+                theme.focused()
+            } else if *shown {
+                Style::default()
+            } else {
+                Style::default().fg(theme.muted)
+            };
+            Spans::from(Span::styled(line, style))
+        })
+        .collect();
+    f.render_widget(Clear, area);
+    let list = Paragraph::new(lines).block(
+        Block::default()
+            .title("Columns")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.highlight)),
+    );
+    f.render_widget(list, area);
+}
+
+/// The host picker of the Cluster tab: agents found via mDNS, checked when polled.
+fn render_host_picker<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, picker: &hosts::HostPicker, theme: &Theme) {
+    let lines: Vec<Spans> = match &picker.entries {
+        None => vec![Spans::from(Span::styled("Looking for agents...", Style::default().fg(theme.muted)))],
+        Some(Err(err)) => vec![Spans::from(Span::styled(format!("Cannot discover agents: {}", err), Style::default().fg(theme.critical)))],
+        Some(Ok(entries)) if entries.is_empty() => vec![Spans::from(Span::styled(
+            "No agents answered. Start rtop on other hosts with --listen ADDR --advertise.",
+            Style::default().fg(theme.muted),
+        ))],
+        Some(Ok(entries)) => entries
+            .iter()
+            .enumerate()
+            .map(|(i, (agent, polled))| {
+                let mark = if *polled { "[x]" } else { "[ ]" };
+                let auth = if agent.auth { " (token)" } else { "" };
+                let line = format!("{} {:<20} {}{}", mark, agent.name, agent.url(), auth);
+                let style = if i == picker.cursor { theme.focused() } else { Style::default() };
+                Spans::from(Span::styled(line, style))
+            })
+            .collect(),
+    };
+    f.render_widget(Clear, area);
+    let list = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+        Block::default()
+            .title("Agents on the network")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.highlight)),
+    );
+    f.render_widget(list, area);
+}
+
+/// Uptime, load averages, task counts and logged-in users, like the first lines of top.
+fn render_summary<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, source: &dyn Source, users: Option<usize>, theme: &Theme) {
+    let load = source.load_average();
+    let tasks = source.task_counts();
+    let muted = Style::default().fg(theme.muted);
+    let mut spans = vec![
+        Span::raw(format!("up {}", summary::format_uptime(source.uptime()))),
+        Span::styled(" | ", muted),
+        Span::raw(format!("load average: {:.2}, {:.2}, {:.2}", load[0], load[1], load[2])),
+        Span::styled(" | ", muted),
+        Span::raw(format!(
+            "tasks: {} total, {} running, {} sleeping, {} stopped, ",
+            tasks.total, tasks.running, tasks.sleeping, tasks.stopped
+        )),
+        Span::styled(
+            format!("{} zombie", tasks.zombie),
+            if tasks.zombie > 0 { Style::default().fg(theme.warning) } else { Style::default() },
+        ),
+    ];
+    if let Some(users) = users {
+        spans.push(Span::styled(" | ", muted));
+        spans.push(Span::raw(format!("{} {}", users, if users == 1 { "user" } else { "users" })));
+    }
+    f.render_widget(Paragraph::new(Spans::from(spans)), area);
+}
+
+fn render_battery<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, battery: &BatteryInfo, theme: &Theme) {
+    let color = match battery.state {
+        BatteryState::Discharging if battery.percent < BATTERY_CRITICAL => theme.critical,
+        BatteryState::Discharging if battery.percent < BATTERY_WARNING => theme.warning,
+        _ => theme.good,
+    };
+    let remaining = battery.time_remaining.map(|time| {
+        let minutes = time.as_secs() / 60;
+        let direction = if battery.state == BatteryState::Charging { "to full" } else { "left" };
+        format!(" {}:{:02} {}", minutes / 60, minutes % 60, direction)
+    });
+    let gauge = Gauge::default()
+        .block(Block::default().title(format!("Battery ({})", battery.state.label())).borders(Borders::ALL))
+        .gauge_style(Style::default().fg(color))
+        .label(format!("{:.0}%{}", battery.percent, remaining.unwrap_or_default()))
+        .ratio((battery.percent / 100.0).clamp(0.0, 1.0));
+    f.render_widget(gauge, area);
+}
+
+/// The history charts that are turned on (CPU, memory), side by side.
+fn render_history<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, history: &history::Histories, shown: (bool, bool), has_swap: bool, theme: &Theme) {
+    let count = [shown.0, shown.1].into_iter().filter(|shown| *shown).count() as u32;
+    let areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Ratio(1, count.max(1)); count as usize])
+        .split(area);
+    let mut areas = areas.into_iter();
+    if let Some(area) = areas.next().filter(|_| shown.0) {
+        render_cpu_history(f, area, &history.cpu, theme);
+    }
+    if let Some(area) = areas.next().filter(|_| shown.1) {
+        render_memory_history(f, area, history, has_swap, theme);
+    }
+}
+
+/// A line chart of percentages over the last `window` seconds, newest on the right.
+fn history_chart<'a>(title: String, datasets: Vec<Dataset<'a>>, window: f64, theme: &Theme) -> Chart<'a> {
+    let muted = Style::default().fg(theme.muted);
+    Chart::new(datasets)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .x_axis(
+            Axis::default()
+                .bounds([-window, 0.0])
+                .labels(vec![Span::raw(format!("-{:.0}s", window)), Span::raw(format!("-{:.0}s", window / 2.0)), Span::raw("now")])
+                .style(muted),
+        )
+        .y_axis(Axis::default().bounds([0.0, 100.0]).labels(vec![Span::raw("0%"), Span::raw("50%"), Span::raw("100%")]).style(muted))
+}
+
+/// Total CPU usage over the last `history_seconds`.
+fn render_cpu_history<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, history: &history::History, theme: &Theme) {
+    let points = history.points();
+    let dataset = Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(theme.cpu_gauge))
+        .data(&points);
+    let window = history.window().as_secs();
+    let title = match points.last() {
+        Some((_, usage)) => format!("CPU History ({}s, now {:.1}%)", window, usage),
+        None => format!("CPU History ({}s)", window),
+    };
+    f.render_widget(history_chart(title, vec![dataset], window as f64, theme), area);
+}
+
+/// Used memory and swap over the last `history_seconds`, to tell growing
+/// memory pressure from a stable high level.
+fn render_memory_history<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, history: &history::Histories, has_swap: bool, theme: &Theme) {
+    let memory = history.memory.points();
+    let swap = history.swap.points();
+    let line = |points, color| {
+        Dataset::default().marker(symbols::Marker::Braille).graph_type(GraphType::Line).style(Style::default().fg(color)).data(points)
+    };
+    let mut datasets = vec![line(&memory, theme.memory_gauge)];
+    let window = history.memory.window().as_secs();
+    let mut title = match memory.last() {
+        Some((_, used)) => format!("Memory History ({}s, now {:.1}%", window, used),
+        None => format!("Memory History ({}s", window),
+    };
+    // Machines without swap get no swap line.
+    if has_swap {
+        datasets.push(line(&swap, theme.warning));
+        if let Some((_, used)) = swap.last() {
+            title.push_str(&format!(", swap {:.1}%", used));
+        }
+    }
+    title.push(')');
+    f.render_widget(history_chart(title, datasets, window as f64, theme), area);
+}
+
+/// The CPU, memory, swap and connections gauges, plus the battery on laptops.
+fn render_gauges<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, app: &App) {
+    let (cpu_baseline, mem_baseline) = app.baseline_comparison();
+    // With a baseline the titles get shorter to leave room for the comparison.
+    let title = |name: &str, comparison: Option<String>| match comparison {
+        Some(comparison) => format!("{} ({})", name, comparison),
+        None => format!("{} Usage", name),
+    };
+    let (cpu_gauge, mem_gauge) =
+        usage_gauges(&app.system, title("CPU", cpu_baseline), title("Memory", mem_baseline), &app.theme);
+
+    // The battery widget, when there is one, takes a fixed width on the right.
+    let (header, battery_area) = match app.battery {
+        Some(_) => {
+            let split = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(0), Constraint::Length(24)].as_ref())
+                .split(area);
+            (split[0], Some(split[1]))
+        }
+        None => (area, None),
+    };
+    let top_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(28),  // CPU
+            Constraint::Percentage(24),  // Memory
+            Constraint::Percentage(18),  // Swap
+            Constraint::Percentage(30),  // Connections
+        ].as_ref())
+        .split(header);
+
+    f.render_widget(cpu_gauge, top_layout[0]);
+    f.render_widget(mem_gauge, top_layout[1]);
+    render_swap(f, top_layout[2], app.system.used_swap(), app.system.total_swap(), &app.config.thresholds, &app.theme);
+    render_connections(f, top_layout[3], &app.connections, app.config.thresholds.close_wait, &app.theme);
+    if let (Some(battery), Some(area)) = (&app.battery, battery_area) {
+        render_battery(f, area, battery, &app.theme);
+    }
+}
+
+/// The CPU and memory gauges of the header.
+fn usage_gauges(source: &dyn Source, cpu_title: String, mem_title: String, theme: &Theme) -> (Shade<Gauge<'static>>, Shade<Gauge<'static>>) {
+    let cpu_usage = source.cpu_usage().clamp(0.0, 100.0);
+    let mem_usage = source.memory_percent().min(100.0);
+
+    // On truecolor terminals the bars shade from green to red as they fill.
+    let cpu_gauge = Gauge::default()
+        .block(Block::default().title(cpu_title).borders(Borders::ALL))
+        .gauge_style(Style::default().fg(theme.cpu_gauge))
+        .percent(cpu_usage.round() as u16);
+
+    let mem_gauge = Gauge::default()
+        .block(Block::default().title(mem_title).borders(Borders::ALL))
+        .gauge_style(Style::default().fg(theme.memory_gauge))
+        .percent(mem_usage.round() as u16);
+    (
+        Shade::new(cpu_gauge, theme.cpu_gauge, theme.shading()).inset(1, 1),
+        Shade::new(mem_gauge, theme.memory_gauge, theme.shading()).inset(1, 1),
+    )
+}
+
+/// Swap usage, colored by `swap_warning`/`swap_critical`.
+fn render_swap<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, used: u64, total: u64, thresholds: &Thresholds, theme: &Theme) {
+    let block = Block::default().title("Swap").borders(Borders::ALL);
+    if total == 0 {
+        f.render_widget(Paragraph::new("no swap").style(Style::default().fg(theme.muted)).block(block), area);
+        return;
+    }
+    let percent = used as f64 / total as f64 * 100.0;
+    let color = if percent > thresholds.swap_critical {
+        theme.critical
+    } else if percent > thresholds.swap_warning {
+        theme.warning
+    } else {
+        theme.good
+    };
+    let gib = |bytes: u64| bytes as f64 / 1024.0 / 1024.0 / 1024.0;
+    let gauge = Gauge::default()
+        .block(block)
+        .gauge_style(Style::default().fg(color))
+        .label(format!("{:.0}% {:.1}/{:.1} GiB", percent, gib(used), gib(total)))
+        .ratio((percent / 100.0).clamp(0.0, 1.0));
+    f.render_widget(Shade::new(gauge, color, theme.shading()).inset(1, 1), area);
+}
+
+/// Popup with the result of a `[[actions]]` command, or a note while it runs.
+fn render_action_output<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, runner: &ActionRunner, theme: &Theme, redact: &Redactor) {
+    let (title, text, color) = match (&runner.finished, runner.running()) {
+        (_, Some(name)) => (name.to_string(), vec![Spans::from("Running...")], theme.warning),
+        (Some(execution), None) => {
+            let (status, color) = match &execution.outcome {
+                Outcome::Exited(0) => ("exited with 0".to_string(), theme.good),
+                Outcome::Exited(code) => (format!("exited with {}", code), theme.critical),
+                Outcome::Signaled => ("killed by a signal".to_string(), theme.critical),
+                Outcome::TimedOut => ("timed out, killed".to_string(), theme.critical),
+                Outcome::FailedToStart(err) => (format!("failed to start: {}", err), theme.critical),
+            };
+            let argv: Vec<String> = execution
+                .argv
+                .iter()
+                .map(|arg| if arg.contains(char::is_whitespace) { format!("'{}'", arg) } else { arg.clone() })
+                .collect();
+            let mut text = vec![
+                Spans::from(Span::styled(format!("$ {}", redact.command(&argv.join(" "))), Style::default().fg(theme.muted))),
+                Spans::from(Span::styled(
+                    format!("{} after {:.1}s", status, execution.duration.as_secs_f64()),
+                    Style::default().fg(color),
+                )),
+                Spans::from(""),
+            ];
+            // The terminal backend does not expand tabs.
+            text.extend(execution.stdout.lines().map(|line| Spans::from(line.replace('\t', "    "))));
+            text.extend(execution.stderr.lines().map(|line| {
+                Spans::from(Span::styled(line.replace('\t', "    "), Style::default().fg(theme.critical)))
+            }));
+            (execution.title.clone(), text, color)
+        }
+        (None, None) => (String::new(), Vec::new(), theme.muted),
+    };
+    f.render_widget(Clear, area);
+    let paragraph = Paragraph::new(text)
+        .block(Block::default().title(title).borders(Borders::ALL).border_style(Style::default().fg(color)))
+        .wrap(Wrap { trim: false });
+    f.render_widget(paragraph, area);
+}
+
+/// The processes shown in the table, and their cgroups summed, that would
+/// exceed `limit` at their current usage.
+fn render_what_if<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, app: &App, limit: whatif::Limit) {
+    let samples: Vec<whatif::Sample> = app
+        .visible_processes()
+        .iter()
+        .map(|(pid, process)| whatif::Sample {
+            pid: *pid,
+            name: app.redact.process(process.name()).into_owned(),
+            cgroup: whatif::cgroup(*pid),
+            cpu: process.cpu_usage() as f64,
+            memory: process.memory(),
+        })
+        .collect();
+    let report = whatif::Report::of(limit, &samples);
+    let block = Block::default()
+        .title(format!("What if limited to {}", limit.describe()))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(app.theme.highlight));
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(50), // Cgroups
+            Constraint::Percentage(50), // Processes
+        ])
+        .split(inner);
+    let over = |excess: &whatif::Excess| {
+        let color = if excess.ratio >= 2.0 { app.theme.critical } else { app.theme.warning };
+        Cell::from(format!("{:.0}%", (excess.ratio - 1.0) * 100.0)).style(Style::default().fg(color))
+    };
+    let table = |title: String, header: [&'static str; 4], rows: Vec<Row<'static>>| {
+        Table::new(rows)
+            .header(Row::new(header.to_vec()).style(Style::default().fg(app.theme.muted)))
+            .block(Block::default().title(title).borders(Borders::TOP))
+            .widths(&[
+                Constraint::Min(30),    // Name
+                Constraint::Length(10), // Processes or PID
+                Constraint::Length(12), // Usage
+                Constraint::Length(8),  // Over by
+            ])
+    };
+    let cgroups: Vec<Row> = report
+        .cgroups
+        .iter()
+        .map(|excess| {
+            Row::new(vec![
+                Cell::from(excess.name.clone()),
+                Cell::from(excess.processes.to_string()),
+                Cell::from(limit.format(excess.usage)),
+                over(excess),
+            ])
+        })
+        .collect();
+    let processes: Vec<Row> = report
+        .processes
+        .iter()
+        .map(|excess| {
+            Row::new(vec![
+                Cell::from(excess.name.clone()),
+                Cell::from(excess.pid.map_or(String::new(), |pid| pid.to_string())),
+                Cell::from(limit.format(excess.usage)),
+                over(excess),
+            ])
+        })
+        .collect();
+    let title = |one: &str, many: &str, count: usize| match count {
+        0 => format!("No {} would exceed the limit", many),
+        1 => format!("1 {} would exceed the limit", one),
+        _ => format!("{} {} would exceed the limit", count, many),
+    };
+    f.render_widget(table(title("cgroup", "cgroups", cgroups.len()), ["Cgroup", "Processes", "Usage", "Over by"], cgroups), chunks[0]);
+    f.render_widget(table(title("process", "processes", processes.len()), ["Process", "PID", "Usage", "Over by"], processes), chunks[1]);
+}
+
+/// The controls line under the tab content, built from the active keymap.
+fn help_text(app: &App) -> String {
+    let key = |action| app.keymap.key(action);
+    if let Some(lock) = &app.lock {
+        return lock.hint();
+    }
+    let entries: Vec<String> = match (&app.state, app.tab) {
+        (AppState::Main, tab) => {
+            let mut entries = vec![
+                format!("{}/{}: Switch view", key(Action::NextTab), key(Action::PreviousTab)),
+                format!("{}: Per-core CPU", key(Action::TogglePerCore)),
+                format!("{}/{}: CPU/memory history", key(Action::ToggleCpuHistory), key(Action::ToggleMemoryHistory)),
+                format!("{}: {}", key(Action::Pause), if app.paused { "Resume" } else { "Pause" }),
+                format!("{}: Lock", key(Action::Lock)),
+            ];
+            match tab {
+                Tab::Processes => {
+                    entries.extend([
+                        format!("{}/{}: Select process", key(Action::Up), key(Action::Down)),
+                        format!("{}/{}: Page", key(Action::PageUp), key(Action::PageDown)),
+                        format!("{}: Details", key(Action::Open)),
+                        format!("{}: Sort by CPU", key(Action::SortCpu)),
+                        format!("{}: Sort by Memory", key(Action::SortMemory)),
+                        format!("{}: Sort by Name", key(Action::SortName)),
+                        format!("{}: Sort by PID", key(Action::SortPid)),
+                        format!("{}/{}: Sort by disk read/write", key(Action::SortDiskRead), key(Action::SortDiskWrite)),
+                        format!("{}: Kill menu", key(Action::KillMenu)),
+                        format!("{}/{}: Tag/untag all", key(Action::Tag), key(Action::UntagAll)),
+                        format!("{}/{}: Nice -/+", key(Action::NiceDown), key(Action::NiceUp)),
+                        format!("{}: What-if limit", key(Action::WhatIf)),
+                        format!("{}: Tree view", key(Action::ToggleTree)),
+                        format!("{}: Filter", key(Action::Filter)),
+                        format!("{}: Filter by user", key(Action::FilterUser)),
+                        format!("{}: Go to PID", key(Action::JumpToPid)),
+                        format!("{}: Columns", key(Action::ColumnSetup)),
+                        format!("{}/{}: Export CSV/JSON", key(Action::ExportCsv), key(Action::ExportJson)),
+                    ]);
+                    if app.tree {
+                        entries.push(format!("{}/{}: Fold/unfold", key(Action::Collapse), key(Action::Expand)));
+                    }
+                    if let Some(name) = &app.config.group_env {
+                        entries.push(format!("{}/{}: Group/filter by {}", key(Action::GroupEnv), key(Action::FilterGroup), name));
+                    }
+                }
+                Tab::Disks => entries.push(format!("{}: Toggle devices/partitions", key(Action::TogglePartitions))),
+                Tab::Network => entries.push(format!("{}: Hide/show virtual interfaces", key(Action::ToggleVirtualInterfaces))),
+                Tab::Cluster => entries.push(format!("{}: Pick agents", key(Action::PickAgents))),
+                Tab::Alerts => entries.extend([
+                    format!("{}/{}: Select rule", key(Action::Up), key(Action::Down)),
+                    format!("{}: Silence {} min / unsilence", key(Action::Silence), app.config.silence_minutes),
+                ]),
+                _ => {}
+            }
+            entries.extend(app.config.actions.iter().map(|a| format!("{}: {}", a.key, a.name)));
+            entries.push(format!("{}: Quit", key(Action::Quit)));
+            return format!("Controls: {}", entries.join(" | "));
+        }
+        (AppState::ProcessMenu, _) => vec![
+            format!("{}/{}: Select signal", key(Action::Up), key(Action::Down)),
+            format!("{}: Send", key(Action::Open)),
+            format!("{}: SIGINT", key(Action::SignalInterrupt)),
+            format!("{}: SIGQUIT", key(Action::SignalQuit)),
+            format!("{}: SIGTERM", key(Action::SignalTerm)),
+            format!("{}: SIGKILL", key(Action::SignalKill)),
+            format!("{}: SIGSTOP (suspend)", key(Action::SignalStop)),
+            format!("{}: SIGCONT (resume)", key(Action::SignalContinue)),
+            format!("{}: Cancel", key(Action::Close)),
+        ],
+        (AppState::Details, _) => vec![
+            format!("{}: Switch tab", key(Action::NextTab)),
+            format!("{}/{}: Select child", key(Action::Up), key(Action::Down)),
+            format!("{}: Open child / edit affinity", key(Action::Open)),
+            format!("{}: Open parent", key(Action::Parent)),
+            format!("{}: {}", key(Action::Pause), if app.paused { "Resume" } else { "Pause" }),
+            format!("{}: Close", key(Action::Close)),
+            format!("{}: Quit", key(Action::Quit)),
+        ],
+        (AppState::ActionOutput | AppState::WhatIf, _) => vec![format!("{}: Close", key(Action::Close))],
+        (AppState::Input, _) => {
+            let Some(input) = &app.input else {
+                return String::new();
+            };
+            if let Some(pending) = app.pending_signal.as_ref().filter(|_| input.prompt == Prompt::Confirm) {
+                let signal = control::signal_name(pending.signal);
+                return format!("Protected: {}. Type yes to send {}: {}█  (Enter: send | Esc: cancel)", pending.protected.join(", "), signal, input.text);
+            }
+            return format!("{}: {}█  (Enter: apply | Esc: cancel | ctrl+u: clear)", input.prompt.label(), input.text);
+        }
+        (AppState::Affinity, _) => {
+            return "Affinity: Arrows: Select CPU | Space: Allow/forbid | a: Allow all | Enter: Apply | Esc: Cancel".to_string();
+        }
+        (AppState::Hosts, _) => {
+            return "Agents: Up/Down: Select | Space: Poll/stop polling | r: Search again | Enter: Apply | Esc: Cancel".to_string();
+        }
+        (AppState::Columns, _) => {
+            return "Columns: Up/Down: Select | Space: Show/hide | shift+Up/Down or [/]: Move | Enter: Apply | Esc: Cancel".to_string();
+        }
+    };
+    let title = match app.state {
+        AppState::ProcessMenu => "Kill Menu",
+        AppState::ActionOutput => "Action",
+        AppState::WhatIf => "What if",
+        _ => "Details",
+    };
+    format!("{}: {}", title, entries.join(" | "))
+}
+
+/// The compact view `rtop attach` draws from the snapshots of a sharing instance.
+pub(crate) fn render_mirror<B: Backend>(f: &mut tui::Frame<B>, client: &MirrorClient, path: &std::path::Path) {
+    let theme = Theme::default().for_depth(ColorDepth::detect());
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3),  // Top gauges
+            Constraint::Min(3),     // Processes
+            Constraint::Length(1),  // Status
+        ])
+        .split(f.size());
+
+    let status = if !client.is_connected() {
+        Paragraph::new(format!("Disconnected from {} | q: Quit", path.display())).style(Style::default().fg(theme.critical))
+    } else {
+        Paragraph::new(format!("Mirroring {} (read-only) | q: Quit", path.display())).style(Style::default().fg(theme.muted))
+    };
+    f.render_widget(status, chunks[2]);
+
+    let Some(snapshot) = client.latest() else {
+        f.render_widget(Paragraph::new("Waiting for the first snapshot..."), chunks[1]);
+        return;
+    };
+
+    let top_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(28),  // CPU
+            Constraint::Percentage(24),  // Memory
+            Constraint::Percentage(18),  // Swap
+            Constraint::Percentage(30),  // Connections
+        ])
+        .split(chunks[0]);
+    let cpu_gauge = Gauge::default()
+        .block(Block::default().title(format!("CPU Usage ({} cores)", snapshot.per_core.len())).borders(Borders::ALL))
+        .gauge_style(Style::default().fg(theme.cpu_gauge))
+        .percent(snapshot.cpu_usage.round().clamp(0.0, 100.0) as u16);
+    let mem_usage = snapshot.used_memory as f64 / snapshot.total_memory.max(1) as f64;
+    let mem_gauge = Gauge::default()
+        .block(Block::default().title("Memory Usage").borders(Borders::ALL))
+        .gauge_style(Style::default().fg(theme.memory_gauge))
+        .percent((mem_usage * 100.0).round() as u16);
+    f.render_widget(cpu_gauge, top_layout[0]);
+    f.render_widget(mem_gauge, top_layout[1]);
+    let thresholds = Thresholds::default();
+    render_swap(f, top_layout[2], snapshot.used_swap, snapshot.total_swap, &thresholds, &theme);
+    render_connections(f, top_layout[3], &snapshot.connections, thresholds.close_wait, &theme);
+
+    let rows: Vec<Row> = snapshot
+        .processes
+        .iter()
+        .map(|process| {
+            Row::new(vec![
+                Cell::from(process.pid.to_string()),
+                Cell::from(process.name.clone()),
+                Cell::from(format!("{:.1}", process.cpu_usage)),
+                Cell::from(format!("{:.1} MB", process.memory as f64 / 1024.0 / 1024.0)),
+            ])
+        })
+        .collect();
+    let table = Table::new(rows)
+        .header(Row::new(vec!["PID", "Name", "CPU%", "RSS"]))
+        .block(Block::default().title("Processes").borders(Borders::ALL))
+        .widths(&[
+            Constraint::Length(8),    // PID
+            Constraint::Min(20),      // Name
+            Constraint::Length(8),    // CPU%
+            Constraint::Length(12),   // RSS
+        ]);
+    f.render_widget(table, chunks[1]);
+}
+
+/// Draws the process table of the snapshot `viewer` is on and returns the
+/// number of rows that fit.
+fn render_snapshot_table<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, viewer: &mut view::Viewer, title: String, config: &Config, theme: &Theme) -> usize {
+    let rows = area.height.saturating_sub(3) as usize;
+    viewer.offset = scroll_offset(viewer.offset, Some(viewer.selected), rows);
+    let thresholds = &config.thresholds;
+    let processes = viewer.processes();
+    let table_rows: Vec<Row> = processes
+        .iter()
+        .enumerate()
+        .skip(viewer.offset)
+        .take(rows)
+        .map(|(i, process)| {
+            let cpu_color = if process.cpu_usage > thresholds.process_cpu_critical {
+                theme.critical
+            } else if process.cpu_usage > thresholds.process_cpu_warning {
+                theme.warning
+            } else {
+                theme.good
+            };
+            let style = if i == viewer.selected { theme.focused() } else { theme.row(i, config.layout.zebra) };
+            Row::new(vec![
+                Cell::from(process.pid.to_string()),
+                Cell::from(text::truncate(process.user.as_deref().unwrap_or("-"), 10)),
+                Cell::from(text::truncate(&process.name, 20)),
+                Cell::from(format!("{:.1}", process.cpu_usage)).style(Style::default().fg(cpu_color)),
+                Cell::from(config.format_number(Column::Rss, process.memory as f64)),
+                Cell::from(config.format_number(Column::Virtual, process.virtual_memory as f64)),
+                Cell::from(text::truncate(&process.command, area.width.saturating_sub(77) as usize)),
+            ])
+            .style(style)
+        })
+        .collect();
+    let column = viewer.sort_by.column();
+    let arrow = if viewer.sort_by.descending() != viewer.sort_reversed { "▼" } else { "▲" };
+    let header = [Column::Pid, Column::User, Column::Name, Column::Cpu, Column::Rss, Column::Virtual, Column::Command]
+        .map(|c| if c == column { format!("{} {}", c.title(), arrow) } else { c.title().to_string() });
+    let table = Table::new(table_rows)
+        .header(Row::new(header.to_vec()))
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .widths(&[
+            Constraint::Length(8),    // PID
+            Constraint::Length(10),   // User
+            Constraint::Length(20),   // Name
+            Constraint::Length(8),    // CPU%
+            Constraint::Length(12),   // RSS
+            Constraint::Length(12),   // Virtual
+            Constraint::Min(20),      // Command
+        ]);
+    f.render_widget(table, area);
+    rows
+}
+
+/// Draws the snapshot `viewer` is on and returns the number of process rows
+/// that fit, for paging.
+pub(crate) fn render_view<B: Backend>(f: &mut tui::Frame<B>, viewer: &mut view::Viewer, path: &std::path::Path, keymap: &Keymap, config: &Config, theme: &Theme) -> usize {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),  // Summary
+            Constraint::Length(3),  // Gauges
+            Constraint::Min(3),     // Processes
+            Constraint::Length(1),  // Help
+        ])
+        .split(f.size());
+    let snapshot = viewer.snapshot();
+    let load = snapshot.load_average;
+    let summary = format!(
+        " {} | {} | up {} | load average: {:.2}, {:.2}, {:.2} | {} processes",
+        snapshot.hostname.as_deref().unwrap_or("unknown host"),
+        snapshot.timestamp,
+        summary::format_uptime(snapshot.uptime),
+        load[0],
+        load[1],
+        load[2],
+        snapshot.processes.len()
+    );
+    f.render_widget(Paragraph::new(summary), chunks[0]);
+
+    let gauges = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(40),  // CPU
+            Constraint::Percentage(35),  // Memory
+            Constraint::Percentage(25),  // Swap
+        ])
+        .split(chunks[1]);
+    let cpu_gauge = Gauge::default()
+        .block(Block::default().title(format!("CPU Usage ({} cores)", snapshot.cpu.cores.len())).borders(Borders::ALL))
+        .gauge_style(Style::default().fg(theme.cpu_gauge))
+        .percent(snapshot.cpu.usage.round().clamp(0.0, 100.0) as u16);
+    let memory = &snapshot.memory;
+    let mem_gauge = Gauge::default()
+        .block(Block::default().title("Memory Usage").borders(Borders::ALL))
+        .gauge_style(Style::default().fg(theme.memory_gauge))
+        .percent((memory.used as f64 / memory.total.max(1) as f64 * 100.0).round().min(100.0) as u16);
+    f.render_widget(cpu_gauge, gauges[0]);
+    f.render_widget(mem_gauge, gauges[1]);
+    render_swap(f, gauges[2], memory.swap_used, memory.swap_total, &config.thresholds, theme);
+
+    let title = if viewer.snapshots.len() > 1 {
+        format!("{} (snapshot {} of {})", path.display(), viewer.index + 1, viewer.snapshots.len())
+    } else {
+        path.display().to_string()
+    };
+    let rows = render_snapshot_table(f, chunks[2], viewer, title, config, theme);
+
+    let key = |action| keymap.key(action);
+    let help = format!(
+        "Viewing a saved snapshot | {}/{}/{}/{}: Select | {}/{}: Previous/next snapshot | {}/{}/{}/{}: Sort | {}: Quit",
+        key(Action::Up), key(Action::Down), key(Action::PageUp), key(Action::PageDown), key(Action::StepBack), key(Action::StepForward),
+        key(Action::SortCpu), key(Action::SortMemory), key(Action::SortName), key(Action::SortPid), key(Action::Quit)
+    );
+    f.render_widget(Paragraph::new(help).style(Style::default().fg(theme.muted)), chunks[3]);
+    rows
+}
+
+/// The CPU Trend sparkline of `pid`, in `color` or, on truecolor terminals,
+/// as a heatmap with each bar shaded by its own usage.
+fn trend_cell(trends: &sparkline::CpuTrends, pid: Pid, color: tui::style::Color, theme: &Theme) -> Cell<'static> {
+    let line = trends.sparkline(pid);
+    if theme.shading().is_none() {
+        return Cell::from(line).style(Style::default().fg(color));
+    }
+    let samples = trends.samples(pid);
+    let padding = sparkline::WIDTH.saturating_sub(samples.len());
+    let bars: Vec<Span> = line
+        .chars()
+        .enumerate()
+        .map(|(i, bar)| {
+            let usage = i.checked_sub(padding).map_or(0.0, |i| samples[i]);
+            Span::styled(bar.to_string(), Style::default().fg(theme.level(usage as f64 / 100.0)))
+        })
+        .collect();
+    Cell::from(Spans::from(bars))
+}
+
+/// A recorded duration as `H:MM:SS`.
+fn format_position(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+/// Draws the sample `replay` is on like the live header and process table,
+/// and returns the number of process rows that fit, for paging.
+pub(crate) fn render_replay<B: Backend>(f: &mut tui::Frame<B>, replay: &mut replay::Replay, path: &std::path::Path, layout: &config::LayoutConfig, keymap: &Keymap, config: &Config, theme: &Theme) -> usize {
+    let snapshot = replay.viewer.snapshot();
+    let per_core_height = if layout.show_per_core { per_core_height(snapshot.cpu.cores.len(), f.size().width) } else { 0 };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(1),  // Summary line
+            Constraint::Length(3),  // Gauges
+            Constraint::Length(per_core_height),  // Per-core gauges
+            Constraint::Length(if layout.cpu_history || layout.memory_history { HISTORY_HEIGHT } else { 0 }),  // History charts
+            Constraint::Length(1),  // Playback
+            Constraint::Min(3),     // Processes
+            Constraint::Length(1),  // Help
+        ].as_ref())
+        .split(f.size());
+    render_summary(f, chunks[0], snapshot, None, theme);
+
+    let gauges = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(40),  // CPU
+            Constraint::Percentage(35),  // Memory
+            Constraint::Percentage(25),  // Swap
+        ])
+        .split(chunks[1]);
+    let cpu_title = format!("CPU Usage ({} cores)", snapshot.cpu.cores.len());
+    let (cpu_gauge, mem_gauge) = usage_gauges(snapshot, cpu_title, "Memory Usage".to_string(), theme);
+    f.render_widget(cpu_gauge, gauges[0]);
+    f.render_widget(mem_gauge, gauges[1]);
+    let memory = &snapshot.memory;
+    render_swap(f, gauges[2], memory.swap_used, memory.swap_total, &config.thresholds, theme);
+    if layout.show_per_core {
+        render_per_core(f, chunks[2], snapshot, &config.thresholds, theme);
+    }
+    if layout.cpu_history || layout.memory_history {
+        let histories = replay.histories(Duration::from_secs(layout.history_seconds));
+        render_history(f, chunks[3], &histories, (layout.cpu_history, layout.memory_history), memory.swap_total > 0, theme);
+    }
+
+    let taken = chrono::DateTime::parse_from_rfc3339(&snapshot.timestamp)
+        .map_or_else(|_| snapshot.timestamp.clone(), |time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string());
+    let (position, length) = replay.position();
+    let state = if replay.playing {
+        Span::styled(format!("▶ Playing {}x", replay.speed()), Style::default().fg(theme.good).add_modifier(Modifier::BOLD))
+    } else {
+        Span::styled(format!("⏸ Paused ({}x)", replay.speed()), Style::default().fg(theme.warning).add_modifier(Modifier::BOLD))
+    };
+    let playback = Spans::from(vec![
+        state,
+        Span::raw(format!(" | {} | {} of {}", taken, format_position(position), format_position(length))),
+    ]);
+    f.render_widget(Paragraph::new(playback), chunks[4]);
+
+    let viewer = &mut replay.viewer;
+    let title = format!("{} (sample {} of {})", path.display(), viewer.index + 1, viewer.snapshots.len());
+    let rows = render_snapshot_table(f, chunks[5], viewer, title, config, theme);
+
+    let key = |action| keymap.key(action);
+    let help = format!(
+        "Replaying a recording | {}: Play/pause | {}/{}: Step | {}/{}: -/+1 min | {}/{}: -/+10 min | {}/{}: Start/end | {}/{}: Speed | {}/{}/{}: Per-core/history | {}/{}/{}/{}: Sort | {}: Quit",
+        key(Action::Play), key(Action::StepBack), key(Action::StepForward), key(Action::SeekBack), key(Action::SeekForward),
+        key(Action::SeekBackFar), key(Action::SeekForwardFar), key(Action::First), key(Action::Last), key(Action::Faster), key(Action::Slower),
+        key(Action::TogglePerCore), key(Action::ToggleCpuHistory), key(Action::ToggleMemoryHistory),
+        key(Action::SortCpu), key(Action::SortMemory), key(Action::SortName), key(Action::SortPid), key(Action::Quit)
+    );
+    f.render_widget(Paragraph::new(help).style(Style::default().fg(theme.muted)), chunks[6]);
+    rows
+}
+
+fn render_connections<B: Backend>(
+    f: &mut tui::Frame<B>,
+    area: tui::layout::Rect,
+    connections: &ConnectionSummary,
+    close_wait_alert: usize,
+    theme: &Theme,
+) {
+    let pile_up = connections.close_wait >= close_wait_alert;
+    let (title, border_style) = if pile_up {
+        ("TCP - CLOSE_WAIT pile-up!", Style::default().fg(theme.critical))
+    } else {
+        ("TCP Connections", Style::default())
+    };
+    let close_wait_style = if pile_up {
+        Style::default().fg(theme.critical)
+    } else {
+        Style::default()
+    };
+    let text = Spans::from(vec![
+        Span::raw(format!("EST {}  LISTEN {}  TW {}  ", connections.established, connections.listen, connections.time_wait)),
+        Span::styled(format!("CW {}", connections.close_wait), close_wait_style),
+        Span::raw(format!("  other {}", connections.other)),
+    ]);
+    let widget = Paragraph::new(text).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(border_style),
+    );
+    f.render_widget(widget, area);
+}
+
+/// Height of the per-core bars for `cores` cores in a terminal `width` wide.
+fn per_core_height(cores: usize, width: u16) -> u16 {
+    let columns = (width.saturating_sub(4) / CORE_CELL_WIDTH).max(1) as usize;
+    cores.div_ceil(columns) as u16 + 2
+}
+
+fn render_per_core<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, source: &dyn Source, thresholds: &Thresholds, theme: &Theme) {
+    let block = Block::default().title("CPU Cores").borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let columns = (inner.width / CORE_CELL_WIDTH).max(1) as usize;
+    for (i, usage) in source.cores().into_iter().enumerate() {
+        let (row, column) = (i / columns, i % columns);
+        if row as u16 >= inner.height {
+            break;
+        }
+        let cell = tui::layout::Rect {
+            x: inner.x + column as u16 * CORE_CELL_WIDTH,
+            y: inner.y + row as u16,
+            width: CORE_CELL_WIDTH.saturating_sub(1).min(inner.width),
+            height: 1,
+        };
+        let color = if usage > thresholds.core_critical {
+            theme.critical
+        } else if usage > thresholds.core_warning {
+            theme.warning
+        } else {
+            theme.good
+        };
+        let label = format!("{:<3}{:>4.0}%", i, usage);
+        // The line starts one column after the label.
+        let track = label.chars().count() as u16 + 1;
+        let gauge = LineGauge::default()
+            .gauge_style(Style::default().fg(color))
+            .label(label)
+            .ratio((usage as f64 / 100.0).clamp(0.0, 1.0));
+        f.render_widget(Shade::new(gauge, color, theme.shading()).inset(track, 0), cell);
+    }
+}
+
+fn render_gpus<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, app: &App) {
+    let thresholds = &app.config.thresholds;
+    let rows: Vec<Row> = app
+        .gpu
+        .gpus
+        .iter()
+        .enumerate()
+        .map(|(i, gpu)| {
+            let utilization = match gpu.utilization {
+                Some(usage) => {
+                    let color = if usage as f32 > thresholds.core_critical {
+                        app.theme.critical
+                    } else if usage as f32 > thresholds.core_warning {
+                        app.theme.warning
+                    } else {
+                        app.theme.good
+                    };
+                    Cell::from(format!("{:>3}%", usage)).style(Style::default().fg(color))
+                }
+                None => Cell::from("   -"),
+            };
+            let temperature = match gpu.temperature {
+                Some(celsius) => {
+                    let color = if celsius >= gpu::TEMPERATURE_CRITICAL {
+                        app.theme.critical
+                    } else if celsius >= gpu::TEMPERATURE_WARNING {
+                        app.theme.warning
+                    } else {
+                        app.theme.good
+                    };
+                    Cell::from(format!("{}°C", celsius)).style(Style::default().fg(color))
+                }
+                None => Cell::from("-"),
+            };
+            Row::new(vec![
+                Cell::from(i.to_string()),
+                Cell::from(gpu.name.clone()),
+                utilization,
+                // Integrated GPUs share system memory and report no VRAM.
+                if gpu.memory_total == 0 {
+                    Cell::from("-")
+                } else {
+                    Cell::from(format!(
+                        "{:.1} / {:.1} GB ({:.0}%)",
+                        gpu.memory_used as f64 / 1024.0 / 1024.0 / 1024.0,
+                        gpu.memory_total as f64 / 1024.0 / 1024.0 / 1024.0,
+                        gpu.memory_ratio() * 100.0
+                    ))
+                },
+                temperature,
+                Cell::from(gpu.power.map_or("-".to_string(), |watts| format!("{:.0} W", watts))),
+            ])
+        })
+        .collect();
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Min(20),
+        Constraint::Length(6),
+        Constraint::Length(26),
+        Constraint::Length(6),
+        Constraint::Length(6),
+    ];
+    let table = Table::new(rows)
+        .header(Row::new(vec!["#", "GPU", "Util", "VRAM", "Temp", "Power"]).style(Style::default().fg(app.theme.muted)))
+        .block(Block::default().title("GPUs").borders(Borders::ALL))
+        .widths(&widths);
+    f.render_widget(table, area);
+}
+
+/// Widths of the process table columns in a row `width` columns wide. The
+/// command line takes whatever the other columns leave.
+fn process_table_widths(app: &App, width: u16) -> Vec<Constraint> {
+    let columns = app.columns();
+    let fixed = |column: &Column| match column {
+        Column::Pid => 8,
+        Column::Name | Column::Command => 20,
+        Column::User => 10,
+        Column::Group => 16,
+        Column::State => 2,
+        Column::Wchan => 20,
+        Column::CpuTrend => sparkline::WIDTH as u16,
+        Column::Nice => 3,
+        Column::Threads => 4,
+        Column::Cpu | Column::GpuUsage => 8,
+        Column::Rss | Column::Virtual | Column::Private | Column::Swap | Column::GpuMemory => 12,
+        Column::DiskRead | Column::DiskWrite | Column::NetSent | Column::NetReceived => 12,
+    };
+    let used: u16 = columns.iter().filter(|column| **column != Column::Command).map(|column| fixed(column) + 1).sum();
+    columns
+        .iter()
+        .map(|column| match column {
+            Column::Name => Constraint::Min(20),
+            Column::Command => Constraint::Length(width.saturating_sub(used).max(20)),
+            _ => Constraint::Length(fixed(column)),
+        })
+        .collect()
+}
+
+/// Screen x of each column of the process table drawn in `area`, laid out
+/// the way `Table` does it (one column of spacing between cells).
+pub(crate) fn process_table_columns(app: &App, area: tui::layout::Rect) -> Vec<(Column, u16)> {
+    let inner = Block::default().borders(Borders::ALL).inner(area);
+    let mut constraints = Vec::new();
+    for width in process_table_widths(app, inner.width) {
+        constraints.extend([width, Constraint::Length(1)]);
+    }
+    constraints.pop();
+    // Table leaves the rest of the row empty instead of stretching its columns.
+    constraints.push(Constraint::Min(0));
+    let chunks = Layout::default().direction(Direction::Horizontal).constraints(constraints).split(inner);
+    app.columns().into_iter().zip(chunks.iter().step_by(2).map(|chunk| chunk.x)).collect()
+}
+
+/// Terminal columns available to `column` of the process table drawn in `area`.
+fn process_column_width(app: &App, area: tui::layout::Rect, column: Column) -> usize {
+    let columns = process_table_columns(app, area);
+    let inner_right = Block::default().borders(Borders::ALL).inner(area).right();
+    let Some(index) = columns.iter().position(|(c, _)| *c == column) else {
+        return 0;
+    };
+    // The next column starts after one column of spacing.
+    let end = columns.get(index + 1).map_or(inner_right, |(_, x)| x.saturating_sub(1));
+    end.saturating_sub(columns[index].1) as usize
+}
+
+/// The first row to show so that `selected` stays within `rows` visible rows,
+/// scrolling as little as possible.
+fn scroll_offset(offset: usize, selected: Option<usize>, rows: usize) -> usize {
+    match selected {
+        Some(selected) if selected < offset => selected,
+        Some(selected) if rows > 0 && selected >= offset + rows => selected + 1 - rows,
+        _ => offset,
+    }
+}
+
+fn render_process_table<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, app: &App) {
+    let processes = app.visible_processes();
+    let tree_rows = if app.tree { app.tree_rows() } else { Vec::new() };
+    let columns = app.columns();
+    let format = |column, value| app.config.format_number(column, value);
+    let name_width = process_column_width(app, area, Column::Name);
+    let command_width = process_column_width(app, area, Column::Command);
+    let zebra = app.zebra();
+    let process_rows: Vec<Row> = processes
+        .iter()
+        .enumerate()
+        .skip(app.process_table_offset)
+        .map(|(i, (pid, process))| {
+            let selected = app.selected_process == Some(i);
+            // Suspended processes are greyed out until they get SIGCONT.
+            let stopped = process.status() == sysinfo::ProcessStatus::Stop;
+            // So are processes stuck in uninterruptible sleep, in red.
+            let stuck = app.d_state_stuck(*pid);
+            let row = app.theme.row(i, zebra);
+            let style = match (selected, stopped) {
+                (true, _) if app.state == AppState::Main => app.theme.focused(),
+                (true, _) => app.theme.selected(),
+                (false, true) => row.fg(app.theme.muted),
+                (false, false) if stuck => row.fg(app.theme.critical),
+                // So are processes breaking a firing alert rule.
+                (false, false) => match app.alert_rules.offending(*pid, &app.firing) {
+                    Some(health::Severity::Critical) => row.fg(app.theme.critical),
+                    Some(_) => row.fg(app.theme.warning),
+                    None => row,
+                },
+            };
+            // Tagged rows stand out in the accent color, like htop's yellow.
+            let style = if app.tagged.contains(pid) { style.fg(app.theme.accent).add_modifier(Modifier::BOLD) } else { style };
+            let cpu_usage = process.cpu_usage();
+            let memory_usage = process.memory() as f64 / 1024.0 / 1024.0;
+            let thresholds = &app.config.thresholds;
+
+            // GPU% is colored by the CPU% thresholds, so GPU hogs stand out alike.
+            let busy_color = |usage: f32| {
+                if stopped {
+                    app.theme.muted
+                } else if usage > thresholds.process_cpu_critical {
+                    app.theme.critical
+                } else if usage > thresholds.process_cpu_warning {
+                    app.theme.warning
+                } else {
+                    app.theme.good
+                }
+            };
+            let cpu_color = busy_color(cpu_usage);
+            // Values far above the process's own recent norm are shown reversed.
+            let anomaly = app.per_process.anomalies.get(*pid);
+            let flag = |style: Style, anomalous: bool| if anomalous { style.add_modifier(Modifier::REVERSED) } else { style };
+
+            let mem_color = if stopped {
+                app.theme.muted
+            } else if memory_usage > thresholds.process_memory_critical {
+                app.theme.critical
+            } else if memory_usage > thresholds.process_memory_warning {
+                app.theme.warning
+            } else {
+                app.theme.good
+            };
+
+            let cells: Vec<Cell> = columns
+                .iter()
+                .map(|column| match column {
+                    Column::Pid => Cell::from(pid.to_string()),
+                    Column::Name => {
+                        let name = app.redact.process(process.name());
+                        let name = match tree_rows.get(i) {
+                            Some(row) => format!("{}{}", row.prefix(), name),
+                            None => name.into_owned(),
+                        };
+                        let name = match app.per_process.emulated.get(pid) {
+                            Some(emulator) => format!("{} [{}]", name, emulator.label()),
+                            None => name,
+                        };
+                        let name = if stopped { format!("{} (stopped)", name) } else { name };
+                        let name = if stuck { format!("{} (stuck)", name) } else { name };
+                        Cell::from(text::truncate(&name, name_width))
+                    }
+                    Column::User => Cell::from(text::truncate(&app.user_name(process), 10)),
+                    Column::Group => Cell::from(text::truncate(app.env_group(process).unwrap_or("-"), 16)),
+                    Column::State if stopped => {
+                        Cell::from("T").style(Style::default().fg(app.theme.warning).add_modifier(Modifier::BOLD))
+                    }
+                    Column::State if stuck => {
+                        Cell::from("D").style(Style::default().fg(app.theme.critical).add_modifier(Modifier::BOLD))
+                    }
+                    Column::State => Cell::from(summary::state_letter(process.status()).to_string()),
+                    Column::Wchan => Cell::from(text::truncate(app.per_process.wchans.get(pid).map_or("-", String::as_str), 20)),
+                    Column::Nice => match control::nice(*pid) {
+                        Ok(nice) => Cell::from(nice.to_string()),
+                        Err(_) => Cell::from("-"),
+                    },
+                    Column::Threads => match app.per_process.statuses.get(pid).and_then(|status| status.threads) {
+                        Some(threads) => Cell::from(threads.to_string()),
+                        None => Cell::from("-"),
+                    },
+                    Column::Command => {
+                        let command = app.redact.command(&process.cmd().join(" ")).into_owned();
+                        // Kernel threads have no command line; ps shows their name in brackets.
+                        let command = if command.is_empty() { format!("[{}]", app.redact.process(process.name())) } else { command };
+                        Cell::from(text::truncate(&command, command_width))
+                    }
+                    Column::Cpu => {
+                        Cell::from(format(*column, cpu_usage as f64)).style(flag(Style::default().fg(cpu_color), anomaly.cpu))
+                    }
+                    Column::CpuTrend => trend_cell(&app.cpu_trends, *pid, cpu_color, &app.theme),
+                    Column::Rss => {
+                        Cell::from(format(*column, process.memory() as f64)).style(flag(Style::default().fg(mem_color), anomaly.memory))
+                    }
+                    Column::Private => Cell::from(format(*column, app.private_memory(*pid, process) as f64)),
+                    Column::Swap => match app.per_process.statuses.get(pid).and_then(|status| status.swap) {
+                        Some(bytes) => Cell::from(format(*column, bytes as f64)),
+                        None => Cell::from("-"),
+                    },
+                    Column::Virtual => {
+                        Cell::from(format(*column, process.virtual_memory() as f64)).style(Style::default().fg(mem_color))
+                    }
+                    Column::GpuUsage => match app.gpu.process_usage.get(pid) {
+                        Some(percent) => {
+                            Cell::from(format(*column, *percent)).style(Style::default().fg(busy_color(*percent as f32)))
+                        }
+                        None => Cell::from("-"),
+                    },
+                    Column::GpuMemory => match app.gpu.process_memory.get(pid) {
+                        Some(bytes) => Cell::from(format(*column, *bytes as f64)),
+                        None => Cell::from("-"),
+                    },
+                    Column::DiskRead => Cell::from(format(*column, app.per_process.disk_io.rate(*pid).read)),
+                    Column::DiskWrite => Cell::from(format(*column, app.per_process.disk_io.rate(*pid).write)),
+                    Column::NetSent => Cell::from(format(*column, app.per_process.net_io.rate(*pid).sent)),
+                    Column::NetReceived => Cell::from(format(*column, app.per_process.net_io.rate(*pid).received)),
+                })
+                .collect();
+            Row::new(cells).style(style)
+        })
+        .collect();
+
+    let totals = ProcessTotals::of(app, &processes);
+    let total_cells: Vec<Cell> = columns
+        .iter()
+        .map(|column| match column {
+            Column::Pid | Column::User | Column::Group | Column::State | Column::Wchan | Column::CpuTrend | Column::Nice | Column::Command => {
+                Cell::from("")
+            }
+            Column::Name => Cell::from(format!("Total: {} processes", totals.count)),
+            Column::Threads => Cell::from(totals.threads.to_string()),
+            Column::Cpu => Cell::from(format(*column, totals.cpu as f64)),
+            Column::Rss => Cell::from(format(*column, totals.memory as f64)),
+            Column::Private => Cell::from(format(*column, totals.private as f64)),
+            Column::Swap => Cell::from(format(*column, totals.swap as f64)),
+            Column::Virtual => Cell::from(format(*column, totals.virtual_memory as f64)),
+            Column::GpuUsage => Cell::from(format(*column, totals.gpu)),
+            Column::GpuMemory => Cell::from(format(*column, totals.gpu_memory as f64)),
+            Column::DiskRead => Cell::from(format(*column, totals.disk_read)),
+            Column::DiskWrite => Cell::from(format(*column, totals.disk_write)),
+            Column::NetSent => Cell::from(format(*column, totals.net_sent)),
+            Column::NetReceived => Cell::from(format(*column, totals.net_received)),
+        })
+        .collect();
+
+    let widths = process_table_widths(app, Block::default().borders(Borders::ALL).inner(area).width);
+    let mut notes = Vec::new();
+    if app.tree {
+        notes.push("tree".to_string());
+    }
+    if !app.filter.is_empty() {
+        notes.push(format!("filter: {}", app.filter));
+    }
+    if let Some(user) = &app.user_filter {
+        notes.push(format!("user: {}", app.redact.user(user)));
+    }
+    let group_env = app.config.group_env.as_deref().unwrap_or_default();
+    if let Some(group) = &app.group_filter {
+        notes.push(format!("{}={}", group_env, group));
+    } else if app.group_by_env {
+        notes.push(format!("grouped by {}", group_env));
+    }
+    let title = if notes.is_empty() { "Processes".to_string() } else { format!("Processes ({})", notes.join(", ")) };
+    let block = Block::default().title(title).borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),     // Processes
+            Constraint::Length(1),  // Totals
+        ].as_ref())
+        .split(inner);
+    let sort = app.comparators.get(&app.sort_by);
+    let process_table = Table::new(process_rows)
+        .header(Row::new(columns.iter().map(|column| {
+            let Some(sort) = sort.filter(|sort| sort.column == Some(*column)) else {
+                return column.title().to_string();
+            };
+            let arrow = if sort.descending != app.sort_reversed { "▼" } else { "▲" };
+            format!("{} {}", column.title(), arrow)
+        })))
+        .widths(&widths);
+    f.render_widget(process_table, chunks[0]);
+    let totals_table = Table::new(vec![Row::new(total_cells)])
+        .style(Style::default().add_modifier(Modifier::BOLD))
+        .widths(&widths);
+    f.render_widget(totals_table, chunks[1]);
+}
+
+/// Sums over the rows of the process table, shown in a row pinned below it.
+struct ProcessTotals {
+    count: usize,
+    cpu: f32,
+    memory: u64,
+    private: u64,
+    swap: u64,
+    threads: u64,
+    virtual_memory: u64,
+    gpu: f64,
+    gpu_memory: u64,
+    disk_read: f64,
+    disk_write: f64,
+    net_sent: f64,
+    net_received: f64,
+}
+
+impl ProcessTotals {
+    fn of(app: &App, rows: &[(Pid, &sysinfo::Process)]) -> ProcessTotals {
+        ProcessTotals {
+            count: rows.len(),
+            cpu: rows.iter().map(|(_, process)| process.cpu_usage()).sum(),
+            memory: rows.iter().map(|(_, process)| process.memory()).sum(),
+            private: rows.iter().map(|(pid, process)| app.private_memory(*pid, process)).sum(),
+            swap: rows.iter().filter_map(|(pid, _)| app.per_process.statuses.get(pid)?.swap).sum(),
+            threads: rows.iter().filter_map(|(pid, _)| app.per_process.statuses.get(pid)?.threads).sum(),
+            virtual_memory: rows.iter().map(|(_, process)| process.virtual_memory()).sum(),
+            gpu: rows.iter().filter_map(|(pid, _)| app.gpu.process_usage.get(pid)).sum(),
+            gpu_memory: rows.iter().filter_map(|(pid, _)| app.gpu.process_memory.get(pid)).sum(),
+            disk_read: rows.iter().map(|(pid, _)| app.per_process.disk_io.rate(*pid).read).sum(),
+            disk_write: rows.iter().map(|(pid, _)| app.per_process.disk_io.rate(*pid).write).sum(),
+            net_sent: rows.iter().map(|(pid, _)| app.per_process.net_io.rate(*pid).sent).sum(),
+            net_received: rows.iter().map(|(pid, _)| app.per_process.net_io.rate(*pid).received).sum(),
+        }
+    }
+}
+
+fn render_interrupts<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, app: &App) {
+    // Fixed columns plus the minimum source width, each followed by one space of column spacing.
+    const FIXED_WIDTH: u16 = 9 + 11 + 9 + 8 + 24;
+    const CPU_WIDTH: u16 = 8;
+    let monitor = &app.interrupts;
+    // Show as many per-CPU columns as fit; the busiest-CPU column covers the rest.
+    let inner_width = area.width.saturating_sub(2);
+    let cpu_columns = (inner_width.saturating_sub(FIXED_WIDTH) / (CPU_WIDTH + 1)) as usize;
+    let cpu_columns = cpu_columns.min(monitor.cpu_count);
+    let source_width = inner_width
+        .saturating_sub(FIXED_WIDTH - 24)
+        .saturating_sub(cpu_columns as u16 * (CPU_WIDTH + 1));
+
+    let rows: Vec<Row> = monitor
+        .rates
+        .iter()
+        .map(|rate| {
+            let busiest = rate.busiest_cpu();
+            // A busy source pinned to a single CPU is the classic NIC IRQ imbalance.
+            let style = match busiest {
+                Some((_, share)) if share > 0.9 && rate.total > 1000.0 && monitor.cpu_count > 1 => {
+                    Style::default().fg(app.theme.warning)
+                }
+                _ => Style::default(),
+            };
+            let mut cells = vec![
+                Cell::from(rate.irq.clone()),
+                Cell::from(format!("{:.0}", rate.total)),
+                Cell::from(busiest.map_or("-".to_string(), |(cpu, _)| format!("CPU{}", cpu))),
+                Cell::from(busiest.map_or("-".to_string(), |(_, share)| format!("{:.0}%", share * 100.0))),
+            ];
+            cells.extend((0..cpu_columns).map(|cpu| {
+                Cell::from(rate.per_cpu.get(cpu).map_or(String::new(), |r| format!("{:.0}", r)))
+            }));
+            cells.push(Cell::from(rate.description.clone()));
+            Row::new(cells).style(style)
+        })
+        .collect();
+
+    let mut header = vec!["IRQ".to_string(), "Total/s".to_string(), "Busiest".to_string(), "Share".to_string()];
+    header.extend((0..cpu_columns).map(|cpu| format!("CPU{}", cpu)));
+    header.push("Source".to_string());
+
+    let mut widths = vec![
+        Constraint::Length(8),    // IRQ
+        Constraint::Length(10),   // Total/s
+        Constraint::Length(8),    // Busiest
+        Constraint::Length(7),    // Share
+    ];
+    widths.extend((0..cpu_columns).map(|_| Constraint::Length(CPU_WIDTH)));
+    widths.push(Constraint::Length(source_width));  // Source
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(60),  // Hardware interrupts
+            Constraint::Min(4),          // Softirqs
+        ])
+        .split(area);
+
+    let table = Table::new(rows)
+        .header(Row::new(header))
+        .block(Block::default().title("Interrupts (per second)").borders(Borders::ALL))
+        .widths(&widths);
+    f.render_widget(table, chunks[0]);
+
+    // Packet processing shows up here long before it shows up as process CPU.
+    let softirq_rows: Vec<Row> = monitor
+        .softirqs
+        .iter()
+        .map(|softirq| {
+            let color = if softirq.percent > 30.0 {
+                app.theme.critical
+            } else if softirq.percent > 10.0 {
+                app.theme.warning
+            } else {
+                app.theme.good
+            };
+            Row::new(vec![
+                Cell::from(format!("CPU{}", softirq.cpu)),
+                Cell::from(format!("{:.1}", softirq.percent)).style(Style::default().fg(color)),
+                Cell::from(format!("{:.0}", softirq.net_rx)),
+                Cell::from(format!("{:.0}", softirq.net_tx)),
+                Cell::from(format!("{:.0}", softirq.other)),
+            ])
+        })
+        .collect();
+    let softirq_table = Table::new(softirq_rows)
+        .header(Row::new(vec!["CPU", "SoftIRQ%", "NET_RX/s", "NET_TX/s", "Other/s"]))
+        .block(Block::default().title("Softirqs").borders(Borders::ALL))
+        .widths(&[
+            Constraint::Length(8),    // CPU
+            Constraint::Length(10),   // SoftIRQ%
+            Constraint::Length(10),   // NET_RX/s
+            Constraint::Length(10),   // NET_TX/s
+            Constraint::Length(10),   // Other/s
+        ]);
+    f.render_widget(softirq_table, chunks[1]);
+}
+
+fn render_disks<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(area);
+
+    let rows: Vec<Row> = app
+        .disks
+        .visible()
+        .map(|disk| {
+            let util_color = if disk.utilization > 80.0 {
+                app.theme.critical
+            } else if disk.utilization > 50.0 {
+                app.theme.warning
+            } else {
+                app.theme.good
+            };
+            let name = if disk.label == disk.name {
+                disk.name.clone()
+            } else {
+                format!("{} ({})", disk.label, disk.name)
+            };
+            Row::new(vec![
+                Cell::from(name),
+                Cell::from(disk.mapping.clone().unwrap_or_else(|| "-".to_string())),
+                Cell::from(disk.scheduler.clone().unwrap_or_else(|| "-".to_string())),
+                Cell::from(disk.queue_depth.map_or("-".to_string(), |d| d.to_string())),
+                Cell::from(disk.in_flight.to_string()),
+                Cell::from(format!("{:.1}", disk.utilization)).style(Style::default().fg(util_color)),
+                Cell::from(format!("{:.1} MB/s", disk.read_bytes_per_sec / 1024.0 / 1024.0)),
+                Cell::from(format!("{:.1} MB/s", disk.write_bytes_per_sec / 1024.0 / 1024.0)),
+            ])
+        })
+        .collect();
+
+    let disk_table = Table::new(rows)
+        .header(Row::new(vec![
+            if app.disks.show_partitions { "Partition" } else { "Device" },
+            "Mapping",
+            "Scheduler",
+            "Queue",
+            "In-flight",
+            "Util%",
+            "Read",
+            "Write",
+        ]))
+        .block(
+            Block::default()
+                .title(if app.disks.show_partitions { "Partitions" } else { "Block Devices" })
+                .borders(Borders::ALL),
+        )
+        .widths(&[
+            Constraint::Length(24),   // Device
+            Constraint::Min(16),      // Mapping
+            Constraint::Length(12),   // Scheduler
+            Constraint::Length(8),    // Queue
+            Constraint::Length(10),   // In-flight
+            Constraint::Length(8),    // Util%
+            Constraint::Length(12),   // Read
+            Constraint::Length(12),   // Write
+        ]);
+    f.render_widget(disk_table, chunks[0]);
+
+    let gb = |bytes: u64| format!("{:.1} GB", bytes as f64 / 1024.0 / 1024.0 / 1024.0);
+    let fs_rows: Vec<Row> = app
+        .filesystems
+        .iter()
+        .map(|fs| {
+            let usage = match (fs.total, fs.available) {
+                (Some(total), Some(available)) if total > 0 => {
+                    let used = total.saturating_sub(available);
+                    format!("{} / {} ({:.0}%)", gb(used), gb(total), used as f64 / total as f64 * 100.0)
+                }
+                _ => "-".to_string(),
+            };
+            // Network-backed filesystems stand out so their latency isn't blamed on local disks.
+            let style = if fs.backend.is_remote() {
+                Style::default().fg(app.theme.accent)
+            } else {
+                Style::default()
+            };
+            Row::new(vec![
+                Cell::from(fs.mount_point.clone()),
+                Cell::from(fs.device.clone()),
+                Cell::from(fs.fs_type.clone()),
+                Cell::from(fs.backend.label()),
+                Cell::from(usage),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let fs_table = Table::new(fs_rows)
+        .header(Row::new(vec!["Mount Point", "Source", "Type", "Backend", "Used"]))
+        .block(Block::default().title("Filesystems").borders(Borders::ALL))
+        .widths(&[
+            Constraint::Min(20),      // Mount Point
+            Constraint::Length(24),   // Source
+            Constraint::Length(8),    // Type
+            Constraint::Length(9),    // Backend
+            Constraint::Length(28),   // Used
+        ]);
+    f.render_widget(fs_table, chunks[1]);
+}
+
+fn render_network<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, app: &App) {
+    // Interface, Kind, Container, RX/s, TX/s, Total RX, Total TX, each followed by one space of column spacing.
+    const FIXED_WIDTH: u16 = 13 + 10 + 23 + 13 + 13 + 11 + 11;
+    let history_width = area.width.saturating_sub(2 + FIXED_WIDTH) / 2;
+    let history_width = history_width.saturating_sub(1);
+
+    let rows: Vec<Row> = app
+        .network
+        .visible()
+        .map(|iface| {
+            let container = iface.peer.as_ref().map_or(String::new(), |peer| {
+                format!("{}@{} ({})", peer.interface, app.redact.process(&peer.process), peer.pid)
+            });
+            let kind_style = if iface.kind.is_virtual() {
+                Style::default().fg(app.theme.dim)
+            } else {
+                Style::default()
+            };
+            Row::new(vec![
+                Cell::from(iface.name.clone()),
+                Cell::from(iface.kind.label()).style(kind_style),
+                Cell::from(container),
+                Cell::from(format_rate(iface.rx_bytes_per_sec)),
+                Cell::from(format_rate(iface.tx_bytes_per_sec)),
+                Cell::from(format!("{:.1} GB", iface.total_received as f64 / 1024.0 / 1024.0 / 1024.0)),
+                Cell::from(format!("{:.1} GB", iface.total_transmitted as f64 / 1024.0 / 1024.0 / 1024.0)),
+                Cell::from(sparkline(&iface.rx_history, history_width as usize))
+                    .style(Style::default().fg(app.theme.rx)),
+                Cell::from(sparkline(&iface.tx_history, history_width as usize))
+                    .style(Style::default().fg(app.theme.tx)),
+            ])
+        })
+        .collect();
+
+    let title = if app.network.hide_virtual {
+        "Network Interfaces (virtual hidden)"
+    } else {
+        "Network Interfaces"
+    };
+    let widths = [
+        Constraint::Length(12),   // Interface
+        Constraint::Length(9),    // Kind
+        Constraint::Length(22),   // Container
+        Constraint::Length(12),   // RX/s
+        Constraint::Length(12),   // TX/s
+        Constraint::Length(10),   // Total RX
+        Constraint::Length(10),   // Total TX
+        Constraint::Length(history_width),  // RX History
+        Constraint::Length(history_width),  // TX History
+    ];
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(5),     // Interfaces
+            Constraint::Length(4),  // UDP / ICMP
+        ])
+        .split(area);
+
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Interface", "Kind", "Container", "RX/s", "TX/s", "Total RX", "Total TX", "RX History", "TX History"]))
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .widths(&widths);
+    f.render_widget(table, chunks[0]);
+
+    let network = &app.network;
+    let counter = |label: &str, key: &str| Span::raw(format!("{} {:.0}/s ", label, network.protocol_rate(key)));
+    // Errors are red while they are happening and yellow if any occurred since boot.
+    let error = |label: &str, key: &str| {
+        let style = if network.protocol_rate(key) > 0.0 {
+            Style::default().fg(app.theme.critical)
+        } else if network.protocol_total(key) > 0 {
+            Style::default().fg(app.theme.warning)
+        } else {
+            Style::default()
+        };
+        Span::styled(
+            format!("{} {:.0}/s ({}) ", label, network.protocol_rate(key), network.protocol_total(key)),
+            style,
+        )
+    };
+    let text = vec![
+        Spans::from(vec![
+            Span::raw("UDP   "),
+            counter("in", "Udp.InDatagrams"),
+            counter("out", "Udp.OutDatagrams"),
+            error("errors", "Udp.InErrors"),
+            error("rcvbuf", "Udp.RcvbufErrors"),
+            error("sndbuf", "Udp.SndbufErrors"),
+            error("no port", "Udp.NoPorts"),
+        ]),
+        Spans::from(vec![
+            Span::raw("ICMP  "),
+            counter("in", "Icmp.InMsgs"),
+            counter("out", "Icmp.OutMsgs"),
+            error("errors", "Icmp.InErrors"),
+            error("unreachable", "Icmp.InDestUnreachs"),
+        ]),
+    ];
+    let bottom = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Min(60),     // UDP / ICMP
+            Constraint::Length(36),  // Identity
+        ])
+        .split(chunks[1]);
+    let protocols = Paragraph::new(text)
+        .block(Block::default().title("UDP / ICMP (rate, total since boot)").borders(Borders::ALL));
+    f.render_widget(protocols, bottom[0]);
+
+    let identity = &network.identity;
+    let route = match &identity.default_route {
+        Some(route) => format!("Gateway {} via {}", route.gateway, route.interface),
+        None => "No default route".to_string(),
+    };
+    let dns = if identity.dns_servers.is_empty() {
+        "DNS -".to_string()
+    } else {
+        format!("DNS {}", identity.dns_servers.join(", "))
+    };
+    // Flag recent changes so a flapping VPN is visible at a glance.
+    let (title, style) = match network.identity_changed.map(|at| at.elapsed().as_secs()) {
+        Some(secs) if secs < 60 => (format!("Route & DNS (changed {}s ago)", secs), Style::default().fg(app.theme.warning)),
+        _ => ("Route & DNS".to_string(), Style::default()),
+    };
+    let identity_widget = Paragraph::new(vec![Spans::from(route), Spans::from(dns)])
+        .style(style)
+        .block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(identity_widget, bottom[1]);
+}
+
+fn render_race<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, app: &App) {
+    const LABEL_WIDTH: usize = 26;
+    const VALUE_WIDTH: usize = 10;
+    let ranking = app.race.ranking(area.height.saturating_sub(2) as usize);
+    let bar_width = (area.width as usize).saturating_sub(2 + LABEL_WIDTH + VALUE_WIDTH);
+    let max = ranking.first().map_or(0.0, |leader| leader.displayed.max(leader.cpu_seconds));
+
+    let lines: Vec<Spans> = ranking
+        .iter()
+        .map(|entry| {
+            let label = format!("{} ({})", app.redact.process(&entry.name), entry.pid);
+            let label = text::truncate(&label, LABEL_WIDTH - 1);
+            // Colors follow the PID so a process keeps its color as it moves up and down.
+            let color = app.theme.palette[usize::from(entry.pid) % app.theme.palette.len()];
+            let label_style = if entry.exited {
+                Style::default().fg(app.theme.dim)
+            } else {
+                Style::default()
+            };
+            Spans::from(vec![
+                Span::styled(text::pad(&label, LABEL_WIDTH), label_style),
+                Span::styled(bar_text(entry.displayed / max.max(f64::EPSILON), bar_width), Style::default().fg(color)),
+                Span::raw(format!(" {:>8.1}s", entry.displayed)),
+            ])
+        })
+        .collect();
+
+    let race = Paragraph::new(lines).block(
+        Block::default()
+            .title("Cumulative CPU time this session")
+            .borders(Borders::ALL),
+    );
+    f.render_widget(race, area);
+}
+
+/// The tab content while the session is locked: only how to unlock.
+fn render_lock<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, app: &App) {
+    let lock = app.lock.as_ref().map_or(String::new(), |lock| lock.hint());
+    let top = area.height.saturating_sub(3) / 2;
+    let text = vec![Spans::from(""); top as usize].into_iter().chain([Spans::from(Span::styled("Locked", Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))), Spans::from(lock)]);
+    let paragraph = Paragraph::new(text.collect::<Vec<_>>()).alignment(Alignment::Center).block(Block::default().borders(Borders::ALL));
+    f.render_widget(paragraph, area);
+}
+
+/// The health checks of `rtop check` and the `[[alerts]]` rules, silenced
+/// ones greyed, above the log of what fired this session.
+/// Draws a plugin panel with the closure `Plugins` keeps for it.
+struct PluginPanel<'a>(&'a dyn Fn(tui::layout::Rect, &mut tui::buffer::Buffer));
+
+impl tui::widgets::Widget for PluginPanel<'_> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        (self.0)(area, buf)
+    }
+}
+
+/// The panels of `[plugins]` and of binaries registering their own, stacked.
+fn render_plugins<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, app: &App) {
+    if app.plugins.is_empty() {
+        let hint = Paragraph::new("No plugins. Enable one under [plugins] in the config file; see the README.")
+            .style(Style::default().fg(app.theme.muted))
+            .block(Block::default().title("Plugins").borders(Borders::ALL));
+        f.render_widget(hint, area);
+        return;
+    }
+    let panels: Vec<_> = app.plugins.panels().collect();
+    let mut constraints: Vec<Constraint> = panels.iter().map(|(_, height, _)| Constraint::Length(*height)).collect();
+    constraints.push(Constraint::Min(0));
+    let chunks = Layout::default().direction(Direction::Vertical).constraints(constraints).split(area);
+    for ((title, _, draw), chunk) in panels.into_iter().zip(chunks.iter()) {
+        let block = Block::default().title(title.to_string()).borders(Borders::ALL);
+        let inner = block.inner(*chunk);
+        f.render_widget(block, *chunk);
+        f.render_widget(PluginPanel(draw), inner);
+    }
+}
+
+fn render_alerts<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, app: &App) {
+    let log_height = if app.alert_log.entries.is_empty() { 0 } else { (app.alert_log.entries.len() as u16 + 3).min(area.height / 2) };
+    let actions = app.watchdog.as_ref().map_or(0, |watchdog| watchdog.actions.len());
+    let watchdog_height = if actions == 0 { 0 } else { (actions as u16 + 3).min(area.height / 3) };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(log_height), Constraint::Length(watchdog_height)].as_ref())
+        .split(area);
+    let area = chunks[0];
+    if log_height > 0 {
+        render_alert_log(f, chunks[1], &app.alert_log, &app.theme);
+    }
+    if let Some(watchdog) = app.watchdog.as_ref().filter(|_| watchdog_height > 0) {
+        render_watchdog_log(f, chunks[2], watchdog, &app.redact, &app.theme);
+    }
+    let now = chrono::Local::now();
+    let silenced: Vec<Option<silence::Silenced>> = app.alerts.iter().map(|alert| app.silences.status(&alert.check, now)).collect();
+    let firing = app
+        .alerts
+        .iter()
+        .zip(&silenced)
+        .filter(|(alert, silenced)| alert.severity != health::Severity::Ok && silenced.is_none())
+        .count();
+    let quiet = silenced.iter().filter(|silenced| silenced.is_some()).count();
+    let rows: Vec<Row> = app
+        .alerts
+        .iter()
+        .zip(&silenced)
+        .enumerate()
+        .map(|(i, (alert, silenced))| {
+            let color = match alert.severity {
+                health::Severity::Critical => app.theme.critical,
+                health::Severity::Warning => app.theme.warning,
+                health::Severity::Ok => app.theme.good,
+            };
+            let mut style = match silenced {
+                Some(_) => Style::default().fg(app.theme.muted),
+                None => Style::default(),
+            };
+            if i == app.selected_alert {
+                style = style.patch(if app.state == AppState::Main { app.theme.focused() } else { app.theme.selected() });
+            }
+            Row::new(vec![
+                Cell::from(alert.severity.label()).style(if silenced.is_some() { style } else { style.fg(color) }),
+                Cell::from(alert.check.clone()),
+                Cell::from(alert.message.clone()),
+                Cell::from(silenced.as_ref().map_or(String::new(), |silenced| silenced.describe())),
+            ])
+            .style(style)
+        })
+        .collect();
+    let table = Table::new(rows)
+        .header(Row::new(vec!["State", "Rule", "Value", "Silenced"]).style(Style::default().fg(app.theme.muted)))
+        .block(Block::default().title(format!("Alerts ({} firing, {} silenced)", firing, quiet)).borders(Borders::ALL))
+        .widths(&[
+            Constraint::Length(6),  // State
+            Constraint::Length(24), // Rule
+            Constraint::Length(36), // Value
+            Constraint::Min(24),    // Silenced
+        ]);
+    f.render_widget(table, area);
+}
+
+/// Signals the watchdog sent this session, newest first.
+fn render_watchdog_log<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, watchdog: &watchdog::Watchdog, redact: &Redactor, theme: &Theme) {
+    let rows: Vec<Row> = watchdog
+        .actions
+        .iter()
+        .rev()
+        .map(|action| {
+            let result = match &action.error {
+                None => Cell::from("sent"),
+                Some(err) => Cell::from(err.clone()).style(Style::default().fg(theme.critical)),
+            };
+            Row::new(vec![
+                Cell::from(action.at.format("%H:%M:%S").to_string()),
+                Cell::from(action.rule.clone()),
+                Cell::from(control::signal_name(action.signal)),
+                Cell::from(format!("{} ({})", redact.process(&action.name), action.pid)),
+                Cell::from(action.reason.clone()),
+                result,
+            ])
+        })
+        .collect();
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Time", "Rule", "Signal", "Process", "Reason", "Result"]).style(Style::default().fg(theme.muted)))
+        .block(Block::default().title(format!("Watchdog actions ({})", watchdog.actions.len())).borders(Borders::ALL))
+        .widths(&[
+            Constraint::Length(8),  // Time
+            Constraint::Length(16), // Rule
+            Constraint::Length(9),  // Signal
+            Constraint::Length(24), // Process
+            Constraint::Min(32),    // Reason
+            Constraint::Length(24), // Result
+        ]);
+    f.render_widget(table, area);
+}
+
+/// Alerts that fired this session, newest first.
+fn render_alert_log<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, log: &alerts::AlertLog, theme: &Theme) {
+    let rows: Vec<Row> = log
+        .entries
+        .iter()
+        .rev()
+        .map(|entry| {
+            let color = match entry.severity {
+                health::Severity::Critical => theme.critical,
+                _ => theme.warning,
+            };
+            let cleared = match entry.cleared {
+                Some(cleared) => {
+                    let seconds = (cleared - entry.fired).num_seconds();
+                    Cell::from(format!("{} ({}m {:02}s)", cleared.format("%H:%M:%S"), seconds / 60, seconds % 60)).style(Style::default().fg(theme.muted))
+                }
+                None => Cell::from("firing").style(Style::default().fg(color)),
+            };
+            Row::new(vec![
+                Cell::from(entry.fired.format("%H:%M:%S").to_string()),
+                Cell::from(entry.severity.label()).style(Style::default().fg(color)),
+                Cell::from(entry.check.clone()),
+                Cell::from(entry.message.clone()),
+                cleared,
+            ])
+        })
+        .collect();
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Fired", "State", "Rule", "Value", "Cleared"]).style(Style::default().fg(theme.muted)))
+        .block(Block::default().title(format!("Fired this session ({})", log.entries.len())).borders(Borders::ALL))
+        .widths(&[
+            Constraint::Length(8),  // Fired
+            Constraint::Length(6),  // State
+            Constraint::Length(24), // Rule
+            Constraint::Min(36),    // Value
+            Constraint::Length(18), // Cleared
+        ]);
+    f.render_widget(table, area);
+}
+
+/// This host and every agent side by side, under cluster-wide totals.
+fn render_cluster<B: Backend>(f: &mut tui::Frame<B>, area: tui::layout::Rect, app: &App) {
+    let Some(cluster) = app.cluster.as_ref().filter(|cluster| !cluster.hosts.is_empty()) else {
+        let hint = Paragraph::new(format!(
+            "No agents. Start rtop on other hosts with --listen and pass their URLs with --agent, use --discover-agents, or press {} to pick agents found on the network.",
+            app.keymap.key(Action::PickAgents)
+        ))
+            .style(Style::default().fg(app.theme.muted))
+            .block(Block::default().title("Cluster").borders(Borders::ALL));
+        f.render_widget(hint, area);
+        return;
+    };
+    let system = &app.system;
+    let local = cluster::HostSample {
+        cpu: system.global_cpu_info().cpu_usage() as f64,
+        memory_used: system.used_memory(),
+        memory_total: system.total_memory(),
+        top: system
+            .processes()
+            .iter()
+            .max_by(|a, b| a.1.cpu_usage().total_cmp(&b.1.cpu_usage()))
+            .map(|(pid, process)| cluster::TopProcess {
+                pid: pid.as_u32(),
+                name: app.redact.process(process.name()).into_owned(),
+                cpu: process.cpu_usage() as f64,
+                memory: process.memory(),
+            }),
+    };
+    let local_name = system.host_name().unwrap_or_else(|| "localhost".to_string());
+    let mut samples = vec![(local_name.as_str(), &local)];
+    samples.extend(cluster.hosts.iter().filter_map(|host| match &host.sample {
+        Some(Ok(sample)) => Some((host.name(), sample)),
+        _ => None,
+    }));
+    let summary = cluster::Summary::of(&samples);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(5), Constraint::Min(0)].as_ref())
+        .split(area);
+    let gb = |bytes: u64| format!("{:.1} GB", bytes as f64 / 1024.0 / 1024.0 / 1024.0);
+    let mut lines = vec![
+        Spans::from(format!(
+            "Hosts: {} of {} answering   Average CPU: {:.1}%   Memory: {} / {}",
+            summary.hosts,
+            cluster.hosts.len() + 1,
+            summary.average_cpu,
+            gb(summary.memory_used),
+            gb(summary.memory_total),
+        )),
+    ];
+    if let Some((host, sample)) = summary.busiest {
+        lines.push(Spans::from(format!("Busiest host: {} ({:.1}% CPU)", host, sample.cpu)));
+    }
+    if let Some((host, process)) = summary.heaviest {
+        lines.push(Spans::from(format!(
+            "Heaviest process: {} ({}) on {}, {:.1}% CPU, {:.1} MB",
+            text::truncate(&process.name, 32),
+            process.pid,
+            host,
+            process.cpu,
+            process.memory as f64 / 1024.0 / 1024.0,
+        )));
+    }
+    let totals = Paragraph::new(lines).block(Block::default().title("Cluster").borders(Borders::ALL));
+    f.render_widget(totals, chunks[0]);
+
+    // Host CPU is colored like the per-core gauges.
+    let thresholds = &app.config.thresholds;
+    let cpu_color = |cpu: f64| {
+        if cpu > thresholds.core_critical as f64 {
+            app.theme.critical
+        } else if cpu > thresholds.core_warning as f64 {
+            app.theme.warning
+        } else {
+            app.theme.good
+        }
+    };
+    let row = |name: &str, sample: &cluster::HostSample| {
+        let top = sample.top.as_ref().map_or(String::new(), |process| {
+            format!("{} ({}) {:.1}%", text::truncate(&process.name, 24), process.pid, process.cpu)
+        });
+        Row::new(vec![
+            Cell::from(name.to_string()),
+            Cell::from(format!("{:.1}", sample.cpu)).style(Style::default().fg(cpu_color(sample.cpu))),
+            Cell::from(format!("{} / {}", gb(sample.memory_used), gb(sample.memory_total))),
+            Cell::from(top),
+        ])
+    };
+    let mut rows = vec![row(&format!("{} (this host)", local_name), &local)];
+    for host in &cluster.hosts {
+        rows.push(match &host.sample {
+            Some(Ok(sample)) => row(host.name(), sample),
+            Some(Err(err)) => Row::new(vec![Cell::from(host.name().to_string()), Cell::from("-"), Cell::from("-"), Cell::from(err.clone())])
+                .style(Style::default().fg(app.theme.critical)),
+            None => Row::new(vec![Cell::from(host.name().to_string()), Cell::from("-"), Cell::from("-"), Cell::from("connecting…")])
+                .style(Style::default().fg(app.theme.muted)),
+        });
+    }
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Host", "CPU%", "Memory", "Top process"]))
+        .block(Block::default().title("Hosts").borders(Borders::ALL))
+        .widths(&[
+            Constraint::Length(28),   // Host
+            Constraint::Length(8),    // CPU%
+            Constraint::Length(22),   // Memory
+            Constraint::Min(48),      // Top process
+        ]);
+    f.render_widget(table, chunks[1]);
+}
+
+/// A horizontal bar `ratio` of `width` long, using eighth blocks for the fractional end.
+fn bar_text(ratio: f64, width: usize) -> String {
+    const PARTIAL: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+    let eighths = (ratio.clamp(0.0, 1.0) * width as f64 * 8.0).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    let remainder = eighths % 8;
+    if remainder > 0 {
+        bar.push(PARTIAL[remainder]);
+    }
+    format!("{:<width$}", bar, width = width)
+}
+
+/// Formats a byte rate with a unit that keeps small and large rates readable.
+fn format_rate(bytes_per_sec: f64) -> String {
+    if bytes_per_sec >= 1024.0 * 1024.0 {
+        format!("{:.1} MB/s", bytes_per_sec / 1024.0 / 1024.0)
+    } else if bytes_per_sec >= 1024.0 {
+        format!("{:.1} KB/s", bytes_per_sec / 1024.0)
+    } else {
+        format!("{:.0} B/s", bytes_per_sec)
+    }
+}
+
+/// Renders the newest `width` samples as block characters scaled to their maximum.
+fn sparkline(history: &std::collections::VecDeque<f64>, width: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let samples: Vec<f64> = history.iter().skip(history.len().saturating_sub(width)).copied().collect();
+    let max = samples.iter().copied().fold(0.0, f64::max);
+    samples
+        .iter()
+        .map(|&value| {
+            if max <= 0.0 {
+                BARS[0]
+            } else {
+                BARS[((value / max) * (BARS.len() - 1) as f64).round() as usize]
+            }
+        })
+        .collect()
+}
+
+fn render_details<B: Backend>(
+    f: &mut tui::Frame<B>,
+    area: tui::layout::Rect,
+    app: &App,
+    details: &ProcessDetails,
+) {
+    let name = app
+        .system
+        .process(details.pid)
+        .map_or("<exited>".into(), |p| app.redact.process(p.name()));
+    let block = Block::default()
+        .title(format!("Process Details - PID: {} ({})", details.pid, name))
+        .borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),  // Tabs
+            Constraint::Min(0),     // Tab content
+        ])
+        .split(inner);
+
+    let titles = DetailsTab::ALL
+        .iter()
+        .map(|tab| Spans::from(tab.title()))
+        .collect();
+    let selected = DetailsTab::ALL
+        .iter()
+        .position(|&tab| tab == details.tab)
+        .unwrap_or(0);
+    let tabs = Tabs::new(titles)
+        .select(selected)
+        .highlight_style(Style::default().fg(app.theme.highlight));
+    f.render_widget(tabs, chunks[0]);
+
+    match details.tab {
+        DetailsTab::Info => render_details_info(f, chunks[1], app, details),
+        DetailsTab::Overview => render_details_overview(f, chunks[1], app, details),
+        DetailsTab::Limits => render_details_limits(f, chunks[1], app, details),
+        DetailsTab::Affinity => render_details_affinity(f, chunks[1], app, details),
+    }
+}
+
+fn render_details_info<B: Backend>(
+    f: &mut tui::Frame<B>,
+    area: tui::layout::Rect,
+    app: &App,
+    details: &ProcessDetails,
+) {
+    let Some(process) = app.system.process(details.pid) else {
+        f.render_widget(Paragraph::new("Process has exited"), area);
+        return;
+    };
+    let label = |name: &str| Span::styled(format!("{:<12}", name), Style::default().fg(app.theme.highlight));
+    let mb = |bytes: u64| format!("{:.1} MB", bytes as f64 / 1024.0 / 1024.0);
+    let optional_mb = |bytes: Option<u64>| bytes.map_or("-".to_string(), mb);
+    let path = |path: &std::path::Path| {
+        if path.as_os_str().is_empty() {
+            "-".to_string()
+        } else {
+            app.redact.path(&path.display().to_string()).into_owned()
+        }
+    };
+
+    let user = match process.user_id() {
+        Some(uid) => match app.system.get_user_by_id(uid) {
+            Some(user) => format!("{} ({})", app.redact.user(user.name()), **uid),
+            None => (**uid).to_string(),
+        },
+        None => "-".to_string(),
+    };
+    let started = chrono::Local
+        .timestamp_opt(process.start_time() as i64, 0)
+        .single()
+        .map_or("-".to_string(), |time| time.format("%Y-%m-%d %H:%M:%S").to_string());
+    let run_time = process.run_time();
+    let command = if process.cmd().is_empty() {
+        format!("[{}]", app.redact.process(process.name()))
+    } else {
+        app.redact.command(&process.cmd().join(" ")).into_owned()
+    };
+
+    let lines = vec![
+        Spans::from(vec![label("Command"), Span::raw(command)]),
+        Spans::from(vec![label("Executable"), Span::raw(path(process.exe()))]),
+        Spans::from(vec![label("Working dir"), Span::raw(path(process.cwd()))]),
+        Spans::from(vec![label("User"), Span::raw(user)]),
+        Spans::from(vec![
+            label("Started"),
+            Span::raw(format!(
+                "{} (running {}:{:02}:{:02})",
+                started,
+                run_time / 3600,
+                run_time / 60 % 60,
+                run_time % 60
+            )),
+        ]),
+        Spans::from(vec![
+            label("Status"),
+            Span::raw(match app.d_state.refreshes(details.pid) {
+                Some(refreshes) => format!("{} for {} refreshes", process.status(), refreshes),
+                None => process.status().to_string(),
+            }),
+        ]),
+        Spans::from(vec![
+            label("Waiting in"),
+            Span::raw(match (&details.wchan, &details.syscall) {
+                (Some(wchan), Some(syscall)) => format!("{} in {}", wchan, syscall),
+                (Some(name), None) | (None, Some(name)) => name.clone(),
+                (None, None) => "-".to_string(),
+            }),
+        ]),
+        Spans::from(vec![
+            label("Emulated"),
+            Span::raw(match app.per_process.emulated.get(&details.pid) {
+                Some(emulator) => format!("yes, under {} (CPU% includes the translation)", emulator.label()),
+                None => "no".to_string(),
+            }),
+        ]),
+        Spans::from(vec![
+            label("Threads"),
+            Span::raw(details.status.threads.map_or("-".to_string(), |t| t.to_string())),
+        ]),
+        Spans::from(vec![
+            label("Memory"),
+            Span::raw(format!(
+                "RSS {}  Virtual {:.2} GB  Swap {}",
+                mb(process.memory()),
+                process.virtual_memory() as f64 / 1024.0 / 1024.0 / 1024.0,
+                optional_mb(details.status.swap)
+            )),
+        ]),
+        Spans::from(vec![
+            label(""),
+            Span::raw(format!(
+                "Anonymous {}  File {}  Shared memory {}",
+                optional_mb(details.status.rss_anon),
+                optional_mb(details.status.rss_file),
+                optional_mb(details.status.rss_shmem)
+            )),
+        ]),
+    ];
+    // Wrap so long command lines stay readable instead of being cut off.
+    f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), area);
+}
+
+fn render_details_overview<B: Backend>(
+    f: &mut tui::Frame<B>,
+    area: tui::layout::Rect,
+    app: &App,
+    details: &ProcessDetails,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4),       // Memory composition
+            Constraint::Percentage(40),  // Children
+            Constraint::Min(3),          // Sockets
+        ])
+        .split(area);
+
+    let memory = match &details.memory {
+        Some(memory) => memory_composition_text(memory, chunks[0].width.saturating_sub(2), &app.theme),
+        None => vec![Spans::from("Memory composition unavailable")],
+    };
+    let memory_block = Paragraph::new(memory)
+        .block(Block::default().title("Memory Composition").borders(Borders::TOP));
+    f.render_widget(memory_block, chunks[0]);
+
+    let children = app.get_children(details.pid);
+    let child_rows: Vec<Row> = children
+        .iter()
+        .map(|(pid, process)| {
+            Row::new(vec![
+                Cell::from(pid.to_string()),
+                Cell::from(app.redact.process(process.name())),
+                Cell::from(format!("{:.1}", process.cpu_usage())),
+                Cell::from(format!("{:.1} MB", process.memory() as f64 / 1024.0 / 1024.0)),
+            ])
+        })
+        .collect();
+    let children_table = Table::new(child_rows)
+        .header(Row::new(vec!["PID", "Name", "CPU%", "RSS"]))
+        .block(
+            Block::default()
+                .title(format!("Children ({})", children.len()))
+                .borders(Borders::TOP),
+        )
+        .widths(&[
+            Constraint::Length(8),    // PID
+            Constraint::Min(20),      // Name
+            Constraint::Length(8),    // CPU%
+            Constraint::Length(12),   // RSS
+        ])
+        // The affinity editor takes the keyboard over the details.
+        .highlight_style(if app.state == AppState::Details { app.theme.focused() } else { app.theme.selected() });
+    // Stateful rendering keeps the highlighted child scrolled into view.
+    let mut children_state = TableState::default();
+    children_state.select(details.selected_child);
+    f.render_stateful_widget(children_table, chunks[1], &mut children_state);
+
+    let socket_rows: Vec<Row> = details
+        .sockets
+        .iter()
+        .map(|socket| {
+            Row::new(vec![
+                Cell::from(socket.protocol.name()),
+                Cell::from(socket.local.to_string()),
+                Cell::from(socket.remote.to_string()),
+                Cell::from(socket.state_name()),
+            ])
+        })
+        .collect();
+    let sockets_table = Table::new(socket_rows)
+        .header(Row::new(vec!["Proto", "Local Address", "Remote Address", "State"]))
+        .block(
+            Block::default()
+                .title(format!("Sockets ({})", details.sockets.len()))
+                .borders(Borders::TOP),
+        )
+        .widths(&[
+            Constraint::Length(6),    // Proto
+            Constraint::Length(46),   // Local Address
+            Constraint::Length(46),   // Remote Address
+            Constraint::Min(11),      // State
+        ]);
+    f.render_widget(sockets_table, chunks[2]);
+}
+
+/// Current consumption of the resource behind `limit`, where rtop can measure it.
+fn limit_usage(app: &App, details: &ProcessDetails, limit: &ResourceLimit) -> Option<u64> {
+    let process = app.system.process(details.pid)?;
+    match limit.name.as_str() {
+        "Max open files" => details.open_files,
+        "Max resident set" => Some(process.memory()),
+        "Max address space" => Some(process.virtual_memory()),
+        "Max processes" => {
+            let uid = process.user_id()?;
+            let count = app
+                .system
+                .processes()
+                .values()
+                .filter(|p| p.user_id() == Some(uid))
+                .count();
+            Some(count as u64)
+        }
+        _ => None,
+    }
+}
+
+/// A checkbox per CPU with its current usage; the pending mask while editing.
+fn render_details_affinity<B: Backend>(
+    f: &mut tui::Frame<B>,
+    area: tui::layout::Rect,
+    app: &App,
+    details: &ProcessDetails,
+) {
+    let editor = app.affinity_editor.as_ref().filter(|editor| editor.pid == details.pid);
+    let allowed = match editor {
+        Some(editor) => editor.allowed.clone(),
+        None => match control::affinity(details.pid, app.system.cpus().len()) {
+            Ok(allowed) => allowed,
+            Err(err) => {
+                f.render_widget(Paragraph::new(format!("Cannot read the affinity: {}", err)), area);
+                return;
+            }
+        },
+    };
+    let hint = if editor.is_some() { "editing" } else { "Enter: edit" };
+    let mut lines = vec![
+        Spans::from(format!("Allowed CPUs: {}  ({})", control::cpu_list(&allowed), hint)),
+        Spans::from(""),
+    ];
+    let cpus = app.system.cpus();
+    for (row, chunk) in allowed.chunks(AFFINITY_COLUMNS).enumerate() {
+        let mut spans = Vec::new();
+        for (column, allowed) in chunk.iter().enumerate() {
+            let cpu = row * AFFINITY_COLUMNS + column;
+            let usage = cpus.get(cpu).map_or(0.0, |cpu| cpu.cpu_usage());
+            let cell = format!("[{}] {:>3} {:>3.0}%", if *allowed { "x" } else { " " }, cpu, usage);
+            let style = if editor.is_some_and(|editor| editor.cursor == cpu) {
+                app.theme.focused()
+            } else if *allowed {
+                Style::default()
+            } else {
+                Style::default().fg(app.theme.muted)
+            };
+            spans.push(Span::styled(cell, style));
+            spans.push(Span::raw("  "));
+        }
+        lines.push(Spans::from(spans));
+    }
+    f.render_widget(Paragraph::new(lines).block(Block::default().title("CPU Affinity").borders(Borders::TOP)), area);
+}
+
+fn render_details_limits<B: Backend>(
+    f: &mut tui::Frame<B>,
+    area: tui::layout::Rect,
+    app: &App,
+    details: &ProcessDetails,
+) {
+    let format_limit = |value: Option<u64>| value.map_or("unlimited".to_string(), |v| v.to_string());
+    let rows: Vec<Row> = details
+        .limits
+        .iter()
+        .map(|limit| {
+            let usage = limit_usage(app, details, limit);
+            let style = match usage.and_then(|u| limit.utilization(u)) {
+                Some(ratio) if ratio >= 0.95 => Style::default().fg(app.theme.critical),
+                Some(ratio) if ratio >= 0.8 => Style::default().fg(app.theme.warning),
+                _ => Style::default(),
+            };
+            Row::new(vec![
+                Cell::from(limit.name.clone()),
+                Cell::from(format_limit(limit.soft)),
+                Cell::from(format_limit(limit.hard)),
+                Cell::from(limit.units.clone()),
+                Cell::from(usage.map_or("-".to_string(), |u| u.to_string())),
+            ])
+            .style(style)
+        })
+        .collect();
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Limit", "Soft", "Hard", "Units", "Usage"]))
+        .block(Block::default().title("Resource Limits").borders(Borders::TOP))
+        .widths(&[
+            Constraint::Length(24),   // Limit
+            Constraint::Length(20),   // Soft
+            Constraint::Length(20),   // Hard
+            Constraint::Length(10),   // Units
+            Constraint::Min(12),      // Usage
+        ]);
+    f.render_widget(table, area);
+}
+
+/// Builds a stacked horizontal bar of `width` cells plus a legend for a memory composition.
+fn memory_composition_text(memory: &MemoryComposition, width: u16, theme: &Theme) -> Vec<Spans<'static>> {
+    let parts = [
+        ("Heap/anon", memory.anon, theme.palette[2]),
+        ("Libraries", memory.libraries, theme.palette[5]),
+        ("Other files", memory.file, theme.palette[4]),
+        ("Shared", memory.shared, theme.palette[1]),
+        ("Stack", memory.stack, theme.palette[3]),
+    ];
+    let total = memory.total().max(1);
+
+    let bar = parts
+        .iter()
+        .map(|&(_, bytes, color)| {
+            let cells = (bytes as f64 / total as f64 * width as f64).round() as usize;
+            Span::styled("█".repeat(cells), Style::default().fg(color))
+        })
+        .collect::<Vec<_>>();
+    let legend = parts
+        .iter()
+        .flat_map(|&(label, bytes, color)| {
+            vec![
+                Span::styled("■ ", Style::default().fg(color)),
+                Span::raw(format!("{} {:.1} MB  ", label, bytes as f64 / 1024.0 / 1024.0)),
+            ]
+        })
+        .collect::<Vec<_>>();
+    vec![Spans::from(bar), Spans::from(legend)]
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: tui::layout::Rect) -> tui::layout::Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparkline() {
+        let history: std::collections::VecDeque<f64> = vec![0.0, 5.0, 10.0, 0.0].into();
+        assert_eq!(sparkline(&history, 3), "▅█▁");
+        assert_eq!(sparkline(&vec![0.0; 2].into(), 5), "▁▁");
+    }
+
+    #[test]
+    fn test_bar_text() {
+        assert_eq!(bar_text(1.0, 4), "████");
+        assert_eq!(bar_text(0.5625, 4), "██▎ ");
+        assert_eq!(bar_text(0.0, 2), "  ");
+    }
+
+    #[test]
+    fn test_process_totals() {
+        let app = App::new(Config::default());
+        let rows = app.visible_processes();
+        let totals = ProcessTotals::of(&app, &rows);
+        assert_eq!(totals.count, rows.len());
+        assert_eq!(totals.memory, rows.iter().map(|(_, process)| process.memory()).sum::<u64>());
+        assert!(totals.virtual_memory >= totals.memory);
+    }
+
+    #[test]
+    fn test_scroll_offset() {
+        assert_eq!(scroll_offset(0, Some(5), 10), 0);
+        assert_eq!(scroll_offset(0, Some(12), 10), 3);
+        assert_eq!(scroll_offset(3, Some(1), 10), 1);
+        assert_eq!(scroll_offset(3, None, 10), 3);
+    }
+}
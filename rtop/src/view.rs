@@ -0,0 +1,184 @@
+// src/view.rs
+use crate::export::{ProcessSnapshot, Snapshot};
+use crate::{AppError, SortBy};
+use clap::Args;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Args, Debug)]
+pub struct ViewArgs {
+    /// JSON snapshot from `rtop export --format json`, a recording of one snapshot per line from `rtop --batch --format json`, or a session from `rtop --record`
+    pub file: PathBuf,
+
+    /// Config file to use instead of ~/.config/rtop/config.toml
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+/// Reads every snapshot in `path`: one JSON object per line, a single
+/// (possibly pretty-printed) object, or a `--record` session.
+pub fn load(path: &Path) -> Result<Vec<Snapshot>, AppError> {
+    let content = std::fs::read_to_string(path)?;
+    if crate::record::is_recording(&content) {
+        return crate::record::parse(&content);
+    }
+    parse(&content)
+}
+
+fn parse(content: &str) -> Result<Vec<Snapshot>, AppError> {
+    let lines: Result<Vec<Snapshot>, _> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect();
+    let snapshots = match lines {
+        Ok(snapshots) => snapshots,
+        Err(err) => match serde_json::from_str(content) {
+            Ok(snapshot) => vec![snapshot],
+            Err(_) => return Err(err.into()),
+        },
+    };
+    if snapshots.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "no snapshots in the file").into());
+    }
+    Ok(snapshots)
+}
+
+/// Browsing state of `rtop view`: which snapshot is shown and how its
+/// processes are ordered.
+pub struct Viewer {
+    pub snapshots: Vec<Snapshot>,
+    pub index: usize,
+    pub sort_by: SortBy,
+    pub sort_reversed: bool,
+    pub selected: usize,
+    /// First process row shown.
+    pub offset: usize,
+}
+
+impl Viewer {
+    pub fn new(snapshots: Vec<Snapshot>) -> Viewer {
+        Viewer {
+            snapshots,
+            index: 0,
+            sort_by: SortBy::Cpu,
+            sort_reversed: false,
+            selected: 0,
+            offset: 0,
+        }
+    }
+
+    pub fn snapshot(&self) -> &Snapshot {
+        &self.snapshots[self.index]
+    }
+
+    /// The processes of the current snapshot in display order. Snapshots
+    /// carry no disk I/O, so those sorts fall back to CPU.
+    pub fn processes(&self) -> Vec<&ProcessSnapshot> {
+        let mut processes: Vec<&ProcessSnapshot> = self.snapshot().processes.iter().collect();
+        match self.sort_by {
+            SortBy::Memory => processes.sort_by_key(|p| std::cmp::Reverse(p.memory)),
+            SortBy::Name => processes.sort_by(|a, b| a.name.cmp(&b.name)),
+            SortBy::Pid => processes.sort_by_key(|p| p.pid),
+            SortBy::Cpu | SortBy::DiskRead | SortBy::DiskWrite => {
+                processes.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage))
+            }
+        }
+        if self.sort_reversed {
+            processes.reverse();
+        }
+        processes
+    }
+
+    /// Sorts by `sort_by`, or inverts the order if it is already the sort.
+    pub fn sort(&mut self, sort_by: SortBy) {
+        self.keep_selection(|viewer| {
+            if viewer.sort_by == sort_by {
+                viewer.sort_reversed = !viewer.sort_reversed;
+            } else {
+                viewer.sort_by = sort_by;
+                viewer.sort_reversed = false;
+            }
+        });
+    }
+
+    /// Moves to the next or previous snapshot of a recording.
+    pub fn step(&mut self, forward: bool) {
+        let index = if forward {
+            self.index + 1
+        } else {
+            self.index.saturating_sub(1)
+        };
+        self.go_to(index);
+    }
+
+    /// Shows snapshot `index`, or the last one if there are fewer.
+    pub fn go_to(&mut self, index: usize) {
+        self.keep_selection(|viewer| viewer.index = index.min(viewer.snapshots.len() - 1));
+    }
+
+    /// Applies `change` and selects the same PID again if it is still listed.
+    fn keep_selection(&mut self, change: impl FnOnce(&mut Viewer)) {
+        let pid = self.processes().get(self.selected).map(|p| p.pid);
+        change(self);
+        let processes = self.processes();
+        self.selected = pid
+            .and_then(|pid| processes.iter().position(|p| p.pid == pid))
+            .unwrap_or(self.selected.min(processes.len().saturating_sub(1)));
+    }
+
+    /// Moves the selection by `rows`, negative for up.
+    pub fn select_by(&mut self, rows: isize) {
+        let last = self.snapshot().processes.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(rows).min(last);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(processes: &[(u32, &str, f32)]) -> String {
+        let processes: Vec<String> = processes
+            .iter()
+            .map(|(pid, name, cpu)| {
+                format!(
+                    r#"{{"pid":{},"parent":null,"name":"{}","command":"","user":null,"status":"Sleeping","cpu_usage":{},"memory":{},"virtual_memory":0,"start_time":0}}"#,
+                    pid, name, cpu, pid * 1000
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"timestamp":"2024-01-01T00:00:00+00:00","hostname":"h","uptime":1,"load_average":[0.0,0.0,0.0],"cpu":{{"usage":1.0,"cores":[1.0]}},"memory":{{"total":1,"used":1,"available":0,"swap_total":0,"swap_used":0}},"processes":[{}]}}"#,
+            processes.join(",")
+        )
+    }
+
+    #[test]
+    fn test_parse_recording() {
+        let recording = format!(
+            "{}\n{}\n",
+            snapshot(&[(1, "a", 5.0), (2, "b", 50.0)]),
+            snapshot(&[(2, "b", 1.0), (3, "c", 9.0)])
+        );
+        let mut viewer = Viewer::new(parse(&recording).unwrap());
+        assert_eq!(viewer.snapshots.len(), 2);
+        let pids = |viewer: &Viewer| viewer.processes().iter().map(|p| p.pid).collect::<Vec<_>>();
+        assert_eq!(pids(&viewer), vec![2, 1]);
+
+        // Stepping keeps PID 2 selected although it moved down.
+        viewer.step(true);
+        assert_eq!(pids(&viewer), vec![3, 2]);
+        assert_eq!(viewer.selected, 1);
+
+        viewer.sort(SortBy::Pid);
+        assert_eq!(viewer.selected, 0);
+        viewer.sort(SortBy::Pid);
+        assert_eq!(pids(&viewer), vec![3, 2]);
+        assert_eq!(viewer.selected, 1);
+
+        let pretty = serde_json::to_string_pretty(&viewer.snapshots[0]).unwrap();
+        assert_eq!(parse(&pretty).unwrap().len(), 1);
+        assert!(parse("").is_err());
+    }
+}
@@ -0,0 +1,331 @@
+// src/watchdog.rs
+use crate::alerts::Metric;
+use crate::control;
+use crate::input;
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, Signal};
+
+/// Actions kept for the Alerts tab; the log file keeps them all.
+const LOG_SIZE: usize = 500;
+
+/// Shortest time before a process that keeps breaking a rule is signalled
+/// again, so a rule without `for_seconds` does not signal on every refresh.
+const RESEND: Duration = Duration::from_secs(10);
+
+/// The `[watchdog]` table: rules that signal runaway processes without
+/// asking.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchdogConfig {
+    /// Acts on `rules`, like `--watchdog`; otherwise they do nothing.
+    pub enabled: bool,
+    /// File every action is appended to, instead of `watchdog.log` in the
+    /// state directory.
+    pub log: Option<PathBuf>,
+    pub rules: Vec<WatchdogRule>,
+}
+
+impl WatchdogConfig {
+    /// Checks every rule, for config errors at startup.
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            rule.validate()?;
+            if self.rules[..i].iter().any(|other| other.name == rule.name) {
+                return Err(format!("watchdog: '{}' is defined twice", rule.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A `[[watchdog.rules]]` table, such as "send SIGTERM to any `ffmpeg`
+/// above 90% CPU for a minute".
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchdogRule {
+    pub name: String,
+    /// Processes whose name or command line contains this, as the filter
+    /// matches them.
+    pub process: String,
+    /// `process_cpu` or `process_memory`.
+    pub metric: Metric,
+    pub above: f64,
+    /// Seconds a process has to stay above `above` before it is signalled.
+    #[serde(default)]
+    pub for_seconds: u64,
+    /// Such as `term`, `SIGKILL` or `stop`.
+    #[serde(default = "default_signal")]
+    pub signal: String,
+}
+
+fn default_signal() -> String {
+    "term".to_string()
+}
+
+impl WatchdogRule {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("watchdog: every rule needs a name".to_string());
+        }
+        if self.process.trim().is_empty() {
+            return Err(format!(
+                "watchdog.{}: process is required, so a rule cannot match every process",
+                self.name
+            ));
+        }
+        if !self.metric.per_process() {
+            return Err(format!(
+                "watchdog.{}: metric is process_cpu or process_memory",
+                self.name
+            ));
+        }
+        if control::signal_named(&self.signal).is_none() {
+            return Err(format!(
+                "watchdog.{}: unknown signal '{}'",
+                self.name, self.signal
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One process as watchdog rules see it.
+pub struct Candidate<'a> {
+    pub pid: Pid,
+    pub name: &'a str,
+    pub command: String,
+    pub cpu: f64,
+    /// Resident memory, in percent of the total.
+    pub memory: f64,
+}
+
+/// A signal the watchdog sent, or failed to send.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Action {
+    pub at: DateTime<Local>,
+    pub rule: String,
+    pub pid: Pid,
+    pub name: String,
+    pub signal: Signal,
+    /// Such as "CPU 99.0% above 90.0% for 31s".
+    pub reason: String,
+    /// Why the signal could not be sent.
+    pub error: Option<String>,
+}
+
+impl Action {
+    /// The line of the log file.
+    fn line(&self) -> String {
+        format!(
+            "{} {} {} {} ({}) {}: {}",
+            self.at.to_rfc3339_opts(SecondsFormat::Secs, false),
+            self.rule,
+            control::signal_name(self.signal),
+            self.name,
+            self.pid,
+            self.reason,
+            self.error.as_deref().unwrap_or("sent")
+        )
+    }
+}
+
+/// Applies the `[[watchdog.rules]]` on every refresh and keeps an audit
+/// log of what it did.
+pub struct Watchdog {
+    rules: Vec<(WatchdogRule, Signal)>,
+    /// Names never signalled, as nobody is there to confirm.
+    protected: Vec<String>,
+    log: Option<PathBuf>,
+    /// Since when each process has been above each rule's threshold.
+    above_since: HashMap<(usize, Pid), Instant>,
+    /// When each process was last signalled for each rule.
+    signalled: HashMap<(usize, Pid), Instant>,
+    /// This session's actions, newest last.
+    pub actions: VecDeque<Action>,
+}
+
+impl Watchdog {
+    /// Rules must have been validated; `log` is where actions are appended.
+    pub fn new(config: &WatchdogConfig, protected: Vec<String>, log: Option<PathBuf>) -> Watchdog {
+        let rules = config
+            .rules
+            .iter()
+            .filter_map(|rule| Some((rule.clone(), control::signal_named(&rule.signal)?)))
+            .collect();
+        Watchdog {
+            rules,
+            protected,
+            log,
+            above_since: HashMap::new(),
+            signalled: HashMap::new(),
+            actions: VecDeque::new(),
+        }
+    }
+
+    /// Signals, through `send`, the processes that stayed above a rule's
+    /// threshold for its `for_seconds`, and returns what was done. A
+    /// process that keeps breaking the rule is signalled again later;
+    /// protected ones never are.
+    pub fn check<'a>(
+        &mut self,
+        now: Instant,
+        processes: impl Iterator<Item = Candidate<'a>>,
+        mut send: impl FnMut(Pid, Signal) -> io::Result<()>,
+    ) -> Vec<Action> {
+        let processes: Vec<Candidate> = processes
+            .filter(|process| !control::is_protected(process.pid, process.name, &self.protected))
+            .collect();
+        let mut above_since = HashMap::new();
+        let mut signalled = HashMap::new();
+        let mut actions = Vec::new();
+        for (index, (rule, signal)) in self.rules.iter().enumerate() {
+            let hold = Duration::from_secs(rule.for_seconds);
+            for process in processes
+                .iter()
+                .filter(|process| input::matches(&rule.process, process.name, &process.command))
+            {
+                let value = match rule.metric {
+                    Metric::ProcessCpu => process.cpu,
+                    _ => process.memory,
+                };
+                if value <= rule.above {
+                    continue;
+                }
+                let key = (index, process.pid);
+                let since = *self.above_since.get(&key).unwrap_or(&now);
+                above_since.insert(key, since);
+                let last = self.signalled.get(&key).copied();
+                if let Some(at) = last {
+                    signalled.insert(key, at);
+                }
+                let held = now.saturating_duration_since(since);
+                let due =
+                    last.is_none_or(|at| now.saturating_duration_since(at) >= hold.max(RESEND));
+                if held < hold || !due {
+                    continue;
+                }
+                signalled.insert(key, now);
+                let label = match rule.metric {
+                    Metric::ProcessCpu => "CPU",
+                    _ => "memory",
+                };
+                actions.push(Action {
+                    at: Local::now(),
+                    rule: rule.name.clone(),
+                    pid: process.pid,
+                    name: process.name.to_string(),
+                    signal: *signal,
+                    reason: format!(
+                        "{} {} above {} for {}s",
+                        label,
+                        rule.metric.format(value),
+                        rule.metric.format(rule.above),
+                        held.as_secs()
+                    ),
+                    error: send(process.pid, *signal).err().map(|err| err.to_string()),
+                });
+            }
+        }
+        self.above_since = above_since;
+        self.signalled = signalled;
+        for action in &actions {
+            if self.actions.len() == LOG_SIZE {
+                self.actions.pop_front();
+            }
+            self.actions.push_back(action.clone());
+        }
+        actions
+    }
+
+    /// Appends `actions` to the log file, if there is one.
+    pub fn append_log(&self, actions: &[Action]) -> io::Result<()> {
+        let Some(path) = self.log.as_ref().filter(|_| !actions.is_empty()) else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        for action in actions {
+            writeln!(file, "{}", action.line())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use sysinfo::PidExt;
+
+    #[test]
+    fn test_watchdog_signals_after_hold() {
+        let config = Config::parse(
+            r#"
+[watchdog]
+enabled = true
+
+[[watchdog.rules]]
+name = "runaway"
+process = "yes"
+metric = "process_cpu"
+above = 90.0
+for_seconds = 30
+signal = "kill"
+"#,
+        )
+        .unwrap();
+        let log = std::env::temp_dir().join(format!("rtop-watchdog-{}.log", std::process::id()));
+        let mut watchdog = Watchdog::new(
+            &config.watchdog,
+            config.protected.clone(),
+            Some(log.clone()),
+        );
+        let start = Instant::now();
+        let mut sent = Vec::new();
+        let mut at = |seconds: u64, cpu: f64| {
+            let processes = [(4242, "yes"), (77, "bash")].map(|(pid, name)| Candidate {
+                pid: Pid::from_u32(pid),
+                name,
+                command: String::new(),
+                cpu,
+                memory: 1.0,
+            });
+            watchdog.check(
+                start + Duration::from_secs(seconds),
+                processes.into_iter(),
+                |pid, signal| {
+                    sent.push((pid, signal));
+                    Err(io::Error::from(io::ErrorKind::PermissionDenied))
+                },
+            )
+        };
+        assert!(at(0, 99.0).is_empty());
+        // Dropping below the threshold starts the hold over.
+        assert!(at(20, 10.0).is_empty());
+        assert!(at(40, 99.0).is_empty());
+        let actions = at(70, 99.0);
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].reason, "CPU 99.0% above 90.0% for 30s");
+        assert!(actions[0].error.is_some());
+        assert!(at(80, 99.0).is_empty());
+        assert_eq!(at(100, 99.0).len(), 1);
+        assert_eq!(sent, vec![(Pid::from_u32(4242), Signal::Kill); 2]);
+
+        watchdog.append_log(&actions).unwrap();
+        let written = std::fs::read_to_string(&log).unwrap();
+        std::fs::remove_file(&log).unwrap();
+        assert!(written.contains(" runaway SIGKILL yes (4242) CPU 99.0% above 90.0% for 30s: "));
+        assert_eq!(watchdog.actions.len(), 2);
+
+        let invalid = Config::parse("[[watchdog.rules]]\nname = \"x\"\nprocess = \"\"\nmetric = \"process_cpu\"\nabove = 1.0\n");
+        assert!(invalid.is_err());
+    }
+}
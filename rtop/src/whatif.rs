@@ -0,0 +1,219 @@
+// src/whatif.rs
+use std::collections::HashMap;
+use sysinfo::Pid;
+
+/// A hypothetical container limit to check the current processes against.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Limit {
+    /// CPU in percent of one core, like the CPU% column (`150%`, or `1.5` cores).
+    Cpu(f64),
+    /// Resident memory in bytes (`512M`, `2GiB`; units are binary).
+    Memory(u64),
+}
+
+impl Limit {
+    pub fn parse(text: &str) -> Result<Limit, String> {
+        let text = text.trim();
+        let invalid = || format!("'{}' is not a limit like 150%, 1.5 (cores) or 512M", text);
+        if let Some(percent) = text.strip_suffix('%') {
+            return percent
+                .trim()
+                .parse()
+                .map(Limit::Cpu)
+                .map_err(|_| invalid());
+        }
+        let split = text
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(split);
+        let number: f64 = number.parse().map_err(|_| invalid())?;
+        let unit = unit.trim().to_ascii_lowercase();
+        let unit = unit.trim_end_matches('b').trim_end_matches('i');
+        let shift = match unit {
+            "" | "c" | "cores" => return Ok(Limit::Cpu(number * 100.0)),
+            "k" => 10,
+            "m" => 20,
+            "g" => 30,
+            "t" => 40,
+            _ => return Err(invalid()),
+        };
+        Ok(Limit::Memory((number * (1u64 << shift) as f64) as u64))
+    }
+
+    /// The part of a CPU% and resident memory sample this limit constrains.
+    fn usage(self, cpu: f64, memory: u64) -> f64 {
+        match self {
+            Limit::Cpu(_) => cpu,
+            Limit::Memory(_) => memory as f64,
+        }
+    }
+
+    fn value(self) -> f64 {
+        match self {
+            Limit::Cpu(percent) => percent,
+            Limit::Memory(bytes) => bytes as f64,
+        }
+    }
+
+    /// `usage` in the limit's terms: a percentage or a size.
+    pub fn format(self, usage: f64) -> String {
+        match self {
+            Limit::Cpu(_) => format!("{:.0}%", usage),
+            Limit::Memory(_) if usage >= (1u64 << 30) as f64 => {
+                format!("{:.2} GiB", usage / (1u64 << 30) as f64)
+            }
+            Limit::Memory(_) => format!("{:.1} MiB", usage / (1u64 << 20) as f64),
+        }
+    }
+
+    pub fn describe(self) -> String {
+        match self {
+            Limit::Cpu(percent) => format!("CPU {:.0}% ({:.2} cores)", percent, percent / 100.0),
+            Limit::Memory(bytes) => format!("memory {}", self.format(bytes as f64)),
+        }
+    }
+}
+
+/// One process of the current sample.
+pub struct Sample {
+    pub pid: Pid,
+    pub name: String,
+    pub cgroup: Option<String>,
+    pub cpu: f64,
+    pub memory: u64,
+}
+
+/// A process or cgroup that would exceed the limit.
+#[derive(Debug, PartialEq)]
+pub struct Excess {
+    /// A process name, or a cgroup path.
+    pub name: String,
+    pub pid: Option<Pid>,
+    pub processes: usize,
+    pub usage: f64,
+    /// `usage` relative to the limit; 1.5 means 50% over.
+    pub ratio: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    pub processes: Vec<Excess>,
+    pub cgroups: Vec<Excess>,
+}
+
+impl Report {
+    /// Everything in `samples` above `limit`, by itself and summed per
+    /// cgroup, furthest over first.
+    pub fn of(limit: Limit, samples: &[Sample]) -> Report {
+        let excess = |name: String, pid, processes, usage: f64| {
+            let ratio = usage / limit.value().max(f64::EPSILON);
+            (ratio > 1.0).then_some(Excess {
+                name,
+                pid,
+                processes,
+                usage,
+                ratio,
+            })
+        };
+        let mut processes: Vec<Excess> = samples
+            .iter()
+            .filter_map(|s| excess(s.name.clone(), Some(s.pid), 1, limit.usage(s.cpu, s.memory)))
+            .collect();
+        let mut groups: HashMap<&str, (usize, f64)> = HashMap::new();
+        for sample in samples {
+            if let Some(cgroup) = &sample.cgroup {
+                let group = groups.entry(cgroup).or_default();
+                group.0 += 1;
+                group.1 += limit.usage(sample.cpu, sample.memory);
+            }
+        }
+        let mut cgroups: Vec<Excess> = groups
+            .into_iter()
+            .filter_map(|(cgroup, (count, usage))| excess(cgroup.to_string(), None, count, usage))
+            .collect();
+        for list in [&mut processes, &mut cgroups] {
+            list.sort_by(|a, b| {
+                b.ratio
+                    .total_cmp(&a.ratio)
+                    .then_with(|| a.name.cmp(&b.name))
+            });
+        }
+        Report { processes, cgroups }
+    }
+}
+
+/// The cgroup of `pid`: the unified (v2) path, or on v1 the `cpu` hierarchy.
+pub fn cgroup(pid: Pid) -> Option<String> {
+    let content = std::fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    parse_cgroup(&content)
+}
+
+fn parse_cgroup(content: &str) -> Option<String> {
+    let entries: Vec<(&str, &str)> = content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, ':');
+            fields.next()?;
+            Some((fields.next()?, fields.next()?))
+        })
+        .collect();
+    entries
+        .iter()
+        .find(|(controllers, _)| controllers.split(',').any(|c| c == "cpu"))
+        .or_else(|| {
+            entries
+                .iter()
+                .find(|(controllers, _)| controllers.is_empty())
+        })
+        .map(|(_, path)| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_limit() {
+        assert_eq!(Limit::parse("150%"), Ok(Limit::Cpu(150.0)));
+        assert_eq!(Limit::parse("1.5"), Ok(Limit::Cpu(150.0)));
+        assert_eq!(Limit::parse("512M"), Ok(Limit::Memory(512 << 20)));
+        assert_eq!(Limit::parse("2 GiB"), Ok(Limit::Memory(2 << 30)));
+        assert!(Limit::parse("lots").is_err());
+        assert!(Limit::parse("5x").is_err());
+
+        assert_eq!(
+            parse_cgroup("0::/system.slice/nginx.service\n").as_deref(),
+            Some("/system.slice/nginx.service")
+        );
+        assert_eq!(
+            parse_cgroup("12:memory:/docker/a\n4:cpu,cpuacct:/docker/b\n0::/\n").as_deref(),
+            Some("/docker/b")
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let sample = |pid: usize, cgroup: &str, cpu: f64| Sample {
+            pid: Pid::from(pid),
+            name: format!("p{}", pid),
+            cgroup: Some(cgroup.to_string()),
+            cpu,
+            memory: 0,
+        };
+        let samples = [
+            sample(1, "/web", 80.0),
+            sample(2, "/web", 60.0),
+            sample(3, "/db", 120.0),
+        ];
+        let report = Report::of(Limit::Cpu(100.0), &samples);
+        assert_eq!(report.processes.len(), 1);
+        assert_eq!(report.processes[0].name, "p3");
+        let cgroups: Vec<(&str, usize)> = report
+            .cgroups
+            .iter()
+            .map(|c| (c.name.as_str(), c.processes))
+            .collect();
+        assert_eq!(cgroups, vec![("/web", 2), ("/db", 1)]);
+        assert_eq!(report.cgroups[0].ratio, 1.4);
+    }
+}
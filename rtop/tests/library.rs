@@ -0,0 +1,40 @@
+// tests/library.rs
+use clap::{Args, FromArgMatches};
+use rtop::cli::TuiArgs;
+use rtop::config::Config;
+use rtop::{App, Tab};
+use sysinfo::SystemExt;
+use tui::backend::TestBackend;
+use tui::Terminal;
+
+#[test]
+fn test_sample_and_draw_outside_the_crate() {
+    let mut app = App::new(Config::default());
+    app.update();
+    assert!(app.system().total_memory() > 0);
+    assert!(!app.get_sorted_processes().is_empty());
+    app.set_filter("no process is called this".to_string());
+    assert!(app.get_sorted_processes().is_empty());
+    assert_eq!(app.filter(), "no process is called this");
+
+    app.set_tab(Tab::Disks);
+    assert_eq!(app.tab(), Tab::Disks);
+    let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+    terminal.draw(|f| rtop::ui::draw(f, &mut app)).unwrap();
+    let screen: String = terminal
+        .backend()
+        .buffer()
+        .content
+        .iter()
+        .map(|cell| cell.symbol.as_str())
+        .collect();
+    assert!(screen.contains("CPU Usage"));
+    assert!(screen.contains("Disks"));
+}
+
+#[test]
+fn test_tui_args_flatten() {
+    let command = TuiArgs::augment_args(clap::Command::new("monitor"));
+    let matches = command.get_matches_from(["monitor", "--once"]);
+    assert!(TuiArgs::from_arg_matches(&matches).is_ok());
+}